@@ -0,0 +1,64 @@
+use httpmock::prelude::*;
+use vulnscan::ScanContext;
+
+/// Local mock targets standing in for the endpoints found by port scanning
+/// - Every server answers unknown paths with a small 404 page
+/// - Every fifth server leaks `.git/HEAD` and `.git/config`, so matching, module dependencies
+///   and reporting are exercised
+///
+/// httpmock pools at most 25 servers unless `HTTPMOCK_MAX_SERVERS` is raised
+pub struct Fixture {
+    // Servers shut down when dropped
+    _servers: Vec<MockServer>,
+    pub endpoints: Vec<ScanContext>,
+}
+
+impl Fixture {
+    pub async fn start(servers: usize) -> Self {
+        let mut started = Vec::with_capacity(servers);
+
+        for i in 0..servers {
+            let server = MockServer::start_async().await;
+
+            if i % 5 == 0 {
+                server
+                    .mock_async(|when, then| {
+                        when.method(GET).path("/.git/HEAD");
+                        then.status(200).body("ref: refs/heads/main\n");
+                    })
+                    .await;
+                server
+                    .mock_async(|when, then| {
+                        when.method(GET).path("/.git/config");
+                        then.status(200)
+                            .body("[core]\n\tbare = false\n[branch \"main\"]\n\tremote = origin\n");
+                    })
+                    .await;
+            }
+
+            server
+                .mock_async(|when, then| {
+                    when.any_request();
+                    then.status(404)
+                        .header("Content-Type", "text/html")
+                        .body("<html><body><h1>404 Not Found</h1></body></html>");
+                })
+                .await;
+
+            started.push(server);
+        }
+
+        let endpoints = started
+            .iter()
+            .map(|server| {
+                let endpoint = format!("{}:{}", server.host(), server.port());
+                ScanContext::new(&endpoint, server.address().ip())
+            })
+            .collect();
+
+        Fixture {
+            _servers: started,
+            endpoints,
+        }
+    }
+}
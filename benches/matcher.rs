@@ -0,0 +1,65 @@
+// Compares the strategies for matching response bodies, used to pick `INLINE_THRESHOLD`
+//
+//     cargo bench --bench matcher
+//
+// Reference numbers: a thread handoff costs ~6 µs, while matching inline takes
+// ~1 µs for 1 KiB, ~10 µs for 16 KiB and ~67 µs for 64 KiB bodies
+
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::Throughput;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use vulnscan::matcher;
+
+static PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<title>Index of /").unwrap());
+
+fn body(size: usize) -> String {
+    "<p>lorem ipsum dolor sit amet</p>\n"
+        .repeat(size / 34 + 1)
+        .chars()
+        .take(size)
+        .collect()
+}
+
+fn strategies(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+
+    let mut group = c.benchmark_group("match_body");
+
+    for size in [1024, 16 * 1024, matcher::INLINE_THRESHOLD, 1024 * 1024] {
+        let body = body(size);
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("inline", size), &body, |b, body| {
+            b.iter(|| PATTERN.is_match(body))
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("spawn_blocking", size),
+            &body,
+            |b, body| {
+                b.to_async(&runtime).iter(|| {
+                    let body = body.clone();
+                    async move {
+                        tokio::task::spawn_blocking(move || PATTERN.is_match(&body))
+                            .await
+                            .unwrap()
+                    }
+                })
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("matcher", size), &body, |b, body| {
+            b.to_async(&runtime)
+                .iter(|| matcher::run(body.clone(), |body| PATTERN.is_match(body)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, strategies);
+criterion_main!(benches);
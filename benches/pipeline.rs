@@ -0,0 +1,84 @@
+// Measures the vulnerability stage against local mock targets
+//
+//     cargo bench --bench pipeline
+//
+// Reference numbers (20 endpoints, every registered HTTP module), to be kept or improved by
+// changes to concurrency, caching or matching, and re-measured when modules are added:
+// - 10 workers:  ~50 ms per run
+// - 100 workers: ~47 ms per run
+//
+// Every mock server listens on 127.0.0.1, so all requests share the adaptive limit
+// of a single host and extra workers barely help.
+
+mod fixture;
+
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use fixture::Fixture;
+use reqwest::Client;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use vulnscan::Selection;
+use vulnscan::http_modules;
+use vulnscan::queue::TaskQueue;
+use vulnscan::watchdog::Watchdog;
+
+const ENDPOINTS: usize = 20;
+
+fn vulnerability_stage(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let fixture = runtime.block_on(Fixture::start(ENDPOINTS));
+    let watchdog = Arc::new(Watchdog::new(
+        Duration::from_secs(60),
+        CancellationToken::new(),
+    ));
+    let http_client = Client::builder()
+        .timeout(Duration::from_secs(5))
+        .danger_accept_invalid_certs(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("vulnerability_stage");
+    group.sample_size(10);
+
+    for workers in [10, 100] {
+        let queue = Arc::new(TaskQueue::new(
+            http_modules(&Selection::default(), None, false),
+            workers,
+            None,
+        ));
+
+        group.bench_with_input(BenchmarkId::new("workers", workers), &queue, |b, queue| {
+            b.to_async(&runtime).iter(|| async {
+                let findings = queue
+                    .run(
+                        "bench.local",
+                        &http_client,
+                        &fixture.endpoints,
+                        &[],
+                        &watchdog,
+                    )
+                    .await;
+                // Other modules may flag every mock server, only the planted leaks are known
+                let leaks = findings
+                    .iter()
+                    .filter(|finding| finding.module == "http/git_config_leakage")
+                    .count();
+                assert_eq!(leaks, ENDPOINTS / 5);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, vulnerability_stage);
+criterion_main!(benches);
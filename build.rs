@@ -0,0 +1,22 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Embed the commit vulnscan is built from, when built from a git checkout
+fn main() {
+    for path in [".git/HEAD", ".git/refs", ".git/packed-refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=VULNSCAN_COMMIT={}", commit.trim());
+    }
+}
@@ -0,0 +1,208 @@
+# Module descriptions, `module-` followed by the module name with `/` replaced by `-`
+
+module-subdomain-c99 = Scrape the latest subdomainfinder.c99.nl scan to enumerate subdomains
+module-subdomain-censys = Use names of certificates indexed by Censys to enumerate subdomains (requires an API key)
+module-subdomain-crtsh = Use crt.sh to enumerate subdomains
+module-subdomain-permutations = Resolve alterations (dev-, staging-, -old, numbered) of discovered subdomains
+module-subdomain-rapiddns = Scrape rapiddns.io to enumerate subdomains
+module-subdomain-securitytrails = Use the SecurityTrails API to enumerate subdomains (requires an API key)
+module-subdomain-webarchive = Use web.archive.org to enumerate subdomains, and check if it archived .env, .git/config or backup files
+module-http-cookie_flags = Check if session cookies are set without the Secure, HttpOnly or SameSite attributes
+module-http-dangerous_methods = Check if servers echo TRACE requests, or store and delete files with PUT and DELETE (uploads require { $flag })
+module-http-debug_endpoints = Check if framework debug pages (phpinfo, Telescope, Rails, Django) are exposed
+module-http-directory_listing = Check if directory listing is publicly accessible
+module-http-dotenv_disclosure = Check if .env is publicly accessible
+module-http-git_config_leakage = Check if .git/config is publicly accessible
+module-http-git_head_leakage = Check if .git/head is publicly accessible
+module-http-log4shell = Check if JNDI lookups in logged headers are resolved (requires { $server } or { $domain })
+module-http-reflected_xss = Check if query parameters are reflected unencoded into HTML
+module-http-sqli_error = Check if broken SQL syntax in parameters leaks database errors (requires { $flag })
+module-http-ssrf_probe = Check if URL parameters are fetched server-side (requires { $server } or { $domain })
+module-http-transport_security = Grade HTTP to HTTPS redirects, HSTS max-age and includeSubDomains, and HSTS preloading
+module-http-webdav = Check if WebDAV is enabled, and lists resources to unauthenticated PROPFIND
+module-service-ftp = Check FTP servers for anonymous access and missing FTPS (AUTH TLS)
+module-service-ntp_monlist = Check if NTP servers answer monlist requests, abused for amplification
+module-service-open_resolver = Check if DNS servers resolve names recursively for anybody, abused for amplification
+module-service-plaintext = Check for plaintext management services (telnet, rlogin) and VNC without authentication
+module-service-smtp = Check mail servers for missing STARTTLS, VRFY/EXPN user enumeration and open relaying (no mail is sent)
+module-service-snmp = Check if SNMP agents answer the default public/private communities with their sysDescr
+module-service-ssh = Check SSH servers for protocol 1, deprecated algorithms and password-only authentication
+module-tls-ccs_injection = Check if OpenSSL accepts an early ChangeCipherSpec (CVE-2014-0224, requires { $flag })
+module-tls-heartbleed = Check if TLS heartbeats over-read server memory (CVE-2014-0160, requires { $flag })
+module-dns-misconfig = Check target domains for missing CAA, absent or broken DNSSEC, lame delegations and name servers in unregistered domains
+module-dns-whois = Report the registrar and dates of target domains from WHOIS, and those of them or of their name server and mail domains expiring within 30 days
+module-cloud-azure_blob = Check if an Azure storage account exists or has publicly listable containers
+module-cloud-gcs = Check if a Google Cloud Storage bucket exists or is publicly listable
+module-cloud-s3 = Check if an Amazon S3 bucket exists or is publicly listable
+module-leaks-github = Search GitHub code for target domains mentioned along with passwords, secrets, tokens, .env files and SQL dumps (requires a token)
+module-leaks-hibp = Summarize accounts of target domains found in data breaches by Have I Been Pwned (requires an API key and verified domains)
+
+# Severities
+
+severity-info = Info
+severity-low = Low
+severity-medium = Medium
+severity-high = High
+
+# Finding titles, `finding-` followed by the kind in kebab case
+
+finding-anonymous-ftp = Anonymous FTP login
+finding-breached-accounts = Accounts in data breaches
+finding-bucket-exists = Cloud storage bucket exists
+finding-bucket-listable = Publicly listable cloud storage bucket
+finding-ccs-injection = OpenSSL ChangeCipherSpec injection
+finding-dangerous-methods = Dangerous HTTP methods
+finding-debug-endpoint-exposure = Exposed debug endpoint
+finding-directory-listing = Directory listing
+finding-dnssec-broken = Broken DNSSEC
+finding-dnssec-missing = Missing DNSSEC
+finding-domain-expiring = Domain expiring
+finding-domain-registration = Domain registration
+finding-dot-env-disclosure = .env disclosure
+finding-git-config-leakage = .git/config leakage
+finding-git-head-leakage = .git/HEAD leakage
+finding-git-repository-exposure = Exposed git repository
+finding-heartbleed = Heartbleed
+finding-historical-exposure = Historically exposed file
+finding-insecure-cookies = Insecure session cookies
+finding-lame-delegation = Lame delegation
+finding-log4-shell-callback = Log4Shell
+finding-missing-caa = Missing CAA record
+finding-no-start-tls = Missing STARTTLS
+finding-ns-takeover = Name server takeover
+finding-ntp-monlist = NTP monlist amplification
+finding-open-relay = Open mail relay
+finding-open-resolver = Open DNS resolver
+finding-plaintext-service = Plaintext management service
+finding-reflected-xss = Reflected XSS
+finding-repository-leak = Domain mentioned in public code
+finding-snmp-default-community = Default SNMP community
+finding-sql-injection = SQL injection
+finding-ssh-legacy-protocol = SSH protocol 1
+finding-ssh-password-only = SSH password-only authentication
+finding-ssh-weak-algorithms = Weak SSH algorithms
+finding-ssrf-callback = Server-side request forgery
+finding-user-enumeration = SMTP user enumeration
+finding-vnc-no-auth = VNC without authentication
+finding-weak-transport-security = Weak transport security
+finding-web-dav-exposure = WebDAV exposure
+
+# Scan reports
+
+scan-completed = Scan completed in { $seconds } seconds
+live-endpoints = Live HTTP endpoints ({ $count })
+non-production-hosts = Non-production hosts ({ $count })
+unreachable-endpoints = Unreachable during scan, checks skipped ({ $count })
+dead-hosts = Hosts not answering a ping, not port scanned ({ $count })
+failed-hosts = Hosts whose port scan failed, not scanned ({ $count })
+timed-out-modules = Module checks given up on after the timeout ({ $count })
+similar-findings = Also on { $count } other URLs with similar evidence:
+reason-named = named { $label }
+reason-apex = apex domain
+reason-private-address = resolves to private address { $ip }
+reason-basic-auth = basic authentication on port { $port }
+
+# Knowledge base
+
+kb-hint = CWE-{ $cwe }, { $owasp }, see `vulnscan kb { $module }`
+kb-cwe = CWE-{ $cwe } ({ $url })
+kb-owasp = OWASP Top 10: { $owasp }
+kb-pci = PCI DSS v4.0: { $requirements }
+kb-remediation = Remediation
+kb-references = References
+
+# Related and lookalike domains
+
+related-pivots = Pivots of { $domain } ({ $count })
+related-candidates = Related domains ({ $count })
+related-not-scanned = These domains are candidates for the scope, confirm who owns them before scanning them
+typosquat-live = { $live } of { $checked } lookalike domains of { $domain } are live
+
+# Retest
+
+replay-findings = Findings of the HAR file ({ $count })
+replay-recorded = Recorded response: { $status }, { $length } bytes
+replay-current = Current response: { $status }, { $length } bytes
+replay-reproduced = The finding still reproduces
+replay-changed = The status is the same but the response changed, verify it manually
+replay-not-reproduced = The finding no longer reproduces
+retest-title = Retest of { $count } findings of { $report }
+retest-still-present = Still present
+retest-remediated = Remediated
+retest-not-retested = Not retested, { $reason }
+retest-summary = { $present } still present, { $remediated } remediated, { $unverified } not retested
+
+# Triage
+
+annotation = Triage: { $annotation }
+annotations-title = Annotated findings ({ $count })
+annotation-saved = Annotated { $id }: { $annotation }
+annotation-removed = Removed the annotation of { $id }
+
+# Shared store
+
+store-recorded = Recorded as run { $run } in the store, { $new } of { $count } findings being new
+history-title = Scans recorded in the store ({ $count })
+history-run = #{ $run } { $targets } by { $operator }, { $started } to { $finished }: { $count } findings, { $new } new
+history-unfinished = interrupted
+
+# Compliance reports
+
+compliance-owasp = OWASP Top 10 (2021): { $failed } of { $total } categories failed
+compliance-pci = PCI DSS v4.0: { $failed } of { $total } requirements failed
+compliance-failed = { $category }: failed, { $findings } { $findings ->
+        [one] finding
+       *[other] findings
+    }, { $ran } of { $checks } checks ran
+compliance-passed = { $category }: passed, { $ran } of { $checks } checks ran
+compliance-not-checked = { $category }: not checked, none of { $checks } checks ran
+compliance-not-covered = { $category }: not covered by any check
+
+# Policies
+
+policy-violations = Policy violations ({ $count })
+policy-violation = { $violation } (violates { $pattern })
+policy-port = Port { $port } open on { $host }
+policy-finding = { $finding }
+policy-failed = { $count ->
+        [one] 1 policy violation
+       *[other] { $count } policy violations
+    } found
+
+# Scan plans
+
+plan-title = Scan plan for { $target }
+plan-hosts = Hosts ({ $count })
+plan-ports-imported = Open ports imported, none is probed
+plan-ports-assumed = Ports assumed open on each host ({ $count })
+plan-ports-probed = Ports probed on each host ({ $count })
+plan-module-skipped = { $name }: skipped
+plan-module-requests = { $name }: up to { $count } { $count ->
+        [one] request
+       *[other] requests
+    }
+plan-module-connections = { $name }: up to { $count } { $count ->
+        [one] connection
+       *[other] connections
+    }
+plan-http-modules = HTTP modules run on each open port
+plan-parameter-modules = Parameter modules run on up to { $count } mined parameters of each open port
+plan-service-udp = Service module { $name } runs on UDP ports { $ports } of each address
+plan-service-tcp = Service module { $name } runs on open ports { $ports }
+plan-tls-modules = TLS modules run on each open port
+plan-dns-modules = DNS modules run on { $count } domains
+plan-leak-modules = Leak modules run on { $count } domains
+plan-cloud-modules = Cloud modules run on { $count } bucket candidates (more with CNAMEs)
+plan-traffic = Estimated traffic: { $probes } port probes, then up to { $requests } requests per open port and { $checks } cloud checks
+
+# Module listings
+
+modules-subdomain = Subdomain Modules
+modules-http = HTTP Modules
+modules-service = Service Modules
+modules-tls = TLS Modules
+modules-dns = DNS Modules
+modules-cloud = Cloud Modules
+modules-leaks = Leak Modules
+modules-stats-title = Subdomain sources of the last scan ({ $target } at { $finished }, { $count } subdomains)
+modules-no-stats = No scan statistics yet, run a scan first
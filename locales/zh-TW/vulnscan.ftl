@@ -0,0 +1,196 @@
+# 模組說明，`module-` 後接模組名稱，`/` 以 `-` 取代
+
+module-subdomain-c99 = 擷取 subdomainfinder.c99.nl 最新的掃描結果以列舉子網域
+module-subdomain-censys = 以 Censys 索引的憑證名稱列舉子網域（需要 API 金鑰）
+module-subdomain-crtsh = 以 crt.sh 列舉子網域
+module-subdomain-permutations = 解析已發現子網域的變形（dev-、staging-、-old、編號）
+module-subdomain-rapiddns = 擷取 rapiddns.io 以列舉子網域
+module-subdomain-securitytrails = 以 SecurityTrails API 列舉子網域（需要 API 金鑰）
+module-subdomain-webarchive = 以 web.archive.org 列舉子網域，並檢查其是否封存了 .env、.git/config 或備份檔
+module-http-cookie_flags = 檢查工作階段 Cookie 是否缺少 Secure、HttpOnly 或 SameSite 屬性
+module-http-dangerous_methods = 檢查伺服器是否回顯 TRACE 請求，或以 PUT 與 DELETE 存放及刪除檔案（上傳需要 { $flag }）
+module-http-debug_endpoints = 檢查框架除錯頁面（phpinfo、Telescope、Rails、Django）是否外露
+module-http-directory_listing = 檢查目錄列表是否可公開存取
+module-http-dotenv_disclosure = 檢查 .env 是否可公開存取
+module-http-git_config_leakage = 檢查 .git/config 是否可公開存取
+module-http-git_head_leakage = 檢查 .git/head 是否可公開存取
+module-http-log4shell = 檢查記錄的標頭中的 JNDI 查詢是否被解析（需要 { $server } 或 { $domain }）
+module-http-reflected_xss = 檢查查詢參數是否未經編碼反射至 HTML
+module-http-sqli_error = 檢查參數中損壞的 SQL 語法是否洩漏資料庫錯誤（需要 { $flag }）
+module-http-ssrf_probe = 檢查 URL 參數是否在伺服器端被擷取（需要 { $server } 或 { $domain }）
+module-http-transport_security = 評等 HTTP 至 HTTPS 重新導向、HSTS max-age 與 includeSubDomains，以及 HSTS 預載
+module-http-webdav = 檢查 WebDAV 是否啟用，以及是否對未驗證的 PROPFIND 列出資源
+module-service-ftp = 檢查 FTP 伺服器是否允許匿名存取及缺少 FTPS（AUTH TLS）
+module-service-ntp_monlist = 檢查 NTP 伺服器是否回應 monlist 請求，可被濫用於放大攻擊
+module-service-open_resolver = 檢查 DNS 伺服器是否為任何人遞迴解析名稱，可被濫用於放大攻擊
+module-service-plaintext = 檢查明文管理服務（telnet、rlogin）及未驗證的 VNC
+module-service-smtp = 檢查郵件伺服器是否缺少 STARTTLS、可經 VRFY/EXPN 列舉使用者及開放轉送（不會寄出郵件）
+module-service-snmp = 檢查 SNMP 代理程式是否以 sysDescr 回應預設的 public/private 社群
+module-service-ssh = 檢查 SSH 伺服器是否支援協定 1、已淘汰的演算法及僅限密碼驗證
+module-tls-ccs_injection = 檢查 OpenSSL 是否接受過早的 ChangeCipherSpec（CVE-2014-0224，需要 { $flag }）
+module-tls-heartbleed = 檢查 TLS 心跳是否過度讀取伺服器記憶體（CVE-2014-0160，需要 { $flag }）
+module-dns-misconfig = 檢查目標網域是否缺少 CAA、DNSSEC 缺失或損壞、委派失效，以及名稱伺服器位於未註冊的網域
+module-dns-whois = 以 WHOIS 回報目標網域的註冊商與日期，以及目標網域或其名稱伺服器與郵件網域是否將於 30 天內到期
+module-cloud-azure_blob = 檢查 Azure 儲存體帳戶是否存在或有可公開列出的容器
+module-cloud-gcs = 檢查 Google Cloud Storage 值區是否存在或可公開列出
+module-cloud-s3 = 檢查 Amazon S3 值區是否存在或可公開列出
+module-leaks-github = 在 GitHub 程式碼中搜尋與密碼、密鑰、權杖、.env 檔及 SQL 傾印一同出現的目標網域（需要權杖）
+module-leaks-hibp = 彙整 Have I Been Pwned 記錄的目標網域外洩帳號（需要 API 金鑰及已驗證的網域）
+
+# 嚴重程度
+
+severity-info = 資訊
+severity-low = 低
+severity-medium = 中
+severity-high = 高
+
+# 發現標題，`finding-` 後接以 kebab case 表示的種類
+
+finding-anonymous-ftp = FTP 匿名登入
+finding-breached-accounts = 外洩資料中的帳號
+finding-bucket-exists = 雲端儲存值區存在
+finding-bucket-listable = 可公開列出的雲端儲存值區
+finding-ccs-injection = OpenSSL ChangeCipherSpec 注入
+finding-dangerous-methods = 危險的 HTTP 方法
+finding-debug-endpoint-exposure = 外露的除錯端點
+finding-directory-listing = 目錄列表
+finding-dnssec-broken = DNSSEC 損壞
+finding-dnssec-missing = 缺少 DNSSEC
+finding-domain-expiring = 網域即將到期
+finding-domain-registration = 網域註冊資訊
+finding-dot-env-disclosure = .env 外洩
+finding-git-config-leakage = .git/config 外洩
+finding-git-head-leakage = .git/HEAD 外洩
+finding-git-repository-exposure = 外露的 git 儲存庫
+finding-heartbleed = Heartbleed
+finding-historical-exposure = 曾外露的檔案
+finding-insecure-cookies = 不安全的工作階段 Cookie
+finding-lame-delegation = 委派失效
+finding-log4-shell-callback = Log4Shell
+finding-missing-caa = 缺少 CAA 記錄
+finding-no-start-tls = 未支援 STARTTLS
+finding-ns-takeover = 名稱伺服器接管
+finding-ntp-monlist = NTP monlist 放大
+finding-open-relay = 開放郵件轉送
+finding-open-resolver = 開放 DNS 解析器
+finding-plaintext-service = 明文管理服務
+finding-reflected-xss = 反射型 XSS
+finding-repository-leak = 公開程式碼提及網域
+finding-snmp-default-community = SNMP 預設社群
+finding-sql-injection = SQL 注入
+finding-ssh-legacy-protocol = SSH 協定 1
+finding-ssh-password-only = SSH 僅限密碼驗證
+finding-ssh-weak-algorithms = 脆弱的 SSH 演算法
+finding-ssrf-callback = 伺服器端請求偽造
+finding-user-enumeration = SMTP 使用者列舉
+finding-vnc-no-auth = 未驗證的 VNC
+finding-weak-transport-security = 脆弱的傳輸安全
+finding-web-dav-exposure = WebDAV 外露
+
+# 掃描報告
+
+scan-completed = 掃描於 { $seconds } 秒內完成
+live-endpoints = 存活的 HTTP 端點（{ $count }）
+non-production-hosts = 非正式環境主機（{ $count }）
+unreachable-endpoints = 掃描期間無法連線，已略過檢查（{ $count }）
+dead-hosts = 未回應 ping 而未掃描連接埠的主機（{ $count }）
+failed-hosts = 連接埠掃描失敗而未掃描的主機（{ $count }）
+timed-out-modules = 逾時而放棄的模組檢查（{ $count }）
+similar-findings = 另有 { $count } 個 URL 的證據相近：
+reason-named = 名稱含 { $label }
+reason-apex = 頂層網域
+reason-private-address = 解析至私有位址 { $ip }
+reason-basic-auth = 連接埠 { $port } 需要基本驗證
+
+# 知識庫
+
+kb-hint = CWE-{ $cwe }，{ $owasp }，詳見 `vulnscan kb { $module }`
+kb-cwe = CWE-{ $cwe }（{ $url }）
+kb-owasp = OWASP Top 10：{ $owasp }
+kb-pci = PCI DSS v4.0：{ $requirements }
+kb-remediation = 修補建議
+kb-references = 參考資料
+
+# 相關與相似網域
+
+related-pivots = { $domain } 的關聯線索（{ $count }）
+related-candidates = 相關網域（{ $count }）
+related-not-scanned = 這些網域僅為範圍候選，掃描前請確認其擁有者
+typosquat-live = { $domain } 的 { $checked } 個相似網域中有 { $live } 個仍在使用
+
+# 重新測試
+
+replay-findings = HAR 檔中的發現（{ $count }）
+replay-recorded = 記錄的回應：{ $status }，{ $length } 位元組
+replay-current = 目前的回應：{ $status }，{ $length } 位元組
+replay-reproduced = 此發現仍可重現
+replay-changed = 狀態碼相同但回應已改變，請手動確認
+replay-not-reproduced = 此發現已無法重現
+retest-title = 重新測試 { $report } 中的 { $count } 個發現
+retest-still-present = 仍然存在
+retest-remediated = 已修補
+retest-not-retested = 未重新測試，{ $reason }
+retest-summary = { $present } 個仍然存在，{ $remediated } 個已修補，{ $unverified } 個未重新測試
+
+# 分類處理
+
+annotation = 分類：{ $annotation }
+annotations-title = 已註記的發現（{ $count }）
+annotation-saved = 已註記 { $id }：{ $annotation }
+annotation-removed = 已移除 { $id } 的註記
+
+# 共用資料庫
+
+store-recorded = 已記錄為資料庫中的第 { $run } 次掃描，{ $count } 個發現中 { $new } 個為新發現
+history-title = 資料庫中記錄的掃描（{ $count }）
+history-run = #{ $run } { $targets }，由 { $operator } 執行，{ $started } 至 { $finished }：{ $count } 個發現，{ $new } 個為新發現
+history-unfinished = 已中斷
+
+# 合規報告
+
+compliance-owasp = OWASP Top 10（2021）：{ $total } 個類別中 { $failed } 個未通過
+compliance-pci = PCI DSS v4.0：{ $total } 項要求中 { $failed } 項未通過
+compliance-failed = { $category }：未通過，{ $findings } 個發現，已執行 { $checks } 項檢查中的 { $ran } 項
+compliance-passed = { $category }：通過，已執行 { $checks } 項檢查中的 { $ran } 項
+compliance-not-checked = { $category }：未檢查，{ $checks } 項檢查皆未執行
+compliance-not-covered = { $category }：沒有涵蓋的檢查
+
+# 政策
+
+policy-violations = 違反政策（{ $count }）
+policy-violation = { $violation }（違反 { $pattern }）
+policy-port = { $host } 開放連接埠 { $port }
+policy-finding = { $finding }
+policy-failed = 發現 { $count } 項違反政策
+
+# 掃描計畫
+
+plan-title = { $target } 的掃描計畫
+plan-hosts = 主機（{ $count }）
+plan-ports-imported = 已匯入開放連接埠，不探測任何連接埠
+plan-ports-assumed = 假設各主機開放的連接埠（{ $count }）
+plan-ports-probed = 各主機探測的連接埠（{ $count }）
+plan-module-skipped = { $name }：略過
+plan-module-requests = { $name }：最多 { $count } 個請求
+plan-module-connections = { $name }：最多 { $count } 個連線
+plan-http-modules = 於各開放連接埠執行的 HTTP 模組
+plan-parameter-modules = 於各開放連接埠最多 { $count } 個探勘參數執行的參數模組
+plan-service-udp = 服務模組 { $name } 於各位址的 UDP 連接埠 { $ports } 執行
+plan-service-tcp = 服務模組 { $name } 於開放連接埠 { $ports } 執行
+plan-tls-modules = 於各開放連接埠執行的 TLS 模組
+plan-dns-modules = 於 { $count } 個網域執行的 DNS 模組
+plan-leak-modules = 於 { $count } 個網域執行的洩漏搜尋模組
+plan-cloud-modules = 於 { $count } 個候選值區執行的雲端模組（CNAME 可能增加更多）
+plan-traffic = 預估流量：{ $probes } 次連接埠探測，接著每個開放連接埠最多 { $requests } 個請求，以及 { $checks } 次雲端檢查
+
+# 模組列表
+
+modules-subdomain = 子網域模組
+modules-http = HTTP 模組
+modules-service = 服務模組
+modules-tls = TLS 模組
+modules-dns = DNS 模組
+modules-cloud = 雲端模組
+modules-leaks = 洩漏搜尋模組
+modules-stats-title = 上次掃描的子網域來源（{ $target }，{ $finished }，{ $count } 個子網域）
+modules-no-stats = 尚無掃描統計，請先執行掃描
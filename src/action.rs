@@ -1,42 +1,379 @@
+use crate::annotations::Annotations;
+use crate::annotations::TriageStatus;
+use crate::artifact;
+use crate::artifact::Domain;
+use crate::artifact::Host;
+use crate::authorization;
+use crate::cache;
+use crate::cache::PortCache;
+use crate::capabilities::Capabilities;
+use crate::capabilities::PingTechnique;
+use crate::capabilities::PortTechnique;
+use crate::capabilities::Techniques;
+use crate::capture;
+use crate::cluster;
+use crate::compliance;
+use crate::compliance::Framework;
+use crate::config::Config;
+use crate::config::PortsConfig;
+use crate::detections;
+use crate::detections::Update;
+use crate::doh;
+use crate::encrypt;
+use crate::encrypt::Recipient;
+use crate::environment::Environments;
+use crate::events;
+use crate::events::Event;
+use crate::export;
+use crate::export::Platform;
+use crate::export::Record;
+use crate::gallery;
+use crate::i18n::Message;
+use crate::i18n::text;
+use crate::inventory;
+use crate::inventory::InventoryFormat;
+use crate::kb;
+use crate::keys;
+use crate::keys::ApiKeys;
+use crate::keys::KeyStore;
+use crate::keys::Origin;
+use crate::manifest::Manifest;
+use crate::modules::Build;
+use crate::modules::Dependency;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Reportable;
+use crate::modules::Selection;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::cloud;
+use crate::modules::http::HttpOptions;
+use crate::modules::http::ParameterTarget;
+use crate::modules::http::ScanContext;
+use crate::modules::http::git_reconstruction;
+use crate::modules::http::parameters;
 use crate::modules::http_modules;
-use crate::modules::{self, subdomain_modules};
+use crate::modules::subdomain;
+use crate::modules::tls::certificate;
+use crate::modules::{
+    self, cloud_modules, derived_subdomain_modules, dns_modules, leak_modules, parameter_modules,
+    service_modules, subdomain_modules, tls_modules,
+};
+use crate::oob::ListenerConfig;
+use crate::oob::Oob;
+use crate::ping;
+use crate::policy::Policy;
+use crate::queue::TaskQueue;
+use crate::related;
+use crate::replay;
+use crate::replay::Outcome;
+use crate::retest;
+use crate::retest::Status;
+use crate::scheduler::Scheduler;
+use crate::scope::Scope;
+use crate::selftest;
+use crate::selftest::Verdict;
+use crate::signing;
+use crate::stats;
+use crate::stats::EnumerationStats;
+#[cfg(feature = "postgres-store")]
+use crate::store::Store;
+use crate::syn::SynScanner;
+use crate::target::Target;
+use crate::throttle::Limiter;
+use crate::typosquat;
+use crate::watchdog::Watchdog;
 
+use anyhow::Context;
 use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use clap::ValueEnum;
+use ed25519_dalek::SigningKey;
+use futures::SinkExt;
+use futures::Stream;
 use futures::StreamExt;
+use futures::TryStreamExt;
+use futures::channel::mpsc;
 use futures::future;
+use futures::future::Either;
 use futures::stream;
+use futures::stream::FuturesUnordered;
 use hickory_resolver::TokioResolver;
 use hickory_resolver::config::ResolverConfig;
 use hickory_resolver::name_server::TokioConnectionProvider;
+use hickory_resolver::proto::rr::RData;
+use hickory_resolver::proto::rr::RecordType;
 use reqwest::Client;
+use reqwest::Url;
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::hash_map::Entry;
+use std::fmt;
+use std::io::ErrorKind;
+use std::net::IpAddr;
 use std::net::SocketAddr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
 use std::time::Instant;
 use tokio::net::TcpStream;
-use tokio::net::lookup_host;
+use tokio::runtime::Runtime;
+use tokio_util::sync::CancellationToken;
 
+// Global upper bounds, requests to each host are further limited by `throttle`
 const SUBDOMAIN_CONCURRENCY: usize = 20;
 const DNS_CONCURRENCY: usize = 100;
-const PORT_CONCURRENCY: usize = 256;
 const VULNERABILITY_CONCURRENCY: usize = 100;
+const CLOUD_CONCURRENCY: usize = 20;
+const SERVICE_CONCURRENCY: usize = 20;
+const TLS_CONCURRENCY: usize = 20;
+// Domains enumerated at once, their resolutions share the DNS budget
+const ENUMERATION_CONCURRENCY: usize = 8;
+// Names waiting for resolution, enumeration pauses when as many are pending
+const RESOLUTION_BUFFER: usize = 1024;
+// Names each subdomain source may send ahead of their attribution
+const SOURCE_BUFFER: usize = 256;
+// How long to wait for late out-of-band callbacks after the last payload was sent
+const OOB_GRACE_PERIOD: Duration = Duration::from_secs(10);
+const OOB_HTTP_PORT: u16 = 80;
+const OOB_DNS_PORT: u16 = 53;
+// Web ports taken as open when port scanning is skipped
+const ASSUMED_PORTS: &[u16] = &[80, 443];
+const TOP_100_PORTS: &[u16] = &[
+    80, 23, 443, 21, 22, 25, 3389, 110, 445, 139, 143, 53, 135, 3306, 8080, 1723, 111, 995, 993,
+    5900, 1025, 587, 8888, 199, 1720, 465, 548, 113, 81, 6001, 10000, 514, 5060, 179, 1026, 2000,
+    8443, 8000, 32768, 554, 26, 1433, 49152, 2001, 515, 8008, 49154, 1027, 5666, 646, 5000, 5631,
+    631, 49153, 8081, 2049, 88, 79, 5800, 106, 2121, 1110, 49155, 6000, 513, 990, 5357, 427, 49156,
+    543, 544, 5101, 144, 7, 389, 8009, 3128, 444, 9999, 5009, 7070, 5190, 3000, 5432, 1900, 3986,
+    13, 1029, 9, 5051, 6646, 49157, 1028, 873, 1755, 2717, 4899, 9100, 119, 37,
+];
 
-/// Scan a target domain
+/// Optional behaviours of the `scan` subcommand
+pub struct ScanOptions {
+    /// Scan domain targets as they are, without enumerating their subdomains
+    pub skip_enumeration: bool,
+    /// Assume web ports are open instead of probing them
+    pub skip_port_scan: bool,
+    /// Port scan every host, rather than those answering a ping
+    pub skip_ping: bool,
+    /// Cross-check a sample of resolved hosts against a DoH resolver
+    pub verify_dns: bool,
+    /// Hosts artifact replacing enumeration and resolution
+    pub hosts_from: Option<PathBuf>,
+    /// Ports artifact or Nmap report replacing every stage up to port scanning
+    pub ports_from: Option<PathBuf>,
+    /// Where to save the artifacts of the enumeration and port scan stages
+    pub artifacts: Option<PathBuf>,
+    /// Where to write the open ports as an Nmap XML report
+    pub export_nmap: Option<PathBuf>,
+    /// Vulnerability management platforms receiving the findings
+    pub push: Vec<Platform>,
+    /// Reuse open ports of hosts scanned less than this long ago
+    pub port_cache_ttl: Option<Duration>,
+    /// Frameworks summarized at the end of the scan
+    pub compliance: Vec<Framework>,
+    /// Exposure accepted per host, failing the scan on anything beyond it
+    pub policy: Option<Policy>,
+    /// Where to write the inventory of hosts, ports and technologies
+    pub inventory: Option<PathBuf>,
+    /// How the inventory is written
+    pub inventory_format: InventoryFormat,
+    /// Where to write the HTML gallery of live endpoints
+    pub gallery: Option<PathBuf>,
+    /// Where to write the requests and responses behind web findings as a HAR file
+    pub har: Option<PathBuf>,
+    /// How progress and findings are printed
+    pub format: ScanFormat,
+    /// Concurrent workers of the vulnerability stage
+    pub workers: usize,
+    /// How much of each response body modules read
+    pub max_body_size: usize,
+    /// Always fetch responses of passive checks instead of revalidating cached ones
+    pub no_cache: bool,
+    /// Stop after resolution and print what the scan would do
+    pub dry_run: bool,
+    /// Skip the authorization check required by the configuration
+    pub i_am_authorized: bool,
+    /// Hosts allowed to be kept and actively checked, every host if unset
+    pub scope: Option<Scope>,
+    /// Where to checkpoint the vulnerability stage, resuming from it if it exists
+    pub checkpoint: Option<PathBuf>,
+    /// Let crt.sh fall back to its public PostgreSQL replica
+    pub crtsh_postgres: bool,
+    /// Enable modules sending payloads that may disturb the target
+    pub aggressive: bool,
+    /// Checks run, by their tags
+    pub selection: Selection,
+    /// Headers sent with every request to the targets, identifying the scan
+    pub headers: HeaderMap,
+    /// Also scan interesting directories of historical URLs from web.archive.org
+    pub archived_paths: bool,
+    /// Reconstruct file listings from leaked `.git` directories
+    pub exploit_git: bool,
+    /// Where to download leaked git objects, if anywhere
+    pub dump: Option<PathBuf>,
+    /// Interactsh-compatible server confirming out-of-band callbacks
+    pub oob_server: Option<String>,
+    /// Authentication token of the OOB server
+    pub oob_token: Option<String>,
+    /// Domain delegated to the built-in OOB listener, used instead of `oob_server`
+    pub oob_domain: Option<String>,
+    /// Public IP returned for callback hosts of the built-in OOB listener
+    pub oob_public_ip: Option<IpAddr>,
+    /// File the events are written to, in any format, instead of stdout with `--format jsonl`
+    pub output: Option<PathBuf>,
+    /// Recipients the report files are encrypted to, written in plaintext if empty
+    pub encrypt_to: Vec<Recipient>,
+    /// Mask credentials of the settings embedded in the scan manifest
+    pub redact_config: bool,
+}
+
+impl ScanOptions {
+    /// Print progress, kept off stdout when it carries newline-delimited findings
+    fn report(&self, message: impl fmt::Display) {
+        match self.format {
+            ScanFormat::Text => println!("{}", message),
+            ScanFormat::Lines | ScanFormat::Jsonl => eprintln!("{}", message),
+        }
+    }
+}
+
+/// How hosts are probed by the port scan stage, with the techniques selected at startup
+struct Prober<'a> {
+    config: &'a PortsConfig,
+    /// Set when SYN scans were selected and the raw socket opened
+    syn: Option<SynScanner>,
+    /// Unset with `--skip-ping`
+    ping: Option<PingTechnique>,
+    /// `name (ip)` of the hosts which did not answer a ping, sorted
+    dead: Mutex<BTreeSet<String>>,
+    /// `name (ip): error` of the hosts whose port scan failed, sorted
+    failed: Mutex<BTreeSet<String>>,
+}
+
+impl<'a> Prober<'a> {
+    /// Open the raw socket of SYN scans when `probing`, which needs a runtime
+    fn new(
+        config: &'a PortsConfig,
+        probing: bool,
+        techniques: Techniques,
+        skip_ping: bool,
+    ) -> Self {
+        let syn = match techniques.ports {
+            PortTechnique::Syn if probing => SynScanner::new()
+                .map_err(|e| log::warn!("Falling back to connect scans: {}", e))
+                .ok(),
+            _ => None,
+        };
+
+        Prober {
+            config,
+            syn,
+            ping: (!skip_ping).then_some(techniques.ping),
+            dead: Mutex::new(BTreeSet::new()),
+            failed: Mutex::new(BTreeSet::new()),
+        }
+    }
+}
+
+/// Findings of a check stage, along with the modules it ran for compliance coverage
+#[derive(Default)]
+struct Checks {
+    findings: Vec<Finding>,
+    modules: Vec<String>,
+}
+
+/// Scan target domains, IP addresses and networks
 /// - Enumerate subdomains
 /// - Resolve subdomains
 /// - Probe open ports on resolved subdomains
 /// - Scan open ports for vulnerabilities
+/// - Check cloud storage buckets derived from the targets
 /// - Report findings
 ///
 /// # Arguments
-/// * `target` - The domain to scan
-pub fn scan(target: &str) -> Result<()> {
-    struct Domain {
-        name: String,
-        open_ports: Vec<u16>,
+/// * `targets` - The domains, IP addresses or networks to scan
+/// * `options` - Optional scan behaviours
+/// * `config` - Settings from the configuration file
+pub fn scan(targets: &[Target], options: &ScanOptions, config: &Config) -> Result<()> {
+    match targets {
+        [] => log::info!("Starting scan of imported hosts"),
+        [target] => log::info!("Starting scan for {}", target),
+        _ => log::info!("Starting scan for {} targets", targets.len()),
+    }
+
+    let signing_key = signing::load_key(&config.signing)?;
+    if options.crtsh_postgres && cfg!(not(feature = "crtsh-postgres")) {
+        bail!("--crtsh-postgres needs vulnscan built with --features crtsh-postgres");
+    }
+
+    let manifest = Manifest::new(
+        config,
+        &options.selection,
+        options.aggressive,
+        options.redact_config,
+    );
+    if options.format == ScanFormat::Jsonl || options.output.is_some() {
+        events::open(options.output.as_deref(), &options.encrypt_to)?;
+        events::emit(Event::Manifest(&manifest));
+    }
+    if options.format != ScanFormat::Jsonl {
+        options.report(manifest.summary());
+    }
+    for registration in modules::held_back(&options.selection, options.aggressive) {
+        match registration.intrusiveness {
+            Intrusiveness::Partial => log::info!(
+                "Only running the safe checks of {} without --aggressive",
+                registration.name
+            ),
+            _ => log::info!("Skipping {} without --aggressive", registration.name),
+        }
+    }
+    // Read by modules from the context of each endpoint, along with the limiter
+    let http_options = HttpOptions {
+        max_body_size: options.max_body_size,
+        cache_dir: if options.no_cache {
+            None
+        } else {
+            cache::default_http_cache_dir()
+                .inspect_err(|e| log::warn!("Responses will not be cached: {}", e))
+                .ok()
+        },
+    };
+    let limiter = Limiter::default();
+
+    for platform in &options.push {
+        platform.validate(config)?;
     }
 
-    log::info!("Starting scan for {}", target);
+    // Results of earlier stages, from a previous run or another tool
+    let imported_hosts = options
+        .hosts_from
+        .as_deref()
+        .map(artifact::load_hosts)
+        .transpose()?;
+    let imported_ports = options
+        .ports_from
+        .as_deref()
+        .map(artifact::load_ports)
+        .transpose()?;
+
+    // Keys of subdomain sources and leak searches, only needed when either runs
+    let enumerating =
+        !(options.skip_enumeration || imported_hosts.is_some() || imported_ports.is_some());
+    let api_keys =
+        if enumerating || !leak_modules(&options.selection, &ApiKeys::default()).is_empty() {
+            ApiKeys::load(config)?
+        } else {
+            ApiKeys::default()
+        };
 
     // Build tokio runtime
     let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -44,39 +381,83 @@ pub fn scan(target: &str) -> Result<()> {
         .build()
         .expect("Failed to build Tokio runtime");
 
+    // Checkpoints are only resumed by scans of the same targets, or of the same imported file
+    let target_name = match options
+        .ports_from
+        .as_deref()
+        .or(options.hosts_from.as_deref())
+    {
+        Some(path) => path.display().to_string(),
+        None => targets
+            .iter()
+            .map(Target::to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+    };
+
+    // Scan history and triage shared by the team, when a store is configured
+    #[cfg(feature = "postgres-store")]
+    let mut store = connect_store(&runtime, config)?;
+    #[cfg(feature = "postgres-store")]
+    let run = match &store {
+        Some(store) if !options.dry_run => Some(runtime.block_on(store.start_run(&target_name))?),
+        _ => None,
+    };
+    #[cfg(not(feature = "postgres-store"))]
+    let store = connect_store(&runtime, config)?;
+
+    // Triage of earlier scans, carried forward to the findings reported again
+    let annotations = open_annotations(&runtime, store.as_ref())?;
+
+    // Dry runs never probe the target, so they need no authorization
+    if config.authorization.required && !options.i_am_authorized && !options.dry_run {
+        // Imported hosts stand in for the targets
+        let names: Vec<String> = match (&imported_ports, &imported_hosts) {
+            (Some(domains), _) => domains.iter().map(|domain| domain.name.clone()).collect(),
+            (None, Some(hosts)) => hosts.iter().map(|host| host.name.clone()).collect(),
+            (None, None) => targets.iter().map(Target::to_string).collect(),
+        };
+
+        for name in &names {
+            runtime.block_on(authorization::verify(
+                name,
+                config.authorization.token.as_deref(),
+            ))?;
+        }
+    }
+
+    // Targets are scanned together, sharing the DNS and port scan budgets
+    // - Hosts probe several ports at once, so fewer of them keep `max_probes` probes in flight
+    let scheduler = Scheduler::new(
+        targets,
+        DNS_CONCURRENCY,
+        (config.ports.max_probes / config.ports.concurrency.max(1)).max(1),
+    );
+
     // Start a timer
     let scan_start = Instant::now();
 
-    // Run the scan
-    runtime.block_on(async {
-        // Passive subdomain enumeration
-        log::trace!("Trying to enumerate subdomains for {}", target);
-
-        let subdomains: HashSet<String> = stream::iter(subdomain_modules().into_iter())
-            .map(|module| async move {
-                match module.enumerate(target).await {
-                    Ok(new_subdomains) => Some(new_subdomains),
-                    Err(e) => {
-                        log::error!("Failed to enumerate subdomains with: {}", e);
-                        None
-                    }
-                }
-            })
-            .buffer_unordered(SUBDOMAIN_CONCURRENCY)
-            .filter_map(future::ready)
-            .collect::<Vec<Vec<String>>>()
-            .await
-            .into_iter()
-            .flatten()
-            .collect();
+    // Module checks are given up on when hung, or all at once on Ctrl-C
+    let token = CancellationToken::new();
+    runtime.spawn(cancel_on_ctrl_c(token.clone()));
+    let watchdog = Arc::new(Watchdog::new(config.modules.timeout(), token));
 
-        println!(
-            "{} subdomains were found during the enumeration stage",
-            subdomains.len()
-        );
+    // Privileges are checked before any probe, rather than failing mid-scan
+    let techniques = Capabilities::detect().select();
+    let probing = imported_ports.is_none() && !options.skip_port_scan && !options.dry_run;
+    if probing {
+        options.report(format!("Scan techniques: {}", techniques));
+    }
+    let prober = {
+        let _runtime = runtime.enter();
+        Prober::new(&config.ports, probing, techniques, options.skip_ping)
+    };
 
-        // Check if subdomains are resolvable
-        log::trace!("Trying to resolve discovered subdomains");
+    // Run the scan, keeping findings for the platforms they are pushed to, and the exchanges
+    // behind them for `--har`
+    let capture = options.har.is_some().then(|| options.headers.clone());
+    let stages = async {
+        let watchdog = &watchdog;
 
         let resolver = TokioResolver::builder_with_config(
             ResolverConfig::default(),
@@ -84,169 +465,2975 @@ pub fn scan(target: &str) -> Result<()> {
         )
         .build();
 
-        let subdomains: Vec<String> = stream::iter(subdomains.into_iter())
-            .map(|domain| async {
-                if is_resolvable(&resolver, &domain).await {
-                    Some(domain)
-                } else {
-                    None
+        // Domains whose subdomains are enumerated, archived URLs and bucket names derive from them
+        let domains: Vec<&str> = if options.skip_enumeration {
+            Vec::new()
+        } else {
+            targets.iter().filter_map(Target::domain).collect()
+        };
+
+        // Imported ports name the hosts they were found on
+        let imported_hosts = match &imported_ports {
+            Some(domains) => Some(
+                domains
+                    .iter()
+                    .map(|domain| Host {
+                        name: domain.name.clone(),
+                        ip: domain.ip,
+                    })
+                    .collect(),
+            ),
+            None => imported_hosts,
+        };
+        let hosts = collect_hosts(
+            targets,
+            &domains,
+            imported_hosts,
+            options,
+            &api_keys,
+            &resolver,
+            &scheduler,
+        )
+        .await;
+
+        if options.dry_run {
+            match options.format {
+                ScanFormat::Text => print_plan(&target_name, &domains, &hosts, options, &api_keys),
+                // The hosts alone, e.g. to feed other tools
+                ScanFormat::Lines => {
+                    for host in &hosts {
+                        println!("{}", host.name);
+                    }
                 }
-            })
-            .buffer_unordered(DNS_CONCURRENCY)
-            .filter_map(future::ready)
-            .collect()
+                // Already streamed as host events
+                ScanFormat::Jsonl => {}
+            }
+            return (
+                Vec::new(),
+                Environments::default(),
+                BTreeSet::new(),
+                Vec::new(),
+            );
+        }
+
+        let subdomains = collect_open_ports(
+            hosts,
+            imported_ports,
+            &domains,
+            options,
+            &prober,
+            &resolver,
+            &scheduler,
+        )
+        .await;
+
+        if let Some(path) = &options.export_nmap {
+            let probed = options.ports_from.is_none() && !options.skip_port_scan;
+            let report = artifact::to_nmap(&subdomains, probed, &manifest);
+            write_report(options, signing_key.as_ref(), "Nmap report", path, report);
+        }
+
+        // Web vulnerability scanning on resolved subdomains
+        log::info!("Starting Web vulnerability scanning");
+
+        let oob = start_oob(options).await;
+        let http_client = build_http_client(&subdomains, options, config);
+
+        // Production-named hosts weigh more, staging and internal ones are reported apart
+        let environments =
+            Environments::classify(&http_client, &limiter, &subdomains, &domains).await;
+
+        // Assets are inventoried apart from findings, for asset management systems
+        if let Some(path) = &options.inventory {
+            log::info!("Fingerprinting open ports for the inventory");
+
+            let assets = inventory::collect(
+                &http_client,
+                &limiter,
+                http_options.max_body_size,
+                &subdomains,
+            )
             .await;
+            match inventory::render(options.inventory_format, &target_name, &assets, &manifest) {
+                Ok(inventory) => {
+                    write_report(options, signing_key.as_ref(), "inventory", path, inventory)
+                }
+                Err(e) => log::error!("Failed to write inventory to {}: {:#}", path.display(), e),
+            }
+        }
 
-        println!("{} subdomains were successfully resolved", subdomains.len());
+        let archived_urls = fetch_archived_urls(&domains, options).await;
+        let archived_exposures = archived_exposure_checks(&domains, options).await;
 
-        // Port scanning on resolved subdomains
-        log::trace!("Trying to probe open ports on successfully resolved subdomains");
+        let contexts =
+            detect_endpoints(&http_client, &limiter, &http_options, &subdomains, options).await;
 
-        let subdomains: Vec<Domain> = stream::iter(subdomains.into_iter())
-            .map(|domain| async {
-                let open_ports = scan_top100_ports(&domain).await;
-                Some(Domain {
-                    name: domain,
-                    open_ports,
+        // Titles, servers and text of the live endpoints stand in for screenshots
+        if let Some(path) = &options.gallery {
+            let entries: Vec<gallery::Entry> = contexts
+                .iter()
+                .filter_map(|context| {
+                    Some(gallery::Entry {
+                        url: context.url(context.scheme.unwrap_or("http"), "/"),
+                        page: context.page.as_ref()?,
+                        technologies: &context.technologies,
+                    })
                 })
-            })
-            .buffer_unordered(PORT_CONCURRENCY)
-            .filter_map(future::ready)
-            .collect()
-            .await;
+                .collect();
+            let html = gallery::render(&target_name, &entries, &manifest);
+            write_report(options, signing_key.as_ref(), "gallery", path, html);
+        }
 
-        log::trace!("Port scanning finished");
+        let endpoints = with_archived_paths(contexts, &archived_urls, options);
+        let parameters = mine_parameters(&http_client, &endpoints, &archived_urls, options).await;
 
-        for subdomain in &subdomains {
-            println!("{}", subdomain.name);
-            for port in &subdomain.open_ports {
-                println!("\t{}", port);
-            }
+        let mut web = web_checks(
+            &http_client,
+            &endpoints,
+            &parameters,
+            oob.clone(),
+            &target_name,
+            options,
+            watchdog,
+        )
+        .await;
+        web.findings.extend(archived_exposures.findings);
+        web.modules.extend(archived_exposures.modules);
+
+        if let Some(oob) = &oob {
+            web.findings.extend(oob_callbacks(oob).await);
         }
 
-        // Web vulnerability scanning on resolved subdomains
-        log::info!("Starting Web vulnerability scanning");
+        log::info!("Web vulnerability scanning finished");
+
+        // Follow up on leaked git repositories
+        if options.exploit_git {
+            let dump_dir = options.dump.as_deref();
+            web.findings =
+                exploit_git_findings(&http_client, &limiter, web.findings, dump_dir).await;
+        }
+
+        // Requests and responses behind the findings, to be replayed by hand
+        if let Some(path) = &options.har {
+            let har = capture::har(&web.findings);
+            write_report(options, signing_key.as_ref(), "HAR", path, har);
+        }
+
+        // Findings are printed as each stage finishes, modules run kept for compliance coverage
+        let mut records = Vec::new();
+        let mut checked = BTreeSet::new();
+        let mut report = |checks: Checks| {
+            checked.extend(checks.modules);
+            report_findings(
+                &checks.findings,
+                &environments,
+                &annotations,
+                options.format,
+                &mut records,
+            );
+        };
+
+        report(web);
+        report(service_checks(&subdomains, options, watchdog).await);
+        report(tls_checks(&subdomains, options, watchdog).await);
+
+        // DNS and cloud storage checks only apply to enumerated domains
+        if !domains.is_empty() {
+            report(dns_checks(&domains, &resolver, options, watchdog).await);
+            report(leak_searches(&domains, &api_keys, options, config, watchdog).await);
+
+            let buckets = bucket_names(&domains, &subdomains, &resolver, &scheduler).await;
+            let cloud = cloud_checks(
+                &buckets,
+                &http_client,
+                &limiter,
+                &http_options,
+                options,
+                watchdog,
+            );
+            report(cloud.await);
+        }
+
+        (records, environments, checked, subdomains)
+    };
+    let (records, environments, checked, subdomains) =
+        runtime.block_on(capture::scope(capture, stages));
+    if let Some(path) = events::close()? {
+        sign(&path, signing_key.as_ref())?;
+    }
+
+    #[cfg(feature = "postgres-store")]
+    if let (Some(store), Some(run)) = (&mut store, run) {
+        let new = runtime.block_on(store.finish_run(run, &records))?;
+        options.report(
+            Message::new("store-recorded")
+                .arg("run", run)
+                .arg("count", records.len())
+                .arg("new", new),
+        );
+    }
 
-        let modules = http_modules();
+    if !options.push.is_empty() {
         let http_client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .danger_accept_invalid_certs(true)
-            .redirect(reqwest::redirect::Policy::none())
+            .timeout(config.http.timeout())
             .build()
             .expect("Failed to build HTTP client");
 
-        // Prepare scan parameters (Lazy Iterator: (Module + Endpoint))
-        let tasks_iter = subdomains
-            .iter()
-            .flat_map(|subdomain| {
-                subdomain
-                    .open_ports
-                    .iter()
-                    .map(move |port| (subdomain, port))
-            })
-            .flat_map(|(subdomain, port)| {
-                modules.iter().map(move |module| {
-                    let endpoint = format!("{}:{}", subdomain.name, port);
-                    (module, endpoint)
-                })
-            });
+        for platform in &options.push {
+            runtime.block_on(platform.push(&http_client, config, &records))?;
+        }
+    }
 
-        // Execute scanning tasks concurrently
-        let findings: Vec<_> = stream::iter(tasks_iter)
-            .map(|(module, url)| {
-                let http_client = http_client.clone();
-                async move { module.scan(&http_client, &url).await }
-            })
-            .buffer_unordered(VULNERABILITY_CONCURRENCY)
-            .filter_map(|scan_result| async move {
-                match scan_result {
-                    Ok(Some(finding)) => Some(finding),
-                    Ok(None) => None,
-                    Err(err) => {
-                        log::debug!("Error: {}", err);
-                        None
-                    }
-                }
-            })
-            .collect()
-            .await;
+    for line in environments.summary(&records) {
+        options.report(line);
+    }
 
-        log::info!("Web vulnerability scanning finished");
+    report_gaps(options, &limiter, prober, &watchdog);
 
-        for finding in findings {
-            println!("{:?}", finding);
+    for framework in &options.compliance {
+        for line in compliance::report(*framework, &records, &checked) {
+            options.report(line);
         }
-    });
+    }
+
+    let violations = match &options.policy {
+        Some(policy) => {
+            let mut violations = policy.port_violations(&subdomains);
+            violations.extend(policy.finding_violations(&records));
+            violations
+        }
+        None => Vec::new(),
+    };
+    if !violations.is_empty() {
+        options.report(Message::new("policy-violations").arg("count", violations.len()));
+        for violation in &violations {
+            options.report(format!("\t{}", violation));
+        }
+    }
 
     // Stop the timer
     let scan_duration = scan_start.elapsed();
-    println!("Scan completed in {} seconds", scan_duration.as_secs_f32());
+    options.report(
+        Message::new("scan-completed").arg("seconds", scan_duration.as_secs_f32().to_string()),
+    );
+
+    // Only exposure the policy does not accept fails the scan
+    if !violations.is_empty() {
+        bail!(
+            "{}",
+            Message::new("policy-failed").arg("count", violations.len())
+        );
+    }
 
     Ok(())
 }
 
-/// List available modules
-pub fn modules() {
-    let subdomain_mods = modules::subdomain_modules();
-    let http_mods = modules::http_modules();
+/// Hosts of the targets, imported or discovered, within scope
+/// - Hosts are streamed as events, and saved as an artifact unless ports were imported
+async fn collect_hosts(
+    targets: &[Target],
+    domains: &[&str],
+    imported: Option<Vec<Host>>,
+    options: &ScanOptions,
+    api_keys: &ApiKeys,
+    resolver: &TokioResolver,
+    scheduler: &Scheduler,
+) -> Vec<Host> {
+    let hosts = match imported {
+        Some(hosts) => hosts,
+        None => discover(targets, domains, options, api_keys, resolver, scheduler).await,
+    };
+    let hosts: Vec<Host> = hosts
+        .into_iter()
+        .filter(|host| in_scope(options, &host.name))
+        .collect();
+    for host in &hosts {
+        events::emit(Event::Host {
+            host: &host.name,
+            ip: host.ip,
+        });
+    }
 
-    println!("Subdomain Modules");
+    // Imported hosts were resolved by another run or tool
+    if options.verify_dns && options.hosts_from.is_none() && options.ports_from.is_none() {
+        verify_resolution(&hosts, options).await;
+    }
 
-    for module in subdomain_mods {
-        println!("\t{}: {}", module.name(), module.description());
+    if options.ports_from.is_none() {
+        save_artifact(options, artifact::HOSTS_FILE, &hosts);
     }
 
-    println!("HTTP Modules");
+    hosts
+}
+
+/// Open ports of `hosts`, imported, assumed or probed, along with the hosts named by the live
+/// certificates of `domains`
+/// - Hosts of different targets alternate, and are saved as an artifact
+async fn collect_open_ports(
+    hosts: Vec<Host>,
+    imported: Option<Vec<Domain>>,
+    domains: &[&str],
+    options: &ScanOptions,
+    prober: &Prober<'_>,
+    resolver: &TokioResolver,
+    scheduler: &Scheduler,
+) -> Vec<Domain> {
+    let mut subdomains: Vec<Domain> = match imported {
+        Some(domains) => domains
+            .into_iter()
+            .filter(|domain| in_scope(options, &domain.name))
+            .inspect(emit_ports)
+            .collect(),
+        None if options.skip_port_scan => hosts
+            .into_iter()
+            .map(|host| Domain {
+                name: host.name,
+                ip: host.ip,
+                open_ports: ASSUMED_PORTS.to_vec(),
+            })
+            .inspect(emit_ports)
+            .collect(),
+        None => {
+            // Port scanning on resolved subdomains
+            log::trace!("Trying to probe open ports on successfully resolved subdomains");
+
+            let subdomains = probe_hosts(hosts, options, prober, resolver, scheduler).await;
+
+            log::trace!("Port scanning finished");
+
+            subdomains
+        }
+    };
+
+    // Live certificates name hosts absent from CT logs, e.g. those issued wildcard certificates
+    if options.ports_from.is_none() && !domains.is_empty() {
+        let harvested =
+            harvest_certificates(&subdomains, domains, options, prober, resolver, scheduler).await;
+        subdomains.extend(harvested);
+    }
+
+    // Later stages buffer a fixed number of hosts, alternating targets keeps them all going
+    let subdomains = scheduler.interleave(subdomains, |subdomain| &subdomain.name);
+
+    save_artifact(options, artifact::PORTS_FILE, &subdomains);
+
+    for subdomain in &subdomains {
+        options.report(&subdomain.name);
+        for port in &subdomain.open_ports {
+            options.report(format!("\t{}", port));
+        }
+    }
+
+    subdomains
+}
 
-    for module in http_mods {
-        println!("\t{}: {}", module.name(), module.description());
+/// Register on the OOB server, or start the built-in listener, for modules confirming blind
+/// issues
+async fn start_oob(options: &ScanOptions) -> Option<Arc<Oob>> {
+    match (&options.oob_server, &options.oob_domain) {
+        (Some(server), _) => match Oob::connect(server, options.oob_token.as_deref()).await {
+            Ok(oob) => Some(Arc::new(oob)),
+            Err(e) => {
+                log::error!("Failed to register on OOB server {}: {}", server, e);
+                None
+            }
+        },
+        (None, Some(domain)) => {
+            let config = ListenerConfig {
+                domain: domain.clone(),
+                http_addr: SocketAddr::from(([0, 0, 0, 0], OOB_HTTP_PORT)),
+                dns_addr: SocketAddr::from(([0, 0, 0, 0], OOB_DNS_PORT)),
+                public_ip: options.oob_public_ip,
+            };
+
+            match Oob::listen(&config).await {
+                Ok(oob) => Some(Arc::new(oob)),
+                Err(e) => {
+                    log::error!("Failed to start OOB listener for {}: {}", domain, e);
+                    None
+                }
+            }
+        }
+        (None, None) => None,
     }
 }
 
-async fn is_resolvable(resolver: &TokioResolver, domain: &str) -> bool {
-    resolver.lookup_ip(domain).await.is_ok()
+/// The client of the web checks, pinning every host to the address resolved for it, so module
+/// requests skip DNS lookups and reuse pooled connections
+fn build_http_client(subdomains: &[Domain], options: &ScanOptions, config: &Config) -> Client {
+    subdomains
+        .iter()
+        .fold(Client::builder(), |builder, domain| {
+            builder.resolve(&domain.name, SocketAddr::new(domain.ip, 0))
+        })
+        .timeout(config.http.timeout())
+        .pool_max_idle_per_host(config.http.pool_max_idle_per_host)
+        .pool_idle_timeout(config.http.pool_idle_timeout())
+        .tcp_keepalive(config.http.tcp_keepalive())
+        .default_headers(options.headers.clone())
+        .danger_accept_invalid_certs(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Historical URLs of `domains`, feeding parameter mining, and interesting paths when requested
+async fn fetch_archived_urls(domains: &[&str], options: &ScanOptions) -> Vec<Url> {
+    let mut archived_urls = Vec::new();
+    for domain in domains {
+        match subdomain::archived_urls(domain, !options.archived_paths).await {
+            Ok(urls) => archived_urls.extend(urls),
+            Err(e) => log::error!("Failed to query archived URLs of {}: {}", domain, e),
+        }
+    }
+    archived_urls
 }
 
-async fn scan_top100_ports(domain: &str) -> Vec<u16> {
-    const TOP_100_PORTS: &[u16] = &[
-        80, 23, 443, 21, 22, 25, 3389, 110, 445, 139, 143, 53, 135, 3306, 8080, 1723, 111, 995,
-        993, 5900, 1025, 587, 8888, 199, 1720, 465, 548, 113, 81, 6001, 10000, 514, 5060, 179,
-        1026, 2000, 8443, 8000, 32768, 554, 26, 1433, 49152, 2001, 515, 8008, 49154, 1027, 5666,
-        646, 5000, 5631, 631, 49153, 8081, 2049, 88, 79, 5800, 106, 2121, 1110, 49155, 6000, 513,
-        990, 5357, 427, 49156, 543, 544, 5101, 144, 7, 389, 8009, 3128, 444, 9999, 5009, 7070,
-        5190, 3000, 5432, 1900, 3986, 13, 1029, 9, 5051, 6646, 49157, 1028, 873, 1755, 2717, 4899,
-        9100, 119, 37,
-    ];
+/// Secrets archived by web.archive.org, which stay exploitable after the site stopped serving
+/// them
+async fn archived_exposure_checks(domains: &[&str], options: &ScanOptions) -> Checks {
+    if !options.selection.allows_module("subdomain/webarchive") {
+        return Checks::default();
+    }
 
-    async fn is_port_open(socket_addr: SocketAddr) -> bool {
-        let timeout = Duration::from_secs(3);
-        let connection = tokio::time::timeout(timeout, TcpStream::connect(&socket_addr));
-        matches!(connection.await, Ok(Ok(_stream)))
+    let mut findings = Vec::new();
+    for domain in domains {
+        match subdomain::archived_exposures(domain).await {
+            Ok(exposures) => findings.extend(exposures),
+            Err(e) => log::error!("Failed to query archived files of {}: {}", domain, e),
+        }
     }
 
-    // Resolve domain to socket address
-    // - Port 1337 is a dummy port in order to satisfy the `SocketAddr` type
-    let socket_addr = lookup_host(format!("{}:1337", domain))
-        .await
-        .expect("DNS lookup failed")
-        .next()
-        .expect("No IP address resolved");
-
-    // Probe top 100 ports
-    let mut open_ports: Vec<u16> = stream::iter(TOP_100_PORTS.iter().copied())
-        .map(|port| {
-            let socket_addr = SocketAddr::new(socket_addr.ip(), port);
+    Checks {
+        findings,
+        modules: vec![String::from("subdomain/webarchive")],
+    }
+}
+
+/// The open ports answering HTTP or HTTPS, their schemes, technologies and soft 404 pages
+/// detected once rather than by every module again
+/// - Live endpoints are listed whether or not anything is found on them
+async fn detect_endpoints(
+    http_client: &Client,
+    limiter: &Limiter,
+    http_options: &HttpOptions,
+    subdomains: &[Domain],
+    options: &ScanOptions,
+) -> Vec<ScanContext> {
+    let ports = subdomains
+        .iter()
+        .flat_map(|domain| domain.open_ports.iter().map(move |port| (domain, *port)));
+    let contexts: Vec<ScanContext> = stream::iter(ports)
+        .map(|(domain, port)| {
+            ScanContext::detect(
+                http_client,
+                limiter,
+                http_options,
+                &domain.name,
+                domain.ip,
+                port,
+            )
+        })
+        .buffered(VULNERABILITY_CONCURRENCY)
+        .collect()
+        .await;
+
+    // Whatever their number, only ports answering HTTP or HTTPS are web endpoints
+    let open_ports = contexts.len();
+    let contexts: Vec<ScanContext> = contexts
+        .into_iter()
+        .filter(|context| context.scheme.is_some())
+        .collect();
+    options.report(format!(
+        "{} of {} open ports speak HTTP",
+        contexts.len(),
+        open_ports
+    ));
+
+    let live: Vec<_> = contexts
+        .iter()
+        .filter_map(|context| Some((context, context.page.as_ref()?)))
+        .collect();
+    if !live.is_empty() {
+        options.report(Message::new("live-endpoints").arg("count", live.len()));
+        for (context, page) in live {
+            let url = context.url(context.scheme.unwrap_or("http"), "/");
+            options.report(format!("\t{} {}", url, page));
+            events::emit(Event::Endpoint { url: &url, page });
+        }
+    }
+
+    contexts
+}
+
+/// The endpoints, each followed by its interesting archived directories with `--archived-paths`,
+/// scanned as `host:port/path` endpoints
+fn with_archived_paths(
+    contexts: Vec<ScanContext>,
+    archived_urls: &[Url],
+    options: &ScanOptions,
+) -> Vec<ScanContext> {
+    let mut archived_paths = if options.archived_paths {
+        subdomain::interesting_paths(archived_urls)
+    } else {
+        HashMap::new()
+    };
+
+    contexts
+        .into_iter()
+        .flat_map(|context| {
+            let paths = archived_paths.remove(&context.endpoint).unwrap_or_default();
+            let with_paths = paths
+                .iter()
+                .map(|path| context.with_base_path(path))
+                .collect::<Vec<_>>();
+            std::iter::once(context).chain(with_paths)
+        })
+        .collect()
+}
+
+/// Parameters of each root endpoint, for the injection modules
+async fn mine_parameters(
+    http_client: &Client,
+    endpoints: &[ScanContext],
+    archived_urls: &[Url],
+    options: &ScanOptions,
+) -> Vec<(ScanContext, Vec<ParameterTarget>)> {
+    log::info!("Starting parameter mining");
+
+    let roots = endpoints.iter().filter(|context| context.is_root());
+    let parameters: Vec<(ScanContext, Vec<ParameterTarget>)> = stream::iter(roots)
+        .map(|context| {
+            let http_client = http_client.clone();
             async move {
-                let is_open = is_port_open(socket_addr).await;
-                if is_open { Some(port) } else { None }
+                let targets = parameters::mine(&http_client, context, archived_urls).await;
+                (context.clone(), targets)
             }
         })
-        .buffer_unordered(1)
-        .filter_map(future::ready) // drop None values
+        .buffer_unordered(VULNERABILITY_CONCURRENCY)
         .collect()
         .await;
 
-    open_ports.sort_unstable();
+    options.report(format!(
+        "{} parameters were mined for injection",
+        parameters
+            .iter()
+            .map(|(_, targets)| targets.len())
+            .sum::<usize>()
+    ));
+
+    parameters
+}
+
+/// Run the web modules on every endpoint, and the injection modules on the mined parameters,
+/// through the task queue
+async fn web_checks(
+    http_client: &Client,
+    endpoints: &[ScanContext],
+    parameters: &[(ScanContext, Vec<ParameterTarget>)],
+    oob: Option<Arc<Oob>>,
+    target_name: &str,
+    options: &ScanOptions,
+    watchdog: &Arc<Watchdog>,
+) -> Checks {
+    let modules = http_modules(&options.selection, oob, options.aggressive);
+    let injection_modules = parameter_modules(&options.selection, options.aggressive);
+    // Modules sending no request in this configuration checked nothing
+    let checked = modules
+        .iter()
+        .filter(|module| module.max_requests() > 0)
+        .map(|module| module.name())
+        .chain(
+            injection_modules
+                .iter()
+                .filter(|module| module.max_requests() > 0)
+                .map(|module| module.name()),
+        )
+        .collect();
+
+    let queue = TaskQueue::new(modules, options.workers, options.checkpoint.as_deref())
+        .with_parameter_modules(injection_modules);
+    let findings = Arc::new(queue)
+        .run(target_name, http_client, endpoints, parameters, watchdog)
+        .await;
+
+    Checks {
+        findings,
+        modules: checked,
+    }
+}
+
+/// Findings confirmed by out-of-band callbacks, waited for before closing `oob`
+async fn oob_callbacks(oob: &Oob) -> Vec<Finding> {
+    log::info!(
+        "Waiting {} seconds for out-of-band callbacks",
+        OOB_GRACE_PERIOD.as_secs()
+    );
+    tokio::time::sleep(OOB_GRACE_PERIOD).await;
+
+    let findings = oob.poll().await.unwrap_or_else(|e| {
+        log::error!("Failed to poll OOB server: {}", e);
+        Vec::new()
+    });
 
-    open_ports
+    oob.close().await;
+    findings
+}
+
+/// Non-HTTP services on the open ports they speak
+async fn service_checks(
+    subdomains: &[Domain],
+    options: &ScanOptions,
+    watchdog: &Watchdog,
+) -> Checks {
+    log::info!("Starting service checks");
+
+    let modules = service_modules(&options.selection);
+
+    // UDP ports are invisible to the port scan, they are probed once per address
+    let mut probed_udp = HashSet::new();
+    let tasks: Vec<_> = subdomains
+        .iter()
+        .flat_map(|subdomain| {
+            modules.iter().flat_map(move |module| {
+                module
+                    .ports()
+                    .iter()
+                    .filter(move |port| module.udp() || subdomain.open_ports.contains(port))
+                    .map(move |port| (module, subdomain, *port))
+            })
+        })
+        .filter(|(module, subdomain, port)| {
+            !module.udp() || probed_udp.insert((subdomain.ip, *port))
+        })
+        .collect();
+
+    let findings: Vec<Finding> = stream::iter(tasks)
+        .map(|(module, subdomain, port)| async move {
+            let target = format!("{}:{}", subdomain.name, port);
+            let execution = module.probe(&subdomain.name, subdomain.ip, port);
+            match watchdog.guard(&module.name(), &target, execution).await {
+                None => Vec::new(),
+                Some(Ok(findings)) => findings,
+                Some(Err(e)) => {
+                    log::debug!(
+                        "{}: Failed to probe {}:{}: {}",
+                        module.name(),
+                        subdomain.name,
+                        port,
+                        e
+                    );
+                    Vec::new()
+                }
+            }
+        })
+        .buffer_unordered(SERVICE_CONCURRENCY)
+        .flat_map(stream::iter)
+        .collect()
+        .await;
+
+    log::info!("Service checks finished");
+
+    Checks {
+        findings,
+        modules: modules.iter().map(|module| module.name()).collect(),
+    }
+}
+
+/// Handshake-level checks of TLS implementations, skipped unless enabled
+async fn tls_checks(subdomains: &[Domain], options: &ScanOptions, watchdog: &Watchdog) -> Checks {
+    let modules: Vec<_> = tls_modules(&options.selection, options.aggressive)
+        .into_iter()
+        .filter(|module| module.max_connections() > 0)
+        .collect();
+    if modules.is_empty() {
+        return Checks::default();
+    }
+
+    log::info!("Starting TLS checks");
+
+    let tasks_iter = subdomains.iter().flat_map(|subdomain| {
+        modules.iter().flat_map(move |module| {
+            subdomain
+                .open_ports
+                .iter()
+                .map(move |port| (module, subdomain, *port))
+        })
+    });
+
+    // Ports without TLS fail the handshake
+    let findings: Vec<Finding> = stream::iter(tasks_iter)
+        .map(|(module, subdomain, port)| async move {
+            let target = format!("{}:{}", subdomain.name, port);
+            let execution = module.check(&subdomain.name, subdomain.ip, port);
+            match watchdog.guard(&module.name(), &target, execution).await {
+                None => None,
+                Some(Ok(finding)) => finding,
+                Some(Err(e)) => {
+                    log::debug!(
+                        "{}: Failed to check {}:{}: {}",
+                        module.name(),
+                        subdomain.name,
+                        port,
+                        e
+                    );
+                    None
+                }
+            }
+        })
+        .buffer_unordered(TLS_CONCURRENCY)
+        .filter_map(future::ready)
+        .collect()
+        .await;
+
+    log::info!("TLS checks finished");
+
+    Checks {
+        findings,
+        modules: modules.iter().map(|module| module.name()).collect(),
+    }
+}
+
+/// DNS configuration checks of the enumerated domains
+async fn dns_checks(
+    domains: &[&str],
+    resolver: &TokioResolver,
+    options: &ScanOptions,
+    watchdog: &Watchdog,
+) -> Checks {
+    log::info!("Starting DNS configuration checks");
+
+    let modules = dns_modules(&options.selection);
+    let tasks_iter = domains
+        .iter()
+        .flat_map(|domain| modules.iter().map(move |module| (module, *domain)));
+
+    let findings: Vec<Finding> = stream::iter(tasks_iter)
+        .map(|(module, domain)| async move {
+            let execution = module.check(resolver, domain);
+            match watchdog.guard(&module.name(), domain, execution).await {
+                None => Vec::new(),
+                Some(Ok(findings)) => findings,
+                Some(Err(e)) => {
+                    log::error!("{}: Failed to check {}: {}", module.name(), domain, e);
+                    Vec::new()
+                }
+            }
+        })
+        .buffer_unordered(SUBDOMAIN_CONCURRENCY)
+        .flat_map(stream::iter)
+        .collect()
+        .await;
+
+    log::info!("DNS configuration checks finished");
+
+    Checks {
+        findings,
+        modules: modules.iter().map(|module| module.name()).collect(),
+    }
+}
+
+/// Public code mentioning the domains, searched one domain at a time for rate limits
+async fn leak_searches(
+    domains: &[&str],
+    api_keys: &ApiKeys,
+    options: &ScanOptions,
+    config: &Config,
+    watchdog: &Watchdog,
+) -> Checks {
+    log::info!("Starting leak searches");
+
+    let modules = leak_modules(&options.selection, api_keys);
+    let search_client = Client::builder()
+        .timeout(config.http.timeout())
+        .build()
+        .expect("Failed to build HTTP client");
+
+    let mut findings = Vec::new();
+    for domain in domains {
+        for module in &modules {
+            let execution = module.search(&search_client, domain);
+            match watchdog.guard(&module.name(), domain, execution).await {
+                None => {}
+                Some(Ok(leaks)) => findings.extend(leaks),
+                Some(Err(e)) => {
+                    log::error!("{}: Failed to search {}: {}", module.name(), domain, e)
+                }
+            }
+        }
+    }
+
+    log::info!("Leak searches finished");
+
+    Checks {
+        findings,
+        modules: modules
+            .iter()
+            .filter(|module| module.max_requests() > 0)
+            .map(|module| module.name())
+            .collect(),
+    }
+}
+
+/// Bucket names derived from the domains and the CNAME records of their subdomains
+async fn bucket_names(
+    domains: &[&str],
+    subdomains: &[Domain],
+    resolver: &TokioResolver,
+    scheduler: &Scheduler,
+) -> BTreeSet<String> {
+    let cnames: Vec<(String, String)> = stream::iter(subdomains)
+        .map(|subdomain| async {
+            let _permit = scheduler
+                .dns
+                .acquire(&scheduler.target_of(&subdomain.name))
+                .await;
+            lookup_cname(resolver, &subdomain.name)
+                .await
+                .map(|cname| (subdomain.name.clone(), cname))
+        })
+        .buffer_unordered(DNS_CONCURRENCY)
+        .filter_map(future::ready)
+        .collect()
+        .await;
+
+    domains
+        .iter()
+        .flat_map(|domain| cloud::bucket_candidates(domain, &cnames))
+        .collect()
+}
+
+/// Cloud storage checks of the candidate bucket names
+async fn cloud_checks(
+    buckets: &BTreeSet<String>,
+    http_client: &Client,
+    limiter: &Limiter,
+    http_options: &HttpOptions,
+    options: &ScanOptions,
+    watchdog: &Watchdog,
+) -> Checks {
+    log::info!("Starting cloud storage scanning");
+
+    let modules = cloud_modules(&options.selection);
+    let tasks_iter = buckets
+        .iter()
+        .flat_map(|bucket| modules.iter().map(move |module| (module, bucket)));
+
+    let findings: Vec<_> = stream::iter(tasks_iter)
+        .map(|(module, bucket)| async move {
+            let execution = module.check(http_client, limiter, http_options, bucket);
+            watchdog.guard(&module.name(), bucket, execution).await
+        })
+        .buffer_unordered(CLOUD_CONCURRENCY)
+        .filter_map(|check_result| async move {
+            match check_result? {
+                Ok(finding) => finding,
+                Err(err) => {
+                    log::debug!("Error: {}", err);
+                    None
+                }
+            }
+        })
+        .collect()
+        .await;
+
+    log::info!("Cloud storage scanning finished");
+
+    Checks {
+        findings,
+        modules: modules.iter().map(|module| module.name()).collect(),
+    }
+}
+
+/// Write a report file, encrypted and signed as configured
+/// - Failures are logged rather than failing the scan, whose findings are still reported
+fn write_report(
+    options: &ScanOptions,
+    signing_key: Option<&SigningKey>,
+    name: &str,
+    path: &Path,
+    content: impl AsRef<[u8]>,
+) {
+    let written = encrypt::write(path, content, &options.encrypt_to)
+        .and_then(|path| sign(&path, signing_key));
+    if let Err(e) = written {
+        log::error!("Failed to write {} to {}: {:#}", name, path.display(), e);
+    }
+}
+
+/// Print what the scan could not cover, which may hide findings
+fn report_gaps(options: &ScanOptions, limiter: &Limiter, prober: Prober, watchdog: &Watchdog) {
+    // Endpoints the circuit breaker gave up on
+    let unreachable = limiter.unreachable_endpoints();
+    if !unreachable.is_empty() {
+        options.report(Message::new("unreachable-endpoints").arg("count", unreachable.len()));
+        for endpoint in unreachable {
+            options.report(format!("\t{}", endpoint));
+        }
+    }
+
+    // Dead hosts may only drop pings, `--skip-ping` scans them anyway
+    let dead = prober.dead.into_inner().expect("Dead hosts poisoned");
+    if !dead.is_empty() {
+        options.report(Message::new("dead-hosts").arg("count", dead.len()));
+        for host in dead {
+            options.report(format!("\t{}", host));
+        }
+    }
+
+    // Hosts failing with other errors than closed or filtered ports were not scanned at all
+    let failed = prober.failed.into_inner().expect("Failed hosts poisoned");
+    if !failed.is_empty() {
+        options.report(Message::new("failed-hosts").arg("count", failed.len()));
+        for host in failed {
+            options.report(format!("\t{}", host));
+        }
+    }
+
+    // Pairs given up on by the watchdog
+    let timed_out = watchdog.timed_out();
+    if !timed_out.is_empty() {
+        options.report(Message::new("timed-out-modules").arg("count", timed_out.len()));
+        for pair in timed_out {
+            options.report(format!("\t{}", pair));
+        }
+    }
+}
+
+/// Stands in for the store in builds without the `postgres-store` feature, never connected
+#[cfg(not(feature = "postgres-store"))]
+type Store = std::convert::Infallible;
+
+/// The store of the team when `[store]` sets one
+#[cfg(feature = "postgres-store")]
+fn connect_store(runtime: &Runtime, config: &Config) -> Result<Option<Store>> {
+    config
+        .store
+        .url
+        .as_deref()
+        .map(|url| runtime.block_on(Store::connect(url)))
+        .transpose()
+}
+
+#[cfg(not(feature = "postgres-store"))]
+fn connect_store(_runtime: &Runtime, config: &Config) -> Result<Option<Store>> {
+    if config.store.url.is_some() {
+        bail!("[store] needs vulnscan built with --features postgres-store");
+    }
+    Ok(None)
+}
+
+/// The annotations of the team in the store when there is one, or the local ones
+#[cfg(feature = "postgres-store")]
+fn open_annotations(runtime: &Runtime, store: Option<&Store>) -> Result<Annotations> {
+    match store {
+        Some(store) => runtime.block_on(store.annotations()),
+        None => Annotations::open_default(),
+    }
+}
+
+#[cfg(not(feature = "postgres-store"))]
+fn open_annotations(_runtime: &Runtime, store: Option<&Store>) -> Result<Annotations> {
+    match store {
+        Some(never) => match *never {},
+        None => Annotations::open_default(),
+    }
+}
+
+/// Sign a written report when a signing key is configured
+fn sign(path: &Path, key: Option<&SigningKey>) -> Result<()> {
+    match key {
+        Some(key) => signing::sign(path, key),
+        None => Ok(()),
+    }
+}
+
+/// Check a report against its detached signature
+pub fn verify(
+    report: &Path,
+    signature: Option<&Path>,
+    public_key: Option<&Path>,
+    config: &Config,
+) -> Result<()> {
+    let public_key = public_key
+        .or(config.signing.public_key_file.as_deref())
+        .context("Give the public key with --public-key, or public_key_file in [signing]")?;
+    let signature = signature.map_or_else(|| signing::signature_path(report), Path::to_path_buf);
+
+    signing::verify(report, &signature, &signing::load_public_key(public_key)?)?;
+    println!("{} is signed by {}", report.display(), public_key.display());
+    Ok(())
+}
+
+/// Report lookalikes of `domain` which are registered, resolve or receive mail
+pub fn typosquat(domain: &str, format: OutputFormat) -> Result<()> {
+    let candidates = typosquat::permutations(domain)?;
+    let checked = candidates.len();
+    log::info!("Checking {} lookalike domains of {}", checked, domain);
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build Tokio runtime");
+
+    let resolver = TokioResolver::builder_with_config(
+        ResolverConfig::default(),
+        TokioConnectionProvider::default(),
+    )
+    .build();
+    let lookalikes = runtime.block_on(typosquat::check(&resolver, candidates));
+
+    match format {
+        OutputFormat::Text => {
+            println!(
+                "{}",
+                Message::new("typosquat-live")
+                    .arg("live", lookalikes.len())
+                    .arg("checked", checked)
+                    .arg("domain", domain.to_string())
+            );
+            for lookalike in &lookalikes {
+                println!("\t{}", lookalike);
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&lookalikes)?),
+    }
+
+    Ok(())
+}
+
+/// Re-issue the request a finding stored by `--har` was observed in, and tell if it reproduces
+///
+/// # Arguments
+/// * `har` - HAR file written by `scan --har`
+/// * `id` - The ID of the finding or its beginning, the stored findings being listed if `None`
+/// * `format` - How the outcome is printed
+pub fn replay(har: &Path, id: Option<&str>, format: OutputFormat) -> Result<()> {
+    let stored = replay::load(har)?;
+
+    let Some(id) = id else {
+        match format {
+            OutputFormat::Text => {
+                println!(
+                    "{}",
+                    Message::new("replay-findings").arg("count", stored.len())
+                );
+                for stored in &stored {
+                    let finding = &stored.finding;
+                    println!(
+                        "\t{} [{}] {}: {}",
+                        &finding.id[..finding.id.len().min(12)],
+                        finding.severity.label(),
+                        modules::title(&finding.kind),
+                        finding.url
+                    );
+                }
+            }
+            OutputFormat::Json => {
+                let findings: Vec<_> = stored.iter().map(|stored| &stored.finding).collect();
+                println!("{}", serde_json::to_string_pretty(&findings)?);
+            }
+        }
+        return Ok(());
+    };
+    let stored = replay::find(&stored, id)?;
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build Tokio runtime");
+
+    // Sent like the scan sent it
+    let http_client = Client::builder()
+        .timeout(Duration::from_secs(30))
+        .danger_accept_invalid_certs(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let (status, body) = runtime.block_on(replay::replay(&http_client, stored))?;
+    let outcome = replay::compare(stored, status, &body);
+
+    match format {
+        OutputFormat::Text => {
+            let finding = &stored.finding;
+            println!(
+                "[{}] {}: {}",
+                finding.severity.label(),
+                modules::title(&finding.kind),
+                finding.url
+            );
+            println!(
+                "\t{}",
+                Message::new("replay-recorded")
+                    .arg("status", stored.status)
+                    .arg("length", stored.body.len())
+            );
+            println!(
+                "\t{}",
+                Message::new("replay-current")
+                    .arg("status", status)
+                    .arg("length", body.len())
+            );
+            println!(
+                "{}",
+                text(match outcome {
+                    Outcome::Reproduced => "replay-reproduced",
+                    Outcome::Changed => "replay-changed",
+                    Outcome::NotReproduced => "replay-not-reproduced",
+                })
+            );
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "finding": stored.finding,
+                "recorded": { "status": stored.status, "length": stored.body.len() },
+                "current": { "status": status, "length": body.len() },
+                "outcome": outcome,
+            }))?
+        ),
+    }
+
+    Ok(())
+}
+
+/// Triage a finding for later reports, or list the annotated findings if `id` is `None`
+/// - Annotations are shared through the store when `[store]` sets one, and kept locally otherwise
+///
+/// # Arguments
+/// * `id` - The ID of the finding, as in finding events and HAR files
+/// * `status` - How the finding was triaged
+/// * `note` - Free text kept along, an empty note removing the previous one
+/// * `clear` - Forget the annotation of the finding instead
+/// * `config` - Settings from the configuration file
+pub fn annotate(
+    id: Option<&str>,
+    status: Option<TriageStatus>,
+    note: Option<&str>,
+    clear: bool,
+    config: &Config,
+) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build Tokio runtime");
+    let store = connect_store(&runtime, config)?;
+    let mut annotations = match &store {
+        Some(_) => open_annotations(&runtime, store.as_ref())?,
+        None => Annotations::open(&Annotations::default_path()?)?,
+    };
+
+    let Some(id) = id else {
+        let count = annotations.iter().count();
+        println!("{}", Message::new("annotations-title").arg("count", count));
+        for (id, annotation) in annotations.iter() {
+            println!("\t{} {} ({})", id, annotation, annotation.updated_at);
+        }
+        return Ok(());
+    };
+
+    let message = if clear {
+        if !annotations.remove(id) {
+            bail!("No annotation of {}", id);
+        }
+        Message::new("annotation-removed").arg("id", id.to_string())
+    } else {
+        let annotation = annotations.annotate(id, status, note)?.to_string();
+        Message::new("annotation-saved")
+            .arg("id", id.to_string())
+            .arg("annotation", annotation)
+    };
+
+    match &store {
+        #[cfg(feature = "postgres-store")]
+        Some(store) => {
+            let id = id.to_lowercase();
+            match annotations.get(&id) {
+                Some(annotation) => runtime.block_on(store.save_annotation(&id, annotation))?,
+                None => {
+                    runtime.block_on(store.remove_annotation(&id))?;
+                }
+            }
+        }
+        #[cfg(not(feature = "postgres-store"))]
+        Some(never) => match *never {},
+        None => annotations.save()?,
+    }
+    println!("{}", message);
+    Ok(())
+}
+
+/// List the latest scans recorded in the store, with how many of their findings were new
+///
+/// # Arguments
+/// * `limit` - How many scans to list
+/// * `format` - How the scans are printed
+/// * `config` - Settings from the configuration file, `[store]` setting the store
+#[cfg(feature = "postgres-store")]
+pub fn history(limit: i64, format: OutputFormat, config: &Config) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build Tokio runtime");
+    let store =
+        connect_store(&runtime, config)?.context("No store configured, set url in [store]")?;
+    let runs = runtime.block_on(store.runs(limit))?;
+
+    match format {
+        OutputFormat::Text => {
+            println!("{}", Message::new("history-title").arg("count", runs.len()));
+            for run in &runs {
+                let finished = run
+                    .finished_at
+                    .clone()
+                    .unwrap_or_else(|| text("history-unfinished"));
+                println!(
+                    "\t{}",
+                    Message::new("history-run")
+                        .arg("run", run.id)
+                        .arg("targets", run.targets.clone())
+                        .arg("operator", run.operator.clone())
+                        .arg("started", run.started_at.clone())
+                        .arg("finished", finished)
+                        .arg("count", run.findings)
+                        .arg("new", run.new)
+                );
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&runs)?),
+    }
+    Ok(())
+}
+
+/// Rerun the module of each finding of a previous scan on the endpoint it was found on, and tell
+/// which findings were remediated
+///
+/// # Arguments
+/// * `report` - Events written by `scan --output` or `--format jsonl`
+/// * `aggressive` - Rerun modules with disruptive checks
+/// * `i_am_authorized` - Skip the authorization check required by the configuration
+/// * `format` - How the remediation status is printed
+pub fn retest(
+    report: &Path,
+    aggressive: bool,
+    i_am_authorized: bool,
+    format: OutputFormat,
+    config: &Config,
+) -> Result<()> {
+    let findings = retest::load(report)?;
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build Tokio runtime");
+
+    if config.authorization.required && !i_am_authorized {
+        let hosts: BTreeSet<String> = findings
+            .iter()
+            .filter_map(|finding| Some(Url::parse(&finding.url).ok()?.host_str()?.to_string()))
+            .collect();
+        for host in &hosts {
+            runtime.block_on(authorization::verify(
+                host,
+                config.authorization.token.as_deref(),
+            ))?;
+        }
+    }
+
+    let http_client = Client::builder()
+        .timeout(config.http.timeout())
+        .danger_accept_invalid_certs(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let count = findings.len();
+    let mut retested = runtime.block_on(retest::retest(&http_client, findings, aggressive));
+    let store = connect_store(&runtime, config)?;
+    let annotations = open_annotations(&runtime, store.as_ref())?;
+    for retested in &mut retested {
+        let id =
+            retested.finding.id.clone().unwrap_or_else(|| {
+                export::finding_id(&retested.finding.kind, &retested.finding.url)
+            });
+        retested.annotation = annotations.get(&id).cloned();
+    }
+
+    let count_of = |wanted: fn(&Status) -> bool| {
+        retested
+            .iter()
+            .filter(|retested| wanted(&retested.status))
+            .count()
+    };
+    let present = count_of(|status| *status == Status::StillPresent);
+    let remediated = count_of(|status| *status == Status::Remediated);
+    let not_retested = count_of(|status| matches!(status, Status::NotRetested { .. }));
+
+    match format {
+        OutputFormat::Text => {
+            println!(
+                "{}",
+                Message::new("retest-title")
+                    .arg("count", count)
+                    .arg("report", report.display().to_string())
+            );
+            for retested in &retested {
+                let finding = &retested.finding;
+                println!(
+                    "[{}] {}: {}",
+                    finding.severity.label(),
+                    modules::title(&finding.kind),
+                    finding.url
+                );
+                let status = match &retested.status {
+                    Status::StillPresent => text("retest-still-present"),
+                    Status::Remediated => text("retest-remediated"),
+                    Status::NotRetested { reason } => Message::new("retest-not-retested")
+                        .arg("reason", reason.clone())
+                        .to_string(),
+                };
+                println!("\t{}", status);
+                if let Some(annotation) = &retested.annotation {
+                    println!(
+                        "\t{}",
+                        Message::new("annotation").arg("annotation", annotation.to_string())
+                    );
+                }
+            }
+            println!(
+                "{}",
+                Message::new("retest-summary")
+                    .arg("present", present)
+                    .arg("remediated", remediated)
+                    .arg("unverified", not_retested)
+            );
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "report": report,
+                "findings": retested,
+                "still_present": present,
+                "remediated": remediated,
+                "not_retested": not_retested,
+            }))?
+        ),
+    }
+
+    Ok(())
+}
+
+/// Find domains sharing analytics IDs, the favicon or the registrant of `domain`
+/// - Candidates are only listed, scanning them is left to whoever confirms they are in scope
+pub fn related(domain: &str, format: OutputFormat, config: &Config) -> Result<()> {
+    let api_keys = ApiKeys::load(config)?;
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build Tokio runtime");
+
+    let http_client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+
+    let (pivots, candidates) = runtime.block_on(async {
+        let pivots = related::pivots(&http_client, domain).await;
+        let candidates = related::candidates(&http_client, &api_keys, domain, &pivots).await;
+        (pivots, candidates)
+    });
+
+    match format {
+        OutputFormat::Text => {
+            println!(
+                "{}",
+                Message::new("related-pivots")
+                    .arg("domain", domain.to_string())
+                    .arg("count", pivots.len())
+            );
+            for pivot in &pivots {
+                println!("\t{}", pivot);
+            }
+            println!(
+                "{}",
+                Message::new("related-candidates").arg("count", candidates.len())
+            );
+            for candidate in &candidates {
+                let pivots: Vec<String> =
+                    candidate.pivots.iter().map(ToString::to_string).collect();
+                println!("\t{}: {}", candidate.domain, pivots.join(", "));
+            }
+            if !candidates.is_empty() {
+                println!("{}", text("related-not-scanned"));
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "domain": domain,
+                "pivots": pivots,
+                "candidates": candidates,
+            }))?
+        ),
+    }
+
+    Ok(())
+}
+
+/// Install newer detection data from `channel`, signed by the owner of `public_key` or by the
+/// release key
+pub fn update(channel: &str, public_key: Option<&Path>) -> Result<()> {
+    let key = match public_key {
+        Some(path) => signing::load_public_key(path)?,
+        None => detections::release_key(),
+    };
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build Tokio runtime");
+
+    let http_client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+
+    match runtime.block_on(detections::update(&http_client, channel, &key))? {
+        Update::Installed { from, to, path } => println!(
+            "Updated detection data from version {} to {}, installed to {}",
+            from,
+            to,
+            path.display()
+        ),
+        Update::UpToDate(version) => println!("Detection data version {} is up to date", version),
+    }
+    Ok(())
+}
+
+/// Cancel the module checks on a first Ctrl-C, so the findings so far are still reported
+async fn cancel_on_ctrl_c(token: CancellationToken) {
+    if tokio::signal::ctrl_c().await.is_err() {
+        return;
+    }
+    log::warn!("Interrupted, giving up on the remaining module checks (Ctrl-C again to abort)");
+    token.cancel();
+
+    if tokio::signal::ctrl_c().await.is_ok() {
+        std::process::exit(130);
+    }
+}
+
+/// Enumerate the domains and resolve the other targets, keeping the hosts with their address
+async fn discover(
+    targets: &[Target],
+    domains: &[&str],
+    options: &ScanOptions,
+    api_keys: &ApiKeys,
+    resolver: &TokioResolver,
+    scheduler: &Scheduler,
+) -> Vec<Host> {
+    // Resolutions of every domain share the DNS budget, so a large domain never starves the others
+    let mut subdomains: Vec<(String, IpAddr)> = stream::iter(domains)
+        .map(|domain| enumerate(domain, options, api_keys, resolver, scheduler))
+        .buffer_unordered(ENUMERATION_CONCURRENCY)
+        .flat_map(stream::iter)
+        .collect()
+        .await;
+
+    // Other targets are scanned as they are
+    let names: Vec<String> = targets
+        .iter()
+        .filter_map(Target::domain)
+        .filter(|_| options.skip_enumeration)
+        .filter(|name| in_scope(options, name))
+        .map(str::to_string)
+        .collect();
+    if !names.is_empty() {
+        let count = names.len();
+        let resolved = resolve_all(resolver, scheduler, stream::iter(names)).await;
+        options.report(format!(
+            "{} of {} domains were successfully resolved",
+            resolved.len(),
+            count
+        ));
+        subdomains.extend(resolved);
+    }
+
+    subdomains.extend(
+        targets
+            .iter()
+            .flat_map(Target::hosts)
+            .filter(|(host, _)| in_scope(options, host)),
+    );
+
+    // Targets may overlap, e.g. a network and one of its addresses
+    subdomains.sort();
+    subdomains.dedup_by(|a, b| a.0 == b.0);
+
+    subdomains
+        .into_iter()
+        .map(|(name, ip)| Host { name, ip })
+        .collect()
+}
+
+/// Enumerate the subdomains of `domain`, keeping the resolvable ones with their address
+async fn enumerate(
+    domain: &str,
+    options: &ScanOptions,
+    api_keys: &ApiKeys,
+    resolver: &TokioResolver,
+    scheduler: &Scheduler,
+) -> Vec<(String, IpAddr)> {
+    // Passive subdomain enumeration
+    log::trace!("Trying to enumerate subdomains for {}", domain);
+
+    let modules = subdomain_modules(options.crtsh_postgres, api_keys);
+    let derived_modules = derived_subdomain_modules();
+    let module_names: Vec<String> = modules
+        .iter()
+        .map(|module| module.name())
+        .chain(derived_modules.iter().map(|module| module.name()))
+        .collect();
+    assert!(
+        module_names.len() <= stats::MAX_SOURCES,
+        "Subdomain modules are attributed with the {} bits of a u64",
+        stats::MAX_SOURCES
+    );
+
+    // Sources send names through bounded channels as they find them, and new names go on to
+    // resolution through another, so that sources pause when resolution falls behind instead of
+    // collecting everything first
+    let (mut sender, receiver) = mpsc::channel(RESOLUTION_BUFFER);
+
+    let enumeration = async {
+        // Subdomain -> bits of the modules which found it
+        let mut attribution: HashMap<String, u64> = HashMap::new();
+        let mut out_of_scope = 0;
+
+        let (sources, found): (Vec<_>, Vec<_>) = modules
+            .iter()
+            .enumerate()
+            .map(|(i, module)| {
+                let (found, names) = mpsc::channel(SOURCE_BUFFER);
+                let source = async move {
+                    if let Err(e) = module.enumerate(domain, found).await {
+                        log::error!(
+                            "Failed to enumerate subdomains with {}: {}",
+                            module.name(),
+                            e
+                        );
+                    }
+                };
+                (source, names.map(move |name| (i, name)))
+            })
+            .unzip();
+        let sources = stream::iter(sources)
+            .buffer_unordered(SUBDOMAIN_CONCURRENCY)
+            .for_each(future::ready);
+
+        let mut found = stream::select_all(found);
+        let attribution_done = async {
+            while let Some((i, subdomain)) = found.next().await {
+                attribute(
+                    &mut attribution,
+                    &mut out_of_scope,
+                    &mut sender,
+                    options.scope.as_ref(),
+                    i,
+                    subdomain,
+                )
+                .await;
+            }
+        };
+        future::join(sources, attribution_done).await;
+
+        // Alterations of the passively found subdomains
+        for (i, module) in derived_modules.iter().enumerate() {
+            let known: HashSet<&str> = attribution.keys().map(String::as_str).collect();
+            let new_subdomains = match module.derive(domain, &known).await {
+                Ok(new_subdomains) => new_subdomains,
+                Err(e) => {
+                    log::error!("Failed to derive subdomains with: {}", e);
+                    Vec::new()
+                }
+            };
+            drop(known);
+
+            for subdomain in new_subdomains {
+                attribute(
+                    &mut attribution,
+                    &mut out_of_scope,
+                    &mut sender,
+                    options.scope.as_ref(),
+                    modules.len() + i,
+                    subdomain,
+                )
+                .await;
+            }
+        }
+
+        // Lets resolution finish once the remaining names are resolved
+        drop(sender);
+        (attribution, out_of_scope)
+    };
+
+    // Check if subdomains are resolvable
+    log::trace!("Trying to resolve discovered subdomains");
+
+    let ((attribution, dropped), subdomains) =
+        future::join(enumeration, resolve_all(resolver, scheduler, receiver)).await;
+
+    options.report(format!(
+        "{} subdomains of {} were found during the enumeration stage",
+        attribution.len(),
+        domain
+    ));
+    if dropped > 0 {
+        log::info!("{} subdomains were dropped as out of scope", dropped);
+    }
+
+    let stats = EnumerationStats::new(domain, &module_names, &attribution);
+    // Still saved for `modules --stats` when stdout carries findings only
+    if options.format == ScanFormat::Text {
+        stats.print();
+    }
+    if let Err(e) = stats.save() {
+        log::warn!("Failed to save enumeration statistics: {}", e);
+    }
+
+    options.report(format!(
+        "{} subdomains were successfully resolved",
+        subdomains.len()
+    ));
+
+    subdomains
+}
+
+/// Record that the module with index `module` found `subdomain`, handing it to resolution if new
+async fn attribute(
+    attribution: &mut HashMap<String, u64>,
+    out_of_scope: &mut usize,
+    sender: &mut mpsc::Sender<String>,
+    scope: Option<&Scope>,
+    module: usize,
+    subdomain: String,
+) {
+    if !scope.is_none_or(|scope| scope.contains(&subdomain)) {
+        *out_of_scope += 1;
+        return;
+    }
+
+    match attribution.entry(subdomain) {
+        Entry::Occupied(mut entry) => *entry.get_mut() |= 1 << module,
+        Entry::Vacant(entry) => {
+            let subdomain = entry.key().clone();
+            entry.insert(1 << module);
+            // Resolution only stops once the channel is closed
+            let _ = sender.send(subdomain).await;
+        }
+    }
+}
+
+/// Keep a stage artifact in `--artifacts`, if set
+fn save_artifact<T: Serialize>(options: &ScanOptions, name: &str, artifact: &T) {
+    let Some(dir) = &options.artifacts else {
+        return;
+    };
+
+    if let Err(e) = artifact::save(dir, name, artifact) {
+        log::warn!("Failed to save {}: {}", name, e);
+    }
+}
+
+fn in_scope(options: &ScanOptions, host: &str) -> bool {
+    options
+        .scope
+        .as_ref()
+        .is_none_or(|scope| scope.contains(host))
+}
+
+/// Print what a scan would do with the resolved subdomains, without probing them
+fn print_plan(
+    target_name: &str,
+    domains: &[&str],
+    hosts: &[Host],
+    options: &ScanOptions,
+    api_keys: &ApiKeys,
+) {
+    println!(
+        "{}",
+        Message::new("plan-title").arg("target", target_name.to_string())
+    );
+
+    println!("{}", Message::new("plan-hosts").arg("count", hosts.len()));
+    for host in hosts {
+        println!("\t{} ({})", host.name, host.ip);
+    }
+
+    let probed_ports = if options.ports_from.is_some() {
+        println!("{}", text("plan-ports-imported"));
+        0
+    } else if options.skip_port_scan {
+        println!(
+            "{}",
+            Message::new("plan-ports-assumed").arg("count", ASSUMED_PORTS.len())
+        );
+        let ports: Vec<String> = ASSUMED_PORTS.iter().map(u16::to_string).collect();
+        println!("\t{}", ports.join(", "));
+        0
+    } else {
+        println!(
+            "{}",
+            Message::new("plan-ports-probed").arg("count", TOP_100_PORTS.len())
+        );
+        let ports: Vec<String> = TOP_100_PORTS.iter().map(u16::to_string).collect();
+        println!("\t{}", ports.join(", "));
+        TOP_100_PORTS.len()
+    };
+
+    let print_module = |name: String, max_requests: usize| {
+        let message = if max_requests == 0 {
+            Message::new("plan-module-skipped")
+        } else {
+            Message::new("plan-module-requests").arg("count", max_requests)
+        };
+        println!("\t{}", message.arg("name", name));
+    };
+
+    // Intrusive modules are not even built without --aggressive
+    let held_back = modules::held_back(&options.selection, options.aggressive);
+    let print_held_back = |category: fn(&Build) -> bool| {
+        for registration in held_back.iter().filter(|registration| {
+            registration.intrusiveness == Intrusiveness::Intrusive && category(&registration.build)
+        }) {
+            print_module(registration.name.to_string(), 0);
+        }
+    };
+
+    // No OOB server is registered in dry runs, so modules relying on callbacks are skipped
+    let http_mods = http_modules(&options.selection, None, options.aggressive);
+    println!("{}", text("plan-http-modules"));
+    for module in &http_mods {
+        print_module(module.name(), module.max_requests());
+    }
+    print_held_back(|build| matches!(build, Build::Http(_)));
+
+    let parameter_mods = parameter_modules(&options.selection, options.aggressive);
+    println!(
+        "{}",
+        Message::new("plan-parameter-modules").arg("count", parameters::MAX_TARGETS)
+    );
+    for module in &parameter_mods {
+        print_module(module.name(), module.max_requests());
+    }
+    print_held_back(|build| matches!(build, Build::Parameter(_)));
+
+    let service_mods = service_modules(&options.selection);
+    for module in &service_mods {
+        let ports: Vec<String> = module.ports().iter().map(u16::to_string).collect();
+        let id = if module.udp() {
+            "plan-service-udp"
+        } else {
+            "plan-service-tcp"
+        };
+        println!(
+            "{}",
+            Message::new(id)
+                .arg("name", module.name())
+                .arg("ports", ports.join(", "))
+        );
+    }
+
+    let tls_mods = tls_modules(&options.selection, options.aggressive);
+    println!("{}", text("plan-tls-modules"));
+    for module in &tls_mods {
+        let connections = module.max_connections();
+        let message = if connections == 0 {
+            Message::new("plan-module-skipped")
+        } else {
+            Message::new("plan-module-connections").arg("count", connections)
+        };
+        println!("\t{}", message.arg("name", module.name()));
+    }
+    print_held_back(|build| matches!(build, Build::Tls(_)));
+
+    let dns_mods = dns_modules(&options.selection);
+    if !domains.is_empty() {
+        println!(
+            "{}",
+            Message::new("plan-dns-modules").arg("count", domains.len())
+        );
+        for module in &dns_mods {
+            println!("\t{}", module.name());
+        }
+    }
+
+    // Leak modules without a key are listed as skipped
+    let leak_mods = leak_modules(&options.selection, api_keys);
+    if !domains.is_empty() && !leak_mods.is_empty() {
+        println!(
+            "{}",
+            Message::new("plan-leak-modules").arg("count", domains.len())
+        );
+        for module in &leak_mods {
+            print_module(module.name(), module.max_requests() * domains.len());
+        }
+    }
+
+    // Bucket names are only derived from enumerated domains
+    let buckets: BTreeSet<String> = domains
+        .iter()
+        .flat_map(|domain| cloud::bucket_candidates(domain, &[]))
+        .collect();
+    let cloud_mods = cloud_modules(&options.selection);
+    if !buckets.is_empty() {
+        println!(
+            "{}",
+            Message::new("plan-cloud-modules").arg("count", buckets.len())
+        );
+        for module in &cloud_mods {
+            println!("\t{}", module.name());
+        }
+    }
+
+    let per_endpoint = http_mods
+        .iter()
+        .map(|module| module.max_requests())
+        .sum::<usize>()
+        + parameters::MAX_TARGETS
+            * parameter_mods
+                .iter()
+                .map(|module| module.max_requests())
+                .sum::<usize>();
+
+    println!(
+        "{}",
+        Message::new("plan-traffic")
+            .arg("probes", hosts.len() * probed_ports)
+            .arg("requests", per_endpoint)
+            .arg("checks", buckets.len() * cloud_mods.len())
+    );
+}
+
+/// How `modules` prints its output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// How `scan` prints its findings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanFormat {
+    Text,
+    /// One tab-separated `url kind severity` line per finding
+    Lines,
+    /// One JSON object per discovered host, open port, live endpoint and finding
+    Jsonl,
+}
+
+/// Metadata of a module, as printed by `modules --format json`
+#[derive(Serialize)]
+struct ModuleInfo {
+    name: String,
+    description: String,
+    category: &'static str,
+    severity: Severity,
+    references: Vec<&'static str>,
+    intrusive: bool,
+    intrusiveness: Intrusiveness,
+    tags: &'static [Tag],
+    requires: &'static [Dependency],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kb: Option<&'static kb::Entry>,
+}
+
+/// List available modules, or the subdomain statistics of the last scan
+pub fn modules(stats: bool, format: OutputFormat) -> Result<()> {
+    if stats {
+        let stats = EnumerationStats::load()?;
+
+        match (stats, format) {
+            (stats, OutputFormat::Json) => println!("{}", serde_json::to_string_pretty(&stats)?),
+            (Some(stats), OutputFormat::Text) => {
+                println!(
+                    "{}",
+                    Message::new("modules-stats-title")
+                        .arg("target", stats.target.clone())
+                        .arg("finished", stats.finished_at.clone())
+                        .arg("count", stats.total)
+                );
+                stats.print();
+            }
+            (None, OutputFormat::Text) => println!("{}", text("modules-no-stats")),
+        }
+        return Ok(());
+    }
+
+    let categories = modules::all();
+
+    match format {
+        OutputFormat::Text => {
+            for (category, modules) in &categories {
+                println!("{}", text(&format!("modules-{}", category)));
+
+                for module in modules {
+                    println!("\t{}: {}", module.name(), module.description());
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let infos: Vec<ModuleInfo> = categories
+                .iter()
+                .flat_map(|(category, modules)| {
+                    modules.iter().map(|module| ModuleInfo {
+                        name: module.name(),
+                        description: module.description().to_string(),
+                        category,
+                        severity: module.severity(),
+                        references: module.references(),
+                        intrusive: modules::intrusiveness(&module.name())
+                            == Intrusiveness::Intrusive,
+                        intrusiveness: modules::intrusiveness(&module.name()),
+                        tags: modules::tags(&module.name()),
+                        requires: modules::dependencies(&module.name()),
+                        kb: kb::get(&module.name()),
+                    })
+                })
+                .collect();
+
+            println!("{}", serde_json::to_string_pretty(&infos)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print what the check of `module` is about and how to fix its findings
+pub fn kb(module: &str, format: OutputFormat) -> Result<()> {
+    let Some(entry) = kb::get(module) else {
+        let known: Vec<&str> = kb::entries().map(|(module, _)| module).collect();
+        bail!(
+            "No knowledge base entry for {}, known modules are {}",
+            module,
+            known.join(", ")
+        );
+    };
+    let references = kb::references(module);
+
+    match format {
+        OutputFormat::Text => {
+            println!("{}", module);
+            println!(
+                "\t{}",
+                Message::new("kb-cwe")
+                    .arg("cwe", entry.cwe)
+                    .arg("url", entry.cwe_url())
+            );
+            println!(
+                "\t{}",
+                Message::new("kb-owasp").arg("owasp", entry.owasp.as_str())
+            );
+            if !entry.pci.is_empty() {
+                println!(
+                    "\t{}",
+                    Message::new("kb-pci").arg("requirements", entry.pci.join(", "))
+                );
+            }
+            println!("{}", text("kb-remediation"));
+            for (i, step) in entry.remediation.iter().enumerate() {
+                println!("\t{}. {}", i + 1, step);
+            }
+            if !references.is_empty() {
+                println!("{}", text("kb-references"));
+                for reference in references {
+                    println!("\t{}", reference);
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let mut value = serde_json::to_value(entry)?;
+            value["module"] = serde_json::json!(module);
+            value["references"] = serde_json::json!(references);
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Check every HTTP module against local known-vulnerable and known-clean fixtures
+pub fn selftest() -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build Tokio runtime");
+
+    let verdicts = runtime.block_on(selftest::run())?;
+
+    println!("HTTP Modules");
+
+    for (module, verdict) in &verdicts {
+        match verdict {
+            Verdict::Passed => println!("\t{}: passed", module),
+            Verdict::Skipped(reason) => println!("\t{}: skipped, {}", module, reason),
+            Verdict::Failed(reason) => println!("\t{}: FAILED, {}", module, reason),
+        }
+    }
+
+    let failed = verdicts
+        .iter()
+        .filter(|(_, verdict)| matches!(verdict, Verdict::Failed(_)))
+        .count();
+
+    if failed > 0 {
+        bail!("{} modules failed the self-test", failed);
+    }
+
+    Ok(())
+}
+
+/// Subcommands of `vulnscan keys`
+pub enum KeysCommand {
+    Set { source: String, key: Option<String> },
+    Remove { source: String },
+    List,
+}
+
+pub fn keys(command: KeysCommand, config: &Config) -> Result<()> {
+    match command {
+        KeysCommand::Set { source, key } => {
+            let mut store = unlock_key_store()?;
+            let key = match key {
+                Some(key) => key,
+                None => rpassword::prompt_password(format!("API key of {}: ", source))?,
+            };
+            store.set(&source, &key)?;
+            store.save()?;
+            println!("Stored the API key of {}", source);
+        }
+        KeysCommand::Remove { source } => {
+            let mut store = unlock_key_store()?;
+            if !store.remove(&source) {
+                bail!("No API key of {} in the key store", source);
+            }
+            store.save()?;
+            println!("Removed the API key of {}", source);
+        }
+        KeysCommand::List => {
+            let keys = ApiKeys::load(config)?;
+            if keys.iter().next().is_none() {
+                println!("No API keys, add some with `vulnscan keys set <source>`");
+            }
+            for (source, key, origin) in keys.iter() {
+                let origin = match origin {
+                    Origin::Config => "configuration",
+                    Origin::KeyStore => "key store",
+                };
+                println!("{}\t{}\t{}", source, mask(key), origin);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Open the key store with the passphrase of `VULNSCAN_KEYS_PASSPHRASE`, or prompt for it
+fn unlock_key_store() -> Result<KeyStore> {
+    let path = KeyStore::default_path()?;
+    let passphrase = match std::env::var(keys::PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => rpassword::prompt_password(format!(
+            "Passphrase of the key store at {}: ",
+            path.display()
+        ))?,
+    };
+    KeyStore::open(&path, &passphrase)
+}
+
+/// Enough of a key to tell keys apart
+fn mask(key: &str) -> String {
+    let shown: String = key.chars().take(4).collect();
+    format!(
+        "{}{}",
+        shown,
+        "*".repeat(key.chars().count().saturating_sub(4).min(12))
+    )
+}
+
+/// Print the findings of a stage, rated for the environment of their host
+/// - Text reports group findings with near-identical evidence, listing the other affected URLs
+///   under the first one
+fn report_findings<F: Reportable>(
+    findings: &[F],
+    environments: &Environments,
+    annotations: &Annotations,
+    format: ScanFormat,
+    records: &mut Vec<Record>,
+) {
+    let stage: Vec<Record> = findings
+        .iter()
+        .map(|finding| {
+            let mut record = environments.record(finding);
+            record.annotation = annotations.get(&record.dedup_key()).cloned();
+            record
+        })
+        .collect();
+
+    for record in &stage {
+        events::emit(Event::finding(record));
+    }
+    match format {
+        ScanFormat::Text => {
+            for members in cluster::cluster(&stage) {
+                print_record(members[0], format);
+                if members.len() > 1 {
+                    println!(
+                        "\t{}",
+                        Message::new("similar-findings").arg("count", members.len() - 1)
+                    );
+                    for record in &members[1..] {
+                        println!("\t\t{}", record.url);
+                    }
+                }
+            }
+        }
+        ScanFormat::Lines => {
+            for record in &stage {
+                print_record(record, format);
+            }
+        }
+        // Already streamed as finding events
+        ScanFormat::Jsonl => {}
+    }
+
+    records.extend(stage);
+}
+
+fn print_record(record: &Record, format: ScanFormat) {
+    match format {
+        ScanFormat::Text => {
+            println!("{}", record);
+            for evidence in &record.evidence {
+                println!("\t{}", evidence);
+            }
+            if let Some(annotation) = &record.annotation {
+                println!(
+                    "\t{}",
+                    Message::new("annotation").arg("annotation", annotation.to_string())
+                );
+            }
+            if let Some((module, entry)) = kb::for_finding(&record.kind, &record.url) {
+                println!(
+                    "\t{}",
+                    Message::new("kb-hint")
+                        .arg("cwe", entry.cwe)
+                        .arg("owasp", entry.owasp.as_str())
+                        .arg("module", module)
+                );
+            }
+        }
+        ScanFormat::Lines => println!("{}\t{}\t{:?}", record.url, record.kind, record.severity),
+        ScanFormat::Jsonl => {}
+    }
+}
+
+/// Replace git leakage findings with the file listing recovered from the leaked `.git/index`
+/// - Findings are kept as-is when reconstruction fails
+/// - Blob objects are only downloaded when `dump_dir` is given
+async fn exploit_git_findings(
+    http_client: &Client,
+    limiter: &Limiter,
+    findings: Vec<Finding>,
+    dump_dir: Option<&Path>,
+) -> Vec<Finding> {
+    // Leaked repository URL -> whether it was successfully reconstructed
+    let mut repositories: HashMap<String, bool> = HashMap::new();
+    let mut results = Vec::new();
+
+    for finding in findings {
+        let repo_url = match finding.kind.as_str() {
+            "GitConfigLeakage" | "GitHeadLeakage" => finding
+                .url
+                .rsplit_once('/')
+                .map(|(base, _)| format!("{}/", base)),
+            _ => None,
+        };
+
+        let Some(repo_url) = repo_url else {
+            results.push(finding);
+            continue;
+        };
+
+        let reconstructed = match repositories.get(&repo_url) {
+            Some(&reconstructed) => reconstructed,
+            None => {
+                log::info!("Trying to reconstruct git repository at {}", repo_url);
+
+                // Dump each repository into its own `<host>_<port>` directory
+                let dump_dir = dump_dir.map(|dir| {
+                    let url = Url::parse(&repo_url).expect("Finding URL should be valid");
+                    dir.join(format!(
+                        "{}_{}",
+                        url.host_str().unwrap_or_default(),
+                        url.port_or_known_default().unwrap_or_default()
+                    ))
+                });
+
+                let reconstructed = match git_reconstruction::reconstruct(
+                    http_client,
+                    limiter,
+                    &repo_url,
+                    dump_dir.as_deref(),
+                )
+                .await
+                {
+                    Ok(files) => {
+                        results.push(
+                            Finding::new(
+                                finding.module.clone(),
+                                "GitRepositoryExposure",
+                                Severity::High,
+                                &repo_url,
+                            )
+                            .with_evidence(files.clone())
+                            .with_metadata("files", &files),
+                        );
+                        true
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to reconstruct {}: {}", repo_url, e);
+                        false
+                    }
+                };
+
+                repositories.insert(repo_url, reconstructed);
+                reconstructed
+            }
+        };
+
+        if !reconstructed {
+            results.push(finding);
+        }
+    }
+
+    results
+}
+
+/// Keep the resolvable names, with the first address each resolves to
+/// Report hosts the system resolver and the DoH resolver disagree about
+async fn verify_resolution(hosts: &[Host], options: &ScanOptions) {
+    let verifier = match doh::Verifier::new() {
+        Ok(verifier) => verifier,
+        Err(e) => {
+            log::error!("Failed to set up the DoH resolver: {}", e);
+            return;
+        }
+    };
+
+    let verification = verifier.verify(hosts).await;
+    options.report(format!(
+        "{} of {} sampled hosts resolved the same over DoH",
+        verification.checked - verification.discrepancies.len(),
+        verification.checked
+    ));
+
+    for discrepancy in &verification.discrepancies {
+        options.report(format!("\t{}", discrepancy));
+    }
+
+    if !verification.discrepancies.is_empty() {
+        log::warn!(
+            "Resolution discrepancies hint at resolver poisoning or ISP interference, unless CDNs answer by location"
+        );
+    }
+}
+
+async fn resolve_all(
+    resolver: &TokioResolver,
+    scheduler: &Scheduler,
+    names: impl Stream<Item = String>,
+) -> Vec<(String, IpAddr)> {
+    names
+        .map(|name| async {
+            let _permit = scheduler.dns.acquire(&scheduler.target_of(&name)).await;
+            let ip = resolve(resolver, &name).await?;
+            Some((name, ip))
+        })
+        .buffer_unordered(DNS_CONCURRENCY)
+        .filter_map(future::ready)
+        .collect()
+        .await
+}
+
+/// In-scope subdomains of `domains` named by certificates of open ports, resolved and port scanned
+async fn harvest_certificates(
+    subdomains: &[Domain],
+    domains: &[&str],
+    options: &ScanOptions,
+    prober: &Prober<'_>,
+    resolver: &TokioResolver,
+    scheduler: &Scheduler,
+) -> Vec<Domain> {
+    log::info!("Harvesting host names from certificates");
+
+    let tasks_iter = subdomains.iter().flat_map(|subdomain| {
+        subdomain
+            .open_ports
+            .iter()
+            .map(move |port| (subdomain, *port))
+    });
+
+    // Ports without TLS fail the handshake
+    let names: BTreeSet<String> = stream::iter(tasks_iter)
+        .map(|(subdomain, port)| async move {
+            certificate::names(&subdomain.name, subdomain.ip, port)
+                .await
+                .unwrap_or_else(|e| {
+                    log::debug!(
+                        "Failed to fetch certificate of {}:{}: {}",
+                        subdomain.name,
+                        port,
+                        e
+                    );
+                    Vec::new()
+                })
+        })
+        .buffer_unordered(TLS_CONCURRENCY)
+        .flat_map(stream::iter)
+        .collect()
+        .await;
+
+    let known: HashSet<&str> = subdomains
+        .iter()
+        .map(|subdomain| subdomain.name.as_str())
+        .collect();
+    let candidates = names.into_iter().filter(|name| {
+        !known.contains(name.as_str())
+            && domains
+                .iter()
+                .any(|domain| name == domain || name.ends_with(&format!(".{}", domain)))
+            && in_scope(options, name)
+    });
+
+    // Second resolution pass, unresolvable names are internal or stale
+    let hosts = resolve_all(resolver, scheduler, stream::iter(candidates)).await;
+    log::info!("Found {} new hosts in certificates", hosts.len());
+
+    let hosts: Vec<Host> = hosts
+        .into_iter()
+        .map(|(name, ip)| Host { name, ip })
+        .collect();
+    if options.skip_port_scan {
+        return hosts
+            .into_iter()
+            .map(|host| Domain {
+                name: host.name,
+                ip: host.ip,
+                open_ports: ASSUMED_PORTS.to_vec(),
+            })
+            .collect();
+    }
+
+    probe_hosts(hosts, options, prober, resolver, scheduler).await
+}
+
+/// Keep the hosts answering a ping, recording the others as dead
+async fn ping_hosts(
+    hosts: Vec<Host>,
+    technique: PingTechnique,
+    options: &ScanOptions,
+    prober: &Prober<'_>,
+) -> Vec<Host> {
+    if hosts.is_empty() {
+        return hosts;
+    }
+
+    let count = hosts.len();
+    let pinged: Vec<(Host, bool)> = stream::iter(hosts)
+        .map(|host| async move {
+            let alive = ping::alive(host.ip, technique, prober.config.connect_timeout()).await;
+            (host, alive)
+        })
+        .buffer_unordered(prober.config.max_probes.max(1))
+        .collect()
+        .await;
+
+    let mut alive = Vec::new();
+    let mut dead = prober.dead.lock().expect("Dead hosts poisoned");
+    for (host, answered) in pinged {
+        if answered {
+            alive.push(host);
+        } else {
+            dead.insert(format!("{} ({})", host.name, host.ip));
+        }
+    }
+    options.report(format!(
+        "{} of {} hosts answered a ping",
+        alive.len(),
+        count
+    ));
+
+    alive
+}
+
+/// Probe the top 100 ports of every host, skipping the hosts whose probes fail
+/// - Hosts scanned within `--port-cache-ttl` reuse their cached ports
+async fn probe_hosts(
+    hosts: Vec<Host>,
+    options: &ScanOptions,
+    prober: &Prober<'_>,
+    resolver: &TokioResolver,
+    scheduler: &Scheduler,
+) -> Vec<Domain> {
+    let mut cache = match PortCache::default_path().and_then(|path| PortCache::open(&path)) {
+        Ok(cache) => Some(cache),
+        Err(e) => {
+            log::warn!("Failed to open the port cache: {:#}", e);
+            None
+        }
+    };
+
+    let mut cached = Vec::new();
+    let mut hosts_to_scan = Vec::new();
+    for host in hosts {
+        let hit = cache
+            .as_ref()
+            .zip(options.port_cache_ttl)
+            .and_then(|(cache, ttl)| cache.get(&host, ttl));
+        match hit {
+            Some(domain) => cached.push(domain),
+            None => hosts_to_scan.push(host),
+        }
+    }
+    if !cached.is_empty() {
+        options.report(format!(
+            "{} hosts reuse open ports from the port cache",
+            cached.len()
+        ));
+        cached.iter().for_each(emit_ports);
+    }
+
+    // Dead hosts would time out on every port
+    if let Some(technique) = prober.ping {
+        hosts_to_scan = ping_hosts(hosts_to_scan, technique, options, prober).await;
+    }
+
+    let results: Vec<(IpAddr, Option<Domain>)> =
+        stream::iter(scheduler.interleave(hosts_to_scan, |host| &host.name))
+            .map(|host| async move {
+                let target = scheduler.target_of(&host.name);
+                let addresses = addresses(resolver, scheduler, &target, &host).await;
+
+                let _permit = scheduler.ports.acquire(&target).await;
+                let domain = match scan_ports(&addresses, TOP_100_PORTS, prober).await {
+                    Ok((ip, open_ports)) => {
+                        let domain = Domain {
+                            name: host.name,
+                            ip,
+                            open_ports,
+                        };
+                        emit_ports(&domain);
+                        Some(domain)
+                    }
+                    Err(e) => {
+                        log::warn!("Skipping {} ({}): {:#}", host.name, host.ip, e);
+                        prober
+                            .failed
+                            .lock()
+                            .expect("Failed hosts poisoned")
+                            .insert(format!("{} ({}): {:#}", host.name, host.ip, e));
+                        None
+                    }
+                };
+                (host.ip, domain)
+            })
+            .buffer_unordered(prober.config.max_probes.max(1))
+            .collect()
+            .await;
+
+    // Failed hosts are not cached, so that they are scanned again
+    if let Some(cache) = &mut cache {
+        for (resolved, domain) in &results {
+            if let Some(domain) = domain {
+                cache.insert(*resolved, domain);
+            }
+        }
+        if let Err(e) = cache.save() {
+            log::warn!("Failed to save the port cache: {}", e);
+        }
+    }
+
+    cached
+        .into_iter()
+        .chain(results.into_iter().filter_map(|(_, domain)| domain))
+        .collect()
+}
+
+/// The addresses of `host` to probe, its IPv6 address first when it has both (RFC 8305)
+async fn addresses(
+    resolver: &TokioResolver,
+    scheduler: &Scheduler,
+    target: &str,
+    host: &Host,
+) -> Vec<IpAddr> {
+    // Hosts of IP targets, and hosts only resolving to IPv6
+    if host.ip.is_ipv6() || host.name.parse::<IpAddr>().is_ok() {
+        return vec![host.ip];
+    }
+
+    let _permit = scheduler.dns.acquire(target).await;
+    match resolver.ipv6_lookup(host.name.as_str()).await {
+        Ok(lookup) => lookup
+            .iter()
+            .take(1)
+            .map(|aaaa| IpAddr::V6(aaaa.0))
+            .chain(std::iter::once(host.ip))
+            .collect(),
+        Err(_) => vec![host.ip],
+    }
+}
+
+/// The first address `domain` resolves to
+async fn resolve(resolver: &TokioResolver, domain: &str) -> Option<IpAddr> {
+    resolver.lookup_ip(domain).await.ok()?.iter().next()
+}
+
+async fn lookup_cname(resolver: &TokioResolver, domain: &str) -> Option<String> {
+    let lookup = resolver.lookup(domain, RecordType::CNAME).await.ok()?;
+    lookup.record_iter().find_map(|record| match record.data() {
+        RData::CNAME(cname) => Some(cname.to_string()),
+        _ => None,
+    })
+}
+
+/// Stream the open ports of a host, as soon as they are known
+fn emit_ports(domain: &Domain) {
+    for &port in &domain.open_ports {
+        events::emit(Event::Port {
+            host: &domain.name,
+            ip: domain.ip,
+            port,
+        });
+    }
+}
+
+/// The open ports among `ports`, and the address of `addresses` answering on most of them
+/// - Probes are started `probe_delay` apart, with at most `concurrency` of them in flight
+/// - Refused, reset and unanswered connections are closed or filtered ports
+/// - Other errors on every address, e.g. unreachable networks or exhausted file descriptors,
+///   fail the host
+async fn scan_ports(
+    addresses: &[IpAddr],
+    ports: &[u16],
+    prober: &Prober<'_>,
+) -> Result<(IpAddr, Vec<u16>)> {
+    let delay = prober.config.probe_delay();
+
+    // Stops at the first failing probe, the others would most likely fail the same way
+    let mut open_ports: Vec<(u16, IpAddr)> = stream::iter(ports.iter().copied().enumerate())
+        .then(|(i, port)| async move {
+            if i > 0 && !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            port
+        })
+        .map(|port| async move {
+            let ip = probe_port(addresses, port, prober).await?;
+            Ok::<_, anyhow::Error>(ip.map(|ip| (port, ip)))
+        })
+        .buffer_unordered(prober.config.concurrency.max(1))
+        .try_filter_map(future::ok) // drop closed ports
+        .try_collect()
+        .await?;
+
+    open_ports.sort_unstable();
+
+    let mut answers: HashMap<IpAddr, usize> = HashMap::new();
+    for (_, ip) in &open_ports {
+        *answers.entry(*ip).or_default() += 1;
+    }
+    let ip = answers
+        .into_iter()
+        .max_by_key(|(ip, answers)| (*answers, ip.is_ipv6()))
+        .map_or(addresses[0], |(ip, _)| ip);
+
+    Ok((ip, open_ports.into_iter().map(|(port, _)| port).collect()))
+}
+
+/// The address of `addresses` accepting a connection on `port`, if any (Happy Eyeballs)
+/// - Addresses are tried in order, the next one as soon as the previous attempt fails, or
+///   alongside it once it took longer than the attempt delay
+/// - IPv4 addresses are probed with SYN segments when a SYN scanner is given
+/// - Unanswered attempts are made again `retries` times, refused ones are not
+async fn probe_port(
+    addresses: &[IpAddr],
+    port: u16,
+    prober: &Prober<'_>,
+) -> Result<Option<IpAddr>> {
+    async fn connect(
+        socket_addr: SocketAddr,
+        config: &PortsConfig,
+        syn: Option<&SynScanner>,
+    ) -> (IpAddr, Result<bool>) {
+        let timeout = config.connect_timeout();
+        if let (Some(syn), IpAddr::V4(ip)) = (syn, socket_addr.ip()) {
+            return (
+                socket_addr.ip(),
+                syn.probe(ip, socket_addr.port(), timeout, config.retries)
+                    .await,
+            );
+        }
+
+        for _ in 0..=config.retries {
+            let connection = tokio::time::timeout(timeout, TcpStream::connect(&socket_addr));
+            let result = match connection.await {
+                Ok(Ok(_stream)) => Ok(true),
+                Ok(Err(e))
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::ConnectionRefused | ErrorKind::ConnectionReset
+                    ) =>
+                {
+                    Ok(false)
+                }
+                Ok(Err(e)) if e.kind() == ErrorKind::TimedOut => continue,
+                Ok(Err(e)) => Err(anyhow!(
+                    "Failed to probe port {}: {}",
+                    socket_addr.port(),
+                    e
+                )),
+                Err(_) => continue,
+            };
+            return (socket_addr.ip(), result);
+        }
+        (socket_addr.ip(), Ok(false))
+    }
+
+    let mut pending = addresses.iter();
+    let mut attempts = FuturesUnordered::new();
+    let mut closed = false;
+    let mut last_error = None;
+
+    loop {
+        if let Some(ip) = pending.next() {
+            attempts.push(connect(
+                SocketAddr::new(*ip, port),
+                prober.config,
+                prober.syn.as_ref(),
+            ));
+        }
+
+        // Without other addresses, only the running attempts are waited for
+        let delay = if pending.len() > 0 {
+            Either::Left(tokio::time::sleep(prober.config.attempt_delay()))
+        } else {
+            Either::Right(future::pending())
+        };
+
+        match future::select(attempts.next(), std::pin::pin!(delay)).await {
+            Either::Left((Some((ip, Ok(true))), _)) => return Ok(Some(ip)),
+            Either::Left((Some((_, Ok(false))), _)) => closed = true,
+            Either::Left((Some((_, Err(e))), _)) => last_error = Some(e),
+            // Every address was tried
+            Either::Left((None, _)) => break,
+            Either::Right(_) => {}
+        }
+    }
+
+    match last_error {
+        Some(e) if !closed => Err(e),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    #[tokio::test]
+    async fn test_attribute_should_pause_until_resolution_catches_up() {
+        // The sender parks once a second name is waiting
+        let (mut sender, mut receiver) = mpsc::channel(1);
+        let mut attribution = HashMap::new();
+        let mut out_of_scope = 0;
+
+        attribute(
+            &mut attribution,
+            &mut out_of_scope,
+            &mut sender,
+            None,
+            0,
+            String::from("a.example.com"),
+        )
+        .await;
+        // Names already handed to resolution are only attributed
+        attribute(
+            &mut attribution,
+            &mut out_of_scope,
+            &mut sender,
+            None,
+            1,
+            String::from("a.example.com"),
+        )
+        .await;
+
+        {
+            let mut pending = std::pin::pin!(attribute(
+                &mut attribution,
+                &mut out_of_scope,
+                &mut sender,
+                None,
+                0,
+                String::from("b.example.com"),
+            ));
+            assert!((&mut pending).now_or_never().is_none());
+
+            assert_eq!(receiver.next().await.as_deref(), Some("a.example.com"));
+            pending.await;
+        }
+        assert_eq!(receiver.next().await.as_deref(), Some("b.example.com"));
+
+        assert_eq!(attribution["a.example.com"], 0b11);
+        assert_eq!(attribution["b.example.com"], 0b01);
+    }
+
+    fn prober(config: &PortsConfig) -> Prober<'_> {
+        Prober {
+            config,
+            syn: None,
+            ping: None,
+            dead: Mutex::new(BTreeSet::new()),
+            failed: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// A port refusing connections, its listener being dropped
+    async fn refused_port() -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    /// A port dropping connection attempts, its accept queue being full, with what keeps it so
+    fn unanswered_port() -> (socket2::Socket, std::net::TcpStream, u16) {
+        let socket =
+            socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None).unwrap();
+        socket
+            .bind(&SocketAddr::from(([127, 0, 0, 1], 0)).into())
+            .unwrap();
+        socket.listen(0).unwrap();
+        let addr = socket.local_addr().unwrap().as_socket().unwrap();
+        let queued = std::net::TcpStream::connect(addr).unwrap();
+        (socket, queued, addr.port())
+    }
+
+    #[tokio::test]
+    async fn test_scan_ports_should_only_fail_hosts_on_other_errors() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = refused_port().await;
+        let localhost: IpAddr = "127.0.0.1".parse().unwrap();
+
+        // Refused ports are closed
+        let config = PortsConfig::default();
+        let (ip, open_ports) = scan_ports(&[localhost], &[open, closed], &prober(&config))
+            .await
+            .unwrap();
+        assert_eq!((ip, open_ports), (localhost, vec![open]));
+
+        // Ports not answering before the timeout are filtered
+        let (_socket, _queued, unanswered) = unanswered_port();
+        let config = PortsConfig {
+            connect_timeout: 100,
+            ..Default::default()
+        };
+        let (_, open_ports) = scan_ports(&[localhost], &[unanswered], &prober(&config))
+            .await
+            .unwrap();
+        assert!(open_ports.is_empty());
+
+        // Unreachable networks fail the host
+        let config = PortsConfig::default();
+        let broadcast: IpAddr = "255.255.255.255".parse().unwrap();
+        assert!(
+            scan_ports(&[broadcast], &[open], &prober(&config))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_port_should_try_the_next_address_on_refusal() {
+        let accepting: IpAddr = "127.0.0.2".parse().unwrap();
+        let listener = tokio::net::TcpListener::bind((accepting, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let refusing: IpAddr = "127.0.0.1".parse().unwrap();
+
+        let config = PortsConfig::default();
+        let ip = probe_port(&[refusing, accepting], port, &prober(&config))
+            .await
+            .unwrap();
+        assert_eq!(ip, Some(accepting));
+    }
+
+    #[tokio::test]
+    async fn test_probe_port_should_try_the_next_address_alongside_a_pending_one() {
+        let (_socket, _queued, port) = unanswered_port();
+        let pending: IpAddr = "127.0.0.1".parse().unwrap();
+        let accepting: IpAddr = "127.0.0.2".parse().unwrap();
+        let _listener = tokio::net::TcpListener::bind((accepting, port))
+            .await
+            .unwrap();
+
+        let config = PortsConfig {
+            connect_timeout: 10_000,
+            attempt_delay: 50,
+            ..Default::default()
+        };
+        let started = std::time::Instant::now();
+        let ip = probe_port(&[pending, accepting], port, &prober(&config))
+            .await
+            .unwrap();
+        assert_eq!(ip, Some(accepting));
+        assert!(started.elapsed() < config.connect_timeout());
+    }
+
+    #[tokio::test]
+    async fn test_probe_port_should_only_fail_when_no_address_answers() {
+        let port = refused_port().await;
+        let refusing: IpAddr = "127.0.0.1".parse().unwrap();
+        let unreachable: [IpAddr; 2] = [
+            "255.255.255.255".parse().unwrap(),
+            "224.0.0.1".parse().unwrap(),
+        ];
+        let config = PortsConfig::default();
+
+        // A refusal tells the port is closed, whatever the other addresses say
+        let ip = probe_port(&[unreachable[0], refusing], port, &prober(&config))
+            .await
+            .unwrap();
+        assert_eq!(ip, None);
+
+        assert!(
+            probe_port(&unreachable, port, &prober(&config))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_port_should_retry_unanswered_attempts() {
+        let (_socket, _queued, port) = unanswered_port();
+        let config = PortsConfig {
+            connect_timeout: 50,
+            retries: 2,
+            ..Default::default()
+        };
+
+        let started = std::time::Instant::now();
+        let ip = probe_port(&["127.0.0.1".parse().unwrap()], port, &prober(&config))
+            .await
+            .unwrap();
+        assert_eq!(ip, None);
+        assert!(started.elapsed() >= config.connect_timeout() * 3);
+    }
+
+    fn options() -> ScanOptions {
+        ScanOptions {
+            skip_enumeration: false,
+            skip_port_scan: false,
+            skip_ping: false,
+            verify_dns: false,
+            hosts_from: None,
+            ports_from: None,
+            artifacts: None,
+            export_nmap: None,
+            push: Vec::new(),
+            port_cache_ttl: None,
+            compliance: Vec::new(),
+            policy: None,
+            inventory: None,
+            inventory_format: InventoryFormat::Assets,
+            gallery: None,
+            har: None,
+            format: ScanFormat::Lines,
+            workers: 1,
+            max_body_size: crate::body::DEFAULT_MAX_BODY_SIZE,
+            no_cache: true,
+            dry_run: false,
+            i_am_authorized: false,
+            scope: None,
+            checkpoint: None,
+            crtsh_postgres: false,
+            aggressive: false,
+            selection: Selection::default(),
+            headers: HeaderMap::new(),
+            archived_paths: false,
+            exploit_git: false,
+            dump: None,
+            oob_server: None,
+            oob_token: None,
+            oob_domain: None,
+            oob_public_ip: None,
+            output: None,
+            encrypt_to: Vec::new(),
+            redact_config: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_open_ports_should_assume_web_ports_when_skipping_port_scan() {
+        let options = ScanOptions {
+            skip_port_scan: true,
+            ..options()
+        };
+        let config = PortsConfig::default();
+        let resolver = TokioResolver::builder_with_config(
+            ResolverConfig::default(),
+            TokioConnectionProvider::default(),
+        )
+        .build();
+        let scheduler = Scheduler::new(&[], 1, 1);
+        let hosts = vec![Host {
+            name: String::from("www.example.com"),
+            ip: IpAddr::from([127, 0, 0, 1]),
+        }];
+
+        let subdomains = collect_open_ports(
+            hosts,
+            None,
+            &[],
+            &options,
+            &prober(&config),
+            &resolver,
+            &scheduler,
+        )
+        .await;
+
+        assert_eq!(subdomains.len(), 1);
+        assert_eq!(subdomains[0].name, "www.example.com");
+        assert_eq!(subdomains[0].open_ports, ASSUMED_PORTS);
+    }
+
+    #[test]
+    fn test_with_archived_paths_should_only_add_directories_when_requested() {
+        use crate::modules::http::context::tests::local;
+
+        let contexts = || vec![local("www.example.com:443"), local("www.example.com:8080")];
+        let urls = vec![Url::parse("https://www.example.com/backup/site.zip").unwrap()];
+
+        let endpoints = with_archived_paths(contexts(), &urls, &options());
+        assert_eq!(endpoints.len(), 2);
+
+        let options = ScanOptions {
+            archived_paths: true,
+            ..options()
+        };
+        let endpoints: Vec<String> = with_archived_paths(contexts(), &urls, &options)
+            .into_iter()
+            .map(|context| context.endpoint)
+            .collect();
+        assert_eq!(
+            endpoints,
+            vec![
+                "www.example.com:443",
+                "www.example.com:443/backup",
+                "www.example.com:8080",
+            ]
+        );
+    }
 }
@@ -0,0 +1,203 @@
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// How a team triaged a finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TriageStatus {
+    /// Verified by hand, to be fixed
+    Confirmed,
+    /// Known and accepted, e.g. behind compensating controls
+    AcceptedRisk,
+    FalsePositive,
+}
+
+impl fmt::Display for TriageStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TriageStatus::Confirmed => "confirmed",
+            TriageStatus::AcceptedRisk => "accepted-risk",
+            TriageStatus::FalsePositive => "false-positive",
+        })
+    }
+}
+
+/// The triage of a finding, carried forward to every later report of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<TriageStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub updated_at: String,
+}
+
+impl Annotation {
+    /// Whether the finding was triaged away, so that policies let it pass
+    pub fn dismisses(&self) -> bool {
+        matches!(
+            self.status,
+            Some(TriageStatus::AcceptedRisk | TriageStatus::FalsePositive)
+        )
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.status, &self.note) {
+            (Some(status), Some(note)) => write!(f, "{}: {}", status, note),
+            (Some(status), None) => write!(f, "{}", status),
+            (None, Some(note)) => write!(f, "{}", note),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Annotations of findings, by the ID of the finding
+pub struct Annotations {
+    path: PathBuf,
+    entries: BTreeMap<String, Annotation>,
+}
+
+impl Annotations {
+    /// `~/.local/share/vulnscan/annotations.json` on Linux
+    pub fn default_path() -> Result<PathBuf> {
+        let data_dir = dirs::data_local_dir().ok_or_else(|| anyhow!("No local data directory"))?;
+        Ok(data_dir.join("vulnscan").join("annotations.json"))
+    }
+
+    /// The annotations at `path`, none until saved if it does not exist
+    pub fn open(path: &Path) -> Result<Self> {
+        let entries = match std::fs::read(path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+
+        Ok(Annotations {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// The annotations at the default path, none if it cannot be found
+    pub fn open_default() -> Result<Self> {
+        match Annotations::default_path() {
+            Ok(path) => Annotations::open(&path),
+            Err(_) => Ok(Annotations {
+                path: PathBuf::new(),
+                entries: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Annotations kept elsewhere than in a file, like in the store, never saved
+    pub fn from_entries(entries: BTreeMap<String, Annotation>) -> Self {
+        Annotations {
+            path: PathBuf::new(),
+            entries,
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Annotation> {
+        self.entries.get(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Annotation)> {
+        self.entries.iter()
+    }
+
+    /// Set the status or note of a finding, keeping what is not given
+    pub fn annotate(
+        &mut self,
+        id: &str,
+        status: Option<TriageStatus>,
+        note: Option<&str>,
+    ) -> Result<&Annotation> {
+        if id.len() != 64 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!("Invalid finding ID {}, give all 64 hexadecimal digits", id);
+        }
+        if status.is_none() && note.is_none() {
+            bail!("Nothing to annotate {} with, give --status or --note", id);
+        }
+
+        let annotation = self
+            .entries
+            .entry(id.to_lowercase())
+            .or_insert_with(|| Annotation {
+                status: None,
+                note: None,
+                updated_at: String::new(),
+            });
+        if status.is_some() {
+            annotation.status = status;
+        }
+        if let Some(note) = note {
+            annotation.note = Some(note.to_string()).filter(|note| !note.trim().is_empty());
+        }
+        annotation.updated_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        Ok(annotation)
+    }
+
+    /// Forget the annotation of a finding, returning whether there was one
+    pub fn remove(&mut self, id: &str) -> bool {
+        self.entries.remove(&id.to_lowercase()).is_some()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_vec_pretty(&self.entries)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate_should_keep_what_is_not_given() {
+        let path =
+            std::env::temp_dir().join(format!("vulnscan-annotations-{}.json", std::process::id()));
+        let id = "e5cde3c9c0f701f8d681917d33f6272f20465877da0d00310eb2b16001993d0e";
+
+        let mut annotations = Annotations::open(&path).unwrap();
+        assert!(
+            annotations
+                .annotate("e5cde3c9", None, Some("short"))
+                .is_err()
+        );
+        assert!(annotations.annotate(id, None, None).is_err());
+        annotations
+            .annotate(id, Some(TriageStatus::AcceptedRisk), Some("Behind the VPN"))
+            .unwrap();
+        annotations
+            .annotate(&id.to_uppercase(), None, Some("Behind the VPN until Q3"))
+            .unwrap();
+        annotations.save().unwrap();
+
+        let annotations = Annotations::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let annotation = annotations.get(id).unwrap();
+        assert_eq!(annotation.status, Some(TriageStatus::AcceptedRisk));
+        assert_eq!(
+            annotation.to_string(),
+            "accepted-risk: Behind the VPN until Q3"
+        );
+        assert!(annotation.dismisses());
+    }
+}
@@ -0,0 +1,466 @@
+use crate::manifest::Manifest;
+use crate::target;
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use quick_xml::escape::escape;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+/// Written by `--artifacts`, read back by `--hosts-from`
+pub const HOSTS_FILE: &str = "hosts.json";
+/// Written by `--artifacts`, read back by `--ports-from`
+pub const PORTS_FILE: &str = "ports.json";
+
+/// A resolved host, produced by the enumeration stage
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Host {
+    pub name: String,
+    pub ip: IpAddr,
+}
+
+/// A host and its open ports, produced by the port scan stage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Domain {
+    pub name: String,
+    pub ip: IpAddr,
+    pub open_ports: Vec<u16>,
+}
+
+#[derive(Deserialize)]
+struct MasscanRecord {
+    // Absent from closing `{"finished": 1}` records
+    ip: Option<IpAddr>,
+    #[serde(default)]
+    ports: Vec<MasscanPort>,
+}
+
+#[derive(Deserialize)]
+struct MasscanPort {
+    port: u16,
+    proto: String,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct NmapRun {
+    #[serde(rename = "host", default)]
+    hosts: Vec<NmapHost>,
+}
+
+#[derive(Deserialize)]
+struct NmapHost {
+    #[serde(rename = "address", default)]
+    addresses: Vec<NmapAddress>,
+    hostnames: Option<NmapHostnames>,
+    ports: Option<NmapPorts>,
+}
+
+#[derive(Deserialize)]
+struct NmapAddress {
+    #[serde(rename = "@addr")]
+    addr: String,
+    #[serde(rename = "@addrtype")]
+    addrtype: String,
+}
+
+#[derive(Deserialize)]
+struct NmapHostnames {
+    #[serde(rename = "hostname", default)]
+    hostnames: Vec<NmapHostname>,
+}
+
+#[derive(Deserialize)]
+struct NmapHostname {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@type")]
+    kind: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NmapPorts {
+    #[serde(rename = "port", default)]
+    ports: Vec<NmapPort>,
+}
+
+#[derive(Deserialize)]
+struct NmapPort {
+    #[serde(rename = "@protocol")]
+    protocol: String,
+    #[serde(rename = "@portid")]
+    portid: u16,
+    state: NmapState,
+}
+
+#[derive(Deserialize)]
+struct NmapState {
+    #[serde(rename = "@state")]
+    state: String,
+}
+
+/// Write a stage artifact as `dir/name`
+pub fn save<T: Serialize>(dir: &Path, name: &str, artifact: &T) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join(name), serde_json::to_vec_pretty(artifact)?)?;
+    Ok(())
+}
+
+/// Load the hosts of an enumeration stage artifact
+pub fn load_hosts(path: &Path) -> Result<Vec<Host>> {
+    load(path)
+}
+
+/// Load open ports, detecting the format
+/// - A port scan stage artifact
+/// - An Nmap XML report (`-oX`)
+/// - A masscan JSON (`-oJ`) or greppable (`-oG`) report
+pub fn load_ports(path: &Path) -> Result<Vec<Domain>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    parse_ports(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn parse_ports(content: &str) -> Result<Vec<Domain>> {
+    let content = content.trim_start_matches('\u{feff}').trim();
+
+    if content.starts_with('<') {
+        return parse_nmap(content).context("Invalid Nmap report");
+    }
+
+    if content.starts_with('[') || content.starts_with('{') {
+        if let Ok(domains) = serde_json::from_str(content) {
+            return Ok(domains);
+        }
+        return parse_masscan_json(content).context("Invalid artifact or masscan report");
+    }
+
+    parse_greppable(content)
+}
+
+/// Open TCP ports of a masscan JSON report, which lists each open port as its own record
+fn parse_masscan_json(content: &str) -> Result<Vec<Domain>> {
+    // Older masscan versions close with an unquoted `{finished: 1}`, others leave a trailing comma
+    let content = content.replace("{finished: 1}", "");
+    let content = match content.strip_suffix(']') {
+        Some(records) => format!("{}]", records.trim_end().trim_end_matches(',')),
+        None => content,
+    };
+
+    let records: Vec<MasscanRecord> = serde_json::from_str(&content)?;
+
+    Ok(merge_ports(records.into_iter().flat_map(|record| {
+        record
+            .ports
+            .into_iter()
+            .filter(|port| port.proto == "tcp" && port.status == "open")
+            .filter_map(move |port| Some((record.ip?, port.port)))
+    })))
+}
+
+/// Open TCP ports of a greppable report, e.g. `Host: 203.0.113.7 ()\tPorts: 80/open/tcp//http//`
+fn parse_greppable(content: &str) -> Result<Vec<Domain>> {
+    let mut ports = Vec::new();
+
+    for line in content.lines().filter(|line| !line.starts_with('#')) {
+        let field = |name: &str| {
+            line.split('\t')
+                .find_map(|field| field.trim().strip_prefix(name))
+                .map(str::trim)
+        };
+
+        let (Some(host), Some(list)) = (field("Host:"), field("Ports:")) else {
+            continue;
+        };
+
+        let host = host.split_whitespace().next().unwrap_or_default();
+        let ip: IpAddr = host
+            .parse()
+            .with_context(|| format!("Invalid host {}", host))?;
+
+        // `port/state/protocol/owner/service/rpc/version`
+        for entry in list.split(',') {
+            let fields: Vec<&str> = entry.trim().split('/').collect();
+            if let [port, "open", "tcp", ..] = fields[..]
+                && let Ok(port) = port.parse()
+            {
+                ports.push((ip, port));
+            }
+        }
+    }
+
+    if ports.is_empty() && !content.lines().all(|line| line.starts_with('#')) {
+        bail!("No open port found, expected a ports.json artifact, an Nmap or a masscan report");
+    }
+
+    Ok(merge_ports(ports))
+}
+
+/// Group `(address, port)` pairs by host, named after its address
+fn merge_ports(ports: impl IntoIterator<Item = (IpAddr, u16)>) -> Vec<Domain> {
+    let mut hosts: BTreeMap<IpAddr, BTreeSet<u16>> = BTreeMap::new();
+    for (ip, port) in ports {
+        hosts.entry(ip).or_default().insert(port);
+    }
+
+    hosts
+        .into_iter()
+        .map(|(ip, open_ports)| Domain {
+            name: target::host_name(ip),
+            ip,
+            open_ports: open_ports.into_iter().collect(),
+        })
+        .collect()
+}
+
+/// Render open ports as an Nmap XML report, for tools that already parse Nmap output
+/// - `probed` tells whether ports were actually probed, rather than assumed or imported
+/// - The manifest of the scan is kept in a comment, which Nmap parsers skip
+pub fn to_nmap(domains: &[Domain], probed: bool, manifest: &Manifest) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let reason = if probed { "syn-ack" } else { "user-set" };
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE nmaprun>\n");
+    // Comments may not contain `--`, which JSON strings can spell with an escape instead
+    let _ = writeln!(
+        xml,
+        "<!-- vulnscan manifest {} -->",
+        serde_json::to_string(manifest)
+            .unwrap_or_default()
+            .replace("--", "-\\u002d")
+    );
+    let _ = writeln!(
+        xml,
+        r#"<nmaprun scanner="vulnscan" start="{}" version="{}" xmloutputversion="1.05">"#,
+        now,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    for domain in domains {
+        let addrtype = if domain.ip.is_ipv4() { "ipv4" } else { "ipv6" };
+
+        let _ = writeln!(xml, r#"<host><status state="up" reason="{}"/>"#, reason);
+        let _ = writeln!(
+            xml,
+            r#"<address addr="{}" addrtype="{}"/>"#,
+            domain.ip, addrtype
+        );
+
+        // Hosts only known by their address have no hostname
+        if domain.name != target::host_name(domain.ip) {
+            let _ = writeln!(
+                xml,
+                r#"<hostnames><hostname name="{}" type="user"/></hostnames>"#,
+                escape(&domain.name)
+            );
+        }
+
+        xml.push_str("<ports>");
+        for port in &domain.open_ports {
+            let _ = write!(
+                xml,
+                r#"<port protocol="tcp" portid="{}"><state state="open" reason="{}" reason_ttl="0"/></port>"#,
+                port, reason
+            );
+        }
+        xml.push_str("</ports>\n</host>\n");
+    }
+
+    let _ = writeln!(
+        xml,
+        r#"<runstats><finished time="{}"/><hosts up="{}" down="0" total="{}"/></runstats>"#,
+        now,
+        domains.len(),
+        domains.len()
+    );
+    xml.push_str("</nmaprun>\n");
+
+    xml
+}
+
+fn load<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let content =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    serde_json::from_slice(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Open TCP ports of every host of an Nmap XML report
+/// - Hosts are named after the hostname given to Nmap, reverse DNS names may point to other sites
+fn parse_nmap(content: &str) -> Result<Vec<Domain>> {
+    let run: NmapRun = quick_xml::de::from_str(content)?;
+
+    let domains = run
+        .hosts
+        .into_iter()
+        .filter_map(|host| {
+            let ip: IpAddr = host
+                .addresses
+                .iter()
+                .filter(|address| address.addrtype.starts_with("ipv"))
+                .find_map(|address| address.addr.parse().ok())?;
+
+            let name = host
+                .hostnames
+                .into_iter()
+                .flat_map(|hostnames| hostnames.hostnames)
+                .find(|hostname| hostname.kind.as_deref() == Some("user"))
+                .map(|hostname| hostname.name.to_lowercase())
+                .unwrap_or_else(|| target::host_name(ip));
+
+            let open_ports = host
+                .ports
+                .into_iter()
+                .flat_map(|ports| ports.ports)
+                .filter(|port| port.protocol == "tcp" && port.state.state == "open")
+                .map(|port| port.portid)
+                .collect();
+
+            Some(Domain {
+                name,
+                ip,
+                open_ports,
+            })
+        })
+        .collect();
+
+    Ok(domains)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::modules::Selection;
+
+    #[test]
+    fn test_parse_nmap_should_keep_open_tcp_ports_of_each_host() {
+        let domains = parse_nmap(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<nmaprun scanner="nmap" args="nmap -oX - www.example.com 198.51.100.7">
+<host><status state="up"/>
+<address addr="203.0.113.7" addrtype="ipv4"/>
+<hostnames><hostname name="www.example.com" type="user"/><hostname name="web-1.hosting.example" type="PTR"/></hostnames>
+<ports><extraports state="closed" count="997"/>
+<port protocol="tcp" portid="22"><state state="filtered"/></port>
+<port protocol="tcp" portid="80"><state state="open"/><service name="http"/></port>
+<port protocol="tcp" portid="443"><state state="open"/><service name="https"/></port>
+</ports>
+</host>
+<taskbegin task="NSE" time="1"/>
+<host><status state="up"/>
+<address addr="198.51.100.7" addrtype="ipv4"/><address addr="00:11:22:33:44:55" addrtype="mac"/>
+<hostnames><hostname name="web-2.hosting.example" type="PTR"/></hostnames>
+<ports><port protocol="udp" portid="53"><state state="open"/></port>
+<port protocol="tcp" portid="8080"><state state="open"/></port></ports>
+</host>
+<runstats><finished time="2"/></runstats>
+</nmaprun>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            domains,
+            [
+                Domain {
+                    name: String::from("www.example.com"),
+                    ip: "203.0.113.7".parse().unwrap(),
+                    open_ports: vec![80, 443],
+                },
+                Domain {
+                    name: String::from("198.51.100.7"),
+                    ip: "198.51.100.7".parse().unwrap(),
+                    open_ports: vec![8080],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_ports_should_merge_masscan_records_by_host() {
+        let expected = [
+            Domain {
+                name: String::from("198.51.100.7"),
+                ip: "198.51.100.7".parse().unwrap(),
+                open_ports: vec![80, 443],
+            },
+            Domain {
+                name: String::from("203.0.113.7"),
+                ip: "203.0.113.7".parse().unwrap(),
+                open_ports: vec![22],
+            },
+        ];
+
+        let json = r#"[
+{   "ip": "203.0.113.7",   "timestamp": "1700000000", "ports": [ {"port": 22, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] },
+{   "ip": "198.51.100.7",   "timestamp": "1700000001", "ports": [ {"port": 443, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 52} ] },
+{   "ip": "198.51.100.7",   "timestamp": "1700000002", "ports": [ {"port": 80, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 52} ] },
+{   "ip": "198.51.100.7",   "timestamp": "1700000003", "ports": [ {"port": 53, "proto": "udp", "status": "open", "reason": "none", "ttl": 52} ] },
+{finished: 1}
+]"#;
+        assert_eq!(parse_ports(json).unwrap(), expected);
+
+        let greppable = "# Masscan 1.3.2 scan initiated Tue Nov 14 22:13:20 2023\n\
+            # Ports scanned: TCP(3;22-22,80-80,443-443) UDP(0;) SCTP(0;) PROTOCOLS(0;)\n\
+            Timestamp: 1700000000\tHost: 203.0.113.7 ()\tPorts: 22/open/tcp//ssh//\n\
+            Timestamp: 1700000001\tHost: 198.51.100.7 ()\tPorts: 443/open/tcp//https//\n\
+            Host: 198.51.100.7 (www.example.com)\tPorts: 80/open/tcp//http///, 8080/closed/tcp//http-proxy///\n\
+            # Masscan done at Tue Nov 14 22:13:32 2023\n";
+        assert_eq!(parse_ports(greppable).unwrap(), expected);
+
+        assert!(parse_ports("not a report").is_err());
+    }
+
+    #[test]
+    fn test_load_ports_should_read_back_saved_artifacts() {
+        let dir = std::env::temp_dir().join(format!("vulnscan-artifact-{}", std::process::id()));
+        let domains = vec![Domain {
+            name: String::from("[2001:db8::1]"),
+            ip: "2001:db8::1".parse().unwrap(),
+            open_ports: vec![443],
+        }];
+
+        save(&dir, PORTS_FILE, &domains).unwrap();
+        let loaded = load_ports(&dir.join(PORTS_FILE)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded, domains);
+    }
+
+    #[test]
+    fn test_to_nmap_should_be_read_back_by_parse_nmap() {
+        let domains = vec![
+            Domain {
+                name: String::from("www.example.com"),
+                ip: "203.0.113.7".parse().unwrap(),
+                open_ports: vec![80, 8443],
+            },
+            Domain {
+                name: String::from("[2001:db8::1]"),
+                ip: "2001:db8::1".parse().unwrap(),
+                open_ports: Vec::new(),
+            },
+        ];
+
+        let xml = to_nmap(
+            &domains,
+            true,
+            &Manifest::new(&Config::default(), &Selection::default(), false, true),
+        );
+
+        assert!(xml.contains(r#"<hostname name="www.example.com" type="user"/>"#));
+        assert_eq!(parse_nmap(&xml).unwrap(), domains);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::body;
+use crate::body::ReadBounded;
+use anyhow::Result;
+use anyhow::bail;
+use hickory_resolver::TokioResolver;
+use hickory_resolver::config::ResolverConfig;
+use hickory_resolver::name_server::TokioConnectionProvider;
+use reqwest::Client;
+use std::time::Duration;
+
+const TXT_PREFIX: &str = "vulnscan-verification=";
+const WELL_KNOWN_PATH: &str = "/.well-known/vulnscan-verification.txt";
+
+/// Check that the owner of `target` authorized the scan, by publishing `token` either
+/// - in a `_vulnscan.<target>` TXT record, as `vulnscan-verification=<token>`
+/// - in `/.well-known/vulnscan-verification.txt`, over HTTPS or HTTP
+pub async fn verify(target: &str, token: Option<&str>) -> Result<()> {
+    let Some(token) = token.filter(|token| !token.is_empty()) else {
+        bail!("Authorization is required, but no authorization.token is configured");
+    };
+
+    if txt_record_matches(target, token).await {
+        log::info!("{} is authorized by its DNS TXT record", target);
+        return Ok(());
+    }
+
+    // A redirect to a domain its owner controls must not authorize the target
+    let http_client = Client::builder()
+        .timeout(Duration::from_secs(10))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+
+    for schema in ["https", "http"] {
+        if well_known_matches(&http_client, &format!("{}://{}", schema, target), token).await {
+            log::info!("{} is authorized by its well-known file", target);
+            return Ok(());
+        }
+    }
+
+    bail!(
+        "{} does not publish the authorization token, add a `_vulnscan.{}` TXT record \
+         or serve {}, or pass --i-am-authorized",
+        target,
+        target,
+        WELL_KNOWN_PATH
+    )
+}
+
+async fn txt_record_matches(target: &str, token: &str) -> bool {
+    let resolver = TokioResolver::builder_with_config(
+        ResolverConfig::default(),
+        TokioConnectionProvider::default(),
+    )
+    .build();
+
+    let Ok(lookup) = resolver.txt_lookup(format!("_vulnscan.{}", target)).await else {
+        return false;
+    };
+
+    lookup
+        .iter()
+        .any(|txt| txt.to_string().strip_prefix(TXT_PREFIX) == Some(token))
+}
+
+async fn well_known_matches(http_client: &Client, base_url: &str, token: &str) -> bool {
+    let Ok(resp) = http_client
+        .get(format!("{}{}", base_url, WELL_KNOWN_PATH))
+        .send()
+        .await
+    else {
+        return false;
+    };
+
+    if !resp.status().is_success() {
+        return false;
+    }
+
+    resp.text_bounded(body::DEFAULT_MAX_BODY_SIZE)
+        .await
+        .is_ok_and(|body| body.lines().any(|line| line.trim() == token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_well_known_matches_should_return_true_when_token_published() {
+        // Set up mock HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path(WELL_KNOWN_PATH);
+                then.status(200).body("8f1c2e\n");
+            })
+            .await;
+
+        // Run check
+        let base_url = format!("http://{}:{}", mock_server.host(), mock_server.port());
+        let result = well_known_matches(&Client::new(), &base_url, "8f1c2e").await;
+
+        // Check result
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_well_known_matches_should_return_false_when_token_differs() {
+        // Set up mock HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path(WELL_KNOWN_PATH);
+                then.status(200).body("another-token\n");
+            })
+            .await;
+
+        // Run check
+        let base_url = format!("http://{}:{}", mock_server.host(), mock_server.port());
+        let result = well_known_matches(&Client::new(), &base_url, "8f1c2e").await;
+
+        // Check result
+        assert!(!result);
+    }
+}
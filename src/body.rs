@@ -0,0 +1,158 @@
+use crate::capture;
+use anyhow::bail;
+use async_trait::async_trait;
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+
+/// How much of a response body modules read, unless `--max-body-size` says otherwise
+pub const DEFAULT_MAX_BODY_SIZE: usize = 256 * 1024;
+/// How much third-party APIs may answer, some listing every name of a zone at once
+pub const MAX_API_RESPONSE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Read response bodies up to a size limit, streaming them chunk by chunk
+/// so that huge responses are never buffered in full
+#[async_trait]
+pub trait ReadBounded {
+    /// The first `limit` bytes of the body
+    async fn bytes_limited(self, limit: usize) -> reqwest::Result<Vec<u8>>;
+
+    /// The body as text, truncated to `limit` bytes
+    async fn text_bounded(self, limit: usize) -> reqwest::Result<String>;
+
+    /// The body as text, rejected rather than truncated when larger than `limit` bytes
+    async fn text_limited(self, limit: usize) -> anyhow::Result<String>;
+
+    /// The body parsed as JSON, rejected when larger than `limit` bytes
+    async fn json_limited<T: DeserializeOwned>(self, limit: usize) -> anyhow::Result<T>;
+}
+
+#[async_trait]
+impl ReadBounded for Response {
+    async fn bytes_limited(mut self, limit: usize) -> reqwest::Result<Vec<u8>> {
+        let mut body = Vec::new();
+
+        while let Some(chunk) = self.chunk().await? {
+            let remaining = limit - body.len();
+            if chunk.len() >= remaining {
+                body.extend_from_slice(&chunk[..remaining]);
+                log::debug!("Truncated response of {} to {} bytes", self.url(), limit);
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        capture::body(self.url(), &body);
+        Ok(body)
+    }
+
+    async fn text_bounded(self, limit: usize) -> reqwest::Result<String> {
+        let body = self.bytes_limited(limit).await?;
+
+        // The limit may split a multi-byte character
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    async fn text_limited(self, limit: usize) -> anyhow::Result<String> {
+        let url = self.url().clone();
+        // One byte over the limit tells oversized bodies apart
+        let body = self.bytes_limited(limit + 1).await?;
+
+        if body.len() > limit {
+            bail!("The response of {} is larger than {} bytes", url, limit);
+        }
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    async fn json_limited<T: DeserializeOwned>(self, limit: usize) -> anyhow::Result<T> {
+        Ok(serde_json::from_str(&self.text_limited(limit).await?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_bytes_limited_should_truncate_large_bodies() {
+        // Set up mock HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200).body("A".repeat(1024 * 1024));
+            })
+            .await;
+
+        // Run request
+        let resp = reqwest::get(format!(
+            "http://{}:{}/",
+            mock_server.host(),
+            mock_server.port()
+        ))
+        .await
+        .unwrap();
+        let body = resp.bytes_limited(1000).await.unwrap();
+
+        // Check result
+        assert_eq!(body.len(), 1000);
+    }
+
+    #[tokio::test]
+    async fn test_bytes_limited_should_return_small_bodies_whole() {
+        // Set up mock HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200).body("DB_PASSWORD=secret");
+            })
+            .await;
+
+        // Run request
+        let resp = reqwest::get(format!(
+            "http://{}:{}/",
+            mock_server.host(),
+            mock_server.port()
+        ))
+        .await
+        .unwrap();
+        let body = resp.bytes_limited(1000).await.unwrap();
+
+        // Check result
+        assert_eq!(body, b"DB_PASSWORD=secret");
+    }
+
+    #[tokio::test]
+    async fn test_json_limited_should_reject_oversized_bodies() {
+        // Set up mock HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200)
+                    .json_body(serde_json::json!([{"name_value": "www.example.com"}]));
+            })
+            .await;
+
+        // Run requests under and over the size of the body
+        let url = format!("http://{}:{}/", mock_server.host(), mock_server.port());
+        let resp = reqwest::get(&url).await.unwrap();
+        let entries: serde_json::Value = resp.json_limited(1000).await.unwrap();
+
+        let resp = reqwest::get(&url).await.unwrap();
+        let oversized = resp.json_limited::<serde_json::Value>(10).await;
+
+        // Check result
+        assert_eq!(entries[0]["name_value"], "www.example.com");
+        assert!(
+            oversized
+                .unwrap_err()
+                .to_string()
+                .contains("larger than 10 bytes")
+        );
+    }
+}
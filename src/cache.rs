@@ -0,0 +1,374 @@
+use crate::artifact::Domain;
+use crate::artifact::Host;
+use crate::body::ReadBounded;
+use crate::modules::http::ScanContext;
+use crate::throttle::SendAdaptive;
+use crate::throttle::SendError;
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reqwest::Method;
+use reqwest::RequestBuilder;
+use reqwest::Response;
+use reqwest::StatusCode;
+use reqwest::header::CONTENT_LENGTH;
+use reqwest::header::ETAG;
+use reqwest::header::IF_MODIFIED_SINCE;
+use reqwest::header::IF_NONE_MATCH;
+use reqwest::header::LAST_MODIFIED;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+// Entries older than this are dropped when saving, whatever the TTL of later scans
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Open ports found by earlier port scans, by host name
+pub struct PortCache {
+    path: PathBuf,
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    /// The address the host resolved to, a host resolving elsewhere is scanned again
+    resolved: IpAddr,
+    /// The address the ports answered on
+    ip: IpAddr,
+    open_ports: Vec<u16>,
+    /// Seconds since the Unix epoch
+    scanned_at: u64,
+}
+
+impl PortCache {
+    /// `~/.local/share/vulnscan/port_cache.json` on Linux
+    pub fn default_path() -> Result<PathBuf> {
+        let data_dir = dirs::data_local_dir().ok_or_else(|| anyhow!("No local data directory"))?;
+        Ok(data_dir.join("vulnscan").join("port_cache.json"))
+    }
+
+    /// Read the cache at `path`, which is empty if it does not exist
+    pub fn open(path: &Path) -> Result<Self> {
+        let entries = match std::fs::read(path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+
+        Ok(PortCache {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// The open ports of `host` scanned less than `ttl` ago, if it still resolves the same
+    pub fn get(&self, host: &Host, ttl: Duration) -> Option<Domain> {
+        let entry = self.entries.get(&host.name)?;
+        if entry.resolved != host.ip || now().saturating_sub(entry.scanned_at) >= ttl.as_secs() {
+            return None;
+        }
+
+        Some(Domain {
+            name: host.name.clone(),
+            ip: entry.ip,
+            open_ports: entry.open_ports.clone(),
+        })
+    }
+
+    /// Remember the open ports of a host resolving to `resolved`
+    pub fn insert(&mut self, resolved: IpAddr, domain: &Domain) {
+        self.entries.insert(
+            domain.name.clone(),
+            Entry {
+                resolved,
+                ip: domain.ip,
+                open_ports: domain.open_ports.clone(),
+                scanned_at: now(),
+            },
+        );
+    }
+
+    pub fn save(&mut self) -> Result<()> {
+        let now = now();
+        self.entries
+            .retain(|_, entry| now.saturating_sub(entry.scanned_at) < MAX_AGE.as_secs());
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_vec(&self.entries)?)?;
+        Ok(())
+    }
+}
+
+/// `~/.local/share/vulnscan/http_cache/` on Linux
+pub fn default_http_cache_dir() -> Result<PathBuf> {
+    let data_dir = dirs::data_local_dir().ok_or_else(|| anyhow!("No local data directory"))?;
+    Ok(data_dir.join("vulnscan").join("http_cache"))
+}
+
+/// A response kept with the validators the server sent along
+#[derive(Serialize, Deserialize)]
+struct CachedResponse {
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// Base64 encoded
+    body: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Seconds since the Unix epoch
+    stored_at: u64,
+}
+
+impl CachedResponse {
+    fn load(path: &Path, url: &str) -> Option<Self> {
+        let entry: CachedResponse = serde_json::from_slice(&std::fs::read(path).ok()?).ok()?;
+        // Guards against digest collisions, and stale entries are revalidated from scratch
+        if entry.url != url || now().saturating_sub(entry.stored_at) >= MAX_AGE.as_secs() {
+            return None;
+        }
+        Some(entry)
+    }
+
+    fn to_response(&self) -> Option<Response> {
+        let body = BASE64.decode(&self.body).ok()?;
+        rebuild(self.status, &self.headers, body)
+    }
+}
+
+fn rebuild(status: u16, headers: &[(String, String)], body: Vec<u8>) -> Option<Response> {
+    let mut builder = http::Response::builder().status(status);
+    for (name, value) in headers {
+        builder = builder.header(name, value);
+    }
+    Some(builder.body(body).ok()?.into())
+}
+
+/// Send GET requests of passive checks, reusing the stored response when the server
+/// answers `304 Not Modified` to its `ETag` or `Last-Modified` validators
+/// - Responses are kept in the cache directory of the context, if any
+/// - Only responses with validators and a body within `--max-body-size` are stored
+/// - Other requests are sent like `send_adaptive`, through the limiter of the context
+#[async_trait]
+pub trait SendCached {
+    async fn send_cached(self, context: &ScanContext) -> Result<Response, SendError>;
+}
+
+#[async_trait]
+impl SendCached for RequestBuilder {
+    async fn send_cached(self, context: &ScanContext) -> Result<Response, SendError> {
+        let (client, request) = self.build_split();
+        let mut request = request?;
+        let dir = context.options.cache_dir.as_ref();
+        let Some(dir) = dir.filter(|_| request.method() == Method::GET) else {
+            return RequestBuilder::from_parts(client, request)
+                .send_adaptive(&context.limiter)
+                .await;
+        };
+
+        let url = request.url().to_string();
+        let path = dir.join(format!("{:x}.json", Sha256::digest(url.as_bytes())));
+        let cached = CachedResponse::load(&path, &url);
+        if let Some(cached) = &cached {
+            let headers = request.headers_mut();
+            if let Some(etag) = cached.etag.as_ref().and_then(|etag| etag.parse().ok()) {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+            if let Some(date) = cached
+                .last_modified
+                .as_ref()
+                .and_then(|date| date.parse().ok())
+            {
+                headers.insert(IF_MODIFIED_SINCE, date);
+            }
+        }
+
+        let resp = RequestBuilder::from_parts(client, request)
+            .send_adaptive(&context.limiter)
+            .await?;
+        if resp.status() == StatusCode::NOT_MODIFIED
+            && let Some(unchanged) = cached.as_ref().and_then(CachedResponse::to_response)
+        {
+            log::debug!("Reusing the cached response of {}", url);
+            return Ok(unchanged);
+        }
+
+        let validator = |name| {
+            resp.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = validator(ETAG);
+        let last_modified = validator(LAST_MODIFIED);
+        if !resp.status().is_success() || (etag.is_none() && last_modified.is_none()) {
+            return Ok(resp);
+        }
+
+        let status = resp.status().as_u16();
+        let headers: Vec<(String, String)> = resp
+            .headers()
+            .iter()
+            // The body is rebuilt, possibly truncated
+            .filter(|(name, _)| *name != CONTENT_LENGTH)
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        // One byte over the limit tells truncated bodies apart, which are not stored
+        let limit = context.options.max_body_size;
+        let bytes = resp.bytes_limited(limit + 1).await?;
+
+        if bytes.len() <= limit {
+            let entry = CachedResponse {
+                url,
+                status,
+                headers: headers.clone(),
+                body: BASE64.encode(&bytes),
+                etag,
+                last_modified,
+                stored_at: now(),
+            };
+            let stored = std::fs::create_dir_all(dir)
+                .and_then(|_| std::fs::write(&path, serde_json::to_vec(&entry)?));
+            if let Err(e) = stored {
+                log::warn!("Failed to cache the response of {}: {}", entry.url, e);
+            }
+        }
+
+        Ok(rebuild(status, &headers, bytes).expect("Headers of a received response are valid"))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Parse a TTL like `90s`, `30m`, `24h` or `7d`
+pub fn parse_ttl(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (value, unit) = input.split_at(split);
+
+    let Ok(value) = value.parse::<u64>() else {
+        bail!("Invalid TTL {}, expected e.g. 30m, 24h or 7d", input);
+    };
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => bail!("Invalid TTL unit in {}, expected s, m, h or d", input),
+    };
+
+    let Some(seconds) = value.checked_mul(seconds) else {
+        bail!("Invalid TTL {}, expected e.g. 30m, 24h or 7d", input);
+    };
+    Ok(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::http::HttpOptions;
+    use crate::modules::http::context::tests::local;
+    use crate::throttle::Limiter;
+    use httpmock::prelude::*;
+
+    #[test]
+    fn test_parse_ttl_should_accept_units() {
+        assert_eq!(parse_ttl("24h").unwrap(), Duration::from_secs(86_400));
+        assert_eq!(parse_ttl("30m").unwrap(), Duration::from_secs(1_800));
+        assert!(parse_ttl("24").is_err());
+        assert!(parse_ttl("h").is_err());
+        assert!(parse_ttl("300000000000000d").is_err());
+    }
+
+    #[test]
+    fn test_get_should_skip_expired_and_moved_hosts() {
+        let path =
+            std::env::temp_dir().join(format!("vulnscan-port-cache-{}.json", std::process::id()));
+        let host = Host {
+            name: String::from("www.example.com"),
+            ip: "203.0.113.10".parse().unwrap(),
+        };
+        let domain = Domain {
+            name: host.name.clone(),
+            ip: "2001:db8::10".parse().unwrap(),
+            open_ports: vec![80, 443],
+        };
+
+        let mut cache = PortCache::open(&path).unwrap();
+        cache.insert(host.ip, &domain);
+        cache.save().unwrap();
+
+        let cache = PortCache::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(cache.get(&host, Duration::from_secs(60)), Some(domain));
+        assert_eq!(cache.get(&host, Duration::ZERO), None);
+
+        let moved = Host {
+            ip: "203.0.113.20".parse().unwrap(),
+            ..host
+        };
+        assert_eq!(cache.get(&moved, Duration::from_secs(60)), None);
+    }
+
+    #[tokio::test]
+    async fn test_send_cached_should_reuse_unchanged_responses() {
+        let mock_server = MockServer::start_async().await;
+        let revalidated = mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/.env")
+                    .header("if-none-match", "\"v1\"");
+                then.status(304);
+            })
+            .await;
+        let fetched = mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/.env");
+                then.status(200)
+                    .header("etag", "\"v1\"")
+                    .body("APP_KEY=secret");
+            })
+            .await;
+
+        let dir = std::env::temp_dir().join(format!("vulnscan-http-cache-{}", std::process::id()));
+        let context = local(&mock_server.address().to_string()).with_scan(
+            &Limiter::default(),
+            &HttpOptions {
+                cache_dir: Some(dir.clone()),
+                ..HttpOptions::default()
+            },
+        );
+        let url = format!("http://{}/.env", mock_server.address());
+        let client = reqwest::Client::new();
+        let first = client.get(&url).send_cached(&context).await.unwrap();
+        let second = client.get(&url).send_cached(&context).await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(first.text().await.unwrap(), "APP_KEY=secret");
+        assert_eq!(second.status(), StatusCode::OK);
+        assert_eq!(second.text().await.unwrap(), "APP_KEY=secret");
+        fetched.assert_async().await;
+        revalidated.assert_async().await;
+    }
+}
@@ -0,0 +1,131 @@
+use crate::ping;
+
+use socket2::Domain;
+use socket2::Protocol;
+use socket2::Socket;
+use socket2::Type;
+use std::fmt;
+
+/// What the scan may do on this system, checked once at startup rather than failing mid-scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// `linux`, `macos`, `windows`...
+    pub os: &'static str,
+    /// Raw TCP sockets can be opened, which needs root or `CAP_NET_RAW`
+    pub raw_tcp: bool,
+    /// ICMP sockets can be opened, unprivileged ones included
+    pub icmp: bool,
+}
+
+/// How open ports are found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortTechnique {
+    /// Half-open scans from a raw socket, faster and leaving no connection in the target's logs
+    Syn,
+    /// Full connections through the system's TCP stack, needing no privilege
+    Connect,
+}
+
+/// How live hosts of network targets are told apart before their ports are scanned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PingTechnique {
+    /// ICMP echo requests, then TCP pings for hosts dropping them
+    Icmp,
+    /// Connection attempts to ports 443 and 80
+    Tcp,
+}
+
+/// The techniques selected from the capabilities, with why
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Techniques {
+    pub ports: PortTechnique,
+    pub ping: PingTechnique,
+    ports_reason: &'static str,
+    ping_reason: &'static str,
+}
+
+impl Capabilities {
+    pub fn detect() -> Self {
+        Capabilities {
+            os: std::env::consts::OS,
+            raw_tcp: Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::TCP)).is_ok(),
+            icmp: ping::icmp_socket().is_ok(),
+        }
+    }
+
+    /// The best techniques the capabilities allow
+    /// - macOS delivers no TCP segment to raw sockets, and Windows refuses to send them, so SYN
+    ///   scans are only selected on Linux
+    pub fn select(&self) -> Techniques {
+        let (ports, ports_reason) = match (self.os, self.raw_tcp) {
+            ("linux", true) => (PortTechnique::Syn, "raw sockets available"),
+            ("linux", false) => (
+                PortTechnique::Connect,
+                "raw sockets need root or CAP_NET_RAW",
+            ),
+            _ => (
+                PortTechnique::Connect,
+                "SYN scans are only supported on Linux",
+            ),
+        };
+
+        let (ping, ping_reason) = if self.icmp {
+            (PingTechnique::Icmp, "ICMP sockets available")
+        } else {
+            (PingTechnique::Tcp, "ICMP sockets unavailable")
+        };
+
+        Techniques {
+            ports,
+            ping,
+            ports_reason,
+            ping_reason,
+        }
+    }
+}
+
+impl fmt::Display for Techniques {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ports = match self.ports {
+            PortTechnique::Syn => "SYN",
+            PortTechnique::Connect => "TCP connect",
+        };
+        let ping = match self.ping {
+            PingTechnique::Icmp => "ICMP echo",
+            PingTechnique::Tcp => "TCP",
+        };
+
+        write!(
+            f,
+            "{} port scan ({}), {} ping ({})",
+            ports, self.ports_reason, ping, self.ping_reason
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_should_fall_back_without_privileges_or_outside_linux() {
+        let capabilities = |os, raw_tcp, icmp| Capabilities { os, raw_tcp, icmp };
+
+        let techniques = capabilities("linux", true, true).select();
+        assert_eq!(
+            (techniques.ports, techniques.ping),
+            (PortTechnique::Syn, PingTechnique::Icmp)
+        );
+
+        let techniques = capabilities("linux", false, false).select();
+        assert_eq!(
+            techniques.to_string(),
+            "TCP connect port scan (raw sockets need root or CAP_NET_RAW), TCP ping (ICMP sockets unavailable)"
+        );
+
+        assert_eq!(
+            capabilities("macos", true, true).select().ports,
+            PortTechnique::Connect
+        );
+    }
+}
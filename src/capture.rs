@@ -0,0 +1,358 @@
+use crate::export;
+use crate::modules::Finding;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use reqwest::Request;
+use reqwest::Response;
+use reqwest::Url;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use serde_json::json;
+use std::cell::RefCell;
+use std::future::Future;
+use std::time::Instant;
+
+tokio::task_local! {
+    /// Exchanges of the module check running on the task
+    static EXCHANGES: RefCell<Vec<Exchange>>;
+
+    /// Headers the scan client adds to every request, set on the tasks of a scan capturing
+    /// exchanges
+    static DEFAULT_HEADERS: HeaderMap;
+}
+
+/// A request sent by a module and the response it got, as the module read it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exchange {
+    pub started_at: DateTime<Utc>,
+    pub milliseconds: u128,
+    pub method: String,
+    pub url: String,
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: Option<Vec<u8>>,
+    pub status: u16,
+    pub version: String,
+    pub response_headers: Vec<(String, String)>,
+    /// Unset when the module did not read the body, truncated to `--max-body-size`
+    pub response_body: Option<Vec<u8>>,
+}
+
+/// A request being sent, completed into an exchange once answered
+pub struct Pending {
+    started_at: DateTime<Utc>,
+    start: Instant,
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+}
+
+/// Run a scan, capturing the exchanges of its module checks when `default_headers` is set
+/// - `default_headers` are those the scan client adds, missing from the requests built by modules
+/// - Tasks the scan spawns only capture when wrapped in `propagate`
+pub async fn scope<F: Future>(default_headers: Option<HeaderMap>, scan: F) -> F::Output {
+    match default_headers {
+        Some(default_headers) => DEFAULT_HEADERS.scope(default_headers, scan).await,
+        None => scan.await,
+    }
+}
+
+/// `task`, to be spawned, capturing exchanges if the current task does
+pub fn propagate<F: Future>(task: F) -> impl Future<Output = F::Output> {
+    scope(DEFAULT_HEADERS.try_with(HeaderMap::clone).ok(), task)
+}
+
+/// Run a module check, attaching to its finding the exchange it was observed in
+/// - The exchange sent to the URL of the finding is kept, the last one sent if several were
+pub async fn attach<E>(
+    check: impl Future<Output = Result<Option<Finding>, E>>,
+) -> Result<Option<Finding>, E> {
+    if DEFAULT_HEADERS.try_with(|_| ()).is_err() {
+        return check.await;
+    }
+
+    EXCHANGES
+        .scope(RefCell::new(Vec::new()), async {
+            let result = check.await;
+            let exchanges = EXCHANGES.with(RefCell::take);
+            result.map(|finding| {
+                finding.map(|mut finding| {
+                    finding.exchange = matching(&finding.url, exchanges);
+                    finding
+                })
+            })
+        })
+        .await
+}
+
+/// Start capturing `request`, unless no module check is capturing on the task
+pub fn request(request: &Request) -> Option<Pending> {
+    let mut headers = DEFAULT_HEADERS.try_with(HeaderMap::clone).ok()?;
+    EXCHANGES.try_with(|_| ()).ok()?;
+
+    headers.extend(request.headers().clone());
+    Some(Pending {
+        started_at: Utc::now(),
+        start: Instant::now(),
+        method: request.method().to_string(),
+        url: request.url().to_string(),
+        headers: pairs(&headers),
+        body: request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(<[u8]>::to_vec),
+    })
+}
+
+/// Complete a captured request with the head of its response, the body following if read
+pub fn response(pending: Pending, resp: &Response) {
+    let exchange = Exchange {
+        started_at: pending.started_at,
+        milliseconds: pending.start.elapsed().as_millis(),
+        method: pending.method,
+        url: pending.url,
+        request_headers: pending.headers,
+        request_body: pending.body,
+        status: resp.status().as_u16(),
+        version: format!("{:?}", resp.version()),
+        response_headers: pairs(resp.headers()),
+        response_body: None,
+    };
+    let _ = EXCHANGES.try_with(|exchanges| exchanges.borrow_mut().push(exchange));
+}
+
+/// Record the body read from the response to `url`
+pub fn body(url: &Url, body: &[u8]) {
+    let _ = EXCHANGES.try_with(|exchanges| {
+        if let Some(exchange) = exchanges
+            .borrow_mut()
+            .iter_mut()
+            .rev()
+            .find(|exchange| exchange.url == url.as_str() && exchange.response_body.is_none())
+        {
+            exchange.response_body = Some(body.to_vec());
+        }
+    });
+}
+
+/// The exchange sent to `url`, or to its path with other query parameters
+fn matching(url: &str, exchanges: Vec<Exchange>) -> Option<Exchange> {
+    let without_query = |url: &str| url.split(['?', '#']).next().unwrap_or_default().to_string();
+
+    let mut fallback = None;
+    for exchange in exchanges.into_iter().rev() {
+        if exchange.url == url {
+            return Some(exchange);
+        }
+        if fallback.is_none() && without_query(&exchange.url) == without_query(url) {
+            fallback = Some(exchange);
+        }
+    }
+    fallback
+}
+
+fn pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
+/// HAR 1.2 log of the exchanges behind `findings`, for replay in Burp or ZAP
+pub fn har(findings: &[Finding]) -> String {
+    let entries: Vec<Value> = findings
+        .iter()
+        .filter_map(|finding| Some(entry(finding, finding.exchange.as_ref()?)))
+        .collect();
+
+    let log = json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "vulnscan", "version": env!("CARGO_PKG_VERSION") },
+            "entries": entries,
+        }
+    });
+    serde_json::to_string_pretty(&log).expect("HAR should serialize")
+}
+
+fn entry(finding: &Finding, exchange: &Exchange) -> Value {
+    let headers = |headers: &[(String, String)]| -> Vec<Value> {
+        headers
+            .iter()
+            .map(|(name, value)| json!({ "name": name, "value": value }))
+            .collect()
+    };
+    let query: Vec<Value> = Url::parse(&exchange.url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect()
+        })
+        .unwrap_or_default();
+    let header = |name: &str| {
+        exchange
+            .response_headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map_or("", |(_, value)| value.as_str())
+    };
+
+    let mut request = json!({
+        "method": exchange.method,
+        "url": exchange.url,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": headers(&exchange.request_headers),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": exchange.request_body.as_ref().map_or(0, Vec::len),
+    });
+    if let Some(body) = &exchange.request_body {
+        let mime_type = exchange
+            .request_headers
+            .iter()
+            .find(|(name, _)| name == "content-type")
+            .map_or("application/octet-stream", |(_, value)| value.as_str());
+        request["postData"] = json!({
+            "mimeType": mime_type,
+            "text": String::from_utf8_lossy(body),
+        });
+    }
+
+    let body = exchange.response_body.as_deref().unwrap_or_default();
+    let mut content = json!({ "size": body.len(), "mimeType": header("content-type") });
+    match std::str::from_utf8(body) {
+        Ok(text) => content["text"] = json!(text),
+        Err(_) => {
+            content["text"] = json!(BASE64.encode(body));
+            content["encoding"] = json!("base64");
+        }
+    }
+
+    json!({
+        "startedDateTime": exchange.started_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "time": exchange.milliseconds,
+        "request": request,
+        "response": {
+            "status": exchange.status,
+            "statusText": reqwest::StatusCode::from_u16(exchange.status)
+                .ok()
+                .and_then(|status| status.canonical_reason())
+                .unwrap_or_default(),
+            "httpVersion": exchange.version,
+            "cookies": [],
+            "headers": headers(&exchange.response_headers),
+            "content": content,
+            "redirectURL": header("location"),
+            "headersSize": -1,
+            "bodySize": body.len(),
+        },
+        "cache": {},
+        "timings": { "send": 0, "wait": exchange.milliseconds, "receive": 0 },
+        "comment": format!("{} {} ({:?})", finding.module, finding.kind, finding.severity),
+        "_finding": {
+            "id": export::finding_id(&finding.kind, &finding.url),
+            "module": finding.module,
+            "kind": finding.kind,
+            "url": finding.url,
+            "severity": finding.severity,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::Severity;
+    use crate::throttle::Limiter;
+    use httpmock::prelude::*;
+    use reqwest::Client;
+
+    #[tokio::test]
+    async fn test_attach_should_keep_the_exchange_of_the_finding() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/.env");
+            then.status(200)
+                .header("content-type", "text/plain")
+                .body("DB_PASSWORD=hunter2");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/");
+            then.status(200).body("home");
+        });
+
+        let client = Client::new();
+        let limiter = Limiter::default();
+        let url = format!("http://{}/.env", server.address());
+        let check = || async {
+            use crate::body::ReadBounded;
+            use crate::throttle::SendAdaptive;
+
+            client
+                .get(&url)
+                .send_adaptive(&limiter)
+                .await?
+                .text_bounded(1024)
+                .await?;
+            client
+                .get(format!("http://{}/", server.address()))
+                .send_adaptive(&limiter)
+                .await?;
+            anyhow::Ok(Some(Finding::new(
+                "http/dotenv_disclosure",
+                "DotEnvDisclosure",
+                Severity::High,
+                &url,
+            )))
+        };
+
+        // Outside of a capturing scan, nothing is captured
+        let finding = attach(check()).await.unwrap().unwrap();
+        assert_eq!(finding.exchange, None);
+
+        let finding = scope(Some(HeaderMap::new()), attach(check()))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let exchange = finding.exchange.as_ref().unwrap();
+        assert_eq!(exchange.url, format!("http://{}/.env", server.address()));
+        assert_eq!(exchange.status, 200);
+        assert_eq!(
+            exchange.response_body.as_deref(),
+            Some(&b"DB_PASSWORD=hunter2"[..])
+        );
+
+        let har: Value = serde_json::from_str(&har(&[finding])).unwrap();
+        let entry = &har["log"]["entries"][0];
+        assert_eq!(entry["request"]["method"], "GET");
+        assert_eq!(entry["response"]["content"]["text"], "DB_PASSWORD=hunter2");
+        assert_eq!(entry["response"]["content"]["mimeType"], "text/plain");
+    }
+
+    #[tokio::test]
+    async fn test_propagate_should_capture_in_spawned_tasks() {
+        let capturing = || async { DEFAULT_HEADERS.try_with(|_| ()).is_ok() };
+
+        let (propagated, spawned) = scope(Some(HeaderMap::new()), async {
+            let propagated = tokio::spawn(propagate(capturing())).await.unwrap();
+            let spawned = tokio::spawn(capturing()).await.unwrap();
+            (propagated, spawned)
+        })
+        .await;
+
+        assert!(propagated);
+        // Task-locals are not inherited by spawned tasks
+        assert!(!spawned);
+        assert!(!tokio::spawn(propagate(capturing())).await.unwrap());
+    }
+}
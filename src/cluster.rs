@@ -0,0 +1,111 @@
+use crate::export::Record;
+
+use std::hash::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+// Bits the evidence hashes of two findings may differ by for them to be grouped, e.g. a templated
+// error page echoing the host name
+const MAX_DISTANCE: u32 = 3;
+
+/// Group findings of the same kind and severity with near-identical evidence, in order of first
+/// appearance, so that one page served by hundreds of hosts is reviewed once
+/// - Evidence is compared by SimHash, which flips few bits for small differences
+pub fn cluster(records: &[Record]) -> Vec<Vec<&Record>> {
+    let mut clusters: Vec<(u64, Vec<&Record>)> = Vec::new();
+
+    for record in records {
+        let hash = simhash(&record.evidence);
+        let similar = clusters.iter_mut().find(|(other, members)| {
+            members[0].kind == record.kind
+                && members[0].severity == record.severity
+                && (hash ^ *other).count_ones() <= MAX_DISTANCE
+        });
+
+        match similar {
+            Some((_, members)) => members.push(record),
+            None => clusters.push((hash, vec![record])),
+        }
+    }
+
+    clusters.into_iter().map(|(_, members)| members).collect()
+}
+
+/// The 64-bit SimHash of the words of `lines`
+fn simhash(lines: &[String]) -> u64 {
+    let mut weights = [0i32; 64];
+
+    let words = lines
+        .iter()
+        .flat_map(|line| line.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty());
+    for word in words {
+        let mut hasher = DefaultHasher::new();
+        word.to_lowercase().hash(&mut hasher);
+        let hash = hasher.finish();
+
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Message;
+    use crate::modules::Severity;
+
+    #[test]
+    fn test_cluster_should_group_near_identical_evidence() {
+        let record = |kind: &str, host: &str, evidence: &str| Record {
+            kind: kind.to_string(),
+            module: None,
+            title: Message::new(kind),
+            url: format!("https://{}.example.com/", host),
+            severity: Severity::Low,
+            evidence: vec![evidence.replace("{host}", host)],
+            annotation: None,
+        };
+        let page = "Fatal error: Uncaught PDOException: SQLSTATE[HY000] [2002] Connection refused \
+                    in /var/www/{host}/vendor/laravel/framework/src/Illuminate/Database/Connectors/\
+                    Connector.php:70 Stack trace: #0 /var/www/{host}/vendor/laravel/framework/src/\
+                    Illuminate/Database/Connectors/Connector.php(70): PDO->__construct() \
+                    #1 {main} thrown in Connector.php on line 70";
+
+        let records = [
+            record("StackTrace", "shop", page),
+            record("StackTrace", "blog", page),
+            record(
+                "StackTrace",
+                "api",
+                "Traceback (most recent call last): File app.py",
+            ),
+            record("DebugPage", "docs", page),
+            record("StackTrace", "mail", page),
+        ];
+
+        let hosts: Vec<Vec<&str>> = cluster(&records)
+            .iter()
+            .map(|members| members.iter().map(|record| record.url.as_str()).collect())
+            .collect();
+        assert_eq!(
+            hosts,
+            vec![
+                vec![
+                    "https://shop.example.com/",
+                    "https://blog.example.com/",
+                    "https://mail.example.com/"
+                ],
+                vec!["https://api.example.com/"],
+                vec!["https://docs.example.com/"],
+            ]
+        );
+    }
+}
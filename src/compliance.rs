@@ -0,0 +1,183 @@
+use crate::export::Record;
+use crate::i18n::Message;
+use crate::kb;
+use crate::kb::Entry;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+const OWASP_TOP_10: &[(&str, &str)] = &[
+    ("A01:2021", "Broken Access Control"),
+    ("A02:2021", "Cryptographic Failures"),
+    ("A03:2021", "Injection"),
+    ("A04:2021", "Insecure Design"),
+    ("A05:2021", "Security Misconfiguration"),
+    ("A06:2021", "Vulnerable and Outdated Components"),
+    ("A07:2021", "Identification and Authentication Failures"),
+    ("A08:2021", "Software and Data Integrity Failures"),
+    ("A09:2021", "Security Logging and Monitoring Failures"),
+    ("A10:2021", "Server-Side Request Forgery"),
+];
+
+// The requirements of PCI DSS v4.0 an external scan can test
+const PCI_DSS: &[(&str, &str)] = &[
+    ("2.2.2", "Vendor default accounts are managed"),
+    (
+        "2.2.4",
+        "Only necessary services, protocols, daemons and functions are enabled",
+    ),
+    (
+        "2.2.5",
+        "Insecure services, protocols or daemons are justified and secured",
+    ),
+    (
+        "2.2.7",
+        "Non-console administrative access is encrypted using strong cryptography",
+    ),
+    (
+        "4.2.1",
+        "Strong cryptography protects cardholder data transmitted over open, public networks",
+    ),
+    (
+        "6.2.4",
+        "Software engineering techniques prevent common attacks",
+    ),
+    (
+        "6.3.3",
+        "Known vulnerabilities are addressed by installing patches",
+    ),
+    (
+        "7.2.1",
+        "An access control model grants access on a need-to-know basis",
+    ),
+    (
+        "8.6.2",
+        "Passwords of accounts are not hard coded in scripts and configuration files",
+    ),
+];
+
+/// Frameworks findings are mapped to, through the knowledge base
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Framework {
+    /// OWASP Top 10 (2021)
+    Owasp,
+    /// PCI DSS v4.0
+    Pci,
+}
+
+impl Framework {
+    fn categories(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Framework::Owasp => OWASP_TOP_10,
+            Framework::Pci => PCI_DSS,
+        }
+    }
+
+    /// Whether the checks behind `entry` fall in `category`
+    fn maps(self, entry: &Entry, category: &str) -> bool {
+        match self {
+            Framework::Owasp => entry.owasp.split(' ').next() == Some(category),
+            Framework::Pci => entry.pci.iter().any(|requirement| requirement == category),
+        }
+    }
+}
+
+/// Lines summarizing, for each category of `framework`, the checks covering it and their findings
+///
+/// # Arguments
+/// * `checked` - Names of the modules run by the scan
+pub fn report(framework: Framework, records: &[Record], checked: &BTreeSet<String>) -> Vec<String> {
+    let mut failed = 0;
+    let mut lines = Vec::new();
+
+    for (id, title) in framework.categories() {
+        let category = format!("{} {}", id, title);
+        let modules: Vec<&str> = kb::entries()
+            .filter(|(_, entry)| framework.maps(entry, id))
+            .map(|(module, _)| module)
+            .collect();
+        let ran = modules
+            .iter()
+            .filter(|module| checked.contains(**module))
+            .count();
+        let findings: Vec<&Record> = records
+            .iter()
+            .filter(|record| {
+                kb::for_finding(&record.kind, &record.url)
+                    .is_some_and(|(_, entry)| framework.maps(entry, id))
+            })
+            .collect();
+
+        let status = if modules.is_empty() {
+            Message::new("compliance-not-covered")
+        } else if !findings.is_empty() {
+            failed += 1;
+            Message::new("compliance-failed").arg("findings", findings.len())
+        } else if ran == 0 {
+            Message::new("compliance-not-checked")
+        } else {
+            Message::new("compliance-passed")
+        };
+        lines.push(format!(
+            "\t{}",
+            status
+                .arg("category", category)
+                .arg("ran", ran)
+                .arg("checks", modules.len())
+        ));
+        for record in findings {
+            lines.push(format!("\t\t{}", record));
+        }
+    }
+
+    let title = Message::new(match framework {
+        Framework::Owasp => "compliance-owasp",
+        Framework::Pci => "compliance-pci",
+    })
+    .arg("failed", failed)
+    .arg("total", framework.categories().len());
+    std::iter::once(title.to_string()).chain(lines).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::Severity;
+    use crate::modules::title;
+
+    #[test]
+    fn test_report_should_tell_failed_from_unchecked_categories() {
+        let records = [Record {
+            kind: String::from("DotEnvDisclosure"),
+            module: None,
+            title: title("DotEnvDisclosure"),
+            url: String::from("https://www.example.com/.env"),
+            severity: Severity::High,
+            evidence: Vec::new(),
+            annotation: None,
+        }];
+        let checked = BTreeSet::from([
+            String::from("http/dotenv_disclosure"),
+            String::from("http/transport_security"),
+        ]);
+
+        let lines = report(Framework::Owasp, &records, &checked);
+
+        assert_eq!(lines[0], "OWASP Top 10 (2021): 1 of 10 categories failed");
+        assert!(lines[1].starts_with("\tA01:2021 Broken Access Control: failed, 1 finding, 1 of "));
+        assert_eq!(
+            lines[2],
+            "\t\t[High] .env disclosure: https://www.example.com/.env"
+        );
+        assert!(lines[3].starts_with("\tA02:2021 Cryptographic Failures: passed, 1 of "));
+        assert_eq!(
+            lines[4],
+            "\tA03:2021 Injection: not checked, none of 2 checks ran"
+        );
+        assert_eq!(
+            lines[5],
+            "\tA04:2021 Insecure Design: not covered by any check"
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use crate::artifact::Host;
+use crate::body;
+use crate::body::ReadBounded;
+use anyhow::Result;
+use anyhow::bail;
+use futures::StreamExt;
+use futures::stream;
+use rand::seq::SliceRandom;
+use reqwest::Client;
+use serde::Deserialize;
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+
+// A sample tells interference apart from noise without sending every name to a third party
+const SAMPLE_SIZE: usize = 25;
+const DOH_CONCURRENCY: usize = 5;
+// DNS RCODE of a name that does not exist
+const NXDOMAIN: u8 = 3;
+
+/// How the system resolver disagrees with the DoH resolver about a host
+#[derive(Debug, PartialEq, Eq)]
+pub enum Discrepancy {
+    /// The name does not exist according to the DoH resolver
+    Nonexistent { host: String, ip: IpAddr },
+    /// The DoH resolver knows other addresses
+    Mismatch {
+        host: String,
+        ip: IpAddr,
+        expected: Vec<IpAddr>,
+    },
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Discrepancy::Nonexistent { host, ip } => {
+                write!(
+                    f,
+                    "{} resolved to {}, but does not exist over DoH",
+                    host, ip
+                )
+            }
+            Discrepancy::Mismatch { host, ip, expected } => write!(
+                f,
+                "{} resolved to {}, but to {} over DoH",
+                host,
+                ip,
+                expected
+                    .iter()
+                    .map(IpAddr::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// Verification of resolved hosts against Cloudflare's DNS-over-HTTPS resolver
+pub struct Verifier {
+    http_client: Client,
+    url: String,
+}
+
+/// Outcome of a verification, `checked` counting the hosts the DoH resolver answered for
+pub struct Verification {
+    pub checked: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl Verifier {
+    pub fn new() -> Result<Self> {
+        Ok(Verifier {
+            http_client: Client::builder().timeout(Duration::from_secs(10)).build()?,
+            url: String::from("https://1.1.1.1/dns-query"),
+        })
+    }
+
+    /// Cross-check a random sample of `hosts`
+    /// - Disagreements hint at resolver poisoning or ISP interference, though CDNs may also answer differently by location
+    pub async fn verify(&self, hosts: &[Host]) -> Verification {
+        let sample: Vec<&Host> = hosts
+            .choose_multiple(&mut rand::thread_rng(), SAMPLE_SIZE)
+            .collect();
+
+        let results: Vec<Option<Discrepancy>> = stream::iter(sample)
+            .map(|host| async move {
+                match self.check(host).await {
+                    Ok(discrepancy) => Some(discrepancy),
+                    Err(e) => {
+                        log::debug!("DoH lookup of {} failed: {}", host.name, e);
+                        None
+                    }
+                }
+            })
+            .buffer_unordered(DOH_CONCURRENCY)
+            .filter_map(futures::future::ready)
+            .collect()
+            .await;
+
+        Verification {
+            checked: results.len(),
+            discrepancies: results.into_iter().flatten().collect(),
+        }
+    }
+
+    async fn check(&self, host: &Host) -> Result<Option<Discrepancy>> {
+        // Declare needed API response fields
+        #[derive(Debug, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct DohResponse {
+            status: u8,
+            #[serde(default)]
+            answer: Vec<Answer>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Answer {
+            data: String,
+        }
+
+        let record_type = match host.ip {
+            IpAddr::V4(_) => "A",
+            IpAddr::V6(_) => "AAAA",
+        };
+
+        let resp = self
+            .http_client
+            .get(&self.url)
+            .query(&[("name", host.name.as_str()), ("type", record_type)])
+            .header("Accept", "application/dns-json")
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            bail!(
+                "Unexpected status code from the DoH resolver: {}",
+                resp.status()
+            );
+        }
+
+        let resp: DohResponse = resp.json_limited(body::MAX_API_RESPONSE_SIZE).await?;
+
+        if resp.status == NXDOMAIN {
+            return Ok(Some(Discrepancy::Nonexistent {
+                host: host.name.clone(),
+                ip: host.ip,
+            }));
+        }
+
+        // CNAMEs are answers too, only addresses are compared
+        let expected: Vec<IpAddr> = resp
+            .answer
+            .iter()
+            .filter_map(|answer| answer.data.parse().ok())
+            .collect();
+
+        if expected.is_empty() || expected.contains(&host.ip) {
+            return Ok(None);
+        }
+
+        Ok(Some(Discrepancy::Mismatch {
+            host: host.name.clone(),
+            ip: host.ip,
+            expected,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    fn host(name: &str, ip: &str) -> Host {
+        Host {
+            name: String::from(name),
+            ip: ip.parse().unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_should_flag_hosts_resolving_differently() {
+        // Set up mock DoH resolver
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .query_param("name", "www.example.com")
+                    .query_param("type", "A")
+                    .header("Accept", "application/dns-json");
+                then.status(200).json_body(serde_json::json!({
+                    "Status": 0,
+                    "Answer": [
+                        { "name": "www.example.com", "type": 5, "data": "cdn.example.net." },
+                        { "name": "cdn.example.net", "type": 1, "data": "203.0.113.7" }
+                    ]
+                }));
+            })
+            .await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).query_param("name", "mail.example.com");
+                then.status(200).json_body(serde_json::json!({
+                    "Status": 0,
+                    "Answer": [{ "name": "mail.example.com", "type": 1, "data": "203.0.113.25" }]
+                }));
+            })
+            .await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).query_param("name", "typo.example.com");
+                then.status(200)
+                    .json_body(serde_json::json!({ "Status": 3 }));
+            })
+            .await;
+
+        let verifier = Verifier {
+            http_client: Client::new(),
+            url: format!(
+                "http://{}:{}/dns-query",
+                mock_server.host(),
+                mock_server.port()
+            ),
+        };
+
+        let mut verification = verifier
+            .verify(&[
+                host("www.example.com", "10.10.34.35"),
+                host("mail.example.com", "203.0.113.25"),
+                host("typo.example.com", "198.51.100.1"),
+            ])
+            .await;
+        verification
+            .discrepancies
+            .sort_by_key(|discrepancy| discrepancy.to_string());
+
+        assert_eq!(verification.checked, 3);
+        assert_eq!(
+            verification.discrepancies,
+            vec![
+                Discrepancy::Nonexistent {
+                    host: String::from("typo.example.com"),
+                    ip: "198.51.100.1".parse().unwrap(),
+                },
+                Discrepancy::Mismatch {
+                    host: String::from("www.example.com"),
+                    ip: "10.10.34.35".parse().unwrap(),
+                    expected: vec!["203.0.113.7".parse().unwrap()],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::Command;
+use std::process::Stdio;
+use std::str::FromStr;
+
+/// Who may read the reports of a scan, given with `--encrypt-to`
+#[derive(Debug, Clone)]
+pub enum Recipient {
+    /// An `age1...` public key
+    Age(age::x25519::Recipient),
+    /// A key ID, fingerprint or email address of the GnuPG keyring
+    Gpg(String),
+}
+
+impl FromStr for Recipient {
+    type Err = String;
+
+    fn from_str(recipient: &str) -> Result<Self, Self::Err> {
+        if recipient.starts_with("age1") {
+            return recipient.parse().map(Recipient::Age).map_err(String::from);
+        }
+        if recipient.trim().is_empty() {
+            return Err(String::from("empty recipient"));
+        }
+        Ok(Recipient::Gpg(recipient.to_string()))
+    }
+}
+
+/// A report file being written, encrypted when recipients are given
+/// - Encrypted files get a `.age` or `.gpg` extension added, so they are never mistaken for
+///   plaintext
+pub struct Output {
+    path: PathBuf,
+    sink: Sink,
+}
+
+enum Sink {
+    Plain(BufWriter<File>),
+    Age(age::stream::StreamWriter<BufWriter<File>>),
+    // `gpg` reading the plaintext from its standard input
+    Gpg(Child),
+}
+
+impl Output {
+    pub fn create(path: &Path, recipients: &[Recipient]) -> Result<Self> {
+        let age: Vec<_> = recipients
+            .iter()
+            .filter_map(|recipient| match recipient {
+                Recipient::Age(recipient) => Some(recipient),
+                Recipient::Gpg(_) => None,
+            })
+            .collect();
+        let gpg: Vec<_> = recipients
+            .iter()
+            .filter_map(|recipient| match recipient {
+                Recipient::Gpg(recipient) => Some(recipient),
+                Recipient::Age(_) => None,
+            })
+            .collect();
+
+        let (path, sink) = match (age.is_empty(), gpg.is_empty()) {
+            (true, true) => (
+                path.to_path_buf(),
+                Sink::Plain(BufWriter::new(create(path)?)),
+            ),
+            (false, true) => {
+                let path = with_extension(path, "age");
+                let encryptor = age::Encryptor::with_recipients(
+                    age.iter()
+                        .map(|recipient| *recipient as &dyn age::Recipient),
+                )?;
+                let writer = encryptor.wrap_output(BufWriter::new(create(&path)?))?;
+                (path, Sink::Age(writer))
+            }
+            (true, false) => {
+                let path = with_extension(path, "gpg");
+                let mut command = Command::new("gpg");
+                // Keys of the keyring are trusted, runners have nobody to ask, and never looked up
+                // on key servers
+                command.args(["--batch", "--yes", "--trust-model", "always"]);
+                command.args(["--auto-key-locate", "local", "--encrypt"]);
+                for recipient in gpg {
+                    command.args(["--recipient", recipient]);
+                }
+                let child = command
+                    .arg("--output")
+                    .arg(&path)
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .context("Failed to run gpg, is GnuPG installed?")?;
+                (path, Sink::Gpg(child))
+            }
+            (false, false) => {
+                bail!("Reports are encrypted either with age or with GnuPG, not both")
+            }
+        };
+
+        Ok(Output { path, sink })
+    }
+
+    /// Where the report ends up, extension included
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Complete the file, which encrypted files are unreadable without
+    pub fn finish(self) -> Result<()> {
+        match self.sink {
+            Sink::Plain(mut writer) => writer.flush()?,
+            Sink::Age(writer) => writer.finish()?.flush()?,
+            Sink::Gpg(mut child) => {
+                drop(child.stdin.take());
+                let status = child.wait()?;
+                if !status.success() {
+                    bail!("gpg failed to encrypt {} ({})", self.path.display(), status);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.sink {
+            Sink::Plain(writer) => writer.write(buf),
+            Sink::Age(writer) => writer.write(buf),
+            Sink::Gpg(child) => child.stdin.as_mut().expect("Piped stdin").write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.sink {
+            Sink::Plain(writer) => writer.flush(),
+            Sink::Age(writer) => writer.flush(),
+            Sink::Gpg(child) => child.stdin.as_mut().expect("Piped stdin").flush(),
+        }
+    }
+}
+
+/// Write a whole report, returning where it ended up
+pub fn write(path: &Path, contents: impl AsRef<[u8]>, recipients: &[Recipient]) -> Result<PathBuf> {
+    let mut output = Output::create(path, recipients)?;
+    output.write_all(contents.as_ref())?;
+    let path = output.path().to_path_buf();
+    output.finish()?;
+    Ok(path)
+}
+
+fn create(path: &Path) -> Result<File> {
+    File::create(path).with_context(|| format!("Failed to create {}", path.display()))
+}
+
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_write_should_encrypt_to_age_recipients() {
+        let dir = std::env::temp_dir().join(format!("vulnscan-encrypt-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let identity = age::x25519::Identity::generate();
+        let recipient: Recipient = identity.to_public().to_string().parse().unwrap();
+
+        let path = write(&dir.join("assets.json"), "{\"assets\":[]}", &[recipient]).unwrap();
+        assert_eq!(path, dir.join("assets.json.age"));
+
+        let encrypted = std::fs::read(&path).unwrap();
+        let decryptor = age::Decryptor::new(&encrypted[..]).unwrap();
+        let mut plaintext = String::new();
+        decryptor
+            .decrypt(std::iter::once(&identity as &dyn age::Identity))
+            .unwrap()
+            .read_to_string(&mut plaintext)
+            .unwrap();
+        assert_eq!(plaintext, "{\"assets\":[]}");
+
+        assert!(matches!(
+            "ops@example.com".parse::<Recipient>(),
+            Ok(Recipient::Gpg(_))
+        ));
+        assert!("age1invalid".parse::<Recipient>().is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
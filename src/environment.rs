@@ -0,0 +1,340 @@
+use crate::artifact::Domain;
+use crate::export::Record;
+use crate::i18n::Message;
+use crate::modules::Reportable;
+use crate::modules::Severity;
+use crate::throttle::Limiter;
+use crate::throttle::SendAdaptive;
+use futures::StreamExt;
+use futures::stream;
+use reqwest::Client;
+use reqwest::StatusCode;
+use reqwest::Url;
+use reqwest::header::WWW_AUTHENTICATE;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+const CLASSIFY_CONCURRENCY: usize = 50;
+
+// Labels, or dash-separated parts of them, naming pre-production and internal hosts
+const NON_PRODUCTION_LABELS: &[&str] = &[
+    "dev",
+    "devel",
+    "develop",
+    "development",
+    "stage",
+    "staging",
+    "stg",
+    "uat",
+    "qa",
+    "test",
+    "testing",
+    "sandbox",
+    "preprod",
+    "demo",
+    "internal",
+    "intranet",
+    "corp",
+];
+const PRODUCTION_LABELS: &[&str] = &["www", "prod", "production", "prd"];
+
+/// Where a host likely runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Environment {
+    /// Named like production, its findings are raised one severity level
+    Production,
+    /// Named or behaving like staging, development or internal hosts, reported apart
+    NonProduction,
+    Unknown,
+}
+
+#[derive(Debug)]
+pub struct Classification {
+    pub environment: Environment,
+    /// What gave the environment away, e.g. `named staging`
+    pub reasons: Vec<Message>,
+}
+
+/// Environments of the scanned hosts
+#[derive(Debug, Default)]
+pub struct Environments(BTreeMap<String, Classification>);
+
+impl Environments {
+    /// Classify hosts by name, then by behaviour: private addresses and landing pages behind
+    /// basic authentication are typical of hosts only reachable or meant for staff
+    ///
+    /// # Arguments
+    /// * `limiter` - The limits shared by every request of the scan
+    /// * `domains` - Target domains, whose own labels are not classified
+    pub async fn classify(
+        http_client: &Client,
+        limiter: &Limiter,
+        subdomains: &[Domain],
+        domains: &[&str],
+    ) -> Self {
+        let classifications = stream::iter(subdomains)
+            .map(|subdomain| async move {
+                let (environment, name_reason) = classify_name(&subdomain.name, domains);
+
+                let mut behaviours = Vec::new();
+                // Scanning a private network says nothing, names published for private addresses do
+                let named = subdomain.name.parse::<IpAddr>().is_err();
+                if named && is_private(subdomain.ip) {
+                    behaviours.push(
+                        Message::new("reason-private-address").arg("ip", subdomain.ip.to_string()),
+                    );
+                }
+                for port in &subdomain.open_ports {
+                    if requires_basic_auth(http_client, limiter, &subdomain.name, *port).await {
+                        behaviours
+                            .push(Message::new("reason-basic-auth").arg("port", port.to_string()));
+                    }
+                }
+
+                // Behaviours override production-looking names
+                let (environment, reasons) = match environment {
+                    Environment::NonProduction => (
+                        environment,
+                        name_reason.into_iter().chain(behaviours).collect(),
+                    ),
+                    _ if !behaviours.is_empty() => (Environment::NonProduction, behaviours),
+                    _ => (environment, name_reason.into_iter().collect()),
+                };
+
+                (
+                    subdomain.name.clone(),
+                    Classification {
+                        environment,
+                        reasons,
+                    },
+                )
+            })
+            .buffer_unordered(CLASSIFY_CONCURRENCY)
+            .collect()
+            .await;
+
+        Environments(classifications)
+    }
+
+    pub fn get(&self, host: &str) -> Environment {
+        self.0
+            .get(host)
+            .map_or(Environment::Unknown, |classification| {
+                classification.environment
+            })
+    }
+
+    /// A record of `finding`, raised one severity level on production hosts
+    pub fn record(&self, finding: &dyn Reportable) -> Record {
+        let mut record = Record::new(finding);
+
+        let production =
+            host_of(&record.url).is_some_and(|host| self.get(&host) == Environment::Production);
+        if production {
+            record.severity = match record.severity {
+                Severity::Info => Severity::Low,
+                Severity::Low => Severity::Medium,
+                Severity::Medium | Severity::High => Severity::High,
+            };
+        }
+
+        record
+    }
+
+    /// Lines grouping non-production hosts with why they were classified so, and their findings
+    pub fn summary(&self, records: &[Record]) -> Vec<String> {
+        let hosts: Vec<(&String, &Classification)> = self
+            .0
+            .iter()
+            .filter(|(_, classification)| classification.environment == Environment::NonProduction)
+            .collect();
+        if hosts.is_empty() {
+            return Vec::new();
+        }
+
+        let mut lines = vec![
+            Message::new("non-production-hosts")
+                .arg("count", hosts.len())
+                .to_string(),
+        ];
+        for (host, classification) in hosts {
+            let reasons: Vec<String> = classification
+                .reasons
+                .iter()
+                .map(Message::to_string)
+                .collect();
+            lines.push(format!("\t{}: {}", host, reasons.join(", ")));
+            for record in records
+                .iter()
+                .filter(|record| host_of(&record.url).as_ref() == Some(host))
+            {
+                lines.push(format!("\t\t{}", record));
+            }
+        }
+        lines
+    }
+}
+
+/// The environment a host's name points to, and the label that gave it away
+fn classify_name(name: &str, domains: &[&str]) -> (Environment, Option<Message>) {
+    let name = name.trim_end_matches('.').to_lowercase();
+
+    // Labels of the target domain say nothing, without one the last two labels are its domain
+    let prefix = match domains
+        .iter()
+        .filter(|domain| name == **domain || name.ends_with(&format!(".{}", domain)))
+        .max_by_key(|domain| domain.len())
+    {
+        Some(domain) => name[..name.len() - domain.len()].trim_end_matches('.'),
+        None => name.rsplitn(3, '.').nth(2).unwrap_or_default(),
+    };
+
+    // e.g. `api-staging2` is split into `api` and `staging`
+    let parts: Vec<&str> = prefix
+        .split(['.', '-', '_'])
+        .map(|part| part.trim_end_matches(|c: char| c.is_ascii_digit()))
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    if let Some(part) = parts
+        .iter()
+        .find(|part| NON_PRODUCTION_LABELS.contains(part))
+    {
+        return (Environment::NonProduction, Some(named(part)));
+    }
+    if prefix.is_empty() {
+        return (Environment::Production, Some(Message::new("reason-apex")));
+    }
+    if let Some(part) = parts.iter().find(|part| PRODUCTION_LABELS.contains(part)) {
+        return (Environment::Production, Some(named(part)));
+    }
+
+    (Environment::Unknown, None)
+}
+
+fn named(label: &str) -> Message {
+    Message::new("reason-named").arg("label", label.to_string())
+}
+
+/// Addresses only reachable from inside a network, or over a VPN
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_loopback() || ip.is_link_local(),
+        IpAddr::V6(ip) => ip.is_loopback() || ip.is_unique_local(),
+    }
+}
+
+/// Whether the landing page of `host:port` asks for basic authentication
+async fn requires_basic_auth(
+    http_client: &Client,
+    limiter: &Limiter,
+    host: &str,
+    port: u16,
+) -> bool {
+    for scheme in ["https", "http"] {
+        let url = format!("{}://{}:{}/", scheme, host, port);
+        let Ok(resp) = http_client.get(&url).send_adaptive(limiter).await else {
+            continue;
+        };
+
+        return resp.status() == StatusCode::UNAUTHORIZED
+            && resp
+                .headers()
+                .get_all(WWW_AUTHENTICATE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .any(|value| value.to_lowercase().starts_with("basic"));
+    }
+
+    false
+}
+
+/// The host a finding was reported against, e.g. `tls://host:port` or `https://host:port/path`
+pub fn host_of(url: &str) -> Option<String> {
+    Url::parse(url)
+        .ok()?
+        .host_str()
+        .map(|host| host.trim_matches(['[', ']']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[test]
+    fn test_classify_name_should_ignore_target_domain_labels() {
+        let domains = ["dev.example.com"];
+
+        assert_eq!(
+            classify_name("api-staging2.dev.example.com", &domains),
+            (Environment::NonProduction, Some(named("staging")))
+        );
+        assert_eq!(
+            classify_name("www.dev.example.com", &domains),
+            (Environment::Production, Some(named("www")))
+        );
+        assert_eq!(
+            classify_name("shop.dev.example.com", &domains),
+            (Environment::Unknown, None)
+        );
+        assert_eq!(
+            classify_name("uat.example.org", &[]),
+            (Environment::NonProduction, Some(named("uat")))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_classify_should_group_hosts_behind_basic_auth() {
+        let mock_server = MockServer::start_async().await;
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(401)
+                    .header("WWW-Authenticate", "Basic realm=\"Preview\"");
+            })
+            .await;
+
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let port = mock_server.port();
+        let subdomains = [
+            Domain {
+                name: String::from("www.localhost"),
+                ip: "203.0.113.10".parse().unwrap(),
+                open_ports: Vec::new(),
+            },
+            Domain {
+                name: String::from("localhost"),
+                ip: "203.0.113.11".parse().unwrap(),
+                open_ports: vec![port],
+            },
+        ];
+
+        let environments =
+            Environments::classify(&client, &Limiter::default(), &subdomains, &["localhost"]).await;
+
+        assert_eq!(environments.get("www.localhost"), Environment::Production);
+        assert_eq!(environments.get("localhost"), Environment::NonProduction);
+
+        let record = Record {
+            kind: String::from("DirectoryListing"),
+            module: None,
+            title: crate::modules::title("DirectoryListing"),
+            url: format!("https://localhost:{}/", port),
+            severity: Severity::Low,
+            evidence: Vec::new(),
+            annotation: None,
+        };
+        assert_eq!(
+            environments.summary(&[record]),
+            vec![
+                String::from("Non-production hosts (1)"),
+                format!("\tlocalhost: basic authentication on port {}", port),
+                format!("\t\t[Low] Directory listing: https://localhost:{}/", port),
+            ]
+        );
+    }
+}
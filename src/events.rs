@@ -0,0 +1,162 @@
+use crate::annotations::Annotation;
+use crate::encrypt::Output;
+use crate::encrypt::Recipient;
+use crate::export::Record;
+use crate::i18n::Lang;
+use crate::inventory::Page;
+use crate::manifest::Manifest;
+use crate::modules::Severity;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::LineWriter;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Where `--format jsonl` streams events, set once at the start of a scan
+static SINK: Mutex<Option<Sink>> = Mutex::new(None);
+
+enum Sink {
+    Stdout,
+    // Flushed line by line so that readers see events as they come, encrypted files excepted
+    File(LineWriter<Output>),
+}
+
+/// Something the scan found, streamed as one JSON object per line as soon as it is known
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// What produced the stream, always the first event
+    Manifest(&'a Manifest),
+    /// A name resolved, or imported, before its ports are scanned
+    Host { host: &'a str, ip: IpAddr },
+    Port {
+        host: &'a str,
+        ip: IpAddr,
+        port: u16,
+    },
+    /// A port answering HTTP, with its landing page
+    Endpoint {
+        url: &'a str,
+        #[serde(flatten)]
+        page: &'a Page,
+    },
+    Finding {
+        /// Stable across scans, as given to `replay`
+        id: String,
+        kind: &'a str,
+        /// Read back by `retest`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        module: Option<&'a str>,
+        /// In English, like the rest of machine-readable output
+        title: String,
+        url: &'a str,
+        severity: Severity,
+        evidence: &'a [String],
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotation: Option<&'a Annotation>,
+    },
+}
+
+impl<'a> Event<'a> {
+    pub fn finding(record: &'a Record) -> Self {
+        Event::Finding {
+            id: record.dedup_key(),
+            kind: &record.kind,
+            module: record.module.as_deref(),
+            title: record.title.translate(Lang::En),
+            url: &record.url,
+            severity: record.severity,
+            evidence: &record.evidence,
+            annotation: record.annotation.as_ref(),
+        }
+    }
+}
+
+/// Stream events to `path`, encrypted to `recipients` if any, or to stdout
+pub fn open(path: Option<&Path>, recipients: &[Recipient]) -> Result<()> {
+    let sink = match path {
+        Some(path) => Sink::File(LineWriter::new(
+            Output::create(path, recipients)
+                .with_context(|| format!("Failed to create the event stream {}", path.display()))?,
+        )),
+        None => Sink::Stdout,
+    };
+    *SINK.lock().expect("Event sink poisoned") = Some(sink);
+    Ok(())
+}
+
+/// Complete the event stream, once the scan emits no more events, returning the file written
+pub fn close() -> Result<Option<PathBuf>> {
+    let sink = SINK.lock().expect("Event sink poisoned").take();
+    let Some(Sink::File(writer)) = sink else {
+        return Ok(None);
+    };
+
+    let output = writer.into_inner().map_err(|e| e.into_error())?;
+    let path = output.path().to_path_buf();
+    output.finish()?;
+    Ok(Some(path))
+}
+
+/// Write an event, unless no stream was opened
+pub fn emit(event: Event) {
+    let mut sink = SINK.lock().expect("Event sink poisoned");
+    let written = match sink.as_mut() {
+        None => return,
+        Some(Sink::Stdout) => writeln!(std::io::stdout(), "{}", line(&event)),
+        Some(Sink::File(writer)) => writeln!(writer, "{}", line(&event)),
+    };
+
+    if let Err(e) = written {
+        log::warn!("Failed to write an event: {}", e);
+    }
+}
+
+/// The event with the time it was emitted at
+fn line(event: &Event) -> Value {
+    let mut line = serde_json::to_value(event).expect("Events serialize to JSON objects");
+    line["time"] = Value::from(chrono::Utc::now().to_rfc3339());
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_should_tag_events_and_flatten_pages() {
+        let page = Page {
+            status: 200,
+            length: 1256,
+            title: Some(String::from("Example Domain")),
+            server: None,
+            excerpt: Some(String::from(
+                "This domain is for use in documentation examples",
+            )),
+        };
+        let endpoint = line(&Event::Endpoint {
+            url: "https://www.example.com:443/",
+            page: &page,
+        });
+
+        assert_eq!(endpoint["event"], "endpoint");
+        assert_eq!(endpoint["url"], "https://www.example.com:443/");
+        assert_eq!(endpoint["status"], 200);
+        assert_eq!(endpoint["title"], "Example Domain");
+        assert!(endpoint["time"].is_string());
+
+        let port = line(&Event::Port {
+            host: "www.example.com",
+            ip: "203.0.113.7".parse().unwrap(),
+            port: 8443,
+        });
+        assert_eq!(port["event"], "port");
+        assert_eq!(port["ip"], "203.0.113.7");
+    }
+}
@@ -0,0 +1,531 @@
+use crate::annotations::Annotation;
+use crate::annotations::TriageStatus;
+use crate::body;
+use crate::body::ReadBounded;
+use crate::config::Config;
+use crate::config::DefectDojoConfig;
+use crate::config::FaradayConfig;
+use crate::i18n::Message;
+use crate::kb;
+use crate::modules::Reportable;
+use crate::modules::Severity;
+use crate::syslog;
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use clap::ValueEnum;
+use reqwest::Client;
+use reqwest::Response;
+use reqwest::Url;
+use reqwest::multipart;
+use serde_json::Value;
+use serde_json::json;
+use sha2::Digest;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Vulnerability management platforms findings are pushed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Platform {
+    #[value(name = "defectdojo")]
+    DefectDojo,
+    Faraday,
+    /// A SIEM collecting CEF or LEEF events over syslog
+    Syslog,
+}
+
+/// The ID of a finding, stable across scans, as pushed to platforms and given to `replay`
+pub(crate) fn finding_id(kind: &str, url: &str) -> String {
+    Sha256::digest(format!("{}\n{}", kind, url))
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// A finding detached from its module, as pushed to platforms
+pub struct Record {
+    pub kind: String,
+    /// The module reporting the finding, if known
+    pub module: Option<String>,
+    /// Localized, only shown to people
+    pub title: Message,
+    pub url: String,
+    pub severity: Severity,
+    pub evidence: Vec<String>,
+    /// Triage carried forward from `annotate`
+    pub annotation: Option<Annotation>,
+}
+
+impl Record {
+    pub fn new(finding: &dyn Reportable) -> Self {
+        Record {
+            kind: finding.kind().to_string(),
+            module: finding.module().map(str::to_string),
+            title: finding.title(),
+            url: finding.url().to_string(),
+            severity: finding.severity(),
+            evidence: finding.evidence(),
+            annotation: None,
+        }
+    }
+
+    /// Stable across scans, so platforms recognize findings imported again
+    pub(crate) fn dedup_key(&self) -> String {
+        finding_id(&self.kind, &self.url)
+    }
+
+    /// Remediation steps then references from the knowledge base, empty for unknown kinds
+    fn resolution(&self) -> String {
+        let Some((module, entry)) = kb::for_finding(&self.kind, &self.url) else {
+            return String::new();
+        };
+        entry
+            .remediation
+            .iter()
+            .map(String::as_str)
+            .chain(kb::references(module))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn description(&self) -> String {
+        let mut description = format!("{} found at {}", self.kind, self.url);
+        for evidence in &self.evidence {
+            description.push('\n');
+            description.push_str(evidence);
+        }
+        description
+    }
+}
+
+/// The localized one-line summary of the finding, e.g. `[High] .env disclosure: https://host/.env`
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {}",
+            self.severity.label(),
+            self.title,
+            self.url
+        )
+    }
+}
+
+/// Settings of `[defectdojo]`, checked before the scan starts
+struct DefectDojo<'a> {
+    url: Url,
+    api_key: &'a str,
+    // Form fields picking the engagement
+    context: Vec<(&'static str, String)>,
+}
+
+/// Settings of `[faraday]`, checked before the scan starts
+struct Faraday<'a> {
+    url: Url,
+    token: &'a str,
+}
+
+impl Platform {
+    /// Fail on missing settings before scanning, rather than when pushing findings
+    pub fn validate(self, config: &Config) -> Result<()> {
+        match self {
+            Platform::DefectDojo => defectdojo(&config.defectdojo).map(drop),
+            Platform::Faraday => faraday(&config.faraday).map(drop),
+            Platform::Syslog => syslog::validate(&config.syslog).map(drop),
+        }
+    }
+
+    pub async fn push(
+        self,
+        http_client: &Client,
+        config: &Config,
+        records: &[Record],
+    ) -> Result<()> {
+        if records.is_empty() {
+            log::info!("No findings to push to {:?}", self);
+            return Ok(());
+        }
+
+        match self {
+            Platform::DefectDojo => push_defectdojo(http_client, &config.defectdojo, records).await,
+            Platform::Faraday => push_faraday(http_client, &config.faraday, records).await,
+            Platform::Syslog => syslog::push(&config.syslog, records).await,
+        }
+    }
+}
+
+fn required<'a>(value: &'a Option<String>, section: &str, key: &str) -> Result<&'a str> {
+    value.as_deref().with_context(|| {
+        format!(
+            "Missing {} in the [{}] section of the configuration",
+            key, section
+        )
+    })
+}
+
+/// `base` with `segments` appended to its path
+fn endpoint(base: &str, segments: &[&str]) -> Result<Url> {
+    let mut url = Url::parse(base).with_context(|| format!("Invalid URL {}", base))?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("Invalid URL {}", base))?
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
+
+fn defectdojo(config: &DefectDojoConfig) -> Result<DefectDojo<'_>> {
+    let url = endpoint(
+        required(&config.url, "defectdojo", "url")?,
+        &["api", "v2", "import-scan", ""],
+    )?;
+    let api_key = required(&config.api_key, "defectdojo", "api_key")?;
+
+    let context = match (
+        config.engagement,
+        &config.product_name,
+        &config.engagement_name,
+    ) {
+        (Some(engagement), _, _) => vec![("engagement", engagement.to_string())],
+        (None, Some(product), Some(engagement)) => vec![
+            ("product_name", product.clone()),
+            ("engagement_name", engagement.clone()),
+            ("auto_create_context", String::from("true")),
+        ],
+        _ => bail!(
+            "Set engagement, or product_name and engagement_name, in the [defectdojo] section of the configuration"
+        ),
+    };
+
+    Ok(DefectDojo {
+        url,
+        api_key,
+        context,
+    })
+}
+
+fn faraday(config: &FaradayConfig) -> Result<Faraday<'_>> {
+    let workspace = required(&config.workspace, "faraday", "workspace")?;
+    let url = endpoint(
+        required(&config.url, "faraday", "url")?,
+        &["_api", "v3", "ws", workspace, "bulk_create"],
+    )?;
+    let token = required(&config.token, "faraday", "token")?;
+
+    Ok(Faraday { url, token })
+}
+
+async fn check_response(platform: &str, response: Response) -> Result<()> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response
+            .text_bounded(body::DEFAULT_MAX_BODY_SIZE)
+            .await
+            .unwrap_or_default();
+        bail!(
+            "{} rejected the findings with {}: {}",
+            platform,
+            status,
+            body.chars().take(512).collect::<String>()
+        );
+    }
+    Ok(())
+}
+
+/// Import findings as a Generic Findings Import scan
+/// - `unique_id_from_tool` lets DefectDojo deduplicate findings across imports
+async fn push_defectdojo(
+    http_client: &Client,
+    config: &DefectDojoConfig,
+    records: &[Record],
+) -> Result<()> {
+    let settings = defectdojo(config)?;
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+
+    let findings: Vec<Value> = records
+        .iter()
+        .map(|record| {
+            let mut finding = json!({
+                "title": record.kind,
+                "description": record.description(),
+                "severity": match record.severity {
+                    Severity::Info => "Info",
+                    Severity::Low => "Low",
+                    Severity::Medium => "Medium",
+                    Severity::High => "High",
+                },
+                "date": date,
+                "unique_id_from_tool": record.dedup_key(),
+                "vuln_id_from_tool": record.kind,
+                "endpoints": [record.url],
+                "static_finding": false,
+                "dynamic_finding": true,
+            });
+            if let Some((module, entry)) = kb::for_finding(&record.kind, &record.url) {
+                finding["cwe"] = json!(entry.cwe);
+                finding["mitigation"] = json!(entry.remediation.join("\n"));
+                finding["references"] = json!(kb::references(module).join("\n"));
+            }
+            match record
+                .annotation
+                .as_ref()
+                .and_then(|annotation| annotation.status)
+            {
+                Some(TriageStatus::Confirmed) => finding["verified"] = json!(true),
+                Some(TriageStatus::AcceptedRisk) => finding["risk_accepted"] = json!(true),
+                Some(TriageStatus::FalsePositive) => finding["false_p"] = json!(true),
+                None => {}
+            }
+            finding
+        })
+        .collect();
+
+    let report = serde_json::to_vec(&json!({ "findings": findings }))?;
+
+    let form = settings.context.into_iter().fold(
+        multipart::Form::new()
+            .text("scan_type", "Generic Findings Import")
+            .text("minimum_severity", "Info")
+            .text("active", "true")
+            .text("verified", "false")
+            .text("deduplication_on_engagement", "true")
+            .part(
+                "file",
+                multipart::Part::bytes(report)
+                    .file_name("vulnscan.json")
+                    .mime_str("application/json")?,
+            ),
+        |form, (name, value)| form.text(name, value),
+    );
+
+    let response = http_client
+        .post(settings.url)
+        .header("Authorization", format!("Token {}", settings.api_key))
+        .multipart(form)
+        .send()
+        .await?;
+    check_response("DefectDojo", response).await?;
+
+    log::info!("Imported {} findings into DefectDojo", records.len());
+    Ok(())
+}
+
+/// Create findings as web vulnerabilities of their host and port
+/// - `external_id` lets Faraday deduplicate findings across imports
+async fn push_faraday(
+    http_client: &Client,
+    config: &FaradayConfig,
+    records: &[Record],
+) -> Result<()> {
+    let settings = faraday(config)?;
+
+    // Host -> (port, scheme) -> vulnerabilities
+    let mut hosts: BTreeMap<String, BTreeMap<(u16, String), Vec<Value>>> = BTreeMap::new();
+
+    for record in records {
+        let Ok(url) = Url::parse(&record.url) else {
+            log::warn!(
+                "Not pushing {} with invalid URL {}",
+                record.kind,
+                record.url
+            );
+            continue;
+        };
+
+        let host = url.host_str().unwrap_or_default().to_string();
+        let port = url.port_or_known_default().unwrap_or_default();
+        let status = record
+            .annotation
+            .as_ref()
+            .and_then(|annotation| annotation.status);
+
+        hosts
+            .entry(host)
+            .or_default()
+            .entry((port, url.scheme().to_string()))
+            .or_default()
+            .push(json!({
+                "type": "VulnerabilityWeb",
+                "name": record.kind,
+                "desc": record.description(),
+                "data": record.evidence.join("\n"),
+                "severity": match record.severity {
+                    Severity::Info => "informational",
+                    Severity::Low => "low",
+                    Severity::Medium => "medium",
+                    Severity::High => "high",
+                },
+                "external_id": record.dedup_key(),
+                "website": url.origin().ascii_serialization(),
+                "path": url.path(),
+                "query": url.query().unwrap_or_default(),
+                "method": "GET",
+                "status": match status {
+                    Some(TriageStatus::AcceptedRisk) => "risk-accepted",
+                    Some(TriageStatus::FalsePositive) => "closed",
+                    _ => "open",
+                },
+                "confirmed": status == Some(TriageStatus::Confirmed),
+                "refs": [],
+                "resolution": record.resolution(),
+                "policyviolations": [],
+            }));
+    }
+
+    let hosts: Vec<Value> = hosts
+        .into_iter()
+        .map(|(host, services)| {
+            let services: Vec<Value> = services
+                .into_iter()
+                .map(|((port, scheme), vulnerabilities)| {
+                    json!({
+                        "name": scheme,
+                        "port": port,
+                        "protocol": "tcp",
+                        "status": "open",
+                        "vulnerabilities": vulnerabilities,
+                    })
+                })
+                .collect();
+
+            json!({
+                "ip": host,
+                "hostnames": [host],
+                "description": "",
+                "services": services,
+                "vulnerabilities": [],
+            })
+        })
+        .collect();
+
+    let body = json!({
+        "hosts": hosts,
+        "command": {
+            "tool": "vulnscan",
+            "command": "vulnscan",
+            "params": "",
+            "user": "vulnscan",
+            "hostname": "",
+            "start_date": chrono::Utc::now().to_rfc3339(),
+            "duration": 0,
+            "import_source": "shell",
+        },
+    });
+
+    let response = http_client
+        .post(settings.url)
+        .header("Authorization", format!("Token {}", settings.token))
+        .json(&body)
+        .send()
+        .await?;
+    check_response("Faraday", response).await?;
+
+    log::info!("Created {} findings in Faraday", records.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::title;
+    use httpmock::prelude::*;
+
+    fn records() -> Vec<Record> {
+        vec![Record {
+            kind: String::from("GitConfigLeakage"),
+            module: Some(String::from("http/git_config_leakage")),
+            title: title("GitConfigLeakage"),
+            url: String::from("https://www.example.com/.git/config"),
+            severity: Severity::High,
+            evidence: vec![String::from("[branch \"main\"]")],
+            annotation: None,
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_push_should_import_generic_findings_into_defectdojo() {
+        // Set up mock DefectDojo server
+        let mock_server = MockServer::start_async().await;
+        let records = records();
+
+        let import = mock_server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/api/v2/import-scan/")
+                    .header("Authorization", "Token s3cr3t")
+                    .body_includes("Generic Findings Import")
+                    .body_includes(records[0].dedup_key())
+                    .body_includes(r#""severity":"High""#)
+                    .body_includes(r#""cwe":527"#);
+                then.status(201).body(r#"{"test": 1}"#);
+            })
+            .await;
+
+        let mut config = Config {
+            defectdojo: DefectDojoConfig {
+                url: Some(format!(
+                    "http://{}:{}",
+                    mock_server.host(),
+                    mock_server.port()
+                )),
+                api_key: Some(String::from("s3cr3t")),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // An engagement is required
+        assert!(Platform::DefectDojo.validate(&config).is_err());
+
+        config.defectdojo.engagement = Some(3);
+        Platform::DefectDojo
+            .push(&Client::new(), &config, &records)
+            .await
+            .unwrap();
+
+        import.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_push_should_bulk_create_web_vulnerabilities_in_faraday() {
+        // Set up mock Faraday server
+        let mock_server = MockServer::start_async().await;
+
+        let bulk_create = mock_server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/_api/v3/ws/acme/bulk_create")
+                    .header("Authorization", "Token s3cr3t")
+                    .body_includes(r#""type":"VulnerabilityWeb""#)
+                    .body_includes(r#""severity":"high""#)
+                    .body_includes(r#""port":443"#)
+                    .body_includes(r#""resolution":"Deploy build artifacts"#);
+                then.status(201);
+            })
+            .await;
+
+        let mut config = Config {
+            faraday: FaradayConfig {
+                url: Some(format!(
+                    "http://{}:{}/",
+                    mock_server.host(),
+                    mock_server.port()
+                )),
+                token: Some(String::from("s3cr3t")),
+                workspace: None,
+            },
+            ..Default::default()
+        };
+
+        // A workspace is required
+        assert!(Platform::Faraday.validate(&config).is_err());
+
+        config.faraday.workspace = Some(String::from("acme"));
+        Platform::Faraday
+            .push(&Client::new(), &config, &records())
+            .await
+            .unwrap();
+
+        bulk_create.assert_async().await;
+    }
+}
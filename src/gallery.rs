@@ -0,0 +1,158 @@
+use crate::inventory::Page;
+use crate::inventory::Technology;
+use crate::manifest::Manifest;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// A live endpoint as listed in the gallery
+pub struct Entry<'a> {
+    pub url: String,
+    pub page: &'a Page,
+    pub technologies: &'a BTreeSet<Technology>,
+}
+
+// Pages of a gallery only show what they were given, scripts and remote content included
+const CONTENT_SECURITY_POLICY: &str = "default-src 'none'; style-src 'unsafe-inline'";
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;background:#f6f7f9;color:#222}\
+header p{color:#666}\
+main{display:grid;grid-template-columns:repeat(auto-fill,minmax(24em,1fr));gap:1em}\
+article{background:#fff;border:1px solid #ddd;border-radius:6px;padding:1em;overflow:hidden}\
+article h2{font-size:1em;margin:0 0 .5em;word-break:break-all}\
+.status{display:inline-block;padding:0 .4em;border-radius:3px;color:#fff;font-weight:bold}\
+.s2{background:#2e7d32}.s3{background:#1565c0}.s4{background:#ef6c00}.s5{background:#c62828}\
+.title{font-weight:bold;margin:.5em 0}.server{color:#555}\
+.tech{display:inline-block;background:#eceff1;border-radius:3px;padding:0 .4em;margin:.2em .2em 0 0;font-size:.85em}\
+pre{white-space:pre-wrap;background:#fafafa;border:1px solid #eee;padding:.5em;font-size:.85em;max-height:10em;overflow:auto}";
+
+/// A standalone HTML page listing `entries`, grouped by status then title so lookalike pages
+/// sit together
+/// - Everything taken from the target is escaped, and the page loads nothing
+pub fn render(target: &str, entries: &[Entry], manifest: &Manifest) -> String {
+    let mut entries: Vec<&Entry> = entries.iter().collect();
+    entries.sort_by(|a, b| {
+        (a.page.status, &a.page.title, &a.url).cmp(&(b.page.status, &b.page.title, &b.url))
+    });
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta http-equiv=\"Content-Security-Policy\" content=\"{}\">\n\
+         <title>{} - vulnscan gallery</title>\n<style>{}</style>\n</head>\n<body>\n\
+         <header>\n<h1>{}</h1>\n<p>{} live endpoints, {} {} ({}), {}</p>\n</header>\n<main>\n",
+        CONTENT_SECURITY_POLICY,
+        escape(target),
+        STYLE,
+        escape(target),
+        entries.len(),
+        manifest.tool,
+        manifest.version,
+        manifest.commit,
+        escape(&manifest.started_at),
+    );
+
+    for entry in entries {
+        let page = entry.page;
+        let _ = write!(
+            html,
+            "<article>\n<h2><span class=\"status s{}\">{}</span> \
+             <a href=\"{}\" rel=\"noreferrer\">{}</a></h2>\n",
+            page.status / 100,
+            page.status,
+            escape(&entry.url),
+            escape(&entry.url),
+        );
+        if let Some(title) = &page.title {
+            let _ = writeln!(html, "<div class=\"title\">{}</div>", escape(title));
+        }
+        let _ = write!(html, "<div class=\"server\">{} bytes", page.length);
+        if let Some(server) = &page.server {
+            let _ = write!(html, ", {}", escape(server));
+        }
+        html.push_str("</div>\n");
+
+        if !entry.technologies.is_empty() {
+            html.push_str("<div>");
+            for technology in entry.technologies {
+                let name = match &technology.version {
+                    Some(version) => format!("{} {}", technology.name, version),
+                    None => technology.name.clone(),
+                };
+                let _ = write!(html, "<span class=\"tech\">{}</span>", escape(&name));
+            }
+            html.push_str("</div>\n");
+        }
+        if let Some(excerpt) = &page.excerpt {
+            let _ = writeln!(html, "<pre>{}</pre>", escape(excerpt));
+        }
+        html.push_str("</article>\n");
+    }
+
+    html.push_str("</main>\n</body>\n</html>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::modules::Selection;
+
+    #[test]
+    fn test_render_should_sort_by_status_and_escape_target_content() {
+        let technologies = BTreeSet::from([Technology {
+            name: String::from("nginx"),
+            version: Some(String::from("1.25.3")),
+        }]);
+        let page = |status, title: &str| Page {
+            status,
+            length: 1024,
+            title: Some(title.to_string()),
+            server: Some(String::from("nginx/1.25.3")),
+            excerpt: Some(String::from("<script>alert(1)</script>")),
+        };
+        let (forbidden, home) = (page(403, "Forbidden"), page(200, "Home & \"Shop\""));
+        let entries = [
+            Entry {
+                url: String::from("https://admin.example.com:443/"),
+                page: &forbidden,
+                technologies: &technologies,
+            },
+            Entry {
+                url: String::from("https://www.example.com:443/"),
+                page: &home,
+                technologies: &technologies,
+            },
+        ];
+
+        let html = render(
+            "example.com",
+            &entries,
+            &Manifest::new(&Config::default(), &Selection::default(), false, true),
+        );
+
+        let www = html.find("https://www.example.com:443/").unwrap();
+        let admin = html.find("https://admin.example.com:443/").unwrap();
+        assert!(www < admin);
+        assert!(html.contains("Home &amp; &quot;Shop&quot;"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<span class=\"tech\">nginx 1.25.3</span>"));
+    }
+}
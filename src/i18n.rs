@@ -0,0 +1,185 @@
+use clap::ValueEnum;
+use fluent::FluentArgs;
+use fluent::FluentResource;
+use fluent::FluentValue;
+use fluent::concurrent::FluentBundle;
+use once_cell::sync::Lazy;
+use std::fmt;
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+static EN: Lazy<FluentBundle<FluentResource>> =
+    Lazy::new(|| bundle(Lang::En, include_str!("../locales/en/vulnscan.ftl")));
+static ZH_TW: Lazy<FluentBundle<FluentResource>> =
+    Lazy::new(|| bundle(Lang::ZhTw, include_str!("../locales/zh-TW/vulnscan.ftl")));
+
+/// Languages of the CLI and report text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    En,
+    /// Traditional Chinese
+    #[value(name = "zh-TW")]
+    ZhTw,
+}
+
+impl Lang {
+    fn id(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::ZhTw => "zh-TW",
+        }
+    }
+
+    /// The language of a POSIX locale, e.g. `zh_TW.UTF-8`, English being the default
+    fn from_locale(locale: &str) -> Option<Self> {
+        let locale = locale.split(['.', '@']).next()?.replace('_', "-");
+        let locale = locale.to_lowercase();
+        match locale.as_str() {
+            "" | "c" | "posix" => None,
+            "zh-tw" | "zh-hk" | "zh-mo" => Some(Lang::ZhTw),
+            _ if locale.starts_with("zh-hant") => Some(Lang::ZhTw),
+            _ => Some(Lang::En),
+        }
+    }
+
+    fn bundle(self) -> &'static FluentBundle<FluentResource> {
+        match self {
+            Lang::En => &EN,
+            Lang::ZhTw => &ZH_TW,
+        }
+    }
+}
+
+/// Pick the language of the output, `None` following the locale of the environment
+pub fn init(lang: Option<Lang>) {
+    let lang = lang.unwrap_or_else(|| {
+        // The precedence POSIX gives to locale variables
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find_map(|locale| Lang::from_locale(&locale))
+            .unwrap_or(Lang::En)
+    });
+    let _ = LANG.set(lang);
+}
+
+pub fn current() -> Lang {
+    LANG.get().copied().unwrap_or(Lang::En)
+}
+
+fn bundle(lang: Lang, source: &'static str) -> FluentBundle<FluentResource> {
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(_, errors)| {
+        panic!("Failed to parse {} messages: {:?}", lang.id(), errors)
+    });
+    let langid: LanguageIdentifier = lang.id().parse().expect("Invalid language identifier");
+
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Isolation marks end up as garbage in terminals and text reports
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .expect("Failed to add messages to bundle");
+    bundle
+}
+
+/// A localizable text, kept as a message id and its arguments until it is displayed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub id: String,
+    args: Vec<(&'static str, FluentValue<'static>)>,
+}
+
+impl Message {
+    pub fn new(id: impl Into<String>) -> Self {
+        Message {
+            id: id.into(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, name: &'static str, value: impl Into<FluentValue<'static>>) -> Self {
+        self.args.push((name, value.into()));
+        self
+    }
+
+    /// The text in `lang`, falling back to English, then to the message id
+    pub fn translate(&self, lang: Lang) -> String {
+        let mut args = FluentArgs::new();
+        for (name, value) in &self.args {
+            args.set(*name, value.clone());
+        }
+
+        for bundle in [lang.bundle(), Lang::En.bundle()] {
+            let Some(pattern) = bundle
+                .get_message(&self.id)
+                .and_then(|message| message.value())
+            else {
+                continue;
+            };
+
+            let mut errors = Vec::new();
+            let text = bundle.format_pattern(pattern, Some(&args), &mut errors);
+            if !errors.is_empty() {
+                log::debug!("Failed to format message {}: {:?}", self.id, errors);
+            }
+            return text.into_owned();
+        }
+
+        self.id.clone()
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.translate(current()))
+    }
+}
+
+/// Shorthand for the text of message `id` in the current language
+pub fn text(id: &str) -> String {
+    Message::new(id).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_should_fall_back_to_id() {
+        let message = Message::new("plan-hosts").arg("count", 3);
+
+        assert_eq!(message.translate(Lang::En), "Hosts (3)");
+        assert_eq!(message.translate(Lang::ZhTw), "主機（3）");
+        assert_eq!(
+            Message::new("no-such-message").translate(Lang::ZhTw),
+            "no-such-message"
+        );
+    }
+
+    #[test]
+    fn test_locales_should_translate_every_message() {
+        let ids = |source: &str| -> Vec<String> {
+            source
+                .lines()
+                .filter_map(|line| line.split_once(" ="))
+                .map(|(id, _)| id.to_string())
+                .filter(|id| !id.starts_with([' ', '#']))
+                .collect()
+        };
+
+        assert_eq!(
+            ids(include_str!("../locales/en/vulnscan.ftl")),
+            ids(include_str!("../locales/zh-TW/vulnscan.ftl"))
+        );
+    }
+
+    #[test]
+    fn test_from_locale_should_recognize_traditional_chinese() {
+        assert_eq!(Lang::from_locale("zh_TW.UTF-8"), Some(Lang::ZhTw));
+        assert_eq!(Lang::from_locale("zh_Hant_HK"), Some(Lang::ZhTw));
+        assert_eq!(Lang::from_locale("en_US.UTF-8"), Some(Lang::En));
+        assert_eq!(Lang::from_locale("C"), None);
+    }
+}
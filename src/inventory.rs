@@ -0,0 +1,590 @@
+use crate::artifact::Domain;
+use crate::body::ReadBounded;
+use crate::detections;
+use crate::manifest::Manifest;
+use crate::throttle::Limiter;
+use crate::throttle::SendAdaptive;
+use anyhow::Result;
+use clap::ValueEnum;
+use futures::StreamExt;
+use futures::stream;
+use rand::Rng;
+use reqwest::Client;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+const FINGERPRINT_CONCURRENCY: usize = 50;
+// How long a port may stay silent before it is taken to wait for the client to speak first
+const BANNER_TIMEOUT: Duration = Duration::from_secs(1);
+// How long a port may take to answer the raw request
+const SNIFF_TIMEOUT: Duration = Duration::from_secs(3);
+// TLS alert record, sent by TLS servers in answer to plaintext
+const TLS_ALERT: u8 = 0x15;
+
+// Characters of visible text kept as the excerpt of a page
+const EXCERPT_LENGTH: usize = 300;
+
+/// How `--inventory` writes the discovered assets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InventoryFormat {
+    /// A plain list of hosts, ports and technologies
+    Assets,
+    /// A CycloneDX 1.5 BOM of services and the technologies they depend on
+    Cyclonedx,
+}
+
+/// A host of the attack surface
+#[derive(Debug, Serialize)]
+pub struct Asset {
+    pub name: String,
+    pub ip: IpAddr,
+    pub services: Vec<Service>,
+}
+
+/// An open port, with the technologies seen when it speaks HTTP
+#[derive(Debug, Serialize)]
+pub struct Service {
+    pub port: u16,
+    /// `http` or `https`, unset for other protocols
+    pub scheme: Option<&'static str>,
+    pub technologies: BTreeSet<Technology>,
+    /// The landing page, unset for other protocols
+    pub page: Option<Page>,
+}
+
+/// Summary of the response to `/`, as listed by httpx
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Page {
+    pub status: u16,
+    /// Bytes of the body, from `Content-Length` when given
+    pub length: usize,
+    pub title: Option<String>,
+    pub server: Option<String>,
+    /// The start of the visible text, telling pages apart without screenshots
+    pub excerpt: Option<String>,
+}
+
+impl fmt::Display for Page {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] [{}]", self.status, self.length)?;
+        if let Some(title) = &self.title {
+            write!(f, " [{}]", title)?;
+        }
+        if let Some(server) = &self.server {
+            write!(f, " [{}]", server)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Technology {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+/// Fingerprint every open port of the scanned hosts with a request to `/`
+/// - Requests share the limits of the scan, and read up to `max_body_size` bytes of each page
+pub async fn collect(
+    http_client: &Client,
+    limiter: &Limiter,
+    max_body_size: usize,
+    domains: &[Domain],
+) -> Vec<Asset> {
+    stream::iter(domains)
+        .then(|domain| async move {
+            let services = stream::iter(&domain.open_ports)
+                .map(|&port| {
+                    fingerprint(
+                        http_client,
+                        limiter,
+                        max_body_size,
+                        &domain.name,
+                        domain.ip,
+                        port,
+                    )
+                })
+                .buffered(FINGERPRINT_CONCURRENCY)
+                .collect()
+                .await;
+
+            Asset {
+                name: domain.name.clone(),
+                ip: domain.ip,
+                services,
+            }
+        })
+        .collect()
+        .await
+}
+
+/// Find the scheme a port speaks, and the technologies behind it, with a request to `/`
+/// - Ports failing the request, e.g. slow or non-compliant servers, are told apart by their
+///   banner
+pub(crate) async fn fingerprint(
+    http_client: &Client,
+    limiter: &Limiter,
+    max_body_size: usize,
+    host: &str,
+    ip: IpAddr,
+    port: u16,
+) -> Service {
+    for scheme in ["https", "http"] {
+        let url = format!("{}://{}:{}/", scheme, host, port);
+        let Ok(response) = http_client.get(&url).send_adaptive(limiter).await else {
+            continue;
+        };
+
+        let technologies = technologies(response.headers());
+        let status = response.status().as_u16();
+        let server = response
+            .headers()
+            .get("server")
+            .and_then(|server| server.to_str().ok())
+            .map(str::to_string);
+        let content_length = response.content_length();
+        let body = response
+            .text_bounded(max_body_size)
+            .await
+            .unwrap_or_default();
+
+        return Service {
+            port,
+            scheme: Some(scheme),
+            technologies,
+            page: Some(Page {
+                status,
+                length: content_length.map_or(body.len(), |length| length as usize),
+                title: title(&body),
+                server,
+                excerpt: excerpt(&body),
+            }),
+        };
+    }
+
+    Service {
+        port,
+        scheme: sniff(ip, port).await,
+        technologies: BTreeSet::new(),
+        page: None,
+    }
+}
+
+/// The title of an HTML page, whitespace collapsed
+fn title(body: &str) -> Option<String> {
+    let title = unescape(&detections::get().markup.title.captures(body)?[1])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// The first `EXCERPT_LENGTH` characters of the text of a page, markup stripped and whitespace
+/// collapsed
+fn excerpt(body: &str) -> Option<String> {
+    // The head, scripts, styles and comments, then any tag
+    let markup = &detections::get().markup;
+    let visible = markup.hidden.replace_all(body, " ");
+    let text = unescape(&markup.tag.replace_all(&visible, " "));
+
+    let excerpt: String = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(EXCERPT_LENGTH)
+        .collect();
+    (!excerpt.is_empty()).then_some(excerpt)
+}
+
+/// Text with the common HTML entities decoded, `&amp;` last so that `&amp;lt;` stays `&lt;`
+fn unescape(text: &str) -> String {
+    [
+        ("&nbsp;", " "),
+        ("&lt;", "<"),
+        ("&gt;", ">"),
+        ("&quot;", "\""),
+        ("&#39;", "'"),
+        ("&amp;", "&"),
+    ]
+    .iter()
+    .fold(text.to_string(), |text, (entity, c)| {
+        text.replace(entity, c)
+    })
+}
+
+/// The scheme a port gives away over a raw connection, unset for other protocols
+/// - Services speaking first (SSH, SMTP, FTP, databases...) are HTTP only when their banner is an
+///   HTTP response
+/// - Silent ones are sent a bare request, answered with an HTTP response by HTTP servers and with
+///   a TLS alert by HTTPS ones
+pub(crate) async fn sniff(ip: IpAddr, port: u16) -> Option<&'static str> {
+    let sniff = async {
+        let mut stream = TcpStream::connect((ip, port)).await.ok()?;
+        let mut banner = [0; 16];
+
+        let length = match tokio::time::timeout(BANNER_TIMEOUT, stream.read(&mut banner)).await {
+            Ok(read) => read.ok()?,
+            Err(_) => {
+                stream.write_all(b"GET / HTTP/1.0\r\n\r\n").await.ok()?;
+                stream.read(&mut banner).await.ok()?
+            }
+        };
+
+        match &banner[..length] {
+            [b'H', b'T', b'T', b'P', b'/', ..] => Some("http"),
+            [TLS_ALERT, 3, ..] => Some("https"),
+            _ => None,
+        }
+    };
+
+    tokio::time::timeout(SNIFF_TIMEOUT, sniff).await.ok()?
+}
+
+/// Products of headers like `Apache/2.4.41 (Ubuntu) OpenSSL/1.1.1f`, and platforms of session cookies
+fn technologies(headers: &HeaderMap) -> BTreeSet<Technology> {
+    let mut technologies = BTreeSet::new();
+
+    let fingerprints = &detections::get().fingerprints;
+
+    for header in &fingerprints.product_headers {
+        for value in headers.get_all(header) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+
+            // Comments between parentheses describe the previous product
+            let mut depth = 0;
+            let products = value.split(|c: char| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                c.is_whitespace() || c == ',' || c == '(' || c == ')' || depth > 0
+            });
+
+            for product in products.filter(|product| !product.is_empty()) {
+                let (name, version) = match product.split_once('/') {
+                    Some((name, version)) => (name, Some(version.to_string())),
+                    None => (product, None),
+                };
+                technologies.insert(Technology {
+                    name: name.to_string(),
+                    version,
+                });
+            }
+        }
+    }
+
+    for cookie in headers.get_all("set-cookie") {
+        let Ok(cookie) = cookie.to_str() else {
+            continue;
+        };
+        let name = cookie.split('=').next().unwrap_or_default().trim();
+
+        // Versions from headers are more telling
+        if let Some(platform) = fingerprints.session_cookies.get(name)
+            && !technologies
+                .iter()
+                .any(|technology| technology.name == *platform)
+        {
+            technologies.insert(Technology {
+                name: platform.to_string(),
+                version: None,
+            });
+        }
+    }
+
+    technologies
+}
+
+/// Render the inventory of a scan of `target`, with the manifest of the scan
+pub fn render(
+    format: InventoryFormat,
+    target: &str,
+    assets: &[Asset],
+    manifest: &Manifest,
+) -> Result<String> {
+    let timestamp = chrono::Utc::now().to_rfc3339();
+
+    let inventory = match format {
+        InventoryFormat::Assets => json!({
+            "target": target,
+            "generated_at": timestamp,
+            "manifest": manifest,
+            "assets": assets,
+        }),
+        InventoryFormat::Cyclonedx => cyclonedx(target, &timestamp, assets, manifest)?,
+    };
+
+    Ok(serde_json::to_string_pretty(&inventory)?)
+}
+
+/// Each open port is a service, depending on the technologies it runs
+/// - The manifest is a property of the vulnscan tool, as CycloneDX has no place for it
+fn cyclonedx(
+    target: &str,
+    timestamp: &str,
+    assets: &[Asset],
+    manifest: &Manifest,
+) -> Result<Value> {
+    let technology_ref = |technology: &Technology| match &technology.version {
+        Some(version) => format!("technology:{}@{}", technology.name, version),
+        None => format!("technology:{}", technology.name),
+    };
+
+    let mut services = Vec::new();
+    let mut dependencies = Vec::new();
+
+    for asset in assets {
+        for service in &asset.services {
+            let bom_ref = format!("service:{}:{}", asset.name, service.port);
+
+            services.push(json!({
+                "bom-ref": bom_ref,
+                "name": format!("{}:{}", asset.name, service.port),
+                "endpoints": [format!(
+                    "{}://{}:{}/",
+                    service.scheme.unwrap_or("tcp"),
+                    asset.name,
+                    service.port
+                )],
+                "properties": [
+                    { "name": "vulnscan:host", "value": asset.name },
+                    { "name": "vulnscan:ip", "value": asset.ip.to_string() },
+                    { "name": "vulnscan:port", "value": service.port.to_string() },
+                ],
+            }));
+
+            dependencies.push(json!({
+                "ref": bom_ref,
+                "dependsOn": service.technologies.iter().map(technology_ref).collect::<Vec<_>>(),
+            }));
+        }
+    }
+
+    let technologies: BTreeSet<&Technology> = assets
+        .iter()
+        .flat_map(|asset| &asset.services)
+        .flat_map(|service| &service.technologies)
+        .collect();
+
+    let components: Vec<Value> = technologies
+        .into_iter()
+        .map(|technology| {
+            let mut component = json!({
+                "bom-ref": technology_ref(technology),
+                "type": "application",
+                "name": technology.name,
+            });
+            if let Some(version) = &technology.version {
+                component["version"] = json!(version);
+            }
+            component
+        })
+        .collect();
+
+    Ok(json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", uuid_v4()),
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp,
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "vulnscan",
+                    "version": manifest.version,
+                    "properties": [
+                        { "name": "vulnscan:manifest", "value": serde_json::to_string(manifest)? },
+                    ],
+                }],
+            },
+            "component": { "type": "application", "name": target },
+        },
+        "services": services,
+        "components": components,
+        "dependencies": dependencies,
+    }))
+}
+
+fn uuid_v4() -> String {
+    let mut bytes: [u8; 16] = rand::thread_rng().r#gen();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::DEFAULT_MAX_BODY_SIZE;
+    use crate::config::Config;
+    use crate::modules::Selection;
+    use httpmock::prelude::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_sniff_should_tell_http_from_banners_of_other_services() {
+        // Answers requests with a response hyper rejects, the status line lacking a reason
+        let http = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let http_port = http.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = http.accept().await.unwrap();
+            let mut request = [0; 64];
+            let _ = stream.read(&mut request).await;
+            let _ = stream
+                .write_all(b"HTTP/1.0 200\r\nBroken Header\r\n\r\n")
+                .await;
+        });
+
+        let ssh = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ssh_port = ssh.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = ssh.accept().await.unwrap();
+            let _ = stream.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").await;
+        });
+
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        assert_eq!(sniff(ip, http_port).await, Some("http"));
+        assert_eq!(sniff(ip, ssh_port).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_collect_should_fingerprint_technologies_from_headers() {
+        // Set up mock target HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200)
+                    .header("Server", "Apache/2.4.41 (Ubuntu; x64) OpenSSL/1.1.1f")
+                    .header("X-Powered-By", "PHP/8.1.2")
+                    .header("Set-Cookie", "PHPSESSID=abc; path=/")
+                    .body("<html><head><TITLE>\n  Acme   Shop\n</TITLE></head></html>");
+            })
+            .await;
+
+        let domains = [Domain {
+            name: mock_server.host(),
+            ip: "127.0.0.1".parse().unwrap(),
+            open_ports: vec![mock_server.port()],
+        }];
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+
+        let assets = collect(
+            &client,
+            &Limiter::default(),
+            DEFAULT_MAX_BODY_SIZE,
+            &domains,
+        )
+        .await;
+
+        let technology = |name: &str, version: Option<&str>| Technology {
+            name: name.to_string(),
+            version: version.map(str::to_string),
+        };
+        let service = &assets[0].services[0];
+        assert!(service.scheme.is_some());
+        assert_eq!(
+            service.technologies,
+            BTreeSet::from([
+                technology("Apache", Some("2.4.41")),
+                technology("OpenSSL", Some("1.1.1f")),
+                technology("PHP", Some("8.1.2")),
+            ])
+        );
+        assert_eq!(
+            service.page.as_ref().unwrap().to_string(),
+            "[200] [56] [Acme Shop] [Apache/2.4.41 (Ubuntu; x64) OpenSSL/1.1.1f]"
+        );
+
+        // Nothing but the head, which is no visible text
+        assert_eq!(service.page.as_ref().unwrap().excerpt, None);
+        assert_eq!(
+            excerpt(
+                "<body><script>track()</script><h1>Sign  in</h1>\n<p>Fish &amp; chips</p></body>"
+            ),
+            Some(String::from("Sign in Fish & chips"))
+        );
+    }
+
+    #[test]
+    fn test_render_cyclonedx_should_link_services_to_technologies() {
+        let assets = [Asset {
+            name: String::from("www.example.com"),
+            ip: "203.0.113.7".parse().unwrap(),
+            services: vec![
+                Service {
+                    port: 443,
+                    scheme: Some("https"),
+                    technologies: BTreeSet::from([Technology {
+                        name: String::from("nginx"),
+                        version: Some(String::from("1.18.0")),
+                    }]),
+                    page: None,
+                },
+                Service {
+                    port: 22,
+                    scheme: None,
+                    technologies: BTreeSet::new(),
+                    page: None,
+                },
+            ],
+        }];
+
+        let bom: Value = serde_json::from_str(
+            &render(
+                InventoryFormat::Cyclonedx,
+                "example.com",
+                &assets,
+                &Manifest::new(&Config::default(), &Selection::default(), false, true),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(bom["bomFormat"], "CycloneDX");
+        assert_eq!(
+            bom["metadata"]["tools"]["components"][0]["properties"][0]["name"],
+            "vulnscan:manifest"
+        );
+        assert_eq!(
+            bom["services"][0]["endpoints"][0],
+            "https://www.example.com:443/"
+        );
+        assert_eq!(
+            bom["services"][1]["endpoints"][0],
+            "tcp://www.example.com:22/"
+        );
+        assert_eq!(bom["components"][0]["version"], "1.18.0");
+        assert_eq!(
+            bom["dependencies"][0]["dependsOn"][0],
+            "technology:nginx@1.18.0"
+        );
+    }
+}
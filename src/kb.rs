@@ -0,0 +1,106 @@
+use crate::modules;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+static KB: Lazy<BTreeMap<String, Entry>> = Lazy::new(|| {
+    toml::from_str(include_str!("kb.toml")).expect("Failed to parse the knowledge base")
+});
+
+/// What a check is about and how to fix what it finds
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Entry {
+    /// Kinds of the findings the module reports
+    pub findings: Vec<String>,
+    // Told apart by URL when several modules report the same kinds
+    #[serde(default, skip_serializing)]
+    url: Option<String>,
+    pub cwe: u32,
+    /// The OWASP Top 10 category, e.g. `A05:2021 Security Misconfiguration`
+    pub owasp: String,
+    /// PCI DSS requirements, e.g. `6.2.4`
+    #[serde(default)]
+    pub pci: Vec<String>,
+    pub remediation: Vec<String>,
+}
+
+impl Entry {
+    pub fn cwe_url(&self) -> String {
+        format!("https://cwe.mitre.org/data/definitions/{}.html", self.cwe)
+    }
+}
+
+/// The entry of the module named `module`, e.g. `http/git_config_leakage`
+pub fn get(module: &str) -> Option<&'static Entry> {
+    KB.get(module)
+}
+
+/// Every entry, by module name
+pub fn entries() -> impl Iterator<Item = (&'static str, &'static Entry)> {
+    KB.iter().map(|(module, entry)| (module.as_str(), entry))
+}
+
+/// The module reporting findings of `kind` at `url`, and its entry
+pub fn for_finding(kind: &str, url: &str) -> Option<(&'static str, &'static Entry)> {
+    let candidates: Vec<(&String, &Entry)> = KB
+        .iter()
+        .filter(|(_, entry)| entry.findings.iter().any(|finding| finding == kind))
+        .collect();
+
+    let (module, entry) = match candidates[..] {
+        [candidate] => candidate,
+        _ => candidates.into_iter().find(|(_, entry)| {
+            entry
+                .url
+                .as_ref()
+                .is_some_and(|part| url.contains(part.as_str()))
+        })?,
+    };
+    Some((module.as_str(), entry))
+}
+
+/// The references of the module named `module`
+pub fn references(module: &str) -> Vec<&'static str> {
+    modules::all()
+        .into_iter()
+        .flat_map(|(_, modules)| modules)
+        .find(|candidate| candidate.name() == module)
+        .map(|module| module.references())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_should_be_keyed_by_existing_modules() {
+        let names: Vec<String> = modules::all()
+            .into_iter()
+            .flat_map(|(_, modules)| modules)
+            .map(|module| module.name())
+            .collect();
+
+        for module in KB.keys() {
+            assert!(names.contains(module), "No module named {}", module);
+        }
+    }
+
+    #[test]
+    fn test_for_finding_should_tell_modules_apart_by_url() {
+        let (module, entry) = for_finding("GitConfigLeakage", "https://example.com/.git/config")
+            .expect("Should know leaked git configurations");
+        assert_eq!(module, "http/git_config_leakage");
+        assert_eq!(entry.cwe, 527);
+
+        let (module, _) = for_finding("BucketListable", "https://example.s3.amazonaws.com/")
+            .expect("Should know listable buckets");
+        assert_eq!(module, "cloud/s3");
+        let (module, _) = for_finding("NoStartTls", "smtp://mail.example.com:25")
+            .expect("Should know mail servers without STARTTLS");
+        assert_eq!(module, "service/smtp");
+
+        assert!(for_finding("BucketListable", "https://example.com/").is_none());
+    }
+}
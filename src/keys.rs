@@ -0,0 +1,276 @@
+use crate::config::Config;
+use aes_gcm::Aes256Gcm;
+use aes_gcm::KeyInit;
+use aes_gcm::aead::Aead;
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use rand::Rng;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Environment variable holding the passphrase of the key store
+pub const PASSPHRASE_ENV: &str = "VULNSCAN_KEYS_PASSPHRASE";
+
+// OWASP's recommendation for PBKDF2-HMAC-SHA256, kept low in tests as debug builds are slow
+const KDF_ITERATIONS: u32 = if cfg!(test) { 1_000 } else { 600_000 };
+
+/// API keys of subdomain sources, encrypted with a key derived from a passphrase
+pub struct KeyStore {
+    path: PathBuf,
+    passphrase: String,
+    keys: BTreeMap<String, String>,
+}
+
+/// On-disk layout of the key store, a fresh salt and nonce being drawn on each save
+#[derive(Serialize, Deserialize)]
+struct Sealed {
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl KeyStore {
+    /// `~/.local/share/vulnscan/keys.json` on Linux
+    pub fn default_path() -> Result<PathBuf> {
+        let data_dir = dirs::data_local_dir().ok_or_else(|| anyhow!("No local data directory"))?;
+        Ok(data_dir.join("vulnscan").join("keys.json"))
+    }
+
+    /// Unlock the key store at `path`, which is empty until saved if it does not exist
+    pub fn open(path: &Path, passphrase: &str) -> Result<Self> {
+        let keys = match std::fs::read_to_string(path) {
+            Ok(content) => unseal(&content, passphrase)
+                .with_context(|| format!("Failed to unlock {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+
+        Ok(KeyStore {
+            path: path.to_path_buf(),
+            passphrase: passphrase.to_string(),
+            keys,
+        })
+    }
+
+    pub fn keys(&self) -> &BTreeMap<String, String> {
+        &self.keys
+    }
+
+    pub fn set(&mut self, source: &str, key: &str) -> Result<()> {
+        if source.is_empty()
+            || !source
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            bail!(
+                "Invalid source name {}, use lowercase letters, digits and dashes",
+                source
+            );
+        }
+        if key.trim().is_empty() {
+            bail!("The API key of {} is empty", source);
+        }
+
+        self.keys.insert(source.to_string(), key.trim().to_string());
+        Ok(())
+    }
+
+    /// Forget the key of `source`, returning whether there was one
+    pub fn remove(&mut self, source: &str) -> bool {
+        self.keys.remove(source).is_some()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let content = seal(&self.keys, &self.passphrase)?;
+        std::fs::write(&self.path, content)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn cipher(passphrase: &str, salt: &[u8], iterations: u32) -> Aes256Gcm {
+    let key =
+        pbkdf2::pbkdf2_hmac_array::<sha2::Sha256, 32>(passphrase.as_bytes(), salt, iterations);
+    Aes256Gcm::new(&key.into())
+}
+
+fn seal(keys: &BTreeMap<String, String>, passphrase: &str) -> Result<String> {
+    let salt: [u8; 16] = rand::thread_rng().r#gen();
+    let nonce: [u8; 12] = rand::thread_rng().r#gen();
+
+    let ciphertext = cipher(passphrase, &salt, KDF_ITERATIONS)
+        .encrypt(&nonce.into(), serde_json::to_vec(keys)?.as_slice())
+        .map_err(|_| anyhow!("Failed to encrypt the key store"))?;
+
+    Ok(serde_json::to_string_pretty(&Sealed {
+        iterations: KDF_ITERATIONS,
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })?)
+}
+
+fn unseal(content: &str, passphrase: &str) -> Result<BTreeMap<String, String>> {
+    let sealed: Sealed = serde_json::from_str(content)?;
+    let nonce: [u8; 12] = STANDARD
+        .decode(&sealed.nonce)?
+        .try_into()
+        .map_err(|_| anyhow!("Invalid nonce"))?;
+
+    // Authentication fails on a wrong passphrase as well as on tampering
+    let plaintext = cipher(
+        passphrase,
+        &STANDARD.decode(&sealed.salt)?,
+        sealed.iterations,
+    )
+    .decrypt(
+        &nonce.into(),
+        STANDARD.decode(&sealed.ciphertext)?.as_slice(),
+    )
+    .map_err(|_| anyhow!("Wrong passphrase or corrupted key store"))?;
+
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Where the API key of a source comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Config,
+    KeyStore,
+}
+
+/// API keys of subdomain sources, looked up by modules through `get`
+/// - `[sources]` of the configuration takes precedence over the key store
+#[derive(Debug, Default)]
+pub struct ApiKeys {
+    keys: BTreeMap<String, (String, Origin)>,
+}
+
+impl ApiKeys {
+    /// Gather keys from the configuration and, when `VULNSCAN_KEYS_PASSPHRASE` is set, the key store
+    pub fn load(config: &Config) -> Result<Self> {
+        let store = match std::env::var(PASSPHRASE_ENV) {
+            Ok(passphrase) => Some(KeyStore::open(&KeyStore::default_path()?, &passphrase)?),
+            Err(_) => {
+                let path = KeyStore::default_path()?;
+                if path.exists() {
+                    log::warn!(
+                        "Not using the key store at {}, set {} to unlock it",
+                        path.display(),
+                        PASSPHRASE_ENV
+                    );
+                }
+                None
+            }
+        };
+
+        Ok(Self::merge(config, store.as_ref()))
+    }
+
+    fn merge(config: &Config, store: Option<&KeyStore>) -> Self {
+        let mut keys = BTreeMap::new();
+
+        if let Some(store) = store {
+            for (source, key) in store.keys() {
+                keys.insert(source.clone(), (key.clone(), Origin::KeyStore));
+            }
+        }
+
+        for (source, key) in &config.sources {
+            if !key.is_empty() {
+                keys.insert(source.clone(), (key.clone(), Origin::Config));
+            }
+        }
+
+        ApiKeys { keys }
+    }
+
+    /// The API key of `source`, modules skip their source without one
+    pub fn get(&self, source: &str) -> Option<&str> {
+        self.keys.get(source).map(|(key, _)| key.as_str())
+    }
+
+    /// `(source, key, origin)` of every known key
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, Origin)> {
+        self.keys
+            .iter()
+            .map(|(source, (key, origin))| (source.as_str(), key.as_str(), *origin))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_store_should_round_trip_only_with_the_passphrase() {
+        let path = std::env::temp_dir()
+            .join(format!("vulnscan-keys-{}", std::process::id()))
+            .join("keys.json");
+
+        let mut store = KeyStore::open(&path, "correct horse").unwrap();
+        store.set("securitytrails", " st-key\n").unwrap();
+        store.set("censys", "id:secret").unwrap();
+        assert!(store.set("Shodan!", "key").is_err());
+        store.save().unwrap();
+
+        // Keys are not written in clear
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("st-key"));
+
+        let mut store = KeyStore::open(&path, "correct horse").unwrap();
+        assert_eq!(store.keys()["securitytrails"], "st-key");
+        assert!(store.remove("censys"));
+        assert!(!store.remove("censys"));
+
+        assert!(KeyStore::open(&path, "battery staple").is_err());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_api_keys_should_prefer_the_configuration() {
+        let path = std::env::temp_dir().join(format!("vulnscan-keys-{}.json", std::process::id()));
+        let mut store = KeyStore::open(&path, "passphrase").unwrap();
+        store.set("securitytrails", "from-store").unwrap();
+        store.set("shodan", "shodan-key").unwrap();
+
+        let config = Config {
+            sources: BTreeMap::from([
+                (String::from("securitytrails"), String::from("from-config")),
+                // An unset `${VAR:-}` leaves the store key in effect
+                (String::from("shodan"), String::new()),
+            ]),
+            ..Default::default()
+        };
+
+        let keys = ApiKeys::merge(&config, Some(&store));
+
+        assert_eq!(keys.get("securitytrails"), Some("from-config"));
+        assert_eq!(keys.get("shodan"), Some("shodan-key"));
+        assert_eq!(keys.get("virustotal"), None);
+        assert_eq!(
+            keys.iter().map(|(_, _, origin)| origin).collect::<Vec<_>>(),
+            vec![Origin::Config, Origin::KeyStore]
+        );
+    }
+}
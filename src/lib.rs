@@ -0,0 +1,59 @@
+//! The scanning pipeline behind the `vulnscan` binary, also used by the benchmarks
+
+pub mod action;
+pub mod annotations;
+mod artifact;
+mod authorization;
+pub mod body;
+pub mod cache;
+mod capabilities;
+mod capture;
+mod cluster;
+pub mod compliance;
+pub mod config;
+pub mod detections;
+mod doh;
+pub mod encrypt;
+mod environment;
+mod events;
+pub mod export;
+mod gallery;
+pub mod i18n;
+pub mod inventory;
+pub mod kb;
+pub mod keys;
+pub mod manifest;
+pub mod matcher;
+mod modules;
+mod oob;
+mod ping;
+pub mod policy;
+pub mod presets;
+pub mod queue;
+mod related;
+mod replay;
+mod retest;
+mod scheduler;
+pub mod scope;
+mod selftest;
+mod signing;
+mod stats;
+#[cfg(feature = "postgres-store")]
+mod store;
+mod syn;
+mod syslog;
+pub mod target;
+mod throttle;
+mod typosquat;
+pub mod watchdog;
+pub mod workspace;
+
+pub use modules::Dependency;
+pub use modules::Finding;
+pub use modules::FollowUp;
+pub use modules::HttpModule;
+pub use modules::Intrusiveness;
+pub use modules::Selection;
+pub use modules::Tag;
+pub use modules::http::ScanContext;
+pub use modules::http_modules;
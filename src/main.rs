@@ -1,36 +1,723 @@
-mod action;
-mod modules;
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use env_logger::Env;
+use reqwest::header::HeaderMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use vulnscan::Selection;
+use vulnscan::Tag;
+use vulnscan::action;
+use vulnscan::action::KeysCommand;
+use vulnscan::action::OutputFormat;
+use vulnscan::action::ScanFormat;
+use vulnscan::annotations::TriageStatus;
+use vulnscan::body;
+use vulnscan::cache;
+use vulnscan::compliance::Framework;
+use vulnscan::config;
+use vulnscan::config::Timing;
+use vulnscan::detections;
+use vulnscan::encrypt::Recipient;
+use vulnscan::export::Platform;
+use vulnscan::i18n;
+use vulnscan::i18n::Lang;
+use vulnscan::inventory::InventoryFormat;
+use vulnscan::policy::Policy;
+use vulnscan::presets::Preset;
+use vulnscan::scope::Scope;
+use vulnscan::target;
+use vulnscan::target::Target;
+use vulnscan::workspace::Workspace;
 
 #[derive(Parser)]
 #[command(arg_required_else_help = true)]
 struct Cli {
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Configuration file [default: ~/.config/vulnscan/config.toml]"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAME",
+        env = "VULNSCAN_PROFILE",
+        help = "Apply the [profiles.NAME] overrides of the configuration file"
+    )]
+    profile: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        ignore_case = true,
+        help = "Language of the output [default: from LC_ALL, LC_MESSAGES or LANG]"
+    )]
+    lang: Option<Lang>,
+
     #[command(subcommand)]
     subcommand: SubCommand,
 }
 
+// Parsed once, the size of the variants does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum SubCommand {
-    Modules,
+    Modules {
+        #[arg(
+            long,
+            help = "Show what each subdomain module contributed to the last scan"
+        )]
+        stats: bool,
+
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    /// Check every HTTP module against built-in vulnerable and clean fixtures
+    Selftest,
+    /// Show the CWE, OWASP category, remediation and references of a module's checks
+    Kb {
+        #[arg(help = "The module, e.g. http/git_config_leakage")]
+        module: String,
+
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    /// Manage API keys of subdomain sources, kept in an encrypted key store
+    Keys {
+        #[command(subcommand)]
+        command: KeysSubCommand,
+    },
+    /// Check that a report was signed with the key of `[signing]` and not modified since
+    Verify {
+        #[arg(help = "The report, e.g. assets.json or assets.json.age")]
+        report: PathBuf,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "The detached signature [default: the report followed by .sig]"
+        )]
+        signature: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Ed25519 public key in PEM, instead of public_key_file of [signing]"
+        )]
+        public_key: Option<PathBuf>,
+    },
+    /// List domains sharing analytics IDs, the favicon or the registrant of a domain, without scanning them
+    Related {
+        #[arg(help = "The domain, e.g. example.com")]
+        domain: String,
+
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    /// Triage a finding, e.g. as an accepted risk, carried forward to later reports of it
+    Annotate {
+        #[arg(
+            help = "The ID of the finding, as in finding events and HAR files, the annotated findings being listed if omitted"
+        )]
+        id: Option<String>,
+
+        #[arg(long, value_enum, help = "How the finding was triaged")]
+        status: Option<TriageStatus>,
+
+        #[arg(
+            long,
+            help = "Free text kept with the finding, an empty note removing it"
+        )]
+        note: Option<String>,
+
+        #[arg(long, conflicts_with_all = ["status", "note"], help = "Forget the annotation of the finding")]
+        clear: bool,
+    },
+    /// List the scans recorded in the store of the team, latest first
+    #[cfg(feature = "postgres-store")]
+    History {
+        #[arg(long, default_value_t = 20, help = "How many scans to list")]
+        limit: i64,
+
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    /// Re-issue the request a finding was observed in and tell whether it still reproduces
+    Replay {
+        #[arg(
+            help = "The ID of the finding or its beginning, the findings of the HAR file being listed if omitted"
+        )]
+        id: Option<String>,
+
+        #[arg(long, value_name = "FILE", help = "HAR file written by `scan --har`")]
+        har: PathBuf,
+
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    /// Rerun only the checks which produced the findings of a previous scan, telling which ones were remediated
+    Retest {
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Events of the previous scan, written by `scan --output` or `--format jsonl`"
+        )]
+        report: PathBuf,
+
+        #[arg(
+            long,
+            help = "Rerun modules sending payloads that may disturb the target"
+        )]
+        aggressive: bool,
+
+        #[arg(
+            long,
+            help = "Skip the target authorization check required by the configuration"
+        )]
+        i_am_authorized: bool,
+
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    /// Find registered lookalikes of a domain: homoglyphs, bitsquats and TLD swaps
+    Typosquat {
+        #[arg(help = "The domain, e.g. example.com")]
+        domain: String,
+
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    /// Fetch newer regex patterns, paths and fingerprints of the checks without a new release
+    Update {
+        #[arg(
+            long,
+            value_name = "URL",
+            env = "VULNSCAN_DETECTIONS_CHANNEL",
+            default_value = detections::DEFAULT_CHANNEL,
+            help = "Where the detection data is published, along with its signature at URL.sig"
+        )]
+        channel: String,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Ed25519 public key (SPKI PEM) the data of another publisher is signed with, instead of the release key"
+        )]
+        public_key: Option<PathBuf>,
+    },
     Scan {
         #[arg(
-            help = "The domain to scan",
-            value_parser = |s: &str| Ok::<String, String>(s.to_lowercase())
+            required_unless_present_any = ["stdin", "hosts_from", "ports_from"],
+            conflicts_with_all = ["stdin", "hosts_from", "ports_from"],
+            help = "The domain, URL, IP address or CIDR range to scan"
+        )]
+        target: Option<Target>,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["hosts_from", "ports_from"],
+            help = "Read targets from stdin, one per line"
+        )]
+        stdin: bool,
+
+        #[arg(
+            long,
+            help = "Scan domain targets as they are instead of enumerating their subdomains"
+        )]
+        skip_enumeration: bool,
+
+        #[arg(
+            long,
+            help = "Assume ports 80 and 443 are open instead of probing the top 100 ports"
+        )]
+        skip_port_scan: bool,
+
+        #[arg(
+            long,
+            help = "Port scan every host instead of only those answering a ping"
+        )]
+        skip_ping: bool,
+
+        #[arg(
+            long,
+            value_enum,
+            value_name = "TEMPLATE",
+            help = "Timing of the port scan, from paranoid (0) to insane (5), overriding [ports]"
+        )]
+        timing: Option<Timing>,
+
+        #[arg(
+            long,
+            help = "Cross-check a sample of resolved hosts against Cloudflare's DNS-over-HTTPS resolver"
+        )]
+        verify_dns: bool,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with = "ports_from",
+            help = "Skip enumeration and resolution, scanning the hosts of a hosts.json artifact"
+        )]
+        hosts_from: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            visible_alias = "import-nmap",
+            conflicts_with = "skip_port_scan",
+            help = "Skip every stage up to port scanning, using the open ports of a ports.json artifact, an Nmap XML or a masscan JSON/greppable report"
+        )]
+        ports_from: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Save the hosts and open ports found by the enumeration and port scan stages to DIR"
+        )]
+        artifacts: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write the open ports found by the port scan stage to FILE as an Nmap XML report"
+        )]
+        export_nmap: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_enum,
+            value_name = "PLATFORM",
+            help = "Push findings to a vulnerability management platform configured in the configuration file (repeatable)"
+        )]
+        push: Vec<Platform>,
+
+        #[arg(
+            long,
+            value_name = "DURATION",
+            value_parser = cache::parse_ttl,
+            conflicts_with_all = ["ports_from", "skip_port_scan"],
+            help = "Reuse open ports of hosts port scanned less than DURATION ago, e.g. 24h"
+        )]
+        port_cache_ttl: Option<Duration>,
+
+        #[arg(
+            long,
+            value_enum,
+            value_name = "FRAMEWORK",
+            help = "Summarize coverage and failures per OWASP Top 10 category or PCI DSS requirement (repeatable)"
+        )]
+        compliance: Vec<Framework>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Flag open ports and findings the YAML policy in FILE does not accept, failing the scan on them"
         )]
-        target: String,
+        policy: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write the discovered hosts, open ports and technologies to FILE"
+        )]
+        inventory: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_enum,
+            requires = "inventory",
+            default_value_t = InventoryFormat::Assets,
+            help = "Format of the inventory"
+        )]
+        inventory_format: InventoryFormat,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write an HTML gallery of the live endpoints with their title, status, server, technologies and text to FILE"
+        )]
+        gallery: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write the requests and responses web findings were observed in to FILE as HAR, for replay in Burp or ZAP"
+        )]
+        har: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_enum,
+            help = "Output format, `lines` prints one tab-separated finding per line and `jsonl` one JSON event per line, progress going to stderr [default: text]"
+        )]
+        format: Option<ScanFormat>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write the events, in any format, to FILE instead of stdout with --format jsonl"
+        )]
+        output: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Keep the command line, artifacts, checkpoint, reports and logs of the scan under DIR/TARGET/TIMESTAMP, unless given explicitly"
+        )]
+        workspace: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "RECIPIENT",
+            help = "Encrypt the written reports to an age public key, or a GnuPG key ID or email (repeatable)"
+        )]
+        encrypt_to: Vec<Recipient>,
+
+        #[arg(
+            long,
+            help = "Mask API keys, tokens and passwords of the settings embedded in the scan manifest"
+        )]
+        redact_config: bool,
+
+        #[arg(
+            long,
+            value_enum,
+            help = "Apply the scope rules, intrusiveness, identification headers and output of a persona, overridable with [presets.NAME] in the configuration file"
+        )]
+        preset: Option<Preset>,
+
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 100,
+            help = "Concurrent workers of the vulnerability stage"
+        )]
+        workers: usize,
+
+        #[arg(
+            long,
+            value_name = "BYTES",
+            default_value_t = body::DEFAULT_MAX_BODY_SIZE,
+            help = "Read at most BYTES of each response body"
+        )]
+        max_body_size: usize,
+
+        #[arg(
+            long,
+            help = "Fetch every response of passive checks instead of revalidating cached ones"
+        )]
+        no_cache: bool,
+
+        #[arg(
+            long,
+            help = "Stop after resolution and print the scan plan, without probing any host"
+        )]
+        dry_run: bool,
+
+        #[arg(
+            long,
+            help = "Skip the target authorization check required by the configuration"
+        )]
+        i_am_authorized: bool,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Only keep and check hosts in scope (Burp JSON, HackerOne CSV or a wildcard list)"
+        )]
+        scope_file: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Checkpoint the vulnerability stage to FILE, resuming from it if it exists"
+        )]
+        checkpoint: Option<PathBuf>,
+
+        #[arg(
+            long,
+            help = "Query the crt.sh PostgreSQL replica (port 5432) when its JSON API keeps failing"
+        )]
+        crtsh_postgres: bool,
+
+        #[arg(
+            long,
+            help = "Enable modules sending payloads that may disturb the target (e.g. SQL injection)"
+        )]
+        aggressive: bool,
+
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            value_name = "TAGS",
+            help = "Only run checks with any of these tags, e.g. exposure,misconfig"
+        )]
+        tags: Vec<Tag>,
+
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            value_name = "TAGS",
+            help = "Never run checks with any of these tags, e.g. intrusive"
+        )]
+        exclude_tags: Vec<Tag>,
+
+        #[arg(
+            long,
+            help = "Also scan interesting directories (backup, admin, .git...) of archived URLs"
+        )]
+        archived_paths: bool,
+
+        #[arg(long, help = "Reconstruct file listings from leaked .git directories")]
+        exploit_git: bool,
+
+        #[arg(
+            long,
+            value_name = "DIR",
+            requires = "exploit_git",
+            help = "Download the leaked source files into DIR"
+        )]
+        dump: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "URL",
+            help = "Interactsh-compatible server used to confirm out-of-band callbacks"
+        )]
+        oob_server: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "TOKEN",
+            requires = "oob_server",
+            help = "Authentication token of the OOB server"
+        )]
+        oob_token: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "DOMAIN",
+            conflicts_with = "oob_server",
+            help = "Confirm out-of-band callbacks with a built-in listener for DOMAIN (needs ports 53 and 80)"
+        )]
+        oob_domain: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "IP",
+            requires = "oob_domain",
+            help = "Public IP returned for callback hosts of the built-in listener"
+        )]
+        oob_public_ip: Option<IpAddr>,
     },
 }
 
-fn main() -> Result<()> {
-    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+#[derive(Subcommand)]
+enum KeysSubCommand {
+    /// Store the API key of a source, prompting for it when not given
+    Set { source: String, key: Option<String> },
+    /// Remove the API key of a source from the key store
+    Remove { source: String },
+    /// List the API keys found in the configuration and the key store
+    List,
+}
 
+fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    // Created before the logger, which also writes to the run directory
+    let workspace = match &cli.subcommand {
+        SubCommand::Scan {
+            workspace: Some(root),
+            target,
+            stdin,
+            ..
+        } => {
+            let name = match (target, stdin) {
+                (Some(target), _) => target.to_string(),
+                (None, true) => "stdin".to_string(),
+                (None, false) => "imported".to_string(),
+            };
+            Some(Workspace::create(root, &name)?)
+        }
+        _ => None,
+    };
+
+    let mut logger =
+        env_logger::Builder::from_env(Env::default().default_filter_or("info,httpmock=warn"));
+    if let Some(workspace) = &workspace {
+        logger
+            .target(env_logger::Target::Pipe(Box::new(workspace.log()?)))
+            .write_style(env_logger::WriteStyle::Never);
+    }
+    logger.init();
+
+    i18n::init(cli.lang);
+    let mut config = config::Config::load(cli.config.as_deref(), cli.profile.as_deref())?;
+
     match &cli.subcommand {
-        SubCommand::Modules => action::modules(),
-        SubCommand::Scan { target } => action::scan(target)?,
+        SubCommand::Modules { stats, format } => action::modules(*stats, *format)?,
+        SubCommand::Selftest => action::selftest()?,
+        SubCommand::Kb { module, format } => action::kb(module, *format)?,
+        SubCommand::Keys { command } => {
+            let command = match command {
+                KeysSubCommand::Set { source, key } => KeysCommand::Set {
+                    source: source.clone(),
+                    key: key.clone(),
+                },
+                KeysSubCommand::Remove { source } => KeysCommand::Remove {
+                    source: source.clone(),
+                },
+                KeysSubCommand::List => KeysCommand::List,
+            };
+            action::keys(command, &config)?
+        }
+        SubCommand::Verify {
+            report,
+            signature,
+            public_key,
+        } => action::verify(report, signature.as_deref(), public_key.as_deref(), &config)?,
+        SubCommand::Related { domain, format } => action::related(domain, *format, &config)?,
+        SubCommand::Annotate {
+            id,
+            status,
+            note,
+            clear,
+        } => action::annotate(id.as_deref(), *status, note.as_deref(), *clear, &config)?,
+        #[cfg(feature = "postgres-store")]
+        SubCommand::History { limit, format } => action::history(*limit, *format, &config)?,
+        SubCommand::Replay { id, har, format } => action::replay(har, id.as_deref(), *format)?,
+        SubCommand::Retest {
+            report,
+            aggressive,
+            i_am_authorized,
+            format,
+        } => action::retest(report, *aggressive, *i_am_authorized, *format, &config)?,
+        SubCommand::Typosquat { domain, format } => action::typosquat(domain, *format)?,
+        SubCommand::Update {
+            channel,
+            public_key,
+        } => action::update(channel, public_key.as_deref())?,
+        SubCommand::Scan {
+            target,
+            stdin,
+            skip_enumeration,
+            skip_port_scan,
+            skip_ping,
+            timing,
+            verify_dns,
+            hosts_from,
+            ports_from,
+            artifacts,
+            export_nmap,
+            push,
+            port_cache_ttl,
+            compliance,
+            policy,
+            inventory,
+            inventory_format,
+            gallery,
+            har,
+            format,
+            output,
+            workspace: _,
+            encrypt_to,
+            redact_config,
+            preset,
+            workers,
+            max_body_size,
+            no_cache,
+            dry_run,
+            i_am_authorized,
+            scope_file,
+            checkpoint,
+            crtsh_postgres,
+            aggressive,
+            tags,
+            exclude_tags,
+            archived_paths,
+            exploit_git,
+            dump,
+            oob_server,
+            oob_token,
+            oob_domain,
+            oob_public_ip,
+        } => {
+            // Scans of imported hosts have no target
+            let targets = match (target, stdin) {
+                (Some(target), _) => vec![target.clone()],
+                (None, true) => target::parse_lines(std::io::stdin().lock())?,
+                (None, false) => Vec::new(),
+            };
+
+            if let Some(timing) = timing {
+                timing.apply(&mut config.ports);
+            }
+
+            let preset = preset.map(|preset| preset.settings(config.presets.get(&preset)));
+
+            let mut options = action::ScanOptions {
+                skip_enumeration: *skip_enumeration,
+                skip_port_scan: *skip_port_scan,
+                skip_ping: *skip_ping,
+                verify_dns: *verify_dns,
+                hosts_from: hosts_from.clone(),
+                ports_from: ports_from.clone(),
+                artifacts: artifacts.clone(),
+                export_nmap: export_nmap.clone(),
+                push: push.clone(),
+                port_cache_ttl: *port_cache_ttl,
+                compliance: compliance.clone(),
+                policy: policy.as_deref().map(Policy::load).transpose()?,
+                inventory: inventory.clone(),
+                inventory_format: *inventory_format,
+                gallery: gallery.clone(),
+                har: har.clone(),
+                format: format
+                    .or(preset.as_ref().map(|preset| preset.format))
+                    .unwrap_or(ScanFormat::Text),
+                workers: *workers,
+                max_body_size: *max_body_size,
+                no_cache: *no_cache,
+                dry_run: *dry_run,
+                i_am_authorized: *i_am_authorized,
+                scope: scope_file.as_deref().map(Scope::load).transpose()?,
+                checkpoint: checkpoint.clone(),
+                crtsh_postgres: *crtsh_postgres,
+                aggressive: *aggressive,
+                selection: Selection {
+                    tags: tags.clone(),
+                    exclude_tags: exclude_tags.clone(),
+                },
+                headers: HeaderMap::new(),
+                archived_paths: *archived_paths,
+                exploit_git: *exploit_git,
+                dump: dump.clone(),
+                oob_server: oob_server.clone(),
+                oob_token: oob_token.clone(),
+                oob_domain: oob_domain.clone(),
+                oob_public_ip: *oob_public_ip,
+                output: output.clone(),
+                encrypt_to: encrypt_to.clone(),
+                redact_config: *redact_config,
+            };
+            if let Some(preset) = &preset {
+                preset.apply(&mut options)?;
+            }
+            if let Some(workspace) = &workspace {
+                log::info!("Keeping the scan in {}", workspace.path.display());
+                workspace.apply(&mut options);
+            }
+
+            action::scan(&targets, &options, &config)?
+        }
     }
 
     Ok(())
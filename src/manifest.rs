@@ -0,0 +1,189 @@
+use crate::config::Config;
+use crate::detections;
+use crate::detections::Release;
+use crate::modules;
+use crate::modules::Selection;
+
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use toml::Table;
+use toml::Value;
+
+/// Setting names holding credentials, masked by `--redact-config`
+const SECRET_KEYS: &[&str] = &[
+    "key",
+    "token",
+    "secret",
+    "password",
+    "passphrase",
+    "authorization",
+    "cookie",
+];
+const REDACTED: &str = "[REDACTED]";
+
+/// What produced a report, embedded in it so that results can be reproduced and audited
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    pub tool: &'static str,
+    pub version: &'static str,
+    /// Commit vulnscan was built from, `unknown` outside a git checkout
+    pub commit: &'static str,
+    pub started_at: String,
+    /// Modules ship with vulnscan, so they share its version and commit
+    pub modules: Vec<ModuleVersion>,
+    /// SHA-256 of the effective settings, secrets included, whether or not they are redacted
+    pub config_sha256: String,
+    pub config: Table,
+    pub wordlists: Vec<Wordlist>,
+    /// Patterns, paths and fingerprints, embedded or installed by `vulnscan update`
+    pub detections: Release,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleVersion {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+/// A built-in wordlist, hashed one entry per line
+#[derive(Debug, Serialize)]
+pub struct Wordlist {
+    pub name: &'static str,
+    pub entries: usize,
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// The manifest of a scan starting now, with the modules `selection` builds
+    /// - `redact` masks credentials of the embedded settings
+    pub fn new(config: &Config, selection: &Selection, aggressive: bool, redact: bool) -> Self {
+        let version = env!("CARGO_PKG_VERSION");
+
+        let mut settings = config.settings.clone();
+        if redact {
+            redact_table(&mut settings, false);
+        }
+
+        Manifest {
+            tool: "vulnscan",
+            version,
+            commit: option_env!("VULNSCAN_COMMIT").unwrap_or("unknown"),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            modules: modules::enabled(selection, aggressive)
+                .into_iter()
+                .map(|name| ModuleVersion { name, version })
+                .collect(),
+            config_sha256: sha256(&toml::to_string(&config.settings).unwrap_or_default()),
+            config: settings,
+            wordlists: modules::wordlists()
+                .into_iter()
+                .map(|(name, words)| Wordlist {
+                    name,
+                    entries: words.len(),
+                    sha256: sha256(
+                        &words
+                            .iter()
+                            .map(|word| format!("{}\n", word))
+                            .collect::<String>(),
+                    ),
+                })
+                .collect(),
+            detections: detections::release().clone(),
+        }
+    }
+
+    /// `vulnscan 0.1.0 (commit), configuration 1a2b3c4d5e6f, detections 1`
+    pub fn summary(&self) -> String {
+        format!(
+            "{} {} ({}), configuration {}, detections {}",
+            self.tool,
+            self.version,
+            self.commit,
+            &self.config_sha256[..12],
+            self.detections.version
+        )
+    }
+}
+
+fn sha256(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Mask the secret values of `table`, or all of them when `secret`
+/// - Subdomain source keys are all secret, paths to key files are not
+fn redact_table(table: &mut Table, secret: bool) {
+    for (key, value) in table.iter_mut() {
+        let name = key.to_lowercase();
+        let secret = secret
+            || name == "sources"
+            || (!name.ends_with("_file") && SECRET_KEYS.iter().any(|word| name.contains(word)));
+
+        redact_value(value, secret);
+    }
+}
+
+fn redact_value(value: &mut Value, secret: bool) {
+    match value {
+        Value::Table(table) => redact_table(table, secret),
+        Value::Array(values) => {
+            for value in values {
+                redact_value(value, secret);
+            }
+        }
+        Value::String(string) if secret => *string = String::from(REDACTED),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_should_redact_secrets_but_hash_them() {
+        let config = Config {
+            settings: toml::from_str(
+                r#"
+                [defectdojo]
+                url = "https://defectdojo.example.com"
+                api_key = "hunter2"
+
+                [signing]
+                key_file = "/etc/vulnscan/signing.pem"
+
+                [sources]
+                securitytrails = "hunter2"
+                "#,
+            )
+            .unwrap(),
+            ..Config::default()
+        };
+
+        let plain = Manifest::new(&config, &Selection::default(), false, false);
+        let redacted = Manifest::new(&config, &Selection::default(), false, true);
+
+        assert_eq!(
+            plain.config["defectdojo"]["api_key"].as_str(),
+            Some("hunter2")
+        );
+        assert_eq!(
+            redacted.config["defectdojo"]["api_key"].as_str(),
+            Some(REDACTED)
+        );
+        assert_eq!(
+            redacted.config["sources"]["securitytrails"].as_str(),
+            Some(REDACTED)
+        );
+        assert_eq!(
+            redacted.config["defectdojo"]["url"].as_str(),
+            Some("https://defectdojo.example.com")
+        );
+        assert_eq!(
+            redacted.config["signing"]["key_file"].as_str(),
+            Some("/etc/vulnscan/signing.pem")
+        );
+        assert_eq!(plain.config_sha256, redacted.config_sha256);
+        assert!(!redacted.modules.is_empty());
+    }
+}
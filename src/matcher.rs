@@ -0,0 +1,71 @@
+use futures::channel::oneshot;
+use once_cell::sync::Lazy;
+use rayon::ThreadPool;
+use rayon::ThreadPoolBuilder;
+
+/// Bodies up to this size are matched on the calling task, see `benches/matcher.rs`
+pub const INLINE_THRESHOLD: usize = 16 * 1024;
+
+/// CPU-bound matching of large bodies, kept away from the async runtime
+static POOL: Lazy<ThreadPool> = Lazy::new(|| {
+    ThreadPoolBuilder::new()
+        .thread_name(|index| format!("matcher-{}", index))
+        .build()
+        .expect("Failed to build matcher thread pool")
+});
+
+/// Run `matcher` over a response body
+/// - Small bodies are matched inline, which is cheaper than a thread handoff
+/// - Large bodies are offloaded to a dedicated rayon pool
+pub async fn run<T, F>(body: String, matcher: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce(&str) -> T + Send + 'static,
+{
+    if body.len() <= INLINE_THRESHOLD {
+        return matcher(&body);
+    }
+
+    let (sender, receiver) = oneshot::channel();
+
+    POOL.spawn(move || {
+        let _ = sender.send(matcher(&body));
+    });
+
+    receiver.await.expect("Matcher task panicked")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[tokio::test]
+    async fn test_run_should_match_small_bodies_inline() {
+        let caller = std::thread::current().id();
+
+        let thread = run(String::from("DB_PASSWORD=secret"), |_| {
+            std::thread::current().id()
+        })
+        .await;
+
+        assert_eq!(thread, caller);
+    }
+
+    #[tokio::test]
+    async fn test_run_should_offload_large_bodies() {
+        let pattern = Regex::new(r"\[core\]").unwrap();
+        let body = format!("{}[core]", "A".repeat(INLINE_THRESHOLD));
+
+        let (is_match, thread) = run(body, move |body| {
+            (
+                pattern.is_match(body),
+                std::thread::current().name().map(str::to_string),
+            )
+        })
+        .await;
+
+        assert!(is_match);
+        assert!(thread.unwrap().starts_with("matcher-"));
+    }
+}
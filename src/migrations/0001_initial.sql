@@ -0,0 +1,37 @@
+-- Scans recorded by `scan` with a [store] configured
+CREATE TABLE runs (
+    id BIGSERIAL PRIMARY KEY,
+    targets TEXT NOT NULL,
+    version TEXT NOT NULL,
+    operator TEXT NOT NULL DEFAULT current_user,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    finished_at TIMESTAMPTZ
+);
+
+-- Every finding ever reported, by finding ID, with the runs it was first and last seen in
+CREATE TABLE findings (
+    id CHAR(64) PRIMARY KEY,
+    kind TEXT NOT NULL,
+    url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    first_seen_run BIGINT NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+    last_seen_run BIGINT NOT NULL REFERENCES runs (id) ON DELETE CASCADE
+);
+
+-- The findings of each run, with the evidence reported then
+CREATE TABLE run_findings (
+    run_id BIGINT NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+    finding_id CHAR(64) NOT NULL REFERENCES findings (id) ON DELETE CASCADE,
+    evidence TEXT[] NOT NULL,
+    PRIMARY KEY (run_id, finding_id)
+);
+
+-- Triage by `annotate`, kept for findings not reported yet
+CREATE TABLE annotations (
+    finding_id CHAR(64) PRIMARY KEY,
+    status TEXT,
+    note TEXT,
+    updated_by TEXT NOT NULL DEFAULT current_user,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,206 @@
+use crate::body::ReadBounded;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::CloudModule;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::cloud::bucket_exists;
+use crate::modules::cloud::bucket_listable;
+use crate::modules::http::HttpOptions;
+use crate::throttle::Limiter;
+use crate::throttle::SendAdaptive;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use reqwest::Client;
+use reqwest::StatusCode;
+
+// Container names tried on existing storage accounts
+pub(super) const CONTAINERS: &[&str] = &[
+    "$web", "assets", "backup", "data", "files", "images", "media", "public", "static", "uploads",
+];
+
+pub struct AzureBlob {
+    // `{account}` is replaced with the candidate storage account name
+    url_template: String,
+}
+
+impl AzureBlob {
+    pub fn new() -> Self {
+        AzureBlob {
+            url_template: String::from("https://{account}.blob.core.windows.net/"),
+        }
+    }
+}
+
+impl Module for AzureBlob {
+    fn name(&self) -> String {
+        String::from("cloud/azure_blob")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-cloud-azure_blob")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec!["https://learn.microsoft.com/en-us/azure/storage/blobs/anonymous-read-access-prevent"]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "cloud/azure_blob",
+        tags: &[Tag::Exposure, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Cloud(|_| Box::new(AzureBlob::new())),
+    }
+}
+
+#[async_trait]
+impl CloudModule for AzureBlob {
+    async fn check(
+        &self,
+        http_client: &Client,
+        limiter: &Limiter,
+        options: &HttpOptions,
+        account: &str,
+    ) -> Result<Option<Finding>> {
+        // Storage account names are 3-24 lowercase letters and digits
+        if !(3..=24).contains(&account.len())
+            || !account
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        {
+            return Ok(None);
+        }
+
+        // Unknown storage accounts do not resolve, so any response proves existence
+        let account_url = self.url_template.replace("{account}", account);
+        if http_client
+            .get(&account_url)
+            .send_adaptive(limiter)
+            .await
+            .is_err()
+        {
+            return Ok(None);
+        }
+
+        for container in CONTAINERS {
+            let url = format!("{}{}?restype=container&comp=list", account_url, container);
+            let resp = http_client.get(&url).send_adaptive(limiter).await?;
+
+            if resp.status() == StatusCode::OK
+                && resp
+                    .text_bounded(options.max_body_size)
+                    .await?
+                    .contains("<EnumerationResults")
+            {
+                return Ok(Some(bucket_listable(self.name(), &url)));
+            }
+        }
+
+        Ok(Some(bucket_exists(self.name(), &account_url)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_check_should_return_some_when_account_found() {
+        // Set up mock Azure server and its responses
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/examplestore/public")
+                    .query_param("comp", "list");
+                then.status(200).body(
+                    r#"<?xml version="1.0" encoding="utf-8"?>
+                    <EnumerationResults ContainerName="public"><Blobs /></EnumerationResults>"#,
+                );
+            })
+            .await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(404)
+                    .body("<Error><Code>ResourceNotFound</Code></Error>");
+            })
+            .await;
+
+        // Set up input arguments
+        let module = AzureBlob {
+            url_template: format!("{}/{{account}}/", mock_server.base_url()),
+        };
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let (limiter, options) = (Limiter::default(), HttpOptions::default());
+
+        // Run check and check result
+        let result = module
+            .check(&client, &limiter, &options, "examplestore")
+            .await
+            .unwrap();
+        assert_eq!(
+            result.map(|finding| finding.kind).as_deref(),
+            Some("BucketListable")
+        );
+
+        let result = module
+            .check(&client, &limiter, &options, "exampleprivate")
+            .await
+            .unwrap();
+        assert_eq!(
+            result.map(|finding| finding.kind).as_deref(),
+            Some("BucketExists")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_should_return_none_when_account_missing() {
+        // Set up input arguments (nothing listens on the discard port)
+        let module = AzureBlob {
+            url_template: String::from("http://127.0.0.1:9/{account}/"),
+        };
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let (limiter, options) = (Limiter::default(), HttpOptions::default());
+
+        // Run check and check result
+        let result = module
+            .check(&client, &limiter, &options, "examplemissing")
+            .await
+            .unwrap();
+        assert!(
+            result.is_none(),
+            "Should return None when account is missing"
+        );
+
+        let result = module
+            .check(&client, &limiter, &options, "example-store")
+            .await
+            .unwrap();
+        assert!(
+            result.is_none(),
+            "Should return None for invalid account names"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::body::ReadBounded;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::CloudModule;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::cloud::bucket_exists;
+use crate::modules::cloud::bucket_listable;
+use crate::modules::cloud::is_valid_bucket_name;
+use crate::modules::http::HttpOptions;
+use crate::throttle::Limiter;
+use crate::throttle::SendAdaptive;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use reqwest::Client;
+use reqwest::StatusCode;
+
+pub struct Gcs {
+    // `{bucket}` is replaced with the candidate bucket name
+    url_template: String,
+}
+
+impl Gcs {
+    pub fn new() -> Self {
+        Gcs {
+            url_template: String::from("https://storage.googleapis.com/{bucket}/"),
+        }
+    }
+}
+
+impl Module for Gcs {
+    fn name(&self) -> String {
+        String::from("cloud/gcs")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-cloud-gcs")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec!["https://cloud.google.com/storage/docs/access-control"]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "cloud/gcs",
+        tags: &[Tag::Exposure, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Cloud(|_| Box::new(Gcs::new())),
+    }
+}
+
+#[async_trait]
+impl CloudModule for Gcs {
+    async fn check(
+        &self,
+        http_client: &Client,
+        limiter: &Limiter,
+        options: &HttpOptions,
+        bucket: &str,
+    ) -> Result<Option<Finding>> {
+        if !is_valid_bucket_name(bucket) {
+            return Ok(None);
+        }
+
+        let url = self.url_template.replace("{bucket}", bucket);
+        let resp = http_client.get(&url).send_adaptive(limiter).await?;
+
+        match resp.status() {
+            StatusCode::OK => {
+                let body = resp.text_bounded(options.max_body_size).await?;
+                if body.contains("<ListBucketResult") {
+                    return Ok(Some(bucket_listable(self.name(), &url)));
+                }
+                Ok(None)
+            }
+            // The bucket exists but anonymous users lack `storage.objects.list`
+            StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
+                Ok(Some(bucket_exists(self.name(), &url)))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_check_should_return_some_when_bucket_found() {
+        // Set up mock GCS server and its responses
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/files.example.com/");
+                then.status(200).body(
+                    r#"<?xml version='1.0' encoding='UTF-8'?>
+                    <ListBucketResult xmlns='http://doc.s3.amazonaws.com/2006-03-01'>
+                    <Name>files.example.com</Name></ListBucketResult>"#,
+                );
+            })
+            .await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/example-private/");
+                then.status(403)
+                    .body("<Error><Code>AccessDenied</Code></Error>");
+            })
+            .await;
+
+        // Set up input arguments
+        let module = Gcs {
+            url_template: format!("{}/{{bucket}}/", mock_server.base_url()),
+        };
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let (limiter, options) = (Limiter::default(), HttpOptions::default());
+
+        // Run check and check result
+        let result = module
+            .check(&client, &limiter, &options, "files.example.com")
+            .await
+            .unwrap();
+        assert_eq!(
+            result.map(|finding| finding.kind).as_deref(),
+            Some("BucketListable")
+        );
+
+        let result = module
+            .check(&client, &limiter, &options, "example-private")
+            .await
+            .unwrap();
+        assert_eq!(
+            result.map(|finding| finding.kind).as_deref(),
+            Some("BucketExists")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_should_return_none_when_bucket_missing() {
+        // Set up mock GCS server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(404)
+                    .body("<Error><Code>NoSuchBucket</Code></Error>");
+            })
+            .await;
+
+        // Set up input arguments
+        let module = Gcs {
+            url_template: format!("{}/{{bucket}}/", mock_server.base_url()),
+        };
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let (limiter, options) = (Limiter::default(), HttpOptions::default());
+
+        // Run check and check result
+        let result = module
+            .check(&client, &limiter, &options, "example-missing")
+            .await
+            .unwrap();
+        assert!(
+            result.is_none(),
+            "Should return None when bucket is missing"
+        );
+    }
+}
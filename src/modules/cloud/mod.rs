@@ -0,0 +1,132 @@
+mod azure_blob;
+mod gcs;
+mod s3;
+
+use crate::detections;
+use crate::modules::Finding;
+use crate::modules::Severity;
+use std::collections::BTreeSet;
+
+/// The bucket exists but its content cannot be listed anonymously
+fn bucket_exists(module: String, url: &str) -> Finding {
+    Finding::new(module, "BucketExists", Severity::Info, url)
+}
+
+/// The bucket content can be listed anonymously
+fn bucket_listable(module: String, url: &str) -> Finding {
+    Finding::new(module, "BucketListable", Severity::High, url)
+}
+
+// Appended to the target name to guess bucket names
+const SUFFIXES: &[&str] = &[
+    "assets", "backup", "backups", "data", "dev", "files", "media", "prod", "public", "static",
+    "staging", "uploads",
+];
+
+/// Derive candidate bucket names
+/// - Permutations of the target domain (`example`, `example-com`, `example-backup`, ...)
+/// - Buckets referenced by CNAME records pointing at S3, GCS or Azure Blob Storage
+///
+/// # Arguments
+/// * `target` - The scanned domain
+/// * `cnames` - `(host, CNAME target)` pairs of resolved subdomains
+pub fn bucket_candidates(target: &str, cnames: &[(String, String)]) -> Vec<String> {
+    let mut candidates = BTreeSet::new();
+
+    // `example.com` -> `example`
+    let base = target.split('.').next().unwrap_or(target);
+
+    candidates.insert(target.to_string());
+    candidates.insert(target.replace('.', "-"));
+    candidates.insert(base.to_string());
+
+    for suffix in SUFFIXES {
+        candidates.insert(format!("{}-{}", base, suffix));
+        candidates.insert(format!("{}{}", base, suffix));
+    }
+
+    let patterns = &detections::get().cloud_cnames;
+    for (host, cname) in cnames {
+        let cname = cname.trim_end_matches('.').to_lowercase();
+
+        // e.g. `assets.s3.amazonaws.com`, `assets.s3-website-us-east-1.amazonaws.com`
+        if let Some(captures) = patterns.s3.captures(&cname) {
+            // Website endpoints without a bucket prefix are named after the aliased host
+            match captures.get(1) {
+                Some(bucket) => candidates.insert(bucket.as_str().to_string()),
+                None => candidates.insert(host.clone()),
+            };
+        } else if patterns.gcs.is_match(&cname) {
+            // e.g. `c.storage.googleapis.com`, the bucket is named after the aliased host
+            candidates.insert(host.clone());
+        } else if let Some(captures) = patterns.azure.captures(&cname) {
+            candidates.insert(captures[1].to_string());
+        }
+    }
+
+    candidates.into_iter().collect()
+}
+
+/// Whether `name` is a valid S3/GCS bucket name
+fn is_valid_bucket_name(name: &str) -> bool {
+    (3..=63).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+        && !name.starts_with(['-', '.'])
+        && !name.ends_with(['-', '.'])
+}
+
+/// Built-in wordlists of the cloud modules, by name
+pub fn wordlists() -> Vec<(&'static str, &'static [&'static str])> {
+    vec![
+        ("cloud/bucket_suffixes", SUFFIXES),
+        ("cloud/azure_blob_containers", azure_blob::CONTAINERS),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_candidates_should_include_cname_buckets() {
+        let cnames = vec![
+            (
+                String::from("cdn.example.com"),
+                String::from("example-cdn.s3.amazonaws.com."),
+            ),
+            (
+                String::from("files.example.com"),
+                String::from("c.storage.googleapis.com."),
+            ),
+            (
+                String::from("blob.example.com"),
+                String::from("examplestore.blob.core.windows.net."),
+            ),
+            (
+                String::from("www.example.com"),
+                String::from("example.github.io."),
+            ),
+        ];
+
+        let candidates = bucket_candidates("example.com", &cnames);
+
+        for expected in [
+            "example",
+            "example.com",
+            "example-com",
+            "example-backup",
+            "example-cdn",
+            "files.example.com",
+            "examplestore",
+        ] {
+            assert!(
+                candidates.contains(&expected.to_string()),
+                "Missing candidate {}",
+                expected
+            );
+        }
+        assert!(!candidates.contains(&String::from("www.example.com")));
+    }
+}
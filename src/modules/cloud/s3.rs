@@ -0,0 +1,200 @@
+use crate::body::ReadBounded;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::CloudModule;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::cloud::bucket_exists;
+use crate::modules::cloud::bucket_listable;
+use crate::modules::cloud::is_valid_bucket_name;
+use crate::modules::http::HttpOptions;
+use crate::throttle::Limiter;
+use crate::throttle::SendAdaptive;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use reqwest::Client;
+use reqwest::StatusCode;
+
+pub struct S3 {
+    // `{bucket}` is replaced with the candidate bucket name
+    url_template: String,
+}
+
+impl S3 {
+    pub fn new() -> Self {
+        S3 {
+            url_template: String::from("https://{bucket}.s3.amazonaws.com/"),
+        }
+    }
+}
+
+impl Module for S3 {
+    fn name(&self) -> String {
+        String::from("cloud/s3")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-cloud-s3")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://docs.aws.amazon.com/AmazonS3/latest/userguide/access-control-block-public-access.html",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "cloud/s3",
+        tags: &[Tag::Exposure, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Cloud(|_| Box::new(S3::new())),
+    }
+}
+
+#[async_trait]
+impl CloudModule for S3 {
+    async fn check(
+        &self,
+        http_client: &Client,
+        limiter: &Limiter,
+        options: &HttpOptions,
+        bucket: &str,
+    ) -> Result<Option<Finding>> {
+        if !is_valid_bucket_name(bucket) {
+            return Ok(None);
+        }
+
+        let url = self.url_template.replace("{bucket}", bucket);
+        let resp = http_client.get(&url).send_adaptive(limiter).await?;
+
+        match resp.status() {
+            StatusCode::OK => {
+                let body = resp.text_bounded(options.max_body_size).await?;
+                if body.contains("<ListBucketResult") {
+                    return Ok(Some(bucket_listable(self.name(), &url)));
+                }
+                Ok(None)
+            }
+            // AccessDenied, AllAccessDisabled, or a bucket living in another region
+            StatusCode::FORBIDDEN
+            | StatusCode::MOVED_PERMANENTLY
+            | StatusCode::TEMPORARY_REDIRECT => Ok(Some(bucket_exists(self.name(), &url))),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_check_should_return_some_when_bucket_found() {
+        // Set up mock S3 server and its responses
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/example-backup/");
+                then.status(200).body(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+                    <ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+                    <Name>example-backup</Name></ListBucketResult>"#,
+                );
+            })
+            .await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/example-private/");
+                then.status(403)
+                    .body("<Error><Code>AccessDenied</Code></Error>");
+            })
+            .await;
+
+        // Set up input arguments
+        let module = S3 {
+            url_template: format!("{}/{{bucket}}/", mock_server.base_url()),
+        };
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let (limiter, options) = (Limiter::default(), HttpOptions::default());
+
+        // Run check and check result
+        let result = module
+            .check(&client, &limiter, &options, "example-backup")
+            .await
+            .unwrap();
+        assert_eq!(
+            result.map(|finding| finding.kind).as_deref(),
+            Some("BucketListable")
+        );
+
+        let result = module
+            .check(&client, &limiter, &options, "example-private")
+            .await
+            .unwrap();
+        assert_eq!(
+            result.map(|finding| finding.kind).as_deref(),
+            Some("BucketExists")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_should_return_none_when_bucket_missing() {
+        // Set up mock S3 server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(404)
+                    .body("<Error><Code>NoSuchBucket</Code></Error>");
+            })
+            .await;
+
+        // Set up input arguments
+        let module = S3 {
+            url_template: format!("{}/{{bucket}}/", mock_server.base_url()),
+        };
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let (limiter, options) = (Limiter::default(), HttpOptions::default());
+
+        // Run check and check result
+        let result = module
+            .check(&client, &limiter, &options, "example-missing")
+            .await
+            .unwrap();
+        assert!(
+            result.is_none(),
+            "Should return None when bucket is missing"
+        );
+
+        let result = module
+            .check(&client, &limiter, &options, "Invalid_Name")
+            .await
+            .unwrap();
+        assert!(
+            result.is_none(),
+            "Should return None for invalid bucket names"
+        );
+    }
+}
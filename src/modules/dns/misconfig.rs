@@ -0,0 +1,467 @@
+use crate::i18n;
+use crate::modules::Build;
+use crate::modules::DnsModule;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use crate::modules::dns;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use hickory_resolver::ResolveError;
+use hickory_resolver::TokioResolver;
+use hickory_resolver::proto::op::Message;
+use hickory_resolver::proto::op::Query;
+use hickory_resolver::proto::op::ResponseCode;
+use hickory_resolver::proto::rr::Name;
+use hickory_resolver::proto::rr::RData;
+use hickory_resolver::proto::rr::RecordType;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+const DNS_PORT: u16 = 53;
+// Second-level labels of country code TLDs open to registrations, e.g. `co.uk`
+const SECOND_LEVEL_SUFFIXES: &[&str] = &["ac", "co", "com", "edu", "gov", "net", "or", "org"];
+
+pub struct Misconfig;
+
+impl Misconfig {
+    pub fn new() -> Self {
+        Misconfig
+    }
+}
+
+/// What the resolver and the name servers told about a zone, `None` when a lookup failed
+#[derive(Debug, Default)]
+struct Observations {
+    caa: Option<bool>,
+    // Key tags of the DS records at the parent
+    ds: Option<Vec<u16>>,
+    // Key tags of the DNSKEY records of the zone
+    dnskey: Option<Vec<u16>>,
+    nameservers: Vec<NameServer>,
+}
+
+#[derive(Debug)]
+struct NameServer {
+    name: String,
+    // The domain of the name server, when it is not registered
+    unregistered: Option<String>,
+    // Why no address of the name server answered authoritatively
+    lame: Option<String>,
+}
+
+impl Module for Misconfig {
+    fn name(&self) -> String {
+        String::from("dns/misconfig")
+    }
+
+    fn description(&self) -> i18n::Message {
+        i18n::Message::new("module-dns-misconfig")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://www.rfc-editor.org/rfc/rfc8659",
+            "https://www.rfc-editor.org/rfc/rfc4035",
+            "https://www.rfc-editor.org/rfc/rfc8499#section-7",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "dns/misconfig",
+        tags: &[Tag::Misconfig, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Dns(|_| Box::new(Misconfig::new())),
+    }
+}
+
+impl Misconfig {
+    fn assess(&self, zone: &str, observations: &Observations) -> Vec<Finding> {
+        let url = dns::url(zone);
+        let finding = |kind, severity| Finding::new(self.name(), kind, severity, &url);
+        let mut findings = Vec::new();
+
+        if observations.caa == Some(false) {
+            findings.push(finding("MissingCaa", Severity::Low));
+        }
+
+        if let (Some(ds), Some(dnskey)) = (&observations.ds, &observations.dnskey) {
+            let dnssec = match (ds.is_empty(), dnskey.is_empty()) {
+                (true, true) => Some((
+                    "DnssecMissing",
+                    Severity::Low,
+                    String::from("The zone is not signed"),
+                )),
+                (true, false) => Some((
+                    "DnssecMissing",
+                    Severity::Low,
+                    String::from("DNSKEY records are published, but no DS record at the parent"),
+                )),
+                (false, true) => Some((
+                    "DnssecBroken",
+                    Severity::Medium,
+                    String::from("DS records at the parent, but no DNSKEY record in the zone"),
+                )),
+                (false, false) if !ds.iter().any(|tag| dnskey.contains(tag)) => Some((
+                    "DnssecBroken",
+                    Severity::Medium,
+                    format!(
+                        "No DNSKEY matches the key tags of the DS records ({:?})",
+                        ds
+                    ),
+                )),
+                (false, false) => None,
+            };
+            if let Some((kind, severity, detail)) = dnssec {
+                findings.push(finding(kind, severity).with_evidence(vec![detail]));
+            }
+        }
+
+        for nameserver in &observations.nameservers {
+            // A takeover explains the lame delegation
+            if let Some(domain) = &nameserver.unregistered {
+                findings.push(
+                    finding("NsTakeover", Severity::High)
+                        .with_evidence(vec![format!(
+                            "{} is in {}, which does not exist",
+                            nameserver.name, domain
+                        )])
+                        .with_metadata("nameserver", &nameserver.name)
+                        .with_metadata("domain", domain),
+                );
+            } else if let Some(reason) = &nameserver.lame {
+                findings.push(
+                    finding("LameDelegation", Severity::Medium)
+                        .with_evidence(vec![format!("{}: {}", nameserver.name, reason)])
+                        .with_metadata("nameserver", &nameserver.name),
+                );
+            }
+        }
+
+        findings
+    }
+}
+
+#[async_trait]
+impl DnsModule for Misconfig {
+    async fn check(&self, resolver: &TokioResolver, zone: &str) -> Result<Vec<Finding>> {
+        let mut observations = Observations {
+            caa: has_caa(resolver, zone).await.ok(),
+            ds: key_tags(resolver, zone, RecordType::DS).await.ok(),
+            dnskey: key_tags(resolver, zone, RecordType::DNSKEY).await.ok(),
+            nameservers: Vec::new(),
+        };
+
+        let nameservers: Vec<String> = records(resolver, zone, RecordType::NS)
+            .await?
+            .into_iter()
+            .filter_map(|rdata| match rdata {
+                RData::NS(ns) => Some(ns.0.to_ascii().trim_end_matches('.').to_lowercase()),
+                _ => None,
+            })
+            .collect();
+
+        for name in nameservers {
+            let unregistered = match registrable_domain(&name) {
+                Some(domain) if !exists(resolver, &domain).await.unwrap_or(true) => Some(domain),
+                _ => None,
+            };
+
+            let addresses: Vec<IpAddr> = match resolver.lookup_ip(name.as_str()).await {
+                Ok(lookup) => lookup.iter().collect(),
+                Err(_) => Vec::new(),
+            };
+
+            let lame = if addresses.is_empty() {
+                Some(String::from("does not resolve"))
+            } else {
+                let mut reason = None;
+                for ip in addresses {
+                    match query_authoritative(SocketAddr::new(ip, DNS_PORT), zone).await {
+                        Ok(()) => {
+                            reason = None;
+                            break;
+                        }
+                        Err(e) => reason = Some(format!("{} {}", ip, e)),
+                    }
+                }
+                reason
+            };
+
+            observations.nameservers.push(NameServer {
+                name,
+                unregistered,
+                lame,
+            });
+        }
+
+        let findings = self.assess(zone, &observations);
+
+        log::info!(
+            "{}: Found {} issues in {}",
+            self.name(),
+            findings.len(),
+            zone
+        );
+
+        Ok(findings)
+    }
+}
+
+/// Records of `name`, empty when it has none or does not exist
+pub(super) async fn records(
+    resolver: &TokioResolver,
+    name: &str,
+    record_type: RecordType,
+) -> Result<Vec<RData>, ResolveError> {
+    match resolver.lookup(name, record_type).await {
+        Ok(lookup) => Ok(lookup
+            .record_iter()
+            .filter(|record| record.record_type() == record_type)
+            .map(|record| record.data().clone())
+            .collect()),
+        Err(e) if e.is_no_records_found() || e.is_nx_domain() => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// CAA records apply to subdomains, so the closest one up the tree counts
+async fn has_caa(resolver: &TokioResolver, zone: &str) -> Result<bool, ResolveError> {
+    let mut name = zone;
+
+    while name.contains('.') {
+        if !records(resolver, name, RecordType::CAA).await?.is_empty() {
+            return Ok(true);
+        }
+        name = name.split_once('.').map_or("", |(_, parent)| parent);
+    }
+
+    Ok(false)
+}
+
+/// Key tags of DS or DNSKEY records, read from their raw data
+async fn key_tags(
+    resolver: &TokioResolver,
+    zone: &str,
+    record_type: RecordType,
+) -> Result<Vec<u16>, ResolveError> {
+    Ok(records(resolver, zone, record_type)
+        .await?
+        .into_iter()
+        .filter_map(|rdata| match rdata {
+            RData::Unknown { rdata, .. } => {
+                let data = rdata.anything();
+                match record_type {
+                    // Key tag, algorithm, digest type, digest
+                    RecordType::DS => Some(u16::from_be_bytes([*data.first()?, *data.get(1)?])),
+                    _ => Some(key_tag(data)),
+                }
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Key tag of DNSKEY record data (RFC 4034, Appendix B)
+fn key_tag(dnskey: &[u8]) -> u16 {
+    let mut sum: u32 = dnskey
+        .iter()
+        .enumerate()
+        .map(|(i, &byte)| {
+            if i % 2 == 0 {
+                (byte as u32) << 8
+            } else {
+                byte as u32
+            }
+        })
+        .sum();
+    sum += (sum >> 16) & 0xffff;
+    (sum & 0xffff) as u16
+}
+
+/// Whether `name` exists, `None` when the resolver could not tell
+async fn exists(resolver: &TokioResolver, name: &str) -> Option<bool> {
+    match resolver.lookup(name, RecordType::SOA).await {
+        Ok(_) => Some(true),
+        Err(e) if e.is_nx_domain() => Some(false),
+        Err(e) if e.is_no_records_found() => Some(true),
+        Err(_) => None,
+    }
+}
+
+/// The domain the host of a name server is registered under, e.g. `example.co.uk` for `ns1.example.co.uk`
+pub(super) fn registrable_domain(host: &str) -> Option<String> {
+    let labels: Vec<&str> = host.split('.').collect();
+    let count = labels.len();
+    if count < 2 {
+        return None;
+    }
+
+    let suffix_labels = if count >= 3
+        && labels[count - 1].len() == 2
+        && SECOND_LEVEL_SUFFIXES.contains(&labels[count - 2])
+    {
+        2
+    } else {
+        1
+    };
+
+    Some(labels[count - suffix_labels - 1..].join("."))
+}
+
+/// Ask `server` directly for the SOA of `zone`, failing unless it answers with authority
+async fn query_authoritative(server: SocketAddr, zone: &str) -> Result<()> {
+    let mut query = Message::new();
+    query
+        .set_id(rand::random())
+        .set_recursion_desired(false)
+        .add_query(Query::query(Name::from_ascii(zone)?, RecordType::SOA));
+
+    let local_address: SocketAddr = match server {
+        SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+        SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+    };
+    let socket = UdpSocket::bind(local_address).await?;
+    socket.send_to(&query.to_vec()?, server).await?;
+
+    let mut buffer = [0u8; 4096];
+    let size = tokio::time::timeout(QUERY_TIMEOUT, socket.recv(&mut buffer))
+        .await
+        .map_err(|_| anyhow!("timed out"))??;
+    let response = Message::from_vec(&buffer[..size])?;
+
+    if response.id() != query.id() {
+        bail!("answered another query");
+    }
+    if response.response_code() != ResponseCode::NoError {
+        bail!("answered {}", response.response_code());
+    }
+    if !response.authoritative() {
+        bail!("answered without authority");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hickory_resolver::proto::op::MessageType;
+
+    #[test]
+    fn test_assess_should_report_each_misconfiguration() {
+        let nameserver = |name: &str, unregistered: Option<&str>, lame: Option<&str>| NameServer {
+            name: String::from(name),
+            unregistered: unregistered.map(String::from),
+            lame: lame.map(String::from),
+        };
+
+        let observations = Observations {
+            caa: Some(false),
+            ds: Some(vec![20326]),
+            dnskey: Some(vec![key_tag(&[1, 1, 3, 8, 0xab, 0xcd])]),
+            nameservers: vec![
+                nameserver("ns1.example.com", None, None),
+                nameserver("ns2.example.net", None, Some("192.0.2.53 answered REFUSED")),
+                nameserver(
+                    "ns.expired-dns.co.uk",
+                    Some("expired-dns.co.uk"),
+                    Some("does not resolve"),
+                ),
+            ],
+        };
+
+        let findings = Misconfig::new().assess("example.com", &observations);
+
+        assert!(
+            findings
+                .iter()
+                .all(|finding| finding.url == "dns://example.com")
+        );
+        let findings: Vec<(&str, Severity, Vec<String>)> = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.kind.as_str(),
+                    finding.severity,
+                    finding.evidence.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("MissingCaa", Severity::Low, vec![]),
+                (
+                    "DnssecBroken",
+                    Severity::Medium,
+                    vec![String::from(
+                        "No DNSKEY matches the key tags of the DS records ([20326])"
+                    )]
+                ),
+                (
+                    "LameDelegation",
+                    Severity::Medium,
+                    vec![String::from("ns2.example.net: 192.0.2.53 answered REFUSED")]
+                ),
+                (
+                    "NsTakeover",
+                    Severity::High,
+                    vec![String::from(
+                        "ns.expired-dns.co.uk is in expired-dns.co.uk, which does not exist"
+                    )]
+                ),
+            ]
+        );
+
+        assert_eq!(
+            registrable_domain("ns1.dns.example.co.uk").as_deref(),
+            Some("example.co.uk")
+        );
+        assert_eq!(registrable_domain("ns1.foo.io").as_deref(), Some("foo.io"));
+    }
+
+    #[tokio::test]
+    async fn test_query_authoritative_should_reject_non_authoritative_answers() {
+        // Set up a name server answering authoritatively, then from its cache
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let server = socket.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 512];
+            for authoritative in [true, false] {
+                let (size, client) = socket.recv_from(&mut buffer).await.unwrap();
+                let mut response = Message::from_vec(&buffer[..size]).unwrap();
+                response
+                    .set_message_type(MessageType::Response)
+                    .set_authoritative(authoritative);
+                socket
+                    .send_to(&response.to_vec().unwrap(), client)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        assert!(query_authoritative(server, "example.com").await.is_ok());
+
+        let error = query_authoritative(server, "example.com")
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "answered without authority");
+    }
+}
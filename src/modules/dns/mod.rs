@@ -0,0 +1,7 @@
+mod misconfig;
+pub mod whois;
+
+/// Findings are reported against the zone, as `dns://example.com`
+pub fn url(zone: &str) -> String {
+    format!("dns://{}", zone)
+}
@@ -0,0 +1,406 @@
+use crate::i18n;
+use crate::modules::Build;
+use crate::modules::DnsModule;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use crate::modules::dns;
+use crate::modules::dns::misconfig::records;
+use crate::modules::dns::misconfig::registrable_domain;
+use anyhow::Context;
+use anyhow::Result;
+use chrono::NaiveDate;
+use hickory_resolver::TokioResolver;
+use hickory_resolver::proto::rr::RData;
+use hickory_resolver::proto::rr::RecordType;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+const WHOIS_PORT: u16 = 43;
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_RESPONSE_SIZE: u64 = 64 * 1024;
+// Registries rate limit WHOIS hard, and registrations rarely change within a day
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+// Registrations expiring this soon are reported, auto-renewals usually go through a month ahead
+const EXPIRY_WARNING_DAYS: i64 = 30;
+
+// Labels of the fields across registries, the first present wins
+const REGISTRAR_FIELDS: &[&str] = &["registrar", "registrar name", "sponsoring registrar"];
+const CREATED_FIELDS: &[&str] = &[
+    "creation date",
+    "created",
+    "created on",
+    "registered on",
+    "registration time",
+    "domain registration date",
+];
+const REGISTRANT_FIELDS: &[&str] = &[
+    "registrant organization",
+    "registrant organisation",
+    "registrant",
+    "org",
+];
+// Registrants hidden behind privacy services tell nothing about ownership
+const PRIVACY_MARKERS: &[&str] = &["redacted", "privacy", "private", "protected", "proxy"];
+const EXPIRES_FIELDS: &[&str] = &[
+    "registry expiry date",
+    "registrar registration expiration date",
+    "expiry date",
+    "expiration date",
+    "expires",
+    "expires on",
+    "expiration time",
+    "paid-till",
+    "renewal date",
+];
+
+pub struct Whois;
+
+impl Whois {
+    pub fn new() -> Self {
+        Whois
+    }
+}
+
+/// What WHOIS tells about a registration
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct WhoisRecord {
+    pub registrar: Option<String>,
+    /// The organization owning the domain, unless hidden by a privacy service
+    pub registrant: Option<String>,
+    pub created: Option<NaiveDate>,
+    pub expires: Option<NaiveDate>,
+}
+
+impl Module for Whois {
+    fn name(&self) -> String {
+        String::from("dns/whois")
+    }
+
+    fn description(&self) -> i18n::Message {
+        i18n::Message::new("module-dns-whois")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://www.rfc-editor.org/rfc/rfc3912",
+            "https://cwe.mitre.org/data/definitions/672.html",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "dns/whois",
+        tags: &[Tag::Misconfig, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Dns(|_| Box::new(Whois::new())),
+    }
+}
+
+impl Whois {
+    /// `DomainRegistration` of `zone`, enriching reports rather than flagging anything
+    fn registration(&self, zone: &str, registration: &WhoisRecord) -> Finding {
+        let created = registration.created.map(|date| date.to_string());
+        let expires = registration.expires.map(|date| date.to_string());
+        let evidence = [
+            ("Registrar", &registration.registrar),
+            ("Created", &created),
+            ("Expires", &expires),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| Some(format!("{}: {}", label, value.as_ref()?)))
+        .collect();
+
+        Finding::new(
+            self.name(),
+            "DomainRegistration",
+            Severity::Info,
+            &dns::url(zone),
+        )
+        .with_evidence(evidence)
+        .with_metadata("registrar", &registration.registrar)
+        .with_metadata("created", created)
+        .with_metadata("expires", expires)
+    }
+
+    /// `DomainExpiring` when `domain`, which `host` is in, expires within `EXPIRY_WARNING_DAYS`
+    fn expiring(
+        &self,
+        domain: &str,
+        host: &str,
+        registration: &WhoisRecord,
+        today: NaiveDate,
+    ) -> Option<Finding> {
+        let expires = registration.expires?;
+        let days = (expires - today).num_days();
+        if days > EXPIRY_WARNING_DAYS {
+            return None;
+        }
+
+        let expiry = match days {
+            ..0 => format!("expired on {}", expires),
+            _ => format!("expires on {}, in {} days", expires, days),
+        };
+        let evidence = if host == domain {
+            format!("{} {}", domain, expiry)
+        } else {
+            format!("{} is in {}, which {}", host, domain, expiry)
+        };
+
+        Some(
+            Finding::new(
+                self.name(),
+                "DomainExpiring",
+                Severity::High,
+                &dns::url(domain),
+            )
+            .with_evidence(vec![evidence])
+            .with_metadata("host", host)
+            .with_metadata("expires", expires.to_string())
+            .with_metadata("days", days),
+        )
+    }
+}
+
+#[async_trait]
+impl DnsModule for Whois {
+    async fn check(&self, resolver: &TokioResolver, zone: &str) -> Result<Vec<Finding>> {
+        let today = chrono::Utc::now().date_naive();
+        let mut findings = Vec::new();
+
+        let registration = lookup(zone).await?;
+        findings.push(self.registration(zone, &registration));
+        findings.extend(self.expiring(zone, zone, &registration, today));
+
+        // Whoever registers an expired domain of a name server or mail exchanger answers for the zone
+        for (host, related) in related_domains(resolver, zone).await {
+            match lookup(&related).await {
+                Ok(registration) => {
+                    findings.extend(self.expiring(&related, &host, &registration, today))
+                }
+                Err(e) => log::debug!("{}: {:#}", related, e),
+            }
+        }
+
+        log::info!(
+            "{}: Found {} expiring domains of {}",
+            self.name(),
+            findings.len() - 1,
+            zone
+        );
+
+        Ok(findings)
+    }
+}
+
+/// `(host, registrable domain)` of the name servers and mail exchangers of `zone` outside of it
+async fn related_domains(resolver: &TokioResolver, zone: &str) -> BTreeSet<(String, String)> {
+    let mut hosts = Vec::new();
+    for record_type in [RecordType::NS, RecordType::MX] {
+        for rdata in records(resolver, zone, record_type)
+            .await
+            .unwrap_or_default()
+        {
+            let name = match rdata {
+                RData::NS(ns) => ns.0,
+                RData::MX(mx) => mx.exchange().clone(),
+                _ => continue,
+            };
+            hosts.push(name.to_ascii().trim_end_matches('.').to_lowercase());
+        }
+    }
+
+    hosts
+        .into_iter()
+        .filter_map(|host| {
+            let domain = registrable_domain(&host)?;
+            (domain != zone).then_some((host, domain))
+        })
+        .collect()
+}
+
+/// The registration of `domain`, from the cache when looked up less than a day ago
+pub async fn lookup(domain: &str) -> Result<WhoisRecord> {
+    let path = cache_path(domain);
+    let fresh = path
+        .as_ref()
+        .and_then(|path| std::fs::metadata(path).ok())
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < CACHE_TTL);
+
+    let response = match path.as_ref().filter(|_| fresh) {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => {
+            let response = whois(domain).await?;
+            if let Some(path) = &path {
+                let stored = path
+                    .parent()
+                    .map_or(Ok(()), std::fs::create_dir_all)
+                    .and_then(|()| std::fs::write(path, &response));
+                if let Err(e) = stored {
+                    log::debug!("Failed to cache WHOIS of {}: {}", domain, e);
+                }
+            }
+            response
+        }
+    };
+
+    parse(&response).with_context(|| format!("{} is not registered", domain))
+}
+
+/// `~/.local/share/vulnscan/whois_cache/<domain>.txt` on Linux
+fn cache_path(domain: &str) -> Option<PathBuf> {
+    Some(
+        dirs::data_local_dir()?
+            .join("vulnscan")
+            .join("whois_cache")
+            .join(format!("{}.txt", domain)),
+    )
+}
+
+/// Ask IANA for the WHOIS server of the TLD, then that server about `domain`
+async fn whois(domain: &str) -> Result<String> {
+    let tld = domain.rsplit('.').next().unwrap_or(domain);
+    let referral = query("whois.iana.org", tld).await?;
+    let server = field(&referral, &["refer", "whois"])
+        .with_context(|| format!("No WHOIS server for .{}", tld))?;
+
+    query(&server, domain).await
+}
+
+async fn query(server: &str, query: &str) -> Result<String> {
+    let exchange = async {
+        let mut stream = TcpStream::connect((server, WHOIS_PORT)).await?;
+        stream
+            .write_all(format!("{}\r\n", query).as_bytes())
+            .await?;
+
+        let mut response = Vec::new();
+        (&mut stream)
+            .take(MAX_RESPONSE_SIZE)
+            .read_to_end(&mut response)
+            .await?;
+        anyhow::Ok(String::from_utf8_lossy(&response).into_owned())
+    };
+
+    timeout(QUERY_TIMEOUT, exchange)
+        .await
+        .with_context(|| format!("{} did not answer in time", server))?
+        .with_context(|| format!("Failed to query {}", server))
+}
+
+/// The value of the first of `names` found in a `Name: value` response
+fn field(response: &str, names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| {
+        response.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let value = value.trim();
+            (key.trim().eq_ignore_ascii_case(name) && !value.is_empty()).then(|| value.to_string())
+        })
+    })
+}
+
+/// Dates come as `2026-08-13T04:00:00Z`, `2026-08-13`, `13-Aug-2026` or `2026.08.13`
+fn date(value: &str) -> Option<NaiveDate> {
+    let prefix = value.split(['T', ' ']).next().unwrap_or(value);
+    ["%Y-%m-%d", "%d-%b-%Y", "%Y.%m.%d", "%d.%m.%Y", "%Y/%m/%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(prefix, format).ok())
+}
+
+/// The registration in a WHOIS response, `None` if the registry has no such domain
+fn parse(response: &str) -> Option<WhoisRecord> {
+    let registration = WhoisRecord {
+        registrar: field(response, REGISTRAR_FIELDS),
+        registrant: field(response, REGISTRANT_FIELDS).filter(|registrant| {
+            let registrant = registrant.to_lowercase();
+            !PRIVACY_MARKERS
+                .iter()
+                .any(|marker| registrant.contains(marker))
+        }),
+        created: field(response, CREATED_FIELDS).and_then(|value| date(&value)),
+        expires: field(response, EXPIRES_FIELDS).and_then(|value| date(&value)),
+    };
+
+    (registration != WhoisRecord::default()).then_some(registration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_should_read_registrations_and_flag_imminent_expiry() {
+        let response = "   Domain Name: EXAMPLE.COM\r\n\
+            \x20  Registrar WHOIS Server: whois.example-registrar.com\r\n\
+            \x20  Creation Date: 1995-08-14T04:00:00Z\r\n\
+            \x20  Registry Expiry Date: 2026-11-01T04:00:00Z\r\n\
+            \x20  Registrar: Example Registrar, Inc.\r\n\
+            \x20  Registrant Organization: REDACTED FOR PRIVACY\r\n";
+
+        let registration = parse(response).unwrap();
+        assert_eq!(
+            registration,
+            WhoisRecord {
+                registrar: Some(String::from("Example Registrar, Inc.")),
+                registrant: None,
+                created: NaiveDate::from_ymd_opt(1995, 8, 14),
+                expires: NaiveDate::from_ymd_opt(2026, 11, 1),
+            }
+        );
+
+        let finding = Whois::new().registration("example.com", &registration);
+        assert_eq!(finding.kind, "DomainRegistration");
+        assert_eq!(
+            finding.evidence,
+            vec![
+                "Registrar: Example Registrar, Inc.",
+                "Created: 1995-08-14",
+                "Expires: 2026-11-01"
+            ]
+        );
+
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let finding = Whois::new()
+            .expiring("example.com", "ns1.example.com", &registration, today)
+            .unwrap();
+        assert_eq!(
+            (finding.kind.as_str(), finding.url.as_str()),
+            ("DomainExpiring", "dns://example.com")
+        );
+        assert_eq!(
+            finding.evidence,
+            vec!["ns1.example.com is in example.com, which expires on 2026-11-01, in 16 days"]
+        );
+        assert_eq!(finding.metadata["days"], 16);
+        let earlier = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        assert!(
+            Whois::new()
+                .expiring("example.com", "example.com", &registration, earlier)
+                .is_none()
+        );
+
+        assert_eq!(
+            parse("No match for \"EXAMPLE-UNREGISTERED.COM\".\r\n"),
+            None
+        );
+        assert_eq!(date("14-Aug-1995"), NaiveDate::from_ymd_opt(1995, 8, 14));
+    }
+}
@@ -0,0 +1,269 @@
+use crate::body;
+use crate::body::ReadBounded;
+use crate::inventory;
+use crate::inventory::Page;
+use crate::inventory::Technology;
+use crate::throttle::Limiter;
+use crate::throttle::SendAdaptive;
+
+use reqwest::Client;
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+// Bytes a soft 404 page may differ by, e.g. when it echoes the requested path
+const SOFT_404_TOLERANCE: usize = 64;
+
+/// What is known about an endpoint before HTTP modules scan it, so they need not find out again
+/// - Requests are sent with `send_adaptive` through `limiter`, sharing the per-host limit of the
+///   scan
+#[derive(Debug, Clone)]
+pub struct ScanContext {
+    /// `host:port` optionally followed by a base path, as in checkpoints and logs
+    pub endpoint: String,
+    pub host: String,
+    pub ip: IpAddr,
+    pub port: u16,
+    /// The path the endpoint was found under, empty for the root of the port
+    pub base_path: String,
+    /// The scheme the port answered with, unset when it answered to neither
+    pub scheme: Option<&'static str>,
+    /// Technologies given away by the headers of the root page
+    pub technologies: BTreeSet<Technology>,
+    /// The response to a path that cannot exist, telling soft 404 pages apart
+    pub baseline: Option<Baseline>,
+    /// The landing page of the port, listed among the assets of the scan
+    pub page: Option<Page>,
+    /// Per-host concurrency and endpoints given up on, shared with every other endpoint
+    pub limiter: Limiter,
+    /// Options of the scan the endpoint belongs to
+    pub options: HttpOptions,
+}
+
+/// Options of a scan read by HTTP modules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpOptions {
+    /// How much of each response body is read, from `--max-body-size`
+    pub max_body_size: usize,
+    /// Where responses of passive checks are cached, unset with `--no-cache`
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        HttpOptions {
+            max_body_size: body::DEFAULT_MAX_BODY_SIZE,
+            cache_dir: None,
+        }
+    }
+}
+
+/// Status and size of the response to a random path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Baseline {
+    pub status: u16,
+    pub length: usize,
+}
+
+impl ScanContext {
+    /// A context nothing was detected for, HTTPS and HTTP both being tried, with a limiter of
+    /// its own and default options
+    ///
+    /// # Arguments
+    /// * `endpoint` - `host:port` optionally followed by a base path
+    /// * `ip` - The address the host was resolved to
+    pub fn new(endpoint: &str, ip: IpAddr) -> Self {
+        let (authority, base_path) = match endpoint.find('/') {
+            Some(slash) => endpoint.split_at(slash),
+            None => (endpoint, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().unwrap_or_default()),
+            None => (authority, 0),
+        };
+
+        ScanContext {
+            endpoint: endpoint.to_string(),
+            host: host.to_string(),
+            ip,
+            port,
+            base_path: base_path.to_string(),
+            scheme: None,
+            technologies: BTreeSet::new(),
+            baseline: None,
+            page: None,
+            limiter: Limiter::default(),
+            options: HttpOptions::default(),
+        }
+    }
+
+    /// The same context, sending requests through `limiter` and scanned with `options`
+    pub fn with_scan(self, limiter: &Limiter, options: &HttpOptions) -> Self {
+        ScanContext {
+            limiter: limiter.clone(),
+            options: options.clone(),
+            ..self
+        }
+    }
+
+    /// Detect the scheme and technologies of a port, and how it answers to missing paths
+    ///
+    /// # Arguments
+    /// * `http_client` - The HTTP client to use
+    /// * `limiter` - The limits shared by every request of the scan
+    /// * `options` - Options of the scan, kept by the context
+    /// * `host` - The host name the port is on
+    /// * `ip` - The address the host was resolved to
+    /// * `port` - The open port
+    pub async fn detect(
+        http_client: &Client,
+        limiter: &Limiter,
+        options: &HttpOptions,
+        host: &str,
+        ip: IpAddr,
+        port: u16,
+    ) -> Self {
+        let service =
+            inventory::fingerprint(http_client, limiter, options.max_body_size, host, ip, port)
+                .await;
+
+        let mut context =
+            ScanContext::new(&format!("{}:{}", host, port), ip).with_scan(limiter, options);
+        context.scheme = service.scheme;
+        context.technologies = service.technologies;
+        context.page = service.page;
+
+        if let Some(scheme) = context.scheme {
+            let url = context.url(scheme, &format!("/vulnscan-{:016x}", rand::random::<u64>()));
+            if let Ok(resp) = http_client.get(&url).send_adaptive(limiter).await {
+                let status = resp.status().as_u16();
+                if let Ok(body) = resp.text_bounded(options.max_body_size).await {
+                    context.baseline = Some(Baseline {
+                        status,
+                        length: body.len(),
+                    });
+                }
+            }
+        }
+
+        context
+    }
+
+    /// The same port, scanned under `base_path`
+    pub fn with_base_path(&self, base_path: &str) -> Self {
+        ScanContext {
+            endpoint: format!("{}:{}{}", self.host, self.port, base_path),
+            base_path: base_path.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// Schemes to try in turn, only the detected one if any
+    pub fn schemes(&self) -> &'static [&'static str] {
+        match self.scheme {
+            Some("https") => &["https"],
+            Some("http") => &["http"],
+            _ => &["https", "http"],
+        }
+    }
+
+    /// Whether the endpoint is the root of its port, rather than a path found under it
+    pub fn is_root(&self) -> bool {
+        self.base_path.is_empty()
+    }
+
+    /// The URL of `path` under the endpoint, `path` starting with `/`
+    pub fn url(&self, scheme: &str, path: &str) -> String {
+        format!("{}://{}{}", scheme, self.endpoint, path)
+    }
+
+    /// Whether a response looks like the one to a missing path, whatever its status
+    pub fn is_soft_404(&self, status: u16, body: &str) -> bool {
+        self.baseline.is_some_and(|baseline| {
+            baseline.status == status && baseline.length.abs_diff(body.len()) <= SOFT_404_TOLERANCE
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    /// A context of a local endpoint nothing was detected for
+    pub fn local(endpoint: &str) -> ScanContext {
+        ScanContext::new(endpoint, IpAddr::from([127, 0, 0, 1]))
+    }
+
+    #[test]
+    fn test_new_should_split_endpoint() {
+        let ip = "127.0.0.1".parse().unwrap();
+        let context = ScanContext::new("www.example.com:8080/backup", ip);
+
+        assert_eq!(context.host, "www.example.com");
+        assert_eq!(context.port, 8080);
+        assert_eq!(context.base_path, "/backup");
+        assert!(!context.is_root());
+        assert_eq!(context.schemes(), &["https", "http"]);
+        assert_eq!(
+            context.url("http", "/.env"),
+            "http://www.example.com:8080/backup/.env"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detect_should_find_scheme_and_soft_404() {
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200)
+                    .header("Server", "nginx/1.25.3")
+                    .body("Home");
+            })
+            .await;
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(200)
+                    .body("<html><body>Nothing here</body></html>");
+            })
+            .await;
+
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let ip = "127.0.0.1".parse().unwrap();
+        let limiter = Limiter::default();
+        let options = HttpOptions {
+            max_body_size: 1024,
+            ..HttpOptions::default()
+        };
+        let context = ScanContext::detect(
+            &client,
+            &limiter,
+            &options,
+            &mock_server.host(),
+            ip,
+            mock_server.port(),
+        )
+        .await;
+
+        // The mock server also answers HTTPS, which is tried first
+        assert_eq!(context.scheme, Some("https"));
+        assert_eq!(context.schemes(), &["https"]);
+        assert!(
+            context
+                .technologies
+                .iter()
+                .any(|technology| technology.name == "nginx")
+        );
+        assert!(context.is_soft_404(200, "<html><body>Nothing here at all</body></html>"));
+        assert!(!context.is_soft_404(200, &"DB_PASSWORD=secret\n".repeat(10)));
+
+        // Modules read the options of the scan
+        assert_eq!(context.options, options);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::detections;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::HttpModule;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::http::ScanContext;
+use crate::throttle::SendAdaptive;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use reqwest::Client;
+use reqwest::header::SET_COOKIE;
+
+pub struct CookieFlags;
+
+impl CookieFlags {
+    pub fn new() -> Self {
+        CookieFlags
+    }
+}
+
+impl Module for CookieFlags {
+    fn name(&self) -> String {
+        String::from("http/cookie_flags")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-http-cookie_flags")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Low
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://owasp.org/www-community/controls/SecureCookieAttribute",
+            "https://owasp.org/www-community/HttpOnly",
+            "https://developer.mozilla.org/en-US/docs/Web/HTTP/Reference/Headers/Set-Cookie",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/cookie_flags",
+        tags: &[Tag::Misconfig, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Http(|_| Box::new(CookieFlags::new())),
+    }
+}
+
+#[async_trait]
+impl HttpModule for CookieFlags {
+    async fn scan(&self, http_client: &Client, context: &ScanContext) -> Result<Option<Finding>> {
+        // Landing pages set the session cookies, archived paths add nothing
+        if !context.is_root() {
+            return Ok(None);
+        }
+
+        // The first scheme the endpoint answers on is the one it serves
+        for &schema in context.schemes() {
+            let url = context.url(schema, "/");
+            let Ok(resp) = http_client.get(&url).send_adaptive(&context.limiter).await else {
+                continue;
+            };
+
+            let cookies: Vec<String> = resp
+                .headers()
+                .get_all(SET_COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .filter_map(|cookie| cookie_issues(cookie, schema == "https"))
+                .collect();
+
+            if cookies.is_empty() {
+                return Ok(None);
+            }
+
+            return Ok(Some(
+                Finding::new(self.name(), "InsecureCookies", Severity::Low, &url)
+                    .with_evidence(cookies),
+            ));
+        }
+
+        Ok(None)
+    }
+}
+
+/// The missing attributes of a session-looking cookie, as `name: missing Secure, HttpOnly`
+fn cookie_issues(set_cookie: &str, https: bool) -> Option<String> {
+    let mut parts = set_cookie.split(';').map(str::trim);
+    let (name, _) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    // Anti-CSRF tokens are read by scripts on purpose
+    let lowercase = name.to_lowercase();
+    if !detections::get().cookies.session.is_match(name)
+        || lowercase.contains("csrf")
+        || lowercase.contains("xsrf")
+    {
+        return None;
+    }
+
+    let attributes: Vec<(String, String)> = parts
+        .map(|attribute| match attribute.split_once('=') {
+            Some((key, value)) => (key.trim().to_lowercase(), value.trim().to_lowercase()),
+            None => (attribute.to_lowercase(), String::new()),
+        })
+        .collect();
+    let has = |key: &str| attributes.iter().any(|(k, _)| k == key);
+
+    // Browsers ignore Secure on cookies set over plain HTTP
+    let mut missing = Vec::new();
+    if https && !has("secure") {
+        missing.push("Secure");
+    }
+    if !has("httponly") {
+        missing.push("HttpOnly");
+    }
+    match attributes.iter().find(|(key, _)| key == "samesite") {
+        None => missing.push("SameSite"),
+        // Browsers reject SameSite=None without Secure, falling back to Lax
+        Some((_, value)) if value == "none" && !has("secure") => missing.push("SameSite"),
+        Some(_) => {}
+    }
+
+    if missing.is_empty() {
+        return None;
+    }
+
+    Some(format!("{}: missing {}", name, missing.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::http::context::tests::local;
+    use httpmock::prelude::*;
+
+    #[test]
+    fn test_cookie_issues_should_skip_other_cookies() {
+        assert_eq!(
+            cookie_issues("PHPSESSID=abc; path=/; HttpOnly", true),
+            Some(String::from("PHPSESSID: missing Secure, SameSite"))
+        );
+        assert_eq!(
+            cookie_issues("JSESSIONID=abc; Secure; HttpOnly; SameSite=Lax", true),
+            None
+        );
+        assert_eq!(cookie_issues("theme=dark; path=/", true), None);
+        assert_eq!(
+            cookie_issues("auth_token=abc; HttpOnly; SameSite=None", false),
+            Some(String::from("auth_token: missing SameSite"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scan_should_aggregate_cookies_of_endpoint() {
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200)
+                    .header("Set-Cookie", "laravel_session=abc; path=/")
+                    .header(
+                        "Set-Cookie",
+                        "remember_me=1; Secure; HttpOnly; SameSite=Strict",
+                    )
+                    .header("Set-Cookie", "csrftoken=def; Secure; SameSite=Lax")
+                    .header("Set-Cookie", "access_token=ghi; Secure; SameSite=Lax");
+            })
+            .await;
+
+        let module = CookieFlags::new();
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
+
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
+
+        let Some(finding) = result else {
+            panic!("Should report session cookies missing attributes");
+        };
+        assert_eq!(finding.kind, "InsecureCookies");
+        assert_eq!(finding.url, format!("https://{}/", endpoint));
+        assert_eq!(
+            finding.evidence,
+            vec![
+                "laravel_session: missing Secure, HttpOnly, SameSite",
+                "access_token: missing HttpOnly",
+            ]
+        );
+    }
+}
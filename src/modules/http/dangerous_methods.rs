@@ -0,0 +1,283 @@
+use crate::body::ReadBounded;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::HttpModule;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::http::ScanContext;
+use crate::throttle::SendAdaptive;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use reqwest::Client;
+use reqwest::Method;
+use reqwest::header::ALLOW;
+
+const METHODS: &[&str] = &["PUT", "DELETE", "TRACE"];
+const TRACE_HEADER: &str = "X-Vulnscan-Trace";
+const CANARY_BODY: &str = "vulnscan canary, safe to delete";
+
+pub struct DangerousMethods {
+    // Uploading and deleting a canary file writes to the target, so it is only done on request
+    aggressive: bool,
+}
+
+impl DangerousMethods {
+    pub fn new(aggressive: bool) -> Self {
+        DangerousMethods { aggressive }
+    }
+}
+
+impl Module for DangerousMethods {
+    fn name(&self) -> String {
+        String::from("http/dangerous_methods")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-http-dangerous_methods").arg("flag", "--aggressive")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://owasp.org/www-project-web-security-testing-guide/latest/4-Web_Application_Security_Testing/02-Configuration_and_Deployment_Management_Testing/06-Test_HTTP_Methods",
+            "https://cwe.mitre.org/data/definitions/650.html",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/dangerous_methods",
+        tags: &[Tag::Misconfig],
+        intrusiveness: Intrusiveness::Partial,
+        requires: &[],
+        build: Build::Http(|options| Box::new(DangerousMethods::new(options.aggressive))),
+    }
+}
+
+#[async_trait]
+impl HttpModule for DangerousMethods {
+    async fn scan(&self, http_client: &Client, context: &ScanContext) -> Result<Option<Finding>> {
+        // Methods are configured per server, archived paths add nothing
+        if !context.is_root() {
+            return Ok(None);
+        }
+
+        // The first scheme the endpoint answers on is the one it serves
+        for &schema in context.schemes() {
+            let url = context.url(schema, "/");
+            let Ok(resp) = http_client
+                .request(Method::OPTIONS, &url)
+                .send_adaptive(&context.limiter)
+                .await
+            else {
+                continue;
+            };
+
+            // IIS lists the methods of the whole server in `Public`
+            let advertised: Vec<String> = resp
+                .headers()
+                .get_all(ALLOW)
+                .iter()
+                .chain(resp.headers().get_all("Public"))
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .map(|method| method.trim().to_uppercase())
+                .filter(|method| METHODS.contains(&method.as_str()))
+                .collect();
+
+            let mut accepted = Vec::new();
+            if traces(http_client, context, &url).await {
+                accepted.push(String::from("TRACE"));
+            }
+            if self.aggressive {
+                accepted.extend(upload_canary(http_client, context, &url).await);
+            }
+
+            let mut advertised: Vec<String> = advertised
+                .into_iter()
+                .filter(|method| !accepted.contains(method))
+                .collect();
+            advertised.sort();
+            advertised.dedup();
+
+            if accepted.is_empty() && advertised.is_empty() {
+                return Ok(None);
+            }
+
+            // Working uploads are High, Cross-Site Tracing Low, methods only advertised Info
+            let severity = if accepted.iter().any(|method| method != "TRACE") {
+                Severity::High
+            } else if !accepted.is_empty() {
+                Severity::Low
+            } else {
+                Severity::Info
+            };
+            let mut evidence = Vec::new();
+            if !accepted.is_empty() {
+                evidence.push(format!("accepted: {}", accepted.join(", ")));
+            }
+            if !advertised.is_empty() {
+                evidence.push(format!("advertised: {}", advertised.join(", ")));
+            }
+
+            return Ok(Some(
+                Finding::new(self.name(), "DangerousMethods", severity, &url)
+                    .with_evidence(evidence)
+                    .with_metadata("accepted", &accepted)
+                    .with_metadata("advertised", &advertised),
+            ));
+        }
+
+        Ok(None)
+    }
+
+    fn max_requests(&self) -> usize {
+        // OPTIONS over HTTPS then HTTP, TRACE, then PUT, GET and DELETE of the canary
+        if self.aggressive { 6 } else { 3 }
+    }
+}
+
+/// Whether TRACE echoes the request back, headers included (Cross-Site Tracing)
+async fn traces(http_client: &Client, context: &ScanContext, url: &str) -> bool {
+    let marker = format!("{:016x}", rand::random::<u64>());
+    let Ok(resp) = http_client
+        .request(Method::TRACE, url)
+        .header(TRACE_HEADER, &marker)
+        .send_adaptive(&context.limiter)
+        .await
+    else {
+        return false;
+    };
+
+    if !resp.status().is_success() {
+        return false;
+    }
+
+    resp.text_bounded(context.options.max_body_size)
+        .await
+        .is_ok_and(|body| body.contains(&marker))
+}
+
+/// The methods confirmed by storing a canary file under `base`, then deleting it
+async fn upload_canary(http_client: &Client, context: &ScanContext, base: &str) -> Vec<String> {
+    let url = format!("{}vulnscan-{:016x}.txt", base, rand::random::<u64>());
+
+    let stored = http_client
+        .put(&url)
+        .body(CANARY_BODY)
+        .send_adaptive(&context.limiter)
+        .await
+        .is_ok_and(|resp| resp.status().is_success());
+    if !stored {
+        return Vec::new();
+    }
+
+    // Servers may answer PUT with success without storing anything
+    let Ok(resp) = http_client.get(&url).send_adaptive(&context.limiter).await else {
+        return Vec::new();
+    };
+    if !resp.status().is_success() {
+        return Vec::new();
+    }
+    let body = resp.text_bounded(context.options.max_body_size).await;
+    if body.ok().as_deref() != Some(CANARY_BODY) {
+        return Vec::new();
+    }
+
+    let deleted = http_client
+        .delete(&url)
+        .send_adaptive(&context.limiter)
+        .await
+        .is_ok_and(|resp| resp.status().is_success());
+    if !deleted {
+        log::warn!("Failed to delete canary {}, remove it by hand", url);
+        return vec![String::from("PUT")];
+    }
+
+    vec![String::from("PUT"), String::from("DELETE")]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::http::context::tests::local;
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_scan_should_only_upload_when_aggressive() {
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(OPTIONS).path("/");
+                then.status(200)
+                    .header("Allow", "GET, HEAD, OPTIONS, PUT, DELETE, TRACE");
+            })
+            .await;
+        let put = mock_server
+            .mock_async(|when, then| {
+                when.method(PUT)
+                    .path_matches(r"^/vulnscan-[0-9a-f]{16}\.txt$")
+                    .body(CANARY_BODY);
+                then.status(201);
+            })
+            .await;
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path_matches(r"^/vulnscan-[0-9a-f]{16}\.txt$");
+                then.status(200).body(CANARY_BODY);
+            })
+            .await;
+        let delete = mock_server
+            .mock_async(|when, then| {
+                when.method(DELETE)
+                    .path_matches(r"^/vulnscan-[0-9a-f]{16}\.txt$");
+                then.status(204);
+            })
+            .await;
+
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
+        let url = format!("https://{}/", endpoint);
+
+        // TRACE is answered with a 404, so it is only advertised
+        let result = DangerousMethods::new(false)
+            .scan(&client, &local(&endpoint))
+            .await
+            .unwrap();
+        let finding = result.expect("Should report advertised methods");
+        assert_eq!(finding.url, url);
+        assert_eq!(finding.severity, Severity::Info);
+        assert_eq!(finding.metadata["accepted"], json!([]));
+        assert_eq!(
+            finding.metadata["advertised"],
+            json!(["DELETE", "PUT", "TRACE"])
+        );
+        assert_eq!(put.calls_async().await, 0);
+
+        let result = DangerousMethods::new(true)
+            .scan(&client, &local(&endpoint))
+            .await
+            .unwrap();
+        let finding = result.expect("Should report accepted methods");
+        assert_eq!(finding.url, url);
+        assert_eq!(finding.severity, Severity::High);
+        assert_eq!(finding.metadata["accepted"], json!(["PUT", "DELETE"]));
+        assert_eq!(finding.metadata["advertised"], json!(["TRACE"]));
+        assert_eq!(delete.calls_async().await, 1);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::body::ReadBounded;
+use crate::cache::SendCached;
+use crate::detections;
+use crate::detections::DebugEndpoint;
+use crate::i18n::Message;
+use crate::matcher;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::HttpModule;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::http::ScanContext;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use reqwest::Client;
+
+pub struct DebugEndpoints;
+
+impl DebugEndpoints {
+    pub fn new() -> Self {
+        DebugEndpoints
+    }
+}
+
+impl Module for DebugEndpoints {
+    fn name(&self) -> String {
+        String::from("http/debug_endpoints")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-http-debug_endpoints")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Medium
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec!["https://cwe.mitre.org/data/definitions/215.html"]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/debug_endpoints",
+        tags: &[Tag::Exposure, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Http(|_| Box::new(DebugEndpoints::new())),
+    }
+}
+
+#[async_trait]
+impl HttpModule for DebugEndpoints {
+    async fn scan(&self, http_client: &Client, context: &ScanContext) -> Result<Option<Finding>> {
+        let checker = |url: String, probe: &'static DebugEndpoint| async move {
+            let resp = http_client.get(&url).send_cached(context).await.ok()?;
+
+            if !probe.any_status && !resp.status().is_success() {
+                return None;
+            }
+
+            let body = resp
+                .text_bounded(context.options.max_body_size)
+                .await
+                .ok()?;
+
+            let is_vulnerable = matcher::run(body, |body| probe.pattern.is_match(body)).await;
+
+            if is_vulnerable {
+                return Some(
+                    Finding::new(self.name(), "DebugEndpointExposure", Severity::Medium, &url)
+                        .with_evidence(vec![probe.technology.to_string()])
+                        .with_metadata("technology", &probe.technology),
+                );
+            }
+
+            None
+        };
+
+        // Send HTTPS and HTTP requests to check if any debug page is accessible
+        for &schema in context.schemes() {
+            for probe in &detections::get().debug_endpoints {
+                let url = context.url(schema, &probe.path);
+                if let Some(finding) = checker(url, probe).await {
+                    return Ok(Some(finding));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn max_requests(&self) -> usize {
+        detections::get().debug_endpoints.len() * 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::http::context::tests::local;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_scan_should_return_some_when_pattern_matched() {
+        // Set up mock target HTTP server and its response
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/info.php");
+                then.status(200).body(
+                    r#"<html><head><title>PHP 8.2.0 - phpinfo()</title></head>
+                    <body><h1 class="p">PHP Version 8.2.0</h1></body></html>"#,
+                );
+            })
+            .await;
+
+        // Set up input arguments
+        let module = DebugEndpoints::new();
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
+
+        // Run scan
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
+
+        // Check result
+        assert!(result.is_some(), "Should return Some when pattern matched");
+
+        if let Some(finding) = result {
+            assert_eq!(finding.url, format!("https://{}/info.php", endpoint));
+            assert_eq!(finding.metadata_str("technology"), Some("PHP"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_should_return_none_when_pattern_unmatched() {
+        // Set up mock target HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        // Set up input arguments
+        let module = DebugEndpoints::new();
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
+
+        // --- Case A: 404 not found ---
+        let not_found = mock_server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(404).body("<html><body>Not Found</body></html>");
+            })
+            .await;
+
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
+        assert!(
+            result.is_none(),
+            "Should return None when server returns 404"
+        );
+
+        not_found.delete_async().await;
+
+        // --- Case B: Soft 404 (unrelated response body) ---
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(200)
+                    .body("<html><body>Page Not Found but 200 OK</body></html>");
+            })
+            .await;
+
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
+        assert!(
+            result.is_none(),
+            "Should return None when server returns 2xx with wrong response body"
+        );
+    }
+}
@@ -1,25 +1,27 @@
+use crate::body::ReadBounded;
+use crate::cache::SendCached;
+use crate::detections;
+use crate::i18n::Message;
+use crate::matcher;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::FollowUp;
 use crate::modules::HttpModule;
+use crate::modules::Intrusiveness;
 use crate::modules::Module;
-use crate::modules::http::HttpFindings;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::http::ScanContext;
 use async_trait::async_trait;
 
 use anyhow::Result;
-use once_cell::sync::Lazy;
-use regex::RegexSet;
 use reqwest::Client;
 
-pub struct DirectoryListing;
+// Listed directories scanned in turn, the first ones of the listing
+const MAX_LISTED_DIRECTORIES: usize = 10;
 
-static VULNERABLE_PATTERN: Lazy<RegexSet> = Lazy::new(|| {
-    RegexSet::new([
-        r"(?i)Index of /.*",                                      // Apache/Nginx
-        r"(?i)directory listing - /.*",                           // Microsoft IIS
-        r"(?i)Directory Listing For /.*",                         // Apache Tomcat
-        r"(?i)Parent Directory",                                  // HTML Link
-        r#"(?i)<A HREF=["']?/[^>]*>\[To Parent Directory\]</A>"#, // Old IIS
-    ])
-    .expect("Failed to compile regex patterns, please check the syntax")
-});
+pub struct DirectoryListing;
 
 impl DirectoryListing {
     pub fn new() -> Self {
@@ -32,39 +34,82 @@ impl Module for DirectoryListing {
         String::from("http/directory_listing")
     }
 
-    fn description(&self) -> String {
-        String::from("Check if directory listing is publicly accessible")
+    fn description(&self) -> Message {
+        Message::new("module-http-directory_listing")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Low
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec!["https://cwe.mitre.org/data/definitions/548.html"]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/directory_listing",
+        tags: &[Tag::Exposure, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Http(|_| Box::new(DirectoryListing::new())),
     }
 }
 
 #[async_trait]
 impl HttpModule for DirectoryListing {
-    async fn scan(&self, http_client: &Client, endpoint: &str) -> Result<Option<HttpFindings>> {
-        let checker = |url: String| async {
+    async fn scan(&self, http_client: &Client, context: &ScanContext) -> Result<Option<Finding>> {
+        let checker = |url: String| async move {
             let client = http_client.clone();
-            let resp = client.get(&url).send().await.ok()?;
+            let resp = client.get(&url).send_cached(context).await.ok()?;
 
             if !resp.status().is_success() {
                 return None;
             }
 
-            let body = resp.text().await.ok()?;
-
-            let is_vulnerable =
-                tokio::task::spawn_blocking(move || VULNERABLE_PATTERN.is_match(&body))
-                    .await
-                    .ok()?;
+            let body = resp
+                .text_bounded(context.options.max_body_size)
+                .await
+                .ok()?;
+
+            let listed = matcher::run(body, |body| {
+                if !detections::get().exposures.directory_listing.is_match(body) {
+                    return None;
+                }
+
+                let mut directories: Vec<String> = Vec::new();
+                let listed = &detections::get().markup.listed_directory;
+                for captures in listed.captures_iter(body) {
+                    let directory = captures[1].to_string();
+                    if !directories.contains(&directory) {
+                        directories.push(directory);
+                    }
+                }
+                Some(directories)
+            })
+            .await;
 
-            if is_vulnerable {
-                return Some(HttpFindings::DirectoryListing(url));
+            // Listed directories are scanned by every HTTP module in turn
+            if let Some(directories) = listed {
+                let follow_ups = directories
+                    .iter()
+                    .take(MAX_LISTED_DIRECTORIES)
+                    .map(|directory| FollowUp::Path(format!("{}/{}", context.base_path, directory)))
+                    .collect();
+
+                return Some(
+                    Finding::new(self.name(), "DirectoryListing", Severity::Low, &url)
+                        .with_follow_ups(follow_ups),
+                );
             }
 
             None
         };
 
         // Send HTTPS and HTTP requests to check if .env is accessible
-        for schema in ["https", "http"] {
-            let url = format!("{}://{}/", schema, endpoint);
+        for &schema in context.schemes() {
+            let url = context.url(schema, "/");
             if let Some(finding) = checker(url).await {
                 return Ok(Some(finding));
             }
@@ -74,8 +119,10 @@ impl HttpModule for DirectoryListing {
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
+    use crate::modules::http::context::tests::local;
     use httpmock::prelude::*;
 
     #[tokio::test]
@@ -86,8 +133,14 @@ mod tests {
         mock_server
             .mock_async(|when, then| {
                 when.method(GET).path("/");
-                then.status(200)
-                    .body("<html><body>Index of /</body></html>");
+                then.status(200).body(concat!(
+                    "<html><body><h1>Index of /</h1>",
+                    "<a href=\"?C=N;O=D\">Name</a>",
+                    "<a href=\"/\">Parent Directory</a>",
+                    "<a href=\"backup/\">backup/</a>",
+                    "<a href=\"notes.txt\">notes.txt</a>",
+                    "</body></html>"
+                ));
             })
             .await;
 
@@ -100,13 +153,17 @@ mod tests {
         let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
 
         // Run scan
-        let result = module.scan(&client, &endpoint).await.unwrap();
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
 
         // Check result
         assert!(result.is_some());
 
-        if let Some(HttpFindings::DirectoryListing(url)) = result {
-            assert_eq!(url, format!("https://{}/", endpoint));
+        if let Some(finding) = result {
+            assert_eq!(finding.url, format!("https://{}/", endpoint));
+            assert_eq!(
+                finding.follow_ups,
+                vec![FollowUp::Path(String::from("/backup"))]
+            );
         }
     }
 
@@ -130,7 +187,7 @@ mod tests {
                 then.status(404);
             })
             .await;
-        let result = module.scan(&client, &endpoint).await.unwrap();
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
         assert!(
             result.is_none(),
             "Should return None when server returns 404"
@@ -143,7 +200,7 @@ mod tests {
                 then.status(200).body("Any response body");
             })
             .await;
-        let result = module.scan(&client, &endpoint).await.unwrap();
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
         assert!(
             result.is_none(),
             "Should return None when response body doesn't contain directory listing"
@@ -1,14 +1,135 @@
+use crate::modules::Build;
+use crate::modules::Intrusiveness;
 use crate::modules::HttpModule;
+use crate::modules::http::ScanContext;
+use crate::i18n::Message;
 use crate::modules::Module;
-use crate::modules::http::HttpFindings;
+use crate::modules::Registration;
+use crate::modules::Tag;
+use crate::modules::Severity;
+use crate::modules::Finding;
+use crate::body::ReadBounded;
+use crate::cache::SendCached;
+use crate::detections;
 use async_trait::async_trait;
 
 use anyhow::Result;
 use reqwest::Client;
 use reqwest::header::CONTENT_TYPE;
+use serde::Deserialize;
+use serde::Serialize;
 
 pub struct DotEnvDisclosure;
 
+// Values shorter than this are hidden entirely when redacted
+const MIN_REVEALED_LENGTH: usize = 8;
+
+/// What kind of credential a `.env` key holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CredentialKind {
+    Database,
+    Mail,
+    ApiKey,
+}
+
+/// A classified `.env` entry with its value redacted
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DotEnvEntry {
+    pub key: String,
+    pub kind: CredentialKind,
+    pub redacted_value: String,
+    // Whether the value looks like a real secret rather than a placeholder
+    pub is_secret: bool,
+}
+
+/// Parse `KEY=value` lines and keep the ones holding credentials
+pub fn classify(body: &str) -> Vec<DotEnvEntry> {
+    body.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix("export ").unwrap_or(line);
+
+            if line.starts_with('#') {
+                return None;
+            }
+
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+
+            if key.is_empty()
+                || !key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+            {
+                return None;
+            }
+
+            let keys = &detections::get().dotenv_keys;
+            let kind = if keys.database.is_match(key) {
+                CredentialKind::Database
+            } else if keys.mail.is_match(key) {
+                CredentialKind::Mail
+            } else if keys.api_key.is_match(key) {
+                CredentialKind::ApiKey
+            } else {
+                return None;
+            };
+
+            let value = unquote(value.trim());
+            let is_secret = !is_placeholder(value)
+                && (keys.secret.is_match(key) || detections::get().credentials.url.is_match(value));
+
+            Some(DotEnvEntry {
+                key: key.to_string(),
+                kind,
+                redacted_value: redact(value),
+                is_secret,
+            })
+        })
+        .collect()
+}
+
+// Strip surrounding quotes, or a trailing comment from unquoted values
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote)
+            && let Some(end) = inner.find(quote)
+        {
+            return &inner[..end];
+        }
+    }
+
+    match value.find(" #") {
+        Some(end) => value[..end].trim_end(),
+        None => value,
+    }
+}
+
+fn is_placeholder(value: &str) -> bool {
+    let lowercase = value.to_lowercase();
+
+    value.is_empty()
+        || detections::get().credentials.placeholders.contains(&lowercase)
+        || lowercase.starts_with("your")
+        || value.starts_with('<')
+        || value.starts_with("${")
+        || value
+            .chars()
+            .all(|c| c == value.chars().next().unwrap_or_default())
+}
+
+// Keep the first two characters so the value can still be recognised, unless they would give
+// away most of a short value
+fn redact(value: &str) -> String {
+    if value.chars().count() < MIN_REVEALED_LENGTH {
+        return "*".repeat(4);
+    }
+
+    let prefix: String = value.chars().take(2).collect();
+    let hidden = value.chars().count().saturating_sub(2).clamp(4, 8);
+    format!("{}{}", prefix, "*".repeat(hidden))
+}
+
 impl DotEnvDisclosure {
     pub fn new() -> Self {
         DotEnvDisclosure
@@ -20,21 +141,40 @@ impl Module for DotEnvDisclosure {
         String::from("http/dotenv_disclosure")
     }
 
-    fn description(&self) -> String {
-        String::from("Check if .env is publicly accessible")
+    fn description(&self) -> Message {
+        Message::new("module-http-dotenv_disclosure")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec!["https://cwe.mitre.org/data/definitions/538.html"]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/dotenv_disclosure",
+        tags: &[Tag::Exposure, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Http(|_| Box::new(DotEnvDisclosure::new())),
     }
 }
 
 #[async_trait]
 impl HttpModule for DotEnvDisclosure {
-    async fn scan(&self, http_client: &Client, endpoint: &str) -> Result<Option<HttpFindings>> {
+    async fn scan(&self, http_client: &Client, context: &ScanContext) -> Result<Option<Finding>> {
         // A checker function:
-        // Return `HttpFindings(url)` if the following conditions are ALL met:
+        // Return a finding with the classified credentials if the following conditions are ALL met:
         //   HTTP 2xx
         //   Response size < 10KB
         //   Content-Type == text/plain
-        let checker = |url: String| async {
-            let resp = &http_client.get(&url).send().await.ok()?;
+        //   Unlike the response to a missing path
+        let checker = |url: String| async move {
+            let resp = http_client.get(&url).send_cached(context).await.ok()?;
 
             if !resp.status().is_success() {
                 return None;
@@ -48,12 +188,45 @@ impl HttpModule for DotEnvDisclosure {
                 return None;
             }
 
-            Some(HttpFindings::DotEnvDisclosure(url))
+            let status = resp.status().as_u16();
+            let body = resp.text_bounded(context.options.max_body_size).await.ok()?;
+
+            // Catch-all pages served as text/plain are no `.env` file
+            if context.is_soft_404(status, &body) {
+                return None;
+            }
+
+            let entries = classify(&body);
+
+            // Raised to High when the file contains real secrets
+            let severity = if entries.iter().any(|entry| entry.is_secret) {
+                Severity::High
+            } else {
+                Severity::Medium
+            };
+            let evidence = entries
+                .iter()
+                .map(|entry| {
+                    format!(
+                        "{}={} ({:?}{})",
+                        entry.key,
+                        entry.redacted_value,
+                        entry.kind,
+                        if entry.is_secret { ", secret" } else { "" }
+                    )
+                })
+                .collect();
+
+            Some(
+                Finding::new(self.name(), "DotEnvDisclosure", severity, &url)
+                    .with_evidence(evidence)
+                    .with_metadata("entries", &entries),
+            )
         };
 
         // Send HTTPS and HTTP requests to check if .env is accessible
-        for schema in ["https", "http"] {
-            let url = format!("{}://{}/.env", schema, endpoint);
+        for &schema in context.schemes() {
+            let url = context.url(schema, "/.env");
             if let Some(finding) = checker(url).await {
                 return Ok(Some(finding));
             }
@@ -63,8 +236,10 @@ impl HttpModule for DotEnvDisclosure {
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
+    use crate::modules::http::context::tests::local;
     use httpmock::prelude::*;
 
     #[tokio::test]
@@ -90,14 +265,59 @@ mod tests {
         let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
 
         // Run scan
-        let result = module.scan(&client, &endpoint).await.unwrap();
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
 
         // Check result
         assert!(result.is_some(), "Should return Some when pattern matched");
 
-        if let Some(HttpFindings::DotEnvDisclosure(url)) = result {
-            assert_eq!(url, format!("https://{}/.env", endpoint));
+        if let Some(finding) = result {
+            let entries: Vec<DotEnvEntry> =
+                serde_json::from_value(finding.metadata["entries"].clone()).unwrap();
+            assert_eq!(finding.url, format!("https://{}/.env", endpoint));
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].kind, CredentialKind::Database);
+            assert_eq!(entries[0].redacted_value, "****");
+        }
+    }
+
+    #[test]
+    fn test_classify_should_only_flag_real_secrets() {
+        let entries = classify(
+            r#"
+            # Application
+            APP_NAME=Laravel
+            DB_HOST=127.0.0.1
+            DB_PASSWORD="s3cr3t-p4ss" # production
+            MAIL_PASSWORD=null
+            export STRIPE_API_KEY=sk_live_abcdef123456
+            AWS_SECRET_ACCESS_KEY=your-secret-here
+            "#,
+        );
+
+        let keys: Vec<(&str, CredentialKind, bool)> = entries
+            .iter()
+            .map(|entry| (entry.key.as_str(), entry.kind, entry.is_secret))
+            .collect();
+
+        assert_eq!(
+            keys,
+            vec![
+                ("DB_HOST", CredentialKind::Database, false),
+                ("DB_PASSWORD", CredentialKind::Database, true),
+                ("MAIL_PASSWORD", CredentialKind::Mail, false),
+                ("STRIPE_API_KEY", CredentialKind::ApiKey, true),
+                ("AWS_SECRET_ACCESS_KEY", CredentialKind::ApiKey, false),
+            ]
+        );
+        assert_eq!(entries[1].redacted_value, "s3********");
+    }
+
+    #[test]
+    fn test_redact_should_hide_short_values_entirely() {
+        for value in ["a", "ab", "abc", "1234567"] {
+            assert_eq!(redact(value), "****");
         }
+        assert_eq!(redact("12345678"), "12******");
     }
 
     #[tokio::test]
@@ -121,7 +341,7 @@ mod tests {
             })
             .await;
 
-        let result = module.scan(&client, &endpoint).await.unwrap();
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
         assert!(
             result.is_none(),
             "Should return None when server returns 404"
@@ -137,7 +357,7 @@ mod tests {
             })
             .await;
 
-        let result = module.scan(&client, &endpoint).await.unwrap();
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
         assert!(
             result.is_none(),
             "Should return None when server returns 2xx with wrong MIME Type"
@@ -153,7 +373,7 @@ mod tests {
             })
             .await;
 
-        let result = module.scan(&client, &endpoint).await.unwrap();
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
         assert!(
             result.is_none(),
             "Should return None when server returns 2xx with large response body"
@@ -1,22 +1,24 @@
+use crate::modules::Build;
+use crate::modules::Intrusiveness;
+use crate::modules::Finding;
 use crate::modules::HttpModule;
+use crate::modules::http::ScanContext;
+use crate::i18n::Message;
 use crate::modules::Module;
-use crate::modules::http::HttpFindings;
+use crate::modules::Registration;
+use crate::modules::Tag;
+use crate::modules::Severity;
+use crate::body::ReadBounded;
+use crate::matcher;
+use crate::cache::SendCached;
+use crate::detections;
 use async_trait::async_trait;
 
 use anyhow::Result;
-use once_cell::sync::Lazy;
-use regex::RegexSet;
 use reqwest::Client;
 
 pub struct GitHeadLeakage;
 
-static VULNERABLE_PATTERN: Lazy<RegexSet> = Lazy::new(|| {
-    RegexSet::new([
-        r"^ref: refs/heads/",
-        r"^[0-9a-f]{40}$",
-    ]).expect("Failed to compile regex patterns")
-});
-
 impl GitHeadLeakage {
     pub fn new() -> Self {
         GitHeadLeakage
@@ -28,39 +30,60 @@ impl Module for GitHeadLeakage {
         String::from("http/git_head_leakage")
     }
 
-    fn description(&self) -> String {
-        String::from("Check if .git/head is publicly accessible")
+    fn description(&self) -> Message {
+        Message::new("module-http-git_head_leakage")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Medium
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec!["https://cwe.mitre.org/data/definitions/527.html"]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/git_head_leakage",
+        tags: &[Tag::Exposure, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Http(|_| Box::new(GitHeadLeakage::new())),
     }
 }
 
 #[async_trait]
 impl HttpModule for GitHeadLeakage {
-    async fn scan(&self, http_client: &Client, endpoint: &str) -> Result<Option<HttpFindings>> {
-        let checker = |url: String| async {
+    async fn scan(&self, http_client: &Client, context: &ScanContext) -> Result<Option<Finding>> {
+        let checker = |url: String| async move {
             let client = http_client.clone();
-            let resp = client.get(&url).send().await.ok()?;
+            let resp = client.get(&url).send_cached(context).await.ok()?;
 
             if !resp.status().is_success() {
                 return None;
             }
 
-            let body = resp.text().await.ok()?;
+            let body = resp.text_bounded(context.options.max_body_size).await.ok()?;
 
             let is_vulnerable =
-                tokio::task::spawn_blocking(move || VULNERABLE_PATTERN.is_match(&body))
-                    .await
-                    .ok()?;
+                matcher::run(body, |body| detections::get().exposures.git_head.is_match(body)).await;
 
             if is_vulnerable {
-                return Some(HttpFindings::GitHeadLeakage(url));
+                return Some(Finding::new(
+                    self.name(),
+                    "GitHeadLeakage",
+                    Severity::Medium,
+                    &url,
+                ));
             }
 
             None
         };
 
         // Send HTTPS and HTTP requests to check if .env is accessible
-        for schema in ["https", "http"] {
-            let url = format!("{}://{}/.git/HEAD", schema, endpoint);
+        for &schema in context.schemes() {
+            let url = context.url(schema, "/.git/HEAD");
             if let Some(finding) = checker(url).await {
                 return Ok(Some(finding));
             }
@@ -69,8 +92,10 @@ impl HttpModule for GitHeadLeakage {
         Ok(None)    }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
+    use crate::modules::http::context::tests::local;
     use httpmock::prelude::*;
 
     #[tokio::test]
@@ -91,13 +116,13 @@ mod tests {
         let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
 
         // Run scan
-        let result = module.scan(&client, &endpoint).await.unwrap();
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
 
         // Check result
         assert!(result.is_some(), "Should return Some when pattern matched");
 
-        if let Some(HttpFindings::GitHeadLeakage(url)) = result {
-            assert_eq!(url, format!("https://{}/.git/HEAD", endpoint));
+        if let Some(finding) = result {
+            assert_eq!(finding.url, format!("https://{}/.git/HEAD", endpoint));
         }
     }
 
@@ -117,7 +142,7 @@ mod tests {
             then.status(404);
         }).await;
 
-        let result = module.scan(&client, &endpoint).await.unwrap();
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
         assert!(result.is_none(), "Should return None when server returns 404");
 
         // --- Case B: Soft 404 (unrelated response body) ---
@@ -127,7 +152,7 @@ mod tests {
                 .body("<html><body>Page Not Found but 200 OK</body></html>");
         }).await;
 
-        let result = module.scan(&client, &endpoint).await.unwrap();
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
         assert!(result.is_none(), "Should return None when server returns 2xx with wrong response body");
 
     }
@@ -0,0 +1,309 @@
+use crate::body::ReadBounded;
+use crate::throttle::Limiter;
+use crate::throttle::SendAdaptive;
+use anyhow::Result;
+use anyhow::bail;
+use flate2::read::ZlibDecoder;
+use reqwest::Client;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+// Indexes and objects are read whole, but never beyond this size
+const MAX_FILE_SIZE: usize = 64 * 1024 * 1024;
+
+/// A file tracked by a leaked `.git/index`
+pub struct IndexEntry {
+    pub path: String,
+    pub sha1: String,
+}
+
+/// Reconstruct the file listing of a leaked git repository
+/// - Download and parse `.git/index`
+/// - Optionally download every blob object into `dump_dir`
+///
+/// # Arguments
+/// * `http_client` - The HTTP client to use
+/// * `limiter` - The limits shared by every request of the scan
+/// * `repo_url` - The URL of the leaked `.git/` directory (with trailing slash)
+/// * `dump_dir` - Where to write the recovered source files, if given
+pub async fn reconstruct(
+    http_client: &Client,
+    limiter: &Limiter,
+    repo_url: &str,
+    dump_dir: Option<&Path>,
+) -> Result<Vec<String>> {
+    let resp = http_client
+        .get(format!("{}index", repo_url))
+        .send_adaptive(limiter)
+        .await?;
+
+    if !resp.status().is_success() {
+        bail!("Failed to download {}index: {}", repo_url, resp.status());
+    }
+
+    let entries = parse_index(&resp.bytes_limited(MAX_FILE_SIZE).await?)?;
+
+    log::info!(
+        "Recovered {} file names from {}index",
+        entries.len(),
+        repo_url
+    );
+
+    if let Some(dump_dir) = dump_dir {
+        for entry in &entries {
+            if let Err(e) = dump_object(http_client, limiter, repo_url, entry, dump_dir).await {
+                log::warn!("Failed to dump {} from {}: {}", entry.path, repo_url, e);
+            }
+        }
+    }
+
+    Ok(entries.into_iter().map(|entry| entry.path).collect())
+}
+
+/// Parse a git index file (versions 2, 3 and 4)
+///
+/// See https://git-scm.com/docs/index-format
+pub fn parse_index(data: &[u8]) -> Result<Vec<IndexEntry>> {
+    let read_u32 = |offset: usize| -> Result<u32> {
+        match data.get(offset..offset + 4) {
+            Some(bytes) => Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+            None => bail!("Truncated git index"),
+        }
+    };
+
+    if data.get(0..4) != Some(b"DIRC") {
+        bail!("Not a git index file (missing DIRC signature)");
+    }
+
+    let version = read_u32(4)?;
+    if !(2..=4).contains(&version) {
+        bail!("Unsupported git index version: {}", version);
+    }
+
+    let count = read_u32(8)? as usize;
+    let mut entries = Vec::with_capacity(count.min(65_536));
+    let mut previous_path: Vec<u8> = Vec::new();
+    let mut offset = 12;
+
+    for _ in 0..count {
+        let entry_start = offset;
+
+        // Skip ctime, mtime, dev, ino, mode, uid, gid and size (10 x 32-bit)
+        offset += 40;
+
+        let sha1 = match data.get(offset..offset + 20) {
+            Some(bytes) => bytes
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>(),
+            None => bail!("Truncated git index"),
+        };
+        offset += 20;
+
+        let flags = match data.get(offset..offset + 2) {
+            Some(bytes) => u16::from_be_bytes([bytes[0], bytes[1]]),
+            None => bail!("Truncated git index"),
+        };
+        offset += 2;
+
+        // Extended flags are only present from version 3 onwards
+        if version >= 3 && flags & 0x4000 != 0 {
+            offset += 2;
+        }
+
+        let path = if version == 4 {
+            // Path is prefix-compressed against the previous entry
+            let mut strip: usize = 0;
+            loop {
+                let Some(&byte) = data.get(offset) else {
+                    bail!("Truncated git index");
+                };
+                offset += 1;
+                strip = (strip << 7) | (byte & 0x7f) as usize;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+                strip += 1;
+            }
+
+            let Some(suffix_len) = data[offset.min(data.len())..].iter().position(|&b| b == 0)
+            else {
+                bail!("Truncated git index");
+            };
+
+            let mut path = previous_path[..previous_path.len().saturating_sub(strip)].to_vec();
+            path.extend_from_slice(&data[offset..offset + suffix_len]);
+            offset += suffix_len + 1;
+            path
+        } else {
+            let Some(path_len) = data[offset.min(data.len())..].iter().position(|&b| b == 0) else {
+                bail!("Truncated git index");
+            };
+
+            let path = data[offset..offset + path_len].to_vec();
+
+            // Entries are NUL-padded to a multiple of 8 bytes
+            let entry_len = offset + path_len - entry_start;
+            offset = entry_start + (entry_len + 8) / 8 * 8;
+            path
+        };
+
+        entries.push(IndexEntry {
+            path: String::from_utf8_lossy(&path).into_owned(),
+            sha1,
+        });
+        previous_path = path;
+    }
+
+    Ok(entries)
+}
+
+/// Download a loose blob object and write its content to `dump_dir`
+async fn dump_object(
+    http_client: &Client,
+    limiter: &Limiter,
+    repo_url: &str,
+    entry: &IndexEntry,
+    dump_dir: &Path,
+) -> Result<()> {
+    // Refuse to write outside `dump_dir` (e.g. `../../.bashrc` in a malicious index)
+    let relative = PathBuf::from(&entry.path);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        bail!("Refusing to write unsafe path: {}", entry.path);
+    }
+
+    let url = format!(
+        "{}objects/{}/{}",
+        repo_url,
+        &entry.sha1[..2],
+        &entry.sha1[2..]
+    );
+    let resp = http_client.get(&url).send_adaptive(limiter).await?;
+
+    if !resp.status().is_success() {
+        bail!("Failed to download {}: {}", url, resp.status());
+    }
+
+    let compressed = resp.bytes_limited(MAX_FILE_SIZE).await?;
+    let mut object = Vec::new();
+    // One byte over the limit tells zlib bombs apart
+    ZlibDecoder::new(&compressed[..])
+        .take(MAX_FILE_SIZE as u64 + 1)
+        .read_to_end(&mut object)?;
+    if object.len() > MAX_FILE_SIZE {
+        bail!("Git object larger than {} bytes: {}", MAX_FILE_SIZE, url);
+    }
+
+    // Loose objects are stored as "<type> <size>\0<content>"
+    let Some(header_len) = object.iter().position(|&b| b == 0) else {
+        bail!("Malformed git object: {}", url);
+    };
+
+    if !object.starts_with(b"blob ") {
+        bail!("Unexpected git object type: {}", url);
+    }
+
+    let destination = dump_dir.join(relative);
+    if let Some(parent) = destination.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&destination, &object[header_len + 1..]).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+    use httpmock::prelude::*;
+    use std::io::Write;
+
+    const SHA1: &str = "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391";
+
+    // Build a version 2 git index containing the given paths
+    fn build_index(paths: &[&str]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"DIRC");
+        data.extend_from_slice(&2u32.to_be_bytes());
+        data.extend_from_slice(&(paths.len() as u32).to_be_bytes());
+
+        for path in paths {
+            let entry_start = data.len();
+            data.extend_from_slice(&[0u8; 40]);
+            for i in 0..20 {
+                data.push(u8::from_str_radix(&SHA1[i * 2..i * 2 + 2], 16).unwrap());
+            }
+            data.extend_from_slice(&(path.len() as u16).to_be_bytes());
+            data.extend_from_slice(path.as_bytes());
+
+            let entry_len = data.len() - entry_start;
+            data.resize(entry_start + (entry_len + 8) / 8 * 8, 0);
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_parse_index_should_return_all_paths() {
+        let entries = parse_index(&build_index(&["README.md", "src/main.rs"])).unwrap();
+
+        let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, vec!["README.md", "src/main.rs"]);
+        assert_eq!(entries[0].sha1, SHA1);
+
+        assert!(parse_index(b"not an index").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reconstruct_should_dump_blobs_when_dump_dir_given() {
+        // Set up mock target HTTP server and its response
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/.git/index");
+                then.status(200)
+                    .body(build_index(&["config/database.yml", "../escape.txt"]));
+            })
+            .await;
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"blob 14\0password: 1234").unwrap();
+        let object = encoder.finish().unwrap();
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path(format!("/.git/objects/{}/{}", &SHA1[..2], &SHA1[2..]));
+                then.status(200).body(object);
+            })
+            .await;
+
+        // Set up input arguments
+        let client = Client::new();
+        let repo_url = format!("http://{}:{}/.git/", mock_server.host(), mock_server.port());
+        let dump_dir = std::env::temp_dir().join(format!("vulnscan-dump-{}", std::process::id()));
+
+        // Run reconstruction
+        let files = reconstruct(&client, &Limiter::default(), &repo_url, Some(&dump_dir))
+            .await
+            .unwrap();
+
+        // Check result
+        assert_eq!(files, vec!["config/database.yml", "../escape.txt"]);
+        assert_eq!(
+            std::fs::read_to_string(dump_dir.join("config/database.yml")).unwrap(),
+            "password: 1234"
+        );
+        assert!(!dump_dir.join("../escape.txt").exists());
+
+        std::fs::remove_dir_all(&dump_dir).unwrap();
+    }
+}
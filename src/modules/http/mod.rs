@@ -1,16 +1,31 @@
+pub mod context;
+mod cookie_flags;
+mod dangerous_methods;
+mod debug_endpoints;
 mod directory_listing;
-mod dotenv_disclosure;
+pub mod dotenv_disclosure;
 mod git_config_leakage;
 mod git_head_leakage;
+pub mod git_reconstruction;
+mod log4shell;
+pub mod parameters;
+mod reflected_xss;
+mod sqli_error;
+mod ssrf_probe;
+mod transport_security;
+mod webdav;
+pub use context::HttpOptions;
+pub use context::ScanContext;
+pub use debug_endpoints::DebugEndpoints;
 pub use directory_listing::DirectoryListing;
 pub use dotenv_disclosure::DotEnvDisclosure;
 pub use git_config_leakage::GitConfigLeakage;
 pub use git_head_leakage::GitHeadLeakage;
+pub use parameters::ParameterTarget;
+pub use reflected_xss::ReflectedXss;
+pub use sqli_error::SqliError;
 
-#[derive(Debug)]
-pub enum HttpFindings {
-    DotEnvDisclosure(String),
-    DirectoryListing(String),
-    GitConfigLeakage(String),
-    GitHeadLeakage(String),
+/// Built-in wordlists of the HTTP modules, by name
+pub fn wordlists() -> Vec<(&'static str, &'static [&'static str])> {
+    vec![("http/ssrf_parameters", ssrf_probe::PARAMETERS)]
 }
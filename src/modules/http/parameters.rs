@@ -0,0 +1,206 @@
+use crate::body::ReadBounded;
+use crate::modules::http::ScanContext;
+use crate::throttle::SendAdaptive;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Client;
+use reqwest::Url;
+use std::collections::BTreeMap;
+
+// Avoid flooding endpoints with many injection requests
+pub const MAX_TARGETS: usize = 20;
+
+static LINK_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)(?:href|src|action)\s*=\s*["']([^"'#]*\?[^"'#]*)["']"#)
+        .expect("Failed to compile regex patterns")
+});
+
+static INPUT_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)<(?:input|textarea|select)\b[^>]*\bname\s*=\s*["']([^"']+)["']"#)
+        .expect("Failed to compile regex patterns")
+});
+
+/// A query parameter accepted by a URL, used by injection modules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParameterTarget {
+    /// The URL without its query string
+    pub url: Url,
+    /// The parameter name
+    pub name: String,
+    /// The value seen in the wild, empty if unknown
+    pub value: String,
+}
+
+/// Mine the parameters of an endpoint
+/// - Links and form fields of its landing page, over HTTPS then HTTP
+/// - Archived URLs pointing to the endpoint
+///
+/// # Arguments
+/// * `http_client` - The HTTP client to use
+/// * `context` - The `host:port` endpoint to mine parameters for
+/// * `archived_urls` - Historical URLs of the target, e.g. from web.archive.org
+pub async fn mine(
+    http_client: &Client,
+    context: &ScanContext,
+    archived_urls: &[Url],
+) -> Vec<ParameterTarget> {
+    let endpoint = &context.endpoint;
+    let mut targets = BTreeMap::new();
+
+    for schema in ["https", "http"] {
+        let Ok(base) = Url::parse(&format!("{}://{}/", schema, endpoint)) else {
+            continue;
+        };
+
+        let Ok(resp) = http_client
+            .get(base.clone())
+            .send_adaptive(&context.limiter)
+            .await
+        else {
+            continue;
+        };
+
+        let body = resp
+            .text_bounded(context.options.max_body_size)
+            .await
+            .unwrap_or_default();
+        discover(&mut targets, &base, &body);
+        break;
+    }
+
+    for url in archived_urls {
+        let url_endpoint = format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        );
+
+        if url_endpoint.eq_ignore_ascii_case(endpoint) {
+            insert_query(&mut targets, url.clone());
+        }
+    }
+
+    into_targets(targets)
+}
+
+/// Collect same-origin parameters from links and form fields of a page
+fn discover(targets: &mut BTreeMap<(String, String), String>, base: &Url, body: &str) {
+    for capture in LINK_PATTERN.captures_iter(body) {
+        let Ok(url) = base.join(&capture[1].replace("&amp;", "&")) else {
+            continue;
+        };
+
+        if url.origin() == base.origin() {
+            insert_query(targets, url);
+        }
+    }
+
+    // Form fields are submitted to the landing page as the most likely target
+    for capture in INPUT_PATTERN.captures_iter(body) {
+        targets
+            .entry((base.to_string(), capture[1].to_string()))
+            .or_default();
+    }
+}
+
+/// Record every query parameter of `url`, keeping the first value seen
+fn insert_query(targets: &mut BTreeMap<(String, String), String>, mut url: Url) {
+    let parameters: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| (name.into(), value.into()))
+        .collect();
+    url.set_query(None);
+    url.set_fragment(None);
+
+    for (name, value) in parameters {
+        targets.entry((url.to_string(), name)).or_insert(value);
+    }
+}
+
+fn into_targets(targets: BTreeMap<(String, String), String>) -> Vec<ParameterTarget> {
+    targets
+        .into_iter()
+        .filter_map(|((url, name), value)| {
+            Some(ParameterTarget {
+                url: Url::parse(&url).ok()?,
+                name,
+                value,
+            })
+        })
+        .take(MAX_TARGETS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::http::context::tests::local;
+    use httpmock::prelude::*;
+
+    #[test]
+    fn test_discover_should_return_same_origin_parameters() {
+        let base = Url::parse("https://example.com/").unwrap();
+        let body = r#"
+            <a href="/search?q=shoes&amp;page=1">Shoes</a>
+            <a href="https://cdn.example.net/app.js?v=3">Elsewhere</a>
+            <form><input type="text" name="email"></form>
+        "#;
+
+        let mut targets = BTreeMap::new();
+        discover(&mut targets, &base, body);
+
+        let targets = into_targets(targets);
+        let parameters: Vec<(&str, &str, &str)> = targets
+            .iter()
+            .map(|target| {
+                (
+                    target.url.as_str(),
+                    target.name.as_str(),
+                    target.value.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            parameters,
+            vec![
+                ("https://example.com/", "email", ""),
+                ("https://example.com/search", "page", "1"),
+                ("https://example.com/search", "q", "shoes"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mine_should_merge_landing_page_and_archived_urls() {
+        // Set up mock target HTTP server and its response
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200).body(r#"<a href="/item?id=7">Item</a>"#);
+            })
+            .await;
+
+        // Set up input arguments
+        let client = Client::new();
+        let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
+        let archived_urls = vec![
+            Url::parse(&format!("http://{}/old/report.php?year=2015", endpoint)).unwrap(),
+            Url::parse("http://elsewhere.example.com/?ignored=1").unwrap(),
+        ];
+
+        // Run mining
+        let targets = mine(&client, &local(&endpoint), &archived_urls).await;
+
+        // Check result
+        let parameters: Vec<(&str, &str)> = targets
+            .iter()
+            .map(|target| (target.url.path(), target.name.as_str()))
+            .collect();
+        assert_eq!(
+            parameters,
+            vec![("/item", "id"), ("/old/report.php", "year")]
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use crate::body::ReadBounded;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::ParameterModule;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::http::ParameterTarget;
+use crate::modules::http::ScanContext;
+use crate::throttle::SendAdaptive;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use reqwest::Client;
+use reqwest::header::CONTENT_TYPE;
+use serde::Deserialize;
+use serde::Serialize;
+
+pub struct ReflectedXss {
+    // Unique per run so reflections cannot come from unrelated page content
+    marker: String,
+}
+
+/// Where an injected marker was reflected in the HTML document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReflectionContext {
+    Attribute,
+    TagBody,
+    Script,
+}
+
+// Characters needed to break out of any HTML context, appended to the marker
+const BREAKOUT: &str = r#""'<>"#;
+
+impl ReflectedXss {
+    pub fn new() -> Self {
+        ReflectedXss {
+            marker: format!("vsx{}", random_token()),
+        }
+    }
+
+    /// Inject a fixed marker, for fixtures echoing a known payload
+    pub fn with_marker(marker: &str) -> Self {
+        ReflectedXss {
+            marker: marker.to_string(),
+        }
+    }
+}
+
+impl Module for ReflectedXss {
+    fn name(&self) -> String {
+        String::from("http/reflected_xss")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-http-reflected_xss")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Medium
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://cwe.mitre.org/data/definitions/79.html",
+            "https://owasp.org/www-community/attacks/xss/",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/reflected_xss",
+        tags: &[Tag::Injection],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Parameter(|_| Box::new(ReflectedXss::new())),
+    }
+}
+
+#[async_trait]
+impl ParameterModule for ReflectedXss {
+    async fn inject(
+        &self,
+        http_client: &Client,
+        context: &ScanContext,
+        target: &ParameterTarget,
+    ) -> Result<Option<Finding>> {
+        let payload = format!("{}{}", self.marker, BREAKOUT);
+
+        let resp = http_client
+            .get(target.url.clone())
+            .query(&[(&target.name, &payload)])
+            .send_adaptive(&context.limiter)
+            .await?;
+
+        let is_html = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("html"));
+
+        if !is_html {
+            return Ok(None);
+        }
+
+        let body = resp.text_bounded(context.options.max_body_size).await?;
+
+        Ok(find_reflection(&body, &payload).map(|context| {
+            Finding::new(
+                self.name(),
+                "ReflectedXss",
+                Severity::Medium,
+                target.url.as_str(),
+            )
+            .with_evidence(vec![
+                format!("parameter: {}", target.name),
+                format!("context: {:?}", context),
+            ])
+            .with_metadata("parameter", &target.name)
+            .with_metadata("context", context)
+        }))
+    }
+}
+
+/// Find where `payload` is reflected unencoded and classify its context
+fn find_reflection(body: &str, payload: &str) -> Option<ReflectionContext> {
+    let position = body.find(payload)?;
+    let before = body[..position].to_lowercase();
+
+    let script_open = before.rfind("<script");
+    if script_open.is_some() && script_open > before.rfind("</script") {
+        return Some(ReflectionContext::Script);
+    }
+
+    if before.rfind('<') > before.rfind('>') {
+        return Some(ReflectionContext::Attribute);
+    }
+
+    Some(ReflectionContext::TagBody)
+}
+
+fn random_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(8)
+        .map(|c| (c as char).to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::http::context::tests::local;
+    use httpmock::prelude::*;
+    use reqwest::Url;
+
+    #[test]
+    fn test_find_reflection_should_classify_context() {
+        let payload = format!("vsxtoken{}", BREAKOUT);
+
+        assert_eq!(
+            find_reflection(&format!("<p>{}</p>", payload), &payload),
+            Some(ReflectionContext::TagBody)
+        );
+        assert_eq!(
+            find_reflection(&format!(r#"<input value="{}">"#, payload), &payload),
+            Some(ReflectionContext::Attribute)
+        );
+        assert_eq!(
+            find_reflection(
+                &format!("<script>var q = '{}';</script>", payload),
+                &payload
+            ),
+            Some(ReflectionContext::Script)
+        );
+        assert_eq!(
+            find_reflection("<p>vsxtoken&quot;&#39;&lt;&gt;</p>", &payload),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inject_should_return_some_when_parameter_reflected() {
+        // Set up mock target HTTP server and its response
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/search")
+                    .query_param("q", r#"vsxtoken"'<>"#);
+                then.status(200)
+                    .header("Content-Type", "text/html")
+                    .body(r#"<p>No results for vsxtoken"'<></p>"#);
+            })
+            .await;
+
+        // Set up input arguments
+        let module = ReflectedXss {
+            marker: String::from("vsxtoken"),
+        };
+        let client = Client::new();
+        let target = ParameterTarget {
+            url: Url::parse(&format!(
+                "http://{}:{}/search",
+                mock_server.host(),
+                mock_server.port()
+            ))
+            .unwrap(),
+            name: String::from("q"),
+            value: String::from("shoes"),
+        };
+
+        // Run injection
+        let result = module
+            .inject(&client, &local(&mock_server.address().to_string()), &target)
+            .await
+            .unwrap();
+
+        // Check result
+        assert!(result.is_some(), "Should return Some when marker reflected");
+
+        if let Some(finding) = result {
+            let context: ReflectionContext =
+                serde_json::from_value(finding.metadata["context"].clone()).unwrap();
+            assert_eq!(finding.url, target.url.as_str());
+            assert_eq!(finding.metadata_str("parameter"), Some("q"));
+            assert_eq!(context, ReflectionContext::TagBody);
+        }
+    }
+}
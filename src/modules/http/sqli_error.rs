@@ -0,0 +1,230 @@
+use crate::body::ReadBounded;
+use crate::detections;
+use crate::i18n::Message;
+use crate::matcher;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::ParameterModule;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::http::ParameterTarget;
+use crate::modules::http::ScanContext;
+use crate::throttle::SendAdaptive;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use reqwest::Client;
+
+// Payloads may break fragile applications, so the module is registered as intrusive
+pub struct SqliError;
+
+// Classic payloads leaving a query syntactically broken
+const PAYLOADS: &[&str] = &["'", "\"", "')", "\\", "1'\""];
+
+impl SqliError {
+    pub fn new() -> Self {
+        SqliError
+    }
+}
+
+impl Module for SqliError {
+    fn name(&self) -> String {
+        String::from("http/sqli_error")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-http-sqli_error").arg("flag", "--aggressive")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://cwe.mitre.org/data/definitions/89.html",
+            "https://owasp.org/www-community/attacks/SQL_Injection",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/sqli_error",
+        tags: &[Tag::Injection, Tag::Intrusive],
+        intrusiveness: Intrusiveness::Intrusive,
+        requires: &[],
+        build: Build::Parameter(|_| Box::new(SqliError::new())),
+    }
+}
+
+#[async_trait]
+impl ParameterModule for SqliError {
+    async fn inject(
+        &self,
+        http_client: &Client,
+        context: &ScanContext,
+        target: &ParameterTarget,
+    ) -> Result<Option<Finding>> {
+        let fetch = |value: String| {
+            let request = http_client
+                .get(target.url.clone())
+                .query(&[(&target.name, value)]);
+            async move {
+                request
+                    .send_adaptive(&context.limiter)
+                    .await
+                    .ok()?
+                    .text_bounded(context.options.max_body_size)
+                    .await
+                    .ok()
+            }
+        };
+
+        // Signatures already present without a payload are not caused by it
+        let baseline = fetch(target.value.clone()).await.unwrap_or_default();
+        let baseline = matcher::run(baseline, match_signature).await;
+
+        for &payload in PAYLOADS {
+            let Some(body) = fetch(format!("{}{}", target.value, payload)).await else {
+                continue;
+            };
+
+            let database = matcher::run(body, match_signature).await;
+
+            if let Some(database) = database
+                && baseline != Some(database)
+            {
+                return Ok(Some(
+                    Finding::new(
+                        self.name(),
+                        "SqlInjection",
+                        Severity::High,
+                        target.url.as_str(),
+                    )
+                    .with_evidence(vec![
+                        format!("parameter: {}", target.name),
+                        format!("database: {}", database),
+                        format!("payload: {}", payload),
+                    ])
+                    .with_metadata("parameter", &target.name)
+                    .with_metadata("database", database)
+                    .with_metadata("payload", payload),
+                ));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn max_requests(&self) -> usize {
+        // A baseline, then every payload
+        PAYLOADS.len() + 1
+    }
+}
+
+/// Return the database whose error signature is found in `body`
+fn match_signature(body: &str) -> Option<&'static str> {
+    detections::get()
+        .sql_errors
+        .iter()
+        .find(|error| error.pattern.is_match(body))
+        .map(|error| error.database.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::http::context::tests::local;
+    use httpmock::prelude::*;
+    use reqwest::Url;
+
+    fn target(mock_server: &MockServer) -> ParameterTarget {
+        ParameterTarget {
+            url: Url::parse(&format!(
+                "http://{}:{}/item",
+                mock_server.host(),
+                mock_server.port()
+            ))
+            .unwrap(),
+            name: String::from("id"),
+            value: String::from("7"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inject_should_return_some_when_error_signature_matched() {
+        // Set up mock target HTTP server and its response
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/item").query_param("id", "7'");
+                then.status(500).body(
+                    "<b>Warning</b>: You have an error in your SQL syntax; check the manual \
+                     that corresponds to your MySQL server version",
+                );
+            })
+            .await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/item");
+                then.status(200).body("<p>Item 7</p>");
+            })
+            .await;
+
+        // Set up input arguments
+        let module = SqliError::new();
+        let client = Client::new();
+        let target = target(&mock_server);
+
+        // Run injection
+        let result = module
+            .inject(&client, &local(&mock_server.address().to_string()), &target)
+            .await
+            .unwrap();
+
+        // Check result
+        assert!(
+            result.is_some(),
+            "Should return Some when signature matched"
+        );
+
+        if let Some(finding) = result {
+            assert_eq!(finding.url, target.url.as_str());
+            assert_eq!(finding.metadata_str("parameter"), Some("id"));
+            assert_eq!(finding.metadata_str("database"), Some("MySQL"));
+            assert_eq!(finding.metadata_str("payload"), Some("'"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inject_should_return_none_when_error_always_shown() {
+        // Set up mock target HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(200).body("Tutorial: fixing ORA-00933 errors");
+            })
+            .await;
+
+        let client = Client::new();
+        let target = target(&mock_server);
+
+        // Signature also present in the baseline response
+        let result = SqliError::new()
+            .inject(&client, &local(&mock_server.address().to_string()), &target)
+            .await
+            .unwrap();
+        assert!(
+            result.is_none(),
+            "Should return None when the signature is not caused by the payload"
+        );
+    }
+}
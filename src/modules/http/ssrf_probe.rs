@@ -0,0 +1,230 @@
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::HttpModule;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::http::ScanContext;
+use crate::oob::Oob;
+use crate::throttle::SendAdaptive;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use reqwest::Client;
+use std::sync::Arc;
+
+pub struct SsrfProbe {
+    oob: Option<Arc<Oob>>,
+}
+
+// Query parameters commonly used to pass URLs to server-side fetchers
+pub(super) const PARAMETERS: &[&str] = &[
+    "url",
+    "uri",
+    "dest",
+    "destination",
+    "redirect",
+    "next",
+    "target",
+    "continue",
+    "return",
+    "callback",
+    "site",
+    "domain",
+    "host",
+    "feed",
+    "fetch",
+    "proxy",
+    "load",
+    "image_url",
+    "img",
+    "path",
+    "page",
+    "view",
+    "to",
+    "out",
+    "reference",
+    "data",
+    "window",
+    "html",
+    "val",
+    "validate",
+];
+
+impl SsrfProbe {
+    pub fn new(oob: Option<Arc<Oob>>) -> Self {
+        SsrfProbe { oob }
+    }
+}
+
+impl Module for SsrfProbe {
+    fn name(&self) -> String {
+        String::from("http/ssrf_probe")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-http-ssrf_probe")
+            .arg("server", "--oob-server")
+            .arg("domain", "--oob-domain")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://cwe.mitre.org/data/definitions/918.html",
+            "https://owasp.org/www-community/attacks/Server_Side_Request_Forgery",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/ssrf_probe",
+        tags: &[Tag::Injection],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Http(|options| Box::new(SsrfProbe::new(options.oob.clone()))),
+    }
+}
+
+#[async_trait]
+impl HttpModule for SsrfProbe {
+    async fn scan(&self, http_client: &Client, context: &ScanContext) -> Result<Option<Finding>> {
+        // Callbacks can only be observed through an OOB server or listener
+        let Some(oob) = &self.oob else {
+            return Ok(None);
+        };
+
+        // Send HTTPS and HTTP requests, each parameter carrying its own callback URL
+        // - Findings are reported later, when the OOB server is polled
+        for &schema in context.schemes() {
+            let url = context.url(schema, "/");
+
+            let query: Vec<(&str, String)> = PARAMETERS
+                .iter()
+                .map(|&parameter| {
+                    let url = url.clone();
+                    let module = self.name();
+                    let host = oob.payload_host(move |interaction| {
+                        Finding::new(module, "SsrfCallback", Severity::High, &url)
+                            .with_evidence(vec![
+                                format!("parameter: {}", parameter),
+                                format!(
+                                    "{} callback from {} at {}",
+                                    interaction.protocol.to_uppercase(),
+                                    interaction.remote_address,
+                                    interaction.timestamp
+                                ),
+                            ])
+                            .with_metadata("parameter", parameter)
+                            .with_metadata("protocol", &interaction.protocol)
+                    });
+                    (parameter, format!("http://{}/", host))
+                })
+                .collect();
+
+            if http_client
+                .get(&url)
+                .query(&query)
+                .send_adaptive(&context.limiter)
+                .await
+                .is_ok()
+            {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn max_requests(&self) -> usize {
+        if self.oob.is_some() { 2 } else { 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::http::context::tests::local;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_scan_should_inject_callback_urls() {
+        // Set up mock interaction server
+        let oob_server = MockServer::start_async().await;
+
+        oob_server
+            .mock_async(|when, then| {
+                when.method(POST).path("/register");
+                then.status(200);
+            })
+            .await;
+
+        let oob = Oob::connect(
+            &format!("http://{}:{}", oob_server.host(), oob_server.port()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Set up mock target HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        let injected = mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/")
+                    .query_param_matches("url", r"^http://[0-9a-v]{33}\.127\.0\.0\.1/$")
+                    .query_param_exists("redirect")
+                    .query_param_exists("image_url");
+                then.status(200);
+            })
+            .await;
+
+        // Set up input arguments
+        let module = SsrfProbe::new(Some(Arc::new(oob)));
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
+
+        // Run scan
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
+
+        // Check result: findings only come from OOB callbacks
+        assert!(result.is_none());
+        injected.assert_calls_async(1).await;
+    }
+
+    #[tokio::test]
+    async fn test_scan_should_return_none_without_oob_server() {
+        // Set up mock target HTTP server
+        let mock_server = MockServer::start_async().await;
+
+        let any_request = mock_server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(200);
+            })
+            .await;
+
+        // Set up input arguments
+        let module = SsrfProbe::new(None);
+        let client = Client::new();
+        let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
+
+        // Run scan
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
+
+        // Check result
+        assert!(result.is_none(), "Should return None without OOB server");
+        any_request.assert_calls_async(0).await;
+    }
+}
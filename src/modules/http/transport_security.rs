@@ -0,0 +1,269 @@
+use crate::detections;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::HttpModule;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::http::ScanContext;
+use crate::throttle::SendAdaptive;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use reqwest::Client;
+use reqwest::Url;
+use reqwest::header::LOCATION;
+use reqwest::header::STRICT_TRANSPORT_SECURITY;
+
+// The minimum max-age accepted for preloading, shorter ones leave long gaps between visits
+const ADEQUATE_MAX_AGE: u64 = 31536000;
+
+pub struct TransportSecurity;
+
+impl TransportSecurity {
+    pub fn new() -> Self {
+        TransportSecurity
+    }
+}
+
+impl Module for TransportSecurity {
+    fn name(&self) -> String {
+        String::from("http/transport_security")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-http-transport_security")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Medium
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://www.rfc-editor.org/rfc/rfc6797",
+            "https://hstspreload.org/",
+            "https://cheatsheetseries.owasp.org/cheatsheets/HTTP_Strict_Transport_Security_Cheat_Sheet.html",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/transport_security",
+        tags: &[Tag::Misconfig, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Http(|_| Box::new(TransportSecurity::new())),
+    }
+}
+
+#[async_trait]
+impl HttpModule for TransportSecurity {
+    async fn scan(&self, http_client: &Client, context: &ScanContext) -> Result<Option<Finding>> {
+        // Transport security applies to whole origins, not to their archived paths
+        if !context.is_root() {
+            return Ok(None);
+        }
+
+        let url = context.url("https", "/");
+        // Browsers never speak plain HTTP to preloaded hosts, whatever they answer
+        if Url::parse(&url)?.host_str().is_some_and(is_preloaded) {
+            return Ok(None);
+        }
+
+        let issues = match http_client.get(&url).send_adaptive(&context.limiter).await {
+            Ok(resp) => hsts_issues(
+                resp.headers()
+                    .get(STRICT_TRANSPORT_SECURITY)
+                    .and_then(|value| value.to_str().ok()),
+            ),
+            // Ports without TLS should send browsers to HTTPS
+            Err(_) => {
+                let url = context.url("http", "/");
+                let Ok(resp) = http_client.get(&url).send_adaptive(&context.limiter).await else {
+                    return Ok(None);
+                };
+
+                let to_https = resp.status().is_redirection()
+                    && resp
+                        .headers()
+                        .get(LOCATION)
+                        .and_then(|location| location.to_str().ok())
+                        .is_some_and(|location| location.starts_with("https://"));
+                if to_https {
+                    return Ok(None);
+                }
+
+                return Ok(Some(self.graded(
+                    &url,
+                    'F',
+                    vec![String::from("HTTP is served without redirecting to HTTPS")],
+                )));
+            }
+        };
+
+        let Some(grade) = issues.iter().map(|(grade, _)| *grade).max() else {
+            return Ok(None);
+        };
+
+        Ok(Some(self.graded(
+            &url,
+            grade,
+            issues.into_iter().map(|(_, issue)| issue).collect(),
+        )))
+    }
+}
+
+impl TransportSecurity {
+    /// A finding of `url`, graded from `B` to `F` with the issues lowering the grade
+    fn graded(&self, url: &str, grade: char, issues: Vec<String>) -> Finding {
+        // Downgradable origins are Medium, missing hardening is Low, missing preloading Info
+        let severity = match grade {
+            'F' => Severity::Medium,
+            'C' => Severity::Low,
+            _ => Severity::Info,
+        };
+        let evidence = std::iter::once(format!("grade: {}", grade))
+            .chain(issues)
+            .collect();
+
+        Finding::new(self.name(), "WeakTransportSecurity", severity, url)
+            .with_evidence(evidence)
+            .with_metadata("grade", grade)
+    }
+}
+
+/// Whether `host` or one of its parents is on the bundled preload list
+fn is_preloaded(host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let mut name = host.as_str();
+
+    loop {
+        if detections::get().hsts.preloaded.contains(name) {
+            return true;
+        }
+        match name.split_once('.') {
+            Some((_, parent)) => name = parent,
+            None => return false,
+        }
+    }
+}
+
+/// Issues of an HTTPS response's Strict-Transport-Security header, with the grade each
+/// limits the origin to, `F` being the worst
+fn hsts_issues(header: Option<&str>) -> Vec<(char, String)> {
+    let Some(header) = header else {
+        return vec![(
+            'F',
+            String::from("HTTPS responses carry no Strict-Transport-Security header"),
+        )];
+    };
+
+    let directives: Vec<(String, Option<&str>)> = header
+        .split(';')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(|directive| match directive.split_once('=') {
+            Some((name, value)) => (
+                name.trim().to_lowercase(),
+                Some(value.trim().trim_matches('"')),
+            ),
+            None => (directive.to_lowercase(), None),
+        })
+        .collect();
+
+    let max_age = directives
+        .iter()
+        .find(|(name, _)| name == "max-age")
+        .and_then(|(_, value)| value.and_then(|value| value.parse::<u64>().ok()));
+
+    let mut issues = Vec::new();
+    match max_age {
+        // max-age=0 tells browsers to forget the host
+        None | Some(0) => issues.push((
+            'F',
+            format!(
+                "Strict-Transport-Security has no usable max-age: {}",
+                header
+            ),
+        )),
+        Some(max_age) if max_age < ADEQUATE_MAX_AGE => issues.push((
+            'C',
+            format!("HSTS max-age of {} seconds is under a year", max_age),
+        )),
+        Some(_) => {}
+    }
+
+    if !directives
+        .iter()
+        .any(|(name, _)| name == "includesubdomains")
+    {
+        issues.push((
+            'B',
+            String::from("HSTS does not include subdomains, which may be reached over HTTP"),
+        ));
+    }
+
+    issues.push((
+        'B',
+        String::from("Not on the HSTS preload list, first visits may be downgraded"),
+    ));
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::http::context::tests::local;
+    use httpmock::prelude::*;
+
+    #[test]
+    fn test_is_preloaded_should_match_parents() {
+        assert!(is_preloaded("api.github.com"));
+        assert!(is_preloaded("example.dev."));
+        assert!(!is_preloaded("github.com.example.org"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_should_grade_short_max_age() {
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200).header(
+                    "Strict-Transport-Security",
+                    "max-age=300; includeSubDomains",
+                );
+            })
+            .await;
+
+        let module = TransportSecurity::new();
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let endpoint = format!("{}:{}", mock_server.host(), mock_server.port());
+
+        let result = module.scan(&client, &local(&endpoint)).await.unwrap();
+
+        let Some(finding) = result else {
+            panic!("Should grade an HSTS header with a short max-age");
+        };
+        assert_eq!(finding.url, format!("https://{}/", endpoint));
+        assert_eq!(finding.metadata_str("grade"), Some("C"));
+        assert_eq!(
+            finding.evidence,
+            vec![
+                "grade: C",
+                "HSTS max-age of 300 seconds is under a year",
+                "Not on the HSTS preload list, first visits may be downgraded",
+            ]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use crate::body::ReadBounded;
+use crate::detections;
+use crate::i18n::Message;
+use crate::matcher;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::HttpModule;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::http::ScanContext;
+use crate::throttle::SendAdaptive;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use reqwest::Client;
+use reqwest::Method;
+use reqwest::StatusCode;
+use reqwest::Url;
+use reqwest::header::CONTENT_TYPE;
+
+// Resources named in evidence at most, the count covers the rest
+const MAX_LISTED: usize = 20;
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?><propfind xmlns="DAV:"><prop><resourcetype/></prop></propfind>"#;
+
+pub struct WebDav;
+
+impl WebDav {
+    pub fn new() -> Self {
+        WebDav
+    }
+}
+
+impl Module for WebDav {
+    fn name(&self) -> String {
+        String::from("http/webdav")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-http-webdav")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Medium
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://www.rfc-editor.org/rfc/rfc4918#section-9.1",
+            "https://learn.microsoft.com/en-us/iis/configuration/system.webserver/webdav/",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "http/webdav",
+        tags: &[Tag::Exposure, Tag::Misconfig],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Http(|_| Box::new(WebDav::new())),
+    }
+}
+
+#[async_trait]
+impl HttpModule for WebDav {
+    async fn scan(&self, http_client: &Client, context: &ScanContext) -> Result<Option<Finding>> {
+        // WebDAV is enabled per server, archived paths add nothing
+        if !context.is_root() {
+            return Ok(None);
+        }
+
+        // The first scheme the endpoint answers on is the one it serves
+        for &schema in context.schemes() {
+            let url = context.url(schema, "/");
+            let Ok(resp) = http_client
+                .request(Method::OPTIONS, &url)
+                .send_adaptive(&context.limiter)
+                .await
+            else {
+                continue;
+            };
+
+            // Compliance classes, e.g. `1,2`
+            let advertised = resp.headers().contains_key("DAV");
+
+            let listed = propfind(http_client, context, &url).await;
+            if !advertised && listed.is_none() {
+                return Ok(None);
+            }
+
+            // Listings expose backups and configuration files, WebDAV alone is attack surface
+            let resources = listed.unwrap_or_default();
+            let severity = if resources.is_empty() {
+                Severity::Info
+            } else {
+                Severity::Medium
+            };
+
+            return Ok(Some(
+                Finding::new(self.name(), "WebDavExposure", severity, &url)
+                    .with_evidence(listed_evidence(&resources))
+                    .with_metadata("resources", &resources),
+            ));
+        }
+
+        Ok(None)
+    }
+
+    fn max_requests(&self) -> usize {
+        // OPTIONS over HTTPS then HTTP, then PROPFIND
+        3
+    }
+}
+
+/// The resources an unauthenticated PROPFIND lists under `url`, None unless it answers a Multi-Status
+async fn propfind(http_client: &Client, context: &ScanContext, url: &str) -> Option<Vec<String>> {
+    let resp = http_client
+        .request(Method::from_bytes(b"PROPFIND").ok()?, url)
+        .header("Depth", "1")
+        .header(CONTENT_TYPE, "application/xml")
+        .body(PROPFIND_BODY)
+        .send_adaptive(&context.limiter)
+        .await
+        .ok()?;
+
+    if resp.status() != StatusCode::MULTI_STATUS {
+        return None;
+    }
+
+    let body = resp
+        .text_bounded(context.options.max_body_size)
+        .await
+        .ok()?;
+    let base = Url::parse(url).ok()?;
+    let hrefs = matcher::run(body, move |body| {
+        detections::get()
+            .markup
+            .webdav_href
+            .captures_iter(body)
+            .map(|captures| captures[1].to_string())
+            // The collection itself is listed first, as a path or a full URL
+            .filter(|href| base.join(href).is_ok_and(|resource| resource.path() != "/"))
+            .collect::<Vec<_>>()
+    })
+    .await;
+
+    Some(hrefs)
+}
+
+/// Resources listed in evidence, the count of the others closing it
+fn listed_evidence(resources: &[String]) -> Vec<String> {
+    let mut evidence: Vec<String> = resources.iter().take(MAX_LISTED).cloned().collect();
+    if resources.len() > MAX_LISTED {
+        evidence.push(format!("and {} more", resources.len() - MAX_LISTED));
+    }
+    evidence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::http::context::tests::local;
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    /// A plain HTTP server answering OPTIONS and PROPFIND like IIS with WebDAV, closing each connection
+    async fn fake_iis() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                // Headers, then the PROPFIND body, TLS handshakes being dropped
+                loop {
+                    let text = String::from_utf8_lossy(&request);
+                    if request.first() == Some(&0x16)
+                        || text.contains("\r\n\r\n")
+                            && (!text.starts_with("PROPFIND") || text.contains("</propfind>"))
+                    {
+                        break;
+                    }
+                    match stream.read(&mut buf).await {
+                        Ok(read) if read > 0 => request.extend(&buf[..read]),
+                        _ => break,
+                    }
+                }
+
+                let response = if request.starts_with(b"OPTIONS") {
+                    String::from(
+                        "HTTP/1.1 200 OK\r\nDAV: 1,2\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    )
+                } else if request.starts_with(b"PROPFIND") {
+                    let body = "<?xml version=\"1.0\"?><a:multistatus xmlns:a=\"DAV:\">\
+                        <a:response><a:href>http://127.0.0.1/</a:href></a:response>\
+                        <a:response><a:href>http://127.0.0.1/backup.zip</a:href></a:response>\
+                        <a:response><a:href>http://127.0.0.1/web.config</a:href></a:response>\
+                        </a:multistatus>";
+                    format!(
+                        "HTTP/1.1 207 Multi-Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                } else {
+                    continue;
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        port
+    }
+
+    #[tokio::test]
+    async fn test_scan_should_report_listed_resources() {
+        let port = fake_iis().await;
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let endpoint = format!("127.0.0.1:{}", port);
+
+        let result = WebDav::new()
+            .scan(&client, &local(&endpoint))
+            .await
+            .unwrap();
+
+        let Some(finding) = result else {
+            panic!("Should report WebDAV listing resources");
+        };
+        assert_eq!(finding.url, format!("http://{}/", endpoint));
+        assert_eq!(finding.severity, Severity::Medium);
+        assert_eq!(
+            finding.evidence,
+            vec!["http://127.0.0.1/backup.zip", "http://127.0.0.1/web.config"]
+        );
+    }
+}
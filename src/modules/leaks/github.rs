@@ -0,0 +1,239 @@
+use crate::body;
+use crate::body::ReadBounded;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::LeakModule;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use anyhow::Result;
+use anyhow::bail;
+use reqwest::Client;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Name of the token in `[sources]` and the key store
+pub const SOURCE: &str = "github";
+
+// Code search allows 10 queries a minute, each dork is one
+const DORKS: &[&str] = &[
+    "password",
+    "secret",
+    "token",
+    "filename:.env",
+    "extension:sql",
+];
+const PER_PAGE: usize = 50;
+
+pub struct GitHub {
+    api_url: String,
+    token: Option<String>,
+}
+
+impl GitHub {
+    pub fn new(token: Option<&str>) -> Self {
+        GitHub {
+            api_url: String::from("https://api.github.com/"),
+            token: token.map(str::to_string),
+        }
+    }
+}
+
+impl Module for GitHub {
+    fn name(&self) -> String {
+        String::from("leaks/github")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-leaks-github")
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://cwe.mitre.org/data/definitions/540.html",
+            "https://docs.github.com/en/rest/search/search#search-code",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "leaks/github",
+        tags: &[Tag::Exposure, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Leak(|options| Box::new(GitHub::new(options.api_keys.get(SOURCE)))),
+    }
+}
+
+#[async_trait]
+impl LeakModule for GitHub {
+    async fn search(&self, http_client: &Client, domain: &str) -> Result<Vec<Finding>> {
+        // Declare needed API response fields
+        #[derive(Debug, Deserialize)]
+        struct SearchResponse {
+            items: Vec<Item>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Item {
+            path: String,
+            html_url: String,
+            repository: Repository,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Repository {
+            full_name: String,
+        }
+
+        let Some(token) = &self.token else {
+            log::info!(
+                "{}: Skipped, no token (set one with `vulnscan keys set {}`)",
+                self.name(),
+                SOURCE
+            );
+            return Ok(Vec::new());
+        };
+
+        // Files matching several dorks are reported once, with every dork they matched
+        let mut files: BTreeMap<String, (Item, Vec<&str>)> = BTreeMap::new();
+
+        for (i, dork) in DORKS.iter().enumerate() {
+            let query = format!("\"{}\" {}", domain, dork);
+            let resp = http_client
+                .get(format!("{}search/code", self.api_url))
+                .bearer_auth(token)
+                .header("Accept", "application/vnd.github+json")
+                .header("User-Agent", "vulnscan")
+                .query(&[("q", query.as_str()), ("per_page", &PER_PAGE.to_string())])
+                .send()
+                .await?;
+
+            let resp: SearchResponse = match resp.status() {
+                status if status.is_success() => {
+                    resp.json_limited(body::MAX_API_RESPONSE_SIZE).await?
+                }
+                StatusCode::UNAUTHORIZED => {
+                    bail!("GitHub rejected the token: {}", resp.status())
+                }
+                // Keep the files already found
+                StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS if i > 0 => {
+                    log::warn!("{}: Rate limited after {} dorks", self.name(), i);
+                    break;
+                }
+                status => bail!("Unexpected status code from GitHub: {}", status),
+            };
+
+            for item in resp.items {
+                files
+                    .entry(item.html_url.clone())
+                    .or_insert_with(|| (item, Vec::new()))
+                    .1
+                    .push(dork);
+            }
+        }
+
+        let findings: Vec<Finding> = files
+            .into_values()
+            .map(|(item, dorks)| {
+                Finding::new(
+                    self.name(),
+                    "RepositoryLeak",
+                    Severity::Info,
+                    &item.html_url,
+                )
+                .with_evidence(vec![
+                    format!("{}: {}", item.repository.full_name, item.path),
+                    format!("Mentions {} along with {}", domain, dorks.join(", ")),
+                ])
+                .with_metadata("repository", &item.repository.full_name)
+                .with_metadata("path", &item.path)
+                .with_metadata("dorks", &dorks)
+            })
+            .collect();
+
+        log::info!(
+            "{}: Found {} files mentioning {}",
+            self.name(),
+            findings.len(),
+            domain
+        );
+
+        Ok(findings)
+    }
+
+    fn max_requests(&self) -> usize {
+        if self.token.is_some() { DORKS.len() } else { 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_search_should_merge_files_matching_several_dorks() {
+        let mock_server = MockServer::start_async().await;
+
+        let item = serde_json::json!({
+            "path": "deploy/.env",
+            "html_url": "https://github.com/acme/site/blob/0123abc/deploy/.env",
+            "repository": { "full_name": "acme/site" }
+        });
+        for dork in ["password", "filename:.env"] {
+            let item = item.clone();
+            mock_server
+                .mock_async(|when, then| {
+                    when.method(GET)
+                        .path("/search/code")
+                        .query_param("q", format!("\"example.com\" {}", dork))
+                        .header("Authorization", "Bearer ghp_token");
+                    then.status(200)
+                        .json_body(serde_json::json!({ "items": [item] }));
+                })
+                .await;
+        }
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/search/code");
+                then.status(200)
+                    .json_body(serde_json::json!({ "items": [] }));
+            })
+            .await;
+
+        let module = GitHub {
+            api_url: format!("http://{}:{}/", mock_server.host(), mock_server.port()),
+            ..GitHub::new(Some("ghp_token"))
+        };
+        let findings = module.search(&Client::new(), "example.com").await.unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, "RepositoryLeak");
+        assert_eq!(findings[0].severity, Severity::Info);
+        assert_eq!(
+            findings[0].evidence,
+            vec![
+                "acme/site: deploy/.env",
+                "Mentions example.com along with password, filename:.env",
+            ]
+        );
+
+        // Without a token, GitHub is not queried at all
+        let module = GitHub::new(None);
+        assert_eq!(module.max_requests(), 0);
+        assert!(
+            module
+                .search(&Client::new(), "example.com")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use crate::body;
+use crate::body::ReadBounded;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::LeakModule;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use anyhow::Result;
+use anyhow::bail;
+use reqwest::Client;
+use reqwest::StatusCode;
+use std::collections::BTreeMap;
+
+/// Name of the API key in `[sources]` and the key store
+pub const SOURCE: &str = "hibp";
+
+pub struct Hibp {
+    api_url: String,
+    api_key: Option<String>,
+}
+
+impl Hibp {
+    pub fn new(api_key: Option<&str>) -> Self {
+        Hibp {
+            api_url: String::from("https://haveibeenpwned.com/api/v3/"),
+            api_key: api_key.map(str::to_string),
+        }
+    }
+}
+
+impl Module for Hibp {
+    fn name(&self) -> String {
+        String::from("leaks/hibp")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-leaks-hibp")
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://haveibeenpwned.com/API/v3#BreachesForDomain",
+            "https://owasp.org/www-community/attacks/Credential_stuffing",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "leaks/hibp",
+        tags: &[Tag::Exposure, Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Leak(|options| Box::new(Hibp::new(options.api_keys.get(SOURCE)))),
+    }
+}
+
+#[async_trait]
+impl LeakModule for Hibp {
+    async fn search(&self, http_client: &Client, domain: &str) -> Result<Vec<Finding>> {
+        let Some(api_key) = &self.api_key else {
+            log::info!(
+                "{}: Skipped, no API key (set one with `vulnscan keys set {}`)",
+                self.name(),
+                SOURCE
+            );
+            return Ok(Vec::new());
+        };
+
+        let url = format!("{}breacheddomain/{}", self.api_url, domain);
+        let resp = http_client
+            .get(&url)
+            .header("hibp-api-key", api_key)
+            .header("User-Agent", "vulnscan")
+            .send()
+            .await?;
+
+        // Aliases, the part before `@`, and the breaches each was in
+        let accounts: BTreeMap<String, Vec<String>> = match resp.status() {
+            status if status.is_success() => resp.json_limited(body::MAX_API_RESPONSE_SIZE).await?,
+            StatusCode::NOT_FOUND => BTreeMap::new(),
+            StatusCode::UNAUTHORIZED => bail!("HIBP rejected the API key: {}", resp.status()),
+            StatusCode::FORBIDDEN => bail!(
+                "{} is not verified for this HIBP API key, add it to the domain search dashboard",
+                domain
+            ),
+            status => bail!("Unexpected status code from HIBP: {}", status),
+        };
+
+        if accounts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Summarized by breach, the accounts themselves are for the domain owner to look up
+
+        let mut breaches: BTreeMap<&str, usize> = BTreeMap::new();
+        for breach in accounts.values().flatten() {
+            *breaches.entry(breach).or_default() += 1;
+        }
+        let mut by_size: Vec<(&str, usize)> = breaches.iter().map(|(b, n)| (*b, *n)).collect();
+        by_size.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let evidence = std::iter::once(format!(
+            "{} accounts of {} in {} breaches",
+            accounts.len(),
+            domain,
+            breaches.len()
+        ))
+        .chain(by_size.iter().map(|(breach, count)| match count {
+            1 => format!("{}: 1 account", breach),
+            _ => format!("{}: {} accounts", breach, count),
+        }))
+        .collect();
+
+        log::info!(
+            "{}: Found {} breached accounts of {}",
+            self.name(),
+            accounts.len(),
+            domain
+        );
+
+        Ok(vec![
+            Finding::new(self.name(), "BreachedAccounts", Severity::Info, &url)
+                .with_evidence(evidence)
+                .with_metadata("accounts", accounts.len())
+                .with_metadata("breaches", &breaches),
+        ])
+    }
+
+    fn max_requests(&self) -> usize {
+        usize::from(self.api_key.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_search_should_summarize_breaches_without_naming_accounts() {
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/breacheddomain/example.com")
+                    .header("hibp-api-key", "hibp-key");
+                then.status(200).json_body(serde_json::json!({
+                    "alice": ["Adobe"],
+                    "bob": ["Adobe", "Gawker"],
+                    "carol": ["LinkedIn", "Adobe"]
+                }));
+            })
+            .await;
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/breacheddomain/example.net");
+                then.status(404);
+            })
+            .await;
+
+        let module = Hibp {
+            api_url: format!("http://{}:{}/", mock_server.host(), mock_server.port()),
+            ..Hibp::new(Some("hibp-key"))
+        };
+        let findings = module.search(&Client::new(), "example.com").await.unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].evidence,
+            vec![
+                "3 accounts of example.com in 3 breaches",
+                "Adobe: 3 accounts",
+                "Gawker: 1 account",
+                "LinkedIn: 1 account",
+            ]
+        );
+        assert!(!findings[0].evidence.join("\n").contains("alice"));
+
+        // No breached account is no finding
+        let findings = module.search(&Client::new(), "example.net").await;
+        assert!(findings.unwrap().is_empty());
+    }
+}
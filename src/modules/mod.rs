@@ -1,40 +1,838 @@
+pub mod cloud;
+pub mod dns;
 pub mod http;
-mod subdomain;
+pub mod leaks;
+pub mod service;
+pub mod subdomain;
+pub mod tls;
 
 use std::vec;
 
-use crate::modules::http::HttpFindings;
+use crate::capture::Exchange;
+use crate::i18n::Message;
+use crate::keys::ApiKeys;
+use crate::modules::http::HttpOptions;
+use crate::modules::http::ParameterTarget;
+use crate::modules::http::ScanContext;
+use crate::oob::Oob;
+use crate::throttle::Limiter;
 use anyhow::Result;
 use async_trait::async_trait;
+use clap::ValueEnum;
+use futures::channel::mpsc::Sender;
+use hickory_resolver::TokioResolver;
+use once_cell::sync::Lazy;
 use reqwest::Client;
+use reqwest::Url;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::Arc;
 
-pub trait Module {
+/// How bad a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+/// Common accessors of findings, used for reporting
+pub trait Reportable {
+    /// The URL at which the finding was observed
+    fn url(&self) -> &str;
+    /// The kind of the finding, as shown in reports
+    fn kind(&self) -> &str;
+    /// How bad the finding is
+    fn severity(&self) -> Severity;
+    /// Supporting details of the finding, one line each
+    fn evidence(&self) -> Vec<String>;
+
+    /// The name of the module reporting the finding, if known
+    fn module(&self) -> Option<&str> {
+        None
+    }
+
+    /// The localized title of the finding
+    fn title(&self) -> Message {
+        title(self.kind())
+    }
+}
+
+/// A finding in a shape shared by every module, so that adding a module touches no central type
+/// - `kind` tells findings apart, e.g. `DotEnvDisclosure`, and gives their localized title
+/// - Details specific to the module go to `metadata`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    /// The name of the module reporting the finding, e.g. `http/dotenv_disclosure`
+    pub module: String,
+    pub kind: String,
+    pub severity: Severity,
+    /// The `host:port` the finding was observed on, the zone alone for DNS findings
+    pub target: String,
+    pub url: String,
+    /// Supporting details, one line each
+    pub evidence: Vec<String>,
+    pub metadata: BTreeMap<String, Value>,
+    /// Further work the finding calls for, queued by the task queue rather than reported
+    #[serde(skip)]
+    pub follow_ups: Vec<FollowUp>,
+    /// The request and response the finding was observed in, captured with `--har`
+    #[serde(skip)]
+    pub exchange: Option<Exchange>,
+}
+
+/// Work a finding calls for on the endpoint it was observed on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FollowUp {
+    /// Scan a path of the port with every HTTP module, e.g. a directory a listing revealed
+    Path(String),
+}
+
+impl Finding {
+    pub fn new(module: impl Into<String>, kind: &str, severity: Severity, url: &str) -> Self {
+        let target = Url::parse(url).ok().map_or_else(String::new, |url| {
+            let host = url.host_str().unwrap_or_default();
+            match url.port_or_known_default() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_string(),
+            }
+        });
+
+        Finding {
+            module: module.into(),
+            kind: kind.to_string(),
+            severity,
+            target,
+            url: url.to_string(),
+            evidence: Vec::new(),
+            metadata: BTreeMap::new(),
+            follow_ups: Vec::new(),
+            exchange: None,
+        }
+    }
+
+    pub fn with_evidence(mut self, evidence: Vec<String>) -> Self {
+        self.evidence = evidence;
+        self
+    }
+
+    pub fn with_metadata(mut self, key: &str, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).expect("Finding metadata should serialize");
+        self.metadata.insert(key.to_string(), value);
+        self
+    }
+
+    pub fn with_follow_ups(mut self, follow_ups: Vec<FollowUp>) -> Self {
+        self.follow_ups = follow_ups;
+        self
+    }
+
+    /// The `key` metadata as a string, if it is one
+    pub fn metadata_str(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key)?.as_str()
+    }
+}
+
+impl Reportable for Finding {
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn evidence(&self) -> Vec<String> {
+        self.evidence.clone()
+    }
+
+    fn module(&self) -> Option<&str> {
+        Some(&self.module)
+    }
+}
+
+/// The title of findings of `kind`, `finding-` followed by the kind in kebab case
+pub fn title(kind: &str) -> Message {
+    let mut id = String::from("finding");
+    for c in kind.chars() {
+        if c.is_ascii_uppercase() {
+            id.push('-');
+        }
+        id.push(c.to_ascii_lowercase());
+    }
+    Message::new(id)
+}
+
+impl Severity {
+    /// The localized name of the severity
+    pub fn label(self) -> Message {
+        Message::new(match self {
+            Severity::Info => "severity-info",
+            Severity::Low => "severity-low",
+            Severity::Medium => "severity-medium",
+            Severity::High => "severity-high",
+        })
+    }
+}
+
+/// Modules run on the workers of the scan, from any thread
+pub trait Module: Send + Sync {
     fn name(&self) -> String;
-    fn description(&self) -> String;
+    /// `module-` followed by the name with `/` replaced by `-`, with the flags it mentions
+    fn description(&self) -> Message;
+
+    /// The highest severity of the findings the module reports
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    /// Background on the issues the module checks
+    fn references(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
 }
 
+/// Modules checking an endpoint, `host:port` optionally followed by a base path, along with
+/// what was detected about it
 #[async_trait]
 pub trait HttpModule: Module {
-    async fn scan(&self, http_client: &Client, endpoint: &str) -> Result<Option<HttpFindings>>;
+    async fn scan(&self, http_client: &Client, context: &ScanContext) -> Result<Option<Finding>>;
+
+    /// Requests sent to an endpoint at most (HTTPS then HTTP), 0 when the module is disabled
+    fn max_requests(&self) -> usize {
+        2
+    }
+}
+
+/// Modules injecting payloads into a parameter mined on the endpoint of `context`
+#[async_trait]
+pub trait ParameterModule: Module {
+    async fn inject(
+        &self,
+        http_client: &Client,
+        context: &ScanContext,
+        target: &ParameterTarget,
+    ) -> Result<Option<Finding>>;
+
+    /// Requests sent for a target at most, 0 when the module is disabled
+    fn max_requests(&self) -> usize {
+        1
+    }
 }
 
 #[async_trait]
 pub trait SubdomainModule: Module {
-    async fn enumerate(&self, domain: &str) -> Result<Vec<String>>;
+    /// Send the subdomains of `domain` to `found` as the source returns them, rather than
+    /// holding them until the source is done
+    /// - The same name may be sent more than once
+    async fn enumerate(&self, domain: &str, found: Sender<String>) -> Result<()>;
 }
 
-pub fn http_modules() -> Vec<Box<dyn HttpModule>> {
-    vec![
-        Box::new(http::DirectoryListing::new()),
-        Box::new(http::DotEnvDisclosure::new()),
-        Box::new(http::GitConfigLeakage::new()),
-        Box::new(http::GitHeadLeakage::new()),
-    ]
+/// Subdomain modules deriving new names from the ones found by `SubdomainModule`s
+#[async_trait]
+pub trait DerivedSubdomainModule: Module {
+    async fn derive(&self, domain: &str, known: &HashSet<&str>) -> Result<Vec<String>>;
+}
+
+/// Modules checking the DNS configuration of a target domain
+#[async_trait]
+pub trait DnsModule: Module {
+    async fn check(&self, resolver: &TokioResolver, zone: &str) -> Result<Vec<Finding>>;
+}
+
+/// Modules speaking the protocol of a non-HTTP service found by the port scan
+#[async_trait]
+pub trait ServiceModule: Module {
+    /// Ports the module probes when they are open
+    fn ports(&self) -> &'static [u16];
+
+    /// Whether the ports are UDP, probed on every host since the port scan only finds TCP ports
+    fn udp(&self) -> bool {
+        false
+    }
+
+    async fn probe(&self, host: &str, ip: IpAddr, port: u16) -> Result<Vec<Finding>>;
+}
+
+/// Modules checking the TLS implementation behind an open port
+#[async_trait]
+pub trait TlsModule: Module {
+    async fn check(&self, host: &str, ip: IpAddr, port: u16) -> Result<Option<Finding>>;
+
+    /// Connections opened to a port at most, 0 when the module is disabled
+    fn max_connections(&self) -> usize {
+        1
+    }
+}
+
+/// Modules checking a storage bucket or account named after the target
+/// - Requests share `limiter` with the rest of the scan and read bodies as `options` allow
+#[async_trait]
+pub trait CloudModule: Module {
+    async fn check(
+        &self,
+        http_client: &Client,
+        limiter: &Limiter,
+        options: &HttpOptions,
+        name: &str,
+    ) -> Result<Option<Finding>>;
+}
+
+/// Modules searching public sources for what the target leaked, e.g. code mentioning its domain
+#[async_trait]
+pub trait LeakModule: Module {
+    async fn search(&self, http_client: &Client, domain: &str) -> Result<Vec<Finding>>;
+
+    /// Requests sent for a domain at most, 0 when the module is skipped
+    fn max_requests(&self) -> usize {
+        1
+    }
+}
+
+/// What modules are built with, each module taking the settings it needs
+pub struct ModuleOptions<'a> {
+    /// The OOB interaction tracker, required by modules confirming blind issues
+    pub oob: Option<Arc<Oob>>,
+    /// Enable modules writing to the target or sending payloads that may disturb it
+    pub aggressive: bool,
+    /// Let crt.sh fall back to its public PostgreSQL replica
+    pub crtsh_postgres: bool,
+    /// Keys of the sources needing one, the others skip their source
+    pub api_keys: &'a ApiKeys,
+}
+
+/// Tags grouping modules across categories, selected with `--tags` and `--exclude-tags`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Tag {
+    /// Files, data or services the target should not expose
+    Exposure,
+    /// Insecure settings of servers and services
+    Misconfig,
+    /// Payloads injected into requests
+    Injection,
+    /// Requests that may disturb the target, only sent with `--aggressive`
+    Intrusive,
+    /// Requests any visitor would send, or none to the target at all
+    Passive,
+}
+
+/// How much the requests of a module may disturb the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Intrusiveness {
+    /// Requests any visitor could send
+    Safe,
+    /// Safe checks, with disruptive ones only enabled with `--aggressive`
+    Partial,
+    /// Never built without `--aggressive`
+    Intrusive,
+}
+
+/// What an HTTP module needs on an endpoint before it runs there
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dependency {
+    /// A technology detected on the port, e.g. `WordPress`
+    Technology(&'static str),
+    /// A finding of another HTTP module on the endpoint, which then runs first
+    Finding(&'static str),
+}
+
+impl Dependency {
+    /// Whether the dependency is met by the technologies detected on an endpoint
+    /// - Finding dependencies are met by the task queue
+    pub fn detected(&self, context: &ScanContext) -> bool {
+        match self {
+            Dependency::Technology(name) => context
+                .technologies
+                .iter()
+                .any(|technology| technology.name.eq_ignore_ascii_case(name)),
+            Dependency::Finding(_) => true,
+        }
+    }
 }
 
-pub fn subdomain_modules() -> Vec<Box<dyn SubdomainModule>> {
+/// A module submitted by its own file with `inventory::submit!`, so that every module
+/// defined is also run
+pub struct Registration {
+    /// The name of the built module
+    pub name: &'static str,
+    pub tags: &'static [Tag],
+    pub intrusiveness: Intrusiveness,
+    /// What the module needs on an endpoint, all of it
+    pub requires: &'static [Dependency],
+    pub build: Build,
+}
+
+/// How to build a module of each category
+pub enum Build {
+    Http(fn(&ModuleOptions) -> Box<dyn HttpModule>),
+    Parameter(fn(&ModuleOptions) -> Box<dyn ParameterModule>),
+    Subdomain(fn(&ModuleOptions) -> Box<dyn SubdomainModule>),
+    DerivedSubdomain(fn(&ModuleOptions) -> Box<dyn DerivedSubdomainModule>),
+    Dns(fn(&ModuleOptions) -> Box<dyn DnsModule>),
+    Service(fn(&ModuleOptions) -> Box<dyn ServiceModule>),
+    Tls(fn(&ModuleOptions) -> Box<dyn TlsModule>),
+    Cloud(fn(&ModuleOptions) -> Box<dyn CloudModule>),
+    Leak(fn(&ModuleOptions) -> Box<dyn LeakModule>),
+}
+
+inventory::collect!(Registration);
+
+/// Modules run by a scan, by their tags
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    /// Run only modules with any of these tags, every module if empty
+    pub tags: Vec<Tag>,
+    /// Never run modules with any of these tags
+    pub exclude_tags: Vec<Tag>,
+}
+
+impl Selection {
+    pub fn allows(&self, tags: &[Tag]) -> bool {
+        (self.tags.is_empty() || self.tags.iter().any(|tag| tags.contains(tag)))
+            && !self.exclude_tags.iter().any(|tag| tags.contains(tag))
+    }
+
+    /// Whether the checks of the module named `name` are allowed
+    /// - Subdomain sources are built whatever the selection, not the checks some of them run
+    pub fn allows_module(&self, name: &str) -> bool {
+        self.allows(tags(name))
+    }
+}
+
+/// The tags a module was registered with, none for unknown names
+pub fn tags(name: &str) -> &'static [Tag] {
+    registration(name).map_or(&[], |registration| registration.tags)
+}
+
+/// What a module needs on an endpoint before it runs there, nothing for unknown names
+pub fn dependencies(name: &str) -> &'static [Dependency] {
+    registration(name).map_or(&[], |registration| registration.requires)
+}
+
+/// How much a module may disturb the target, safe for unknown names
+pub fn intrusiveness(name: &str) -> Intrusiveness {
+    registration(name).map_or(Intrusiveness::Safe, |registration| {
+        registration.intrusiveness
+    })
+}
+
+/// The name a module was registered with, `None` for unknown names
+pub fn registered_name(name: &str) -> Option<&'static str> {
+    registration(name).map(|registration| registration.name)
+}
+
+fn registration(name: &str) -> Option<&'static Registration> {
+    inventory::iter::<Registration>
+        .into_iter()
+        .find(|registration| registration.name == name)
+}
+
+/// Selected modules whose disruptive checks are skipped without `--aggressive`, by name
+/// - Intrusive modules are not built at all, partial ones only run their safe checks
+pub fn held_back(selection: &Selection, aggressive: bool) -> Vec<&'static Registration> {
+    if aggressive {
+        return Vec::new();
+    }
+
+    let mut registrations: Vec<&'static Registration> = inventory::iter::<Registration>
+        .into_iter()
+        .filter(|registration| {
+            registration.intrusiveness != Intrusiveness::Safe && selection.allows(registration.tags)
+        })
+        .collect();
+    registrations.sort_by_key(|registration| registration.name);
+    registrations
+}
+
+/// Names of the modules a scan builds, sorted
+/// - Intrusive modules are only built with `--aggressive`
+pub fn enabled(selection: &Selection, aggressive: bool) -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = inventory::iter::<Registration>
+        .into_iter()
+        .filter(|registration| {
+            let source = matches!(
+                registration.build,
+                Build::Subdomain(_) | Build::DerivedSubdomain(_)
+            );
+            (source || selection.allows(registration.tags))
+                && (aggressive || registration.intrusiveness != Intrusiveness::Intrusive)
+        })
+        .map(|registration| registration.name)
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+/// Built-in wordlists of the modules, by name
+pub fn wordlists() -> Vec<(&'static str, &'static [&'static str])> {
+    let mut wordlists = Vec::new();
+    wordlists.extend(cloud::wordlists());
+    wordlists.extend(http::wordlists());
+    wordlists.extend(service::wordlists());
+    wordlists.extend(subdomain::wordlists());
+    wordlists
+}
+
+/// The registered modules of a category `selection` allows, by name
+fn registered<M: Module + ?Sized>(
+    selection: &Selection,
+    options: &ModuleOptions,
+    category: impl Fn(&Build) -> Option<fn(&ModuleOptions) -> Box<M>>,
+) -> Vec<Box<M>> {
+    let mut modules: Vec<Box<M>> = inventory::iter::<Registration>
+        .into_iter()
+        .filter(|registration| selection.allows(registration.tags))
+        // Modules are never trusted to hold back their own intrusive requests
+        .filter(|registration| {
+            options.aggressive || registration.intrusiveness != Intrusiveness::Intrusive
+        })
+        .filter_map(|registration| category(&registration.build))
+        .map(|build| build(options))
+        .collect();
+    // Registrations come in link order
+    modules.sort_by_key(|module| module.name());
+    modules
+}
+
+/// Options of modules built without API keys, when no category calls for them
+fn keyless(oob: Option<Arc<Oob>>, aggressive: bool) -> ModuleOptions<'static> {
+    static NO_KEYS: Lazy<ApiKeys> = Lazy::new(ApiKeys::default);
+    ModuleOptions {
+        oob,
+        aggressive,
+        crtsh_postgres: false,
+        api_keys: &NO_KEYS,
+    }
+}
+
+/// HTTP modules
+///
+/// # Arguments
+/// * `selection` - The modules to build, by their tags
+/// * `oob` - The OOB interaction tracker, required by modules confirming blind issues
+/// * `aggressive` - Enable modules writing to the target
+pub fn http_modules(
+    selection: &Selection,
+    oob: Option<Arc<Oob>>,
+    aggressive: bool,
+) -> Vec<Box<dyn HttpModule>> {
+    registered(selection, &keyless(oob, aggressive), |build| match build {
+        Build::Http(build) => Some(*build),
+        _ => None,
+    })
+}
+
+/// Injection modules run against mined `(url, parameter)` targets
+///
+/// # Arguments
+/// * `selection` - The modules to build, by their tags
+/// * `aggressive` - Enable modules sending payloads that may disturb the target
+pub fn parameter_modules(selection: &Selection, aggressive: bool) -> Vec<Box<dyn ParameterModule>> {
+    registered(selection, &keyless(None, aggressive), |build| match build {
+        Build::Parameter(build) => Some(*build),
+        _ => None,
+    })
+}
+
+/// Subdomain modules
+///
+/// # Arguments
+/// * `crtsh_postgres` - Let crt.sh fall back to its public PostgreSQL replica
+/// * `api_keys` - Keys of the sources needing one, the others skip their source
+pub fn subdomain_modules(
+    crtsh_postgres: bool,
+    api_keys: &ApiKeys,
+) -> Vec<Box<dyn SubdomainModule>> {
+    let options = ModuleOptions {
+        oob: None,
+        aggressive: false,
+        crtsh_postgres,
+        api_keys,
+    };
+    // Sources find what checks run on, whatever the selected checks
+    registered(&Selection::default(), &options, |build| match build {
+        Build::Subdomain(build) => Some(*build),
+        _ => None,
+    })
+}
+
+pub fn derived_subdomain_modules() -> Vec<Box<dyn DerivedSubdomainModule>> {
+    registered(
+        &Selection::default(),
+        &keyless(None, false),
+        |build| match build {
+            Build::DerivedSubdomain(build) => Some(*build),
+            _ => None,
+        },
+    )
+}
+
+pub fn dns_modules(selection: &Selection) -> Vec<Box<dyn DnsModule>> {
+    registered(selection, &keyless(None, false), |build| match build {
+        Build::Dns(build) => Some(*build),
+        _ => None,
+    })
+}
+
+pub fn service_modules(selection: &Selection) -> Vec<Box<dyn ServiceModule>> {
+    registered(selection, &keyless(None, false), |build| match build {
+        Build::Service(build) => Some(*build),
+        _ => None,
+    })
+}
+
+/// TLS modules
+///
+/// # Arguments
+/// * `selection` - The modules to build, by their tags
+/// * `aggressive` - Enable modules sending malformed handshakes
+pub fn tls_modules(selection: &Selection, aggressive: bool) -> Vec<Box<dyn TlsModule>> {
+    registered(selection, &keyless(None, aggressive), |build| match build {
+        Build::Tls(build) => Some(*build),
+        _ => None,
+    })
+}
+
+pub fn cloud_modules(selection: &Selection) -> Vec<Box<dyn CloudModule>> {
+    registered(selection, &keyless(None, false), |build| match build {
+        Build::Cloud(build) => Some(*build),
+        _ => None,
+    })
+}
+
+/// Leak modules
+///
+/// # Arguments
+/// * `selection` - The modules to build, by their tags
+/// * `api_keys` - Keys of the sources needing one, the others skip their source
+pub fn leak_modules(selection: &Selection, api_keys: &ApiKeys) -> Vec<Box<dyn LeakModule>> {
+    let options = ModuleOptions {
+        oob: None,
+        aggressive: false,
+        crtsh_postgres: false,
+        api_keys,
+    };
+    registered(selection, &options, |build| match build {
+        Build::Leak(build) => Some(*build),
+        _ => None,
+    })
+}
+
+/// Every module by category, as listed by `modules`, without API keys nor OOB server
+/// - Intrusive modules are listed too, as if `--aggressive` was given
+pub fn all() -> Vec<(&'static str, Vec<Box<dyn Module>>)> {
+    let every = Selection::default();
+    let subdomain = subdomain_modules(false, &ApiKeys::default())
+        .into_iter()
+        .map(|module| module as Box<dyn Module>)
+        .chain(
+            derived_subdomain_modules()
+                .into_iter()
+                .map(|module| module as Box<dyn Module>),
+        );
+    let http = http_modules(&every, None, true)
+        .into_iter()
+        .map(|module| module as Box<dyn Module>)
+        .chain(
+            parameter_modules(&every, true)
+                .into_iter()
+                .map(|module| module as Box<dyn Module>),
+        );
+
     vec![
-        Box::new(subdomain::CrtSh::new()),
-        Box::new(subdomain::WebArchive::new()),
+        ("subdomain", subdomain.collect()),
+        ("http", http.collect()),
+        (
+            "service",
+            service_modules(&every)
+                .into_iter()
+                .map(|module| module as Box<dyn Module>)
+                .collect(),
+        ),
+        (
+            "tls",
+            tls_modules(&every, true)
+                .into_iter()
+                .map(|module| module as Box<dyn Module>)
+                .collect(),
+        ),
+        (
+            "dns",
+            dns_modules(&every)
+                .into_iter()
+                .map(|module| module as Box<dyn Module>)
+                .collect(),
+        ),
+        (
+            "cloud",
+            cloud_modules(&every)
+                .into_iter()
+                .map(|module| module as Box<dyn Module>)
+                .collect(),
+        ),
+        (
+            "leaks",
+            leak_modules(&every, &ApiKeys::default())
+                .into_iter()
+                .map(|module| module as Box<dyn Module>)
+                .collect(),
+        ),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_should_list_each_registered_module_once_in_its_category() {
+        let mut names = HashSet::new();
+
+        for (category, modules) in all() {
+            assert!(!modules.is_empty(), "No {} module registered", category);
+            for module in modules {
+                let name = module.name();
+                assert!(
+                    name.starts_with(&format!("{}/", category)),
+                    "{} registered as a {} module",
+                    name,
+                    category
+                );
+                assert!(names.insert(name.clone()), "{} registered twice", name);
+                // Tags are looked up by the name of the registration
+                assert!(
+                    !tags(&name).is_empty(),
+                    "{} registered as another name",
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_registrations_should_fit_subdomain_attribution() {
+        let sources = inventory::iter::<Registration>
+            .into_iter()
+            .filter(|registration| {
+                matches!(
+                    registration.build,
+                    Build::Subdomain(_) | Build::DerivedSubdomain(_)
+                )
+            })
+            .count();
+
+        assert!(
+            sources <= crate::stats::MAX_SOURCES,
+            "{} subdomain modules registered",
+            sources
+        );
+    }
+
+    #[test]
+    fn test_dependencies_should_name_http_modules_without_cycles() {
+        let http: Vec<String> = http_modules(&Selection::default(), None, true)
+            .iter()
+            .map(|module| module.name())
+            .collect();
+
+        for registration in inventory::iter::<Registration> {
+            // Following findings dependencies from any module ends within the module count
+            let mut names = vec![registration.name];
+            for _ in 0..=http.len() {
+                names = names
+                    .iter()
+                    .flat_map(|name| dependencies(name))
+                    .filter_map(|dependency| match dependency {
+                        Dependency::Finding(name) => Some(*name),
+                        Dependency::Technology(_) => None,
+                    })
+                    .inspect(|name| {
+                        assert!(
+                            http.iter().any(|module| module == name),
+                            "{} depends on the unknown HTTP module {}",
+                            registration.name,
+                            name
+                        )
+                    })
+                    .collect();
+            }
+            assert!(names.is_empty(), "{} depends on a cycle", registration.name);
+        }
+    }
+
+    #[test]
+    fn test_selection_should_exclude_after_including() {
+        let selection = Selection {
+            tags: vec![Tag::Exposure, Tag::Injection],
+            exclude_tags: vec![Tag::Intrusive],
+        };
+
+        assert!(selection.allows_module("http/dotenv_disclosure"));
+        assert!(selection.allows_module("http/reflected_xss"));
+        assert!(!selection.allows_module("http/sqli_error"));
+        assert!(!selection.allows_module("http/transport_security"));
+        assert!(Selection::default().allows(&[]));
+
+        // Only checks are selected, sources are built whatever the selection
+        let names = |modules: Vec<Box<dyn HttpModule>>| -> Vec<String> {
+            modules.iter().map(|module| module.name()).collect()
+        };
+        let http = names(http_modules(&selection, None, true));
+        assert!(http.contains(&String::from("http/dotenv_disclosure")));
+        assert!(!http.contains(&String::from("http/transport_security")));
+        assert!(dns_modules(&selection).is_empty());
+        assert!(!subdomain_modules(false, &ApiKeys::default()).is_empty());
+        assert!(enabled(&selection, true).contains(&"subdomain/crtsh"));
+    }
+
+    #[test]
+    fn test_registered_should_hold_back_intrusive_modules_without_aggressive() {
+        let sqli_error = |aggressive| {
+            parameter_modules(&Selection::default(), aggressive)
+                .iter()
+                .any(|module| module.name() == "http/sqli_error")
+        };
+
+        assert!(!sqli_error(false));
+        assert!(sqli_error(true));
+        assert!(tls_modules(&Selection::default(), false).is_empty());
+
+        let skipped: Vec<(&str, Intrusiveness)> = held_back(&Selection::default(), false)
+            .iter()
+            .map(|registration| (registration.name, registration.intrusiveness))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                ("http/dangerous_methods", Intrusiveness::Partial),
+                ("http/sqli_error", Intrusiveness::Intrusive),
+                ("tls/ccs_injection", Intrusiveness::Intrusive),
+                ("tls/heartbleed", Intrusiveness::Intrusive),
+            ]
+        );
+        assert!(held_back(&Selection::default(), true).is_empty());
+
+        // The `intrusive` tag selects the modules never built without `--aggressive`
+        for registration in inventory::iter::<Registration> {
+            assert_eq!(
+                registration.tags.contains(&Tag::Intrusive),
+                registration.intrusiveness == Intrusiveness::Intrusive,
+                "{} is tagged inconsistently",
+                registration.name
+            );
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::ServiceModule;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use crate::modules::service::no_start_tls;
+use crate::modules::service::session::Session;
+use crate::modules::service::session::TIMEOUT;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use std::net::IpAddr;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+const PORTS: &[u16] = &[21];
+// Anonymous FTP asks for an email address as the password by convention
+const ANONYMOUS_PASSWORD: &str = "vulnscan@example.com";
+
+pub struct Ftp;
+
+impl Ftp {
+    pub fn new() -> Self {
+        Ftp
+    }
+}
+
+impl Module for Ftp {
+    fn name(&self) -> String {
+        String::from("service/ftp")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-service-ftp")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Medium
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://www.rfc-editor.org/rfc/rfc1635",
+            "https://www.rfc-editor.org/rfc/rfc4217",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "service/ftp",
+        tags: &[Tag::Exposure, Tag::Misconfig],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Service(|_| Box::new(Ftp::new())),
+    }
+}
+
+#[async_trait]
+impl ServiceModule for Ftp {
+    fn ports(&self) -> &'static [u16] {
+        PORTS
+    }
+
+    async fn probe(&self, host: &str, ip: IpAddr, port: u16) -> Result<Vec<Finding>> {
+        let url = format!("ftp://{}:{}", host, port);
+        let mut session = Session::connect(ip, port).await?;
+
+        let greeting = session.read_reply().await?;
+        if greeting.code != 220 {
+            bail!("{} refused the session: {}", url, greeting);
+        }
+
+        let mut findings = Vec::new();
+
+        // Servers predating FEAT may still accept AUTH TLS, asked last on a separate session
+        let feat = session.command("FEAT").await?;
+        let ftps = feat.is_positive() && feat.advertises("AUTH");
+
+        let user = session.command("USER anonymous").await?;
+        let logged_in = match user.code {
+            230 => true,
+            331 => {
+                session
+                    .command(&format!("PASS {}", ANONYMOUS_PASSWORD))
+                    .await?
+                    .code
+                    == 230
+            }
+            _ => false,
+        };
+
+        if logged_in {
+            let entries = match list_root(&mut session, ip).await {
+                Ok(entries) => Some(entries),
+                Err(e) => {
+                    log::debug!("{}: Failed to list {}: {}", self.name(), url, e);
+                    None
+                }
+            };
+            let evidence = match entries {
+                Some(entries) => format!("{} entries in /", entries),
+                None => String::from("/ could not be listed"),
+            };
+            findings.push(
+                Finding::new(self.name(), "AnonymousFtp", Severity::Medium, &url)
+                    .with_evidence(vec![evidence])
+                    .with_metadata("entries", entries),
+            );
+        }
+
+        let _ = session.command("QUIT").await;
+        drop(session);
+
+        if !ftps && !accepts_auth_tls(ip, port).await? {
+            findings.push(no_start_tls(
+                self.name(),
+                &url,
+                String::from("AUTH TLS is not supported"),
+            ));
+        }
+
+        Ok(findings)
+    }
+}
+
+/// Count the entries of the root directory over a passive data connection
+async fn list_root(session: &mut Session, ip: IpAddr) -> Result<usize> {
+    let pasv = session.command("PASV").await?;
+    if pasv.code != 227 {
+        bail!("PASV was refused: {}", pasv);
+    }
+
+    // The advertised address is ignored, it is often private behind NAT and could point elsewhere
+    let port =
+        passive_port(&pasv.to_string()).ok_or_else(|| anyhow!("Invalid PASV reply: {}", pasv))?;
+    let mut data = timeout(TIMEOUT, TcpStream::connect((ip, port))).await??;
+
+    let reply = session.command("NLST /").await?;
+    match reply.code {
+        125 | 150 => (),
+        // Empty directories are an error to some servers
+        450 | 550 => return Ok(0),
+        _ => bail!("NLST was refused: {}", reply),
+    }
+
+    let mut listing = String::new();
+    timeout(TIMEOUT, data.read_to_string(&mut listing)).await??;
+    session.read_reply().await?;
+
+    Ok(listing
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count())
+}
+
+/// The port of a `227 Entering Passive Mode (h1,h2,h3,h4,p1,p2)` reply
+fn passive_port(reply: &str) -> Option<u16> {
+    let start = reply.find('(')?;
+    let end = start + reply[start..].find(')')?;
+    let numbers: Vec<u16> = reply[start + 1..end]
+        .split(',')
+        .map(|number| number.trim().parse().ok())
+        .collect::<Option<_>>()?;
+
+    match numbers[..] {
+        [_, _, _, _, high, low] if high < 256 && low < 256 => Some(high << 8 | low),
+        _ => None,
+    }
+}
+
+/// Whether the server agrees to upgrade a fresh session, which is then dropped
+async fn accepts_auth_tls(ip: IpAddr, port: u16) -> Result<bool> {
+    let mut session = Session::connect(ip, port).await?;
+    session.read_reply().await?;
+    Ok(session.command("AUTH TLS").await?.code == 234)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::service::tests::summary;
+    use tokio::io::AsyncBufReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::io::BufReader;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_passive_port_should_decode_pasv_reply() {
+        assert_eq!(
+            passive_port("227 Entering Passive Mode (10,0,0,5,195,80)."),
+            Some(50000)
+        );
+        assert_eq!(
+            passive_port("227 Entering Passive Mode (10,0,0,5,300,80)"),
+            None
+        );
+        assert_eq!(passive_port("227 Entering Passive Mode"), None);
+    }
+
+    #[tokio::test]
+    async fn test_probe_should_list_root_of_anonymous_server_without_ftps() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let data_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let data_port = data_listener.local_addr().unwrap().port();
+
+        // Serves the probe session, then the AUTH TLS session
+        let server = tokio::spawn(async move {
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+
+                write.write_all(b"220 FTP ready\r\n").await.unwrap();
+
+                while let Ok(Some(command)) = lines.next_line().await {
+                    let response = match command.as_str() {
+                        "FEAT" => String::from("211-Features:\r\n MDTM\r\n SIZE\r\n211 End\r\n"),
+                        "USER anonymous" => String::from("331 Send your email address\r\n"),
+                        "PASS vulnscan@example.com" => String::from("230 Logged in\r\n"),
+                        "PASV" => format!(
+                            "227 Entering Passive Mode (192,168,1,2,{},{})\r\n",
+                            data_port >> 8,
+                            data_port & 0xff
+                        ),
+                        "NLST /" => {
+                            write.write_all(b"150 Here it comes\r\n").await.unwrap();
+                            let (mut data, _) = data_listener.accept().await.unwrap();
+                            data.write_all(b"pub\r\nincoming\r\nREADME\r\n")
+                                .await
+                                .unwrap();
+                            drop(data);
+                            String::from("226 Transfer complete\r\n")
+                        }
+                        "QUIT" => String::from("221 Bye\r\n"),
+                        _ => String::from("500 Unknown command\r\n"),
+                    };
+                    write.write_all(response.as_bytes()).await.unwrap();
+                }
+            }
+        });
+
+        let findings = Ftp::new()
+            .probe("ftp.example.com", "127.0.0.1".parse().unwrap(), port)
+            .await
+            .unwrap();
+        server.abort();
+
+        let url = format!("ftp://ftp.example.com:{}", port);
+        assert!(findings.iter().all(|finding| finding.url == url));
+        assert_eq!(
+            summary(&findings),
+            vec![
+                ("AnonymousFtp", vec!["3 entries in /"]),
+                ("NoStartTls", vec!["AUTH TLS is not supported"]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,39 @@
+mod ftp;
+mod ntp;
+mod plaintext;
+mod resolver;
+mod session;
+mod smtp;
+mod snmp;
+mod ssh;
+
+use crate::modules::Finding;
+use crate::modules::Severity;
+
+/// The service only talks plaintext: no STARTTLS for SMTP, no AUTH TLS for FTP
+fn no_start_tls(module: String, url: &str, reason: String) -> Finding {
+    Finding::new(module, "NoStartTls", Severity::Medium, url).with_evidence(vec![reason])
+}
+
+/// Built-in wordlists of the service modules, by name
+pub fn wordlists() -> Vec<(&'static str, &'static [&'static str])> {
+    vec![("service/snmp_communities", snmp::COMMUNITIES)]
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::modules::Finding;
+
+    /// The kind and evidence of each finding, as the probe tests compare them
+    pub fn summary(findings: &[Finding]) -> Vec<(&str, Vec<&str>)> {
+        findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.kind.as_str(),
+                    finding.evidence.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,175 @@
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::ServiceModule;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use crate::modules::service::session::udp_socket;
+use anyhow::Result;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio::time::timeout_at;
+
+const PORTS: &[u16] = &[123];
+// monlist answers span up to 100 packets, sent back to back
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(3);
+// Mode 7 (private), implementation XNTPD, request MON_GETLIST_1, padded like `ntpdc` does
+const MONLIST_REQUEST: [u8; 8] = [0x17, 0x00, 0x03, 0x2a, 0, 0, 0, 0];
+const REQUEST_SIZE: usize = 48;
+const MON_GETLIST_1: u8 = 0x2a;
+
+pub struct NtpMonlist;
+
+impl NtpMonlist {
+    pub fn new() -> Self {
+        NtpMonlist
+    }
+}
+
+impl Module for NtpMonlist {
+    fn name(&self) -> String {
+        String::from("service/ntp_monlist")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-service-ntp_monlist")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Medium
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://nvd.nist.gov/vuln/detail/CVE-2013-5211",
+            "https://www.cisa.gov/news-events/alerts/2014/01/13/ntp-amplification-attacks-using-cve-2013-5211",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "service/ntp_monlist",
+        tags: &[Tag::Misconfig],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Service(|_| Box::new(NtpMonlist::new())),
+    }
+}
+
+#[async_trait]
+impl ServiceModule for NtpMonlist {
+    fn ports(&self) -> &'static [u16] {
+        PORTS
+    }
+
+    fn udp(&self) -> bool {
+        true
+    }
+
+    async fn probe(&self, host: &str, ip: IpAddr, port: u16) -> Result<Vec<Finding>> {
+        let socket = udp_socket(ip, port).await?;
+
+        let mut request = [0; REQUEST_SIZE];
+        request[..MONLIST_REQUEST.len()].copy_from_slice(&MONLIST_REQUEST);
+        socket.send(&request).await?;
+
+        let deadline = Instant::now() + RESPONSE_TIMEOUT;
+        let mut buf = vec![0; 65535];
+        let mut packets = 0;
+        let mut bytes = 0;
+
+        while let Ok(received) = timeout_at(deadline, socket.recv(&mut buf)).await {
+            let received = received?;
+            if is_monlist_response(&buf[..received]) {
+                packets += 1;
+                bytes += received;
+            }
+        }
+
+        if packets == 0 {
+            return Ok(Vec::new());
+        }
+
+        let url = format!("ntp://{}:{}", host, port);
+        Ok(vec![
+            Finding::new(self.name(), "NtpMonlist", Severity::Medium, &url)
+                .with_evidence(vec![format!(
+                    "{} packets of {} bytes in total for a {}-byte request ({}x amplification)",
+                    packets,
+                    bytes,
+                    REQUEST_SIZE,
+                    bytes / REQUEST_SIZE
+                )])
+                .with_metadata("packets", packets)
+                .with_metadata("bytes", bytes),
+        ])
+    }
+}
+
+/// Whether `packet` is a mode 7 response to MON_GETLIST_1 carrying entries
+fn is_monlist_response(packet: &[u8]) -> bool {
+    match packet {
+        // Response bit, mode 7, then the request code and the number of entries
+        [flags, _, _, code, err_items, items, ..] => {
+            flags & 0x80 != 0
+                && flags & 0x07 == 7
+                && *code == MON_GETLIST_1
+                // The upper nibble holds an error code
+                && err_items & 0xf0 == 0
+                && (u16::from(err_items & 0x0f) << 8 | u16::from(*items)) > 0
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::service::tests::summary;
+    use tokio::net::UdpSocket;
+
+    #[tokio::test]
+    async fn test_probe_should_measure_monlist_amplification() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = server.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let mut buf = [0; 512];
+            let (_, peer) = server.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..MONLIST_REQUEST.len()], MONLIST_REQUEST);
+
+            // 6 entries of 72 bytes per packet, as ntpd sends them
+            let mut response = vec![0x97, 0x00, 0x03, 0x2a, 0x00, 0x06, 0x00, 0x48];
+            response.resize(8 + 6 * 72, 0);
+            for _ in 0..3 {
+                server.send_to(&response, peer).await.unwrap();
+            }
+            // An error response is not an answer
+            server
+                .send_to(&[0x97, 0x00, 0x03, 0x2a, 0x40, 0x00, 0x00, 0x00], peer)
+                .await
+                .unwrap();
+        });
+
+        let findings = NtpMonlist::new()
+            .probe("ntp.example.com", "127.0.0.1".parse().unwrap(), port)
+            .await
+            .unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].url, format!("ntp://ntp.example.com:{}", port));
+        assert_eq!(
+            summary(&findings),
+            vec![(
+                "NtpMonlist",
+                vec!["3 packets of 1320 bytes in total for a 48-byte request (27x amplification)"]
+            )]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::ServiceModule;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use crate::modules::service::session::TIMEOUT;
+use anyhow::Result;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+use tokio::time::timeout;
+use tokio::time::timeout_at;
+
+const TELNET_PORT: u16 = 23;
+const RLOGIN_PORT: u16 = 513;
+const VNC_PORT: u16 = 5900;
+const PORTS: &[u16] = &[TELNET_PORT, RLOGIN_PORT, VNC_PORT];
+// How long to wait for a telnet server to start negotiating or prompt
+const BANNER_TIMEOUT: Duration = Duration::from_secs(3);
+
+// Telnet commands (RFC 854)
+const IAC: u8 = 255;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const WILL: u8 = 251;
+const DONT: u8 = 254;
+
+// RFB security type without authentication
+const VNC_SECURITY_NONE: u8 = 1;
+
+pub struct Plaintext;
+
+impl Plaintext {
+    pub fn new() -> Self {
+        Plaintext
+    }
+
+    /// A management service without encryption is exposed
+    fn plaintext(&self, url: &str, banner: String) -> Finding {
+        let service = url.split(':').next().unwrap_or_default();
+        Finding::new(self.name(), "PlaintextService", Severity::Medium, url)
+            .with_evidence(vec![format!("{}: {}", service, banner)])
+            .with_metadata("service", service)
+    }
+}
+
+impl Module for Plaintext {
+    fn name(&self) -> String {
+        String::from("service/plaintext")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-service-plaintext")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://www.rfc-editor.org/rfc/rfc854",
+            "https://www.rfc-editor.org/rfc/rfc1282",
+            "https://www.rfc-editor.org/rfc/rfc6143#section-7.2",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "service/plaintext",
+        tags: &[Tag::Exposure, Tag::Misconfig],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Service(|_| Box::new(Plaintext::new())),
+    }
+}
+
+#[async_trait]
+impl ServiceModule for Plaintext {
+    fn ports(&self) -> &'static [u16] {
+        PORTS
+    }
+
+    async fn probe(&self, host: &str, ip: IpAddr, port: u16) -> Result<Vec<Finding>> {
+        let mut stream = timeout(TIMEOUT, TcpStream::connect((ip, port))).await??;
+
+        let finding = match port {
+            TELNET_PORT => telnet(&mut stream)
+                .await?
+                .map(|banner| self.plaintext(&format!("telnet://{}:{}", host, port), banner)),
+            RLOGIN_PORT => rlogin(&mut stream)
+                .await?
+                .map(|banner| self.plaintext(&format!("rlogin://{}:{}", host, port), banner)),
+            _ => vnc(&mut stream).await?.map(|version| {
+                Finding::new(
+                    self.name(),
+                    "VncNoAuth",
+                    Severity::High,
+                    &format!("vnc://{}:{}", host, port),
+                )
+                .with_evidence(vec![format!("{} offers the None security type", version)])
+            }),
+        };
+
+        Ok(finding.into_iter().collect())
+    }
+}
+
+/// The banner of a telnet server, confirmed by option negotiation or a login prompt
+async fn telnet(stream: &mut TcpStream) -> Result<Option<String>> {
+    let deadline = Instant::now() + BANNER_TIMEOUT;
+    let mut received = Vec::new();
+    let mut buf = [0; 1024];
+
+    loop {
+        let text = strip_telnet_commands(&received).to_lowercase();
+        let negotiates = received
+            .windows(2)
+            .any(|pair| pair[0] == IAC && (WILL..=DONT).contains(&pair[1]));
+        let prompts = ["login", "username", "password"]
+            .iter()
+            .any(|prompt| text.contains(prompt));
+
+        if prompts || (negotiates && !text.trim().is_empty()) {
+            break;
+        }
+
+        match timeout_at(deadline, stream.read(&mut buf)).await {
+            Ok(Ok(read)) if read > 0 => received.extend(&buf[..read]),
+            // Negotiation alone confirms telnet, servers may wait for an answer before prompting
+            _ if negotiates => break,
+            _ => return Ok(None),
+        }
+    }
+
+    let banner = strip_telnet_commands(&received);
+    Ok(Some(
+        banner
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" / "),
+    ))
+}
+
+/// Text sent by a telnet server, without its commands and option negotiation
+fn strip_telnet_commands(bytes: &[u8]) -> String {
+    let mut text = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            (IAC, Some(&IAC)) => {
+                text.push(IAC);
+                i += 2;
+            }
+            // Subnegotiations end with IAC SE
+            (IAC, Some(&SB)) => {
+                i = bytes[i..]
+                    .windows(2)
+                    .position(|pair| pair == [IAC, SE])
+                    .map_or(bytes.len(), |end| i + end + 2);
+            }
+            (IAC, Some(command)) if (WILL..=DONT).contains(command) => i += 3,
+            (IAC, _) => i += 2,
+            (byte, _) => {
+                text.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&text)
+        .chars()
+        .filter(|c| !c.is_control() || *c == '\n')
+        .collect()
+}
+
+/// The reply of an rlogin server to the connection handshake (RFC 1282)
+async fn rlogin(stream: &mut TcpStream) -> Result<Option<String>> {
+    stream
+        .write_all(b"\0vulnscan\0vulnscan\0xterm/38400\0")
+        .await?;
+
+    let mut buf = [0; 256];
+    let read = match timeout(BANNER_TIMEOUT, stream.read(&mut buf)).await {
+        Ok(Ok(read)) if read > 0 => read,
+        _ => return Ok(None),
+    };
+
+    // A null byte accepts the connection, servers refusing unprivileged source ports answer `\x01` and why
+    let reply = String::from_utf8_lossy(&buf[1..read]).trim().to_string();
+    match buf[0] {
+        0 => Ok(Some(if reply.is_empty() {
+            String::from("Connection accepted")
+        } else {
+            reply
+        })),
+        1 if reply.to_lowercase().contains("rlogin") => Ok(Some(reply)),
+        _ => Ok(None),
+    }
+}
+
+/// The protocol version of a VNC server offering the `None` security type (RFC 6143)
+async fn vnc(stream: &mut TcpStream) -> Result<Option<String>> {
+    let mut version = [0; 12];
+    timeout(TIMEOUT, stream.read_exact(&mut version)).await??;
+    if !version.starts_with(b"RFB ") {
+        return Ok(None);
+    }
+
+    let version = String::from_utf8_lossy(&version).trim().to_string();
+    // Clients answer with the highest version they share with the server
+    let reply = if version.as_str() < "RFB 003.008" {
+        format!("{}\n", version)
+    } else {
+        String::from("RFB 003.008\n")
+    };
+    stream.write_all(reply.as_bytes()).await?;
+
+    // 3.3 servers pick the security type, later ones list those they support
+    let none_offered = if version.as_str() < "RFB 003.007" {
+        timeout(TIMEOUT, stream.read_u32()).await?? == VNC_SECURITY_NONE as u32
+    } else {
+        let count = timeout(TIMEOUT, stream.read_u8()).await?? as usize;
+        let mut types = vec![0; count];
+        timeout(TIMEOUT, stream.read_exact(&mut types)).await??;
+        types.contains(&VNC_SECURITY_NONE)
+    };
+
+    Ok(none_offered.then_some(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Serve one connection, writing the first chunk then each next one after reading from the client
+    async fn serve(chunks: Vec<&'static [u8]>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            for (i, chunk) in chunks.into_iter().enumerate() {
+                if i > 0 {
+                    let _ = stream.read(&mut buf).await;
+                }
+                stream.write_all(chunk).await.unwrap();
+            }
+            let _ = stream.read(&mut buf).await;
+        });
+
+        port
+    }
+
+    #[tokio::test]
+    async fn test_telnet_should_confirm_negotiating_server() {
+        let port = serve(vec![
+            b"\xff\xfd\x18\xff\xfd\x20\xff\xfa\x18\x01\xff\xf0Ubuntu 22.04 LTS\r\nrouter login: ",
+        ])
+        .await;
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+
+        assert_eq!(
+            telnet(&mut stream).await.unwrap(),
+            Some(String::from("Ubuntu 22.04 LTS / router login:"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_vnc_should_report_none_security_type() {
+        let open = serve(vec![b"RFB 003.008\n", b"\x02\x01\x02"]).await;
+        let protected = serve(vec![b"RFB 003.008\n", b"\x01\x02"]).await;
+
+        let mut stream = TcpStream::connect(("127.0.0.1", open)).await.unwrap();
+        assert_eq!(
+            vnc(&mut stream).await.unwrap(),
+            Some(String::from("RFB 003.008"))
+        );
+
+        let mut stream = TcpStream::connect(("127.0.0.1", protected)).await.unwrap();
+        assert_eq!(vnc(&mut stream).await.unwrap(), None);
+    }
+}
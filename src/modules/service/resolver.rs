@@ -0,0 +1,188 @@
+use crate::i18n;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::ServiceModule;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use crate::modules::service::session::udp_socket;
+use anyhow::Result;
+use anyhow::bail;
+use hickory_resolver::proto::op::Message;
+use hickory_resolver::proto::op::Query;
+use hickory_resolver::proto::op::ResponseCode;
+use hickory_resolver::proto::rr::Name;
+use hickory_resolver::proto::rr::RecordType;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::time::timeout;
+
+const PORTS: &[u16] = &[53];
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+// A reserved name nobody is authoritative for but its own servers, so only recursion answers it
+const PROBE_NAME: &str = "example.com.";
+
+pub struct OpenResolver;
+
+impl OpenResolver {
+    pub fn new() -> Self {
+        OpenResolver
+    }
+}
+
+impl Module for OpenResolver {
+    fn name(&self) -> String {
+        String::from("service/open_resolver")
+    }
+
+    fn description(&self) -> i18n::Message {
+        i18n::Message::new("module-service-open_resolver")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Medium
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://www.cisa.gov/news-events/alerts/2013/03/29/dns-amplification-attacks",
+            "https://www.rfc-editor.org/rfc/rfc5358",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "service/open_resolver",
+        tags: &[Tag::Misconfig],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Service(|_| Box::new(OpenResolver::new())),
+    }
+}
+
+#[async_trait]
+impl ServiceModule for OpenResolver {
+    fn ports(&self) -> &'static [u16] {
+        PORTS
+    }
+
+    fn udp(&self) -> bool {
+        true
+    }
+
+    async fn probe(&self, host: &str, ip: IpAddr, port: u16) -> Result<Vec<Finding>> {
+        let mut query = Message::new();
+        query
+            .set_id(rand::random())
+            .set_recursion_desired(true)
+            .add_query(Query::query(Name::from_ascii(PROBE_NAME)?, RecordType::A));
+
+        let socket = udp_socket(ip, port).await?;
+        socket.send(&query.to_vec()?).await?;
+
+        let mut buffer = [0u8; 4096];
+        let Ok(size) = timeout(QUERY_TIMEOUT, socket.recv(&mut buffer)).await else {
+            return Ok(Vec::new());
+        };
+        let response = Message::from_vec(&buffer[..size?])?;
+
+        if response.id() != query.id() {
+            bail!("{} answered another query", host);
+        }
+
+        // Authoritative-only servers refuse, or answer without recursion available
+        if response.response_code() != ResponseCode::NoError
+            || !response.recursion_available()
+            || response.answers().is_empty()
+        {
+            return Ok(Vec::new());
+        }
+
+        let answers = response
+            .answers()
+            .iter()
+            .map(|record| record.data().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let url = format!("dns://{}:{}", host, port);
+        Ok(vec![
+            Finding::new(self.name(), "OpenResolver", Severity::Medium, &url).with_evidence(vec![
+                format!("{} A {}", PROBE_NAME.trim_end_matches('.'), answers),
+            ]),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::service::tests::summary;
+    use hickory_resolver::proto::op::MessageType;
+    use hickory_resolver::proto::rr::RData;
+    use hickory_resolver::proto::rr::Record;
+    use hickory_resolver::proto::rr::rdata::A;
+    use tokio::net::UdpSocket;
+
+    /// Answer one query, recursively or not
+    async fn fake_server(recursive: bool) -> u16 {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = server.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let mut buf = [0; 512];
+            let (size, peer) = server.recv_from(&mut buf).await.unwrap();
+            let query = Message::from_vec(&buf[..size]).unwrap();
+
+            let mut response = Message::new();
+            response
+                .set_id(query.id())
+                .set_message_type(MessageType::Response)
+                .set_recursion_available(recursive)
+                .add_queries(query.queries().to_vec());
+            if recursive {
+                response.add_answer(Record::from_rdata(
+                    Name::from_ascii(PROBE_NAME).unwrap(),
+                    300,
+                    RData::A(A::new(93, 184, 215, 14)),
+                ));
+            } else {
+                response.set_response_code(ResponseCode::Refused);
+            }
+
+            server
+                .send_to(&response.to_vec().unwrap(), peer)
+                .await
+                .unwrap();
+        });
+
+        port
+    }
+
+    #[tokio::test]
+    async fn test_probe_should_report_recursive_answers_only() {
+        let module = OpenResolver::new();
+        let ip = "127.0.0.1".parse().unwrap();
+
+        let open = fake_server(true).await;
+        let findings = module.probe("ns1.example.net", ip, open).await.unwrap();
+        assert_eq!(
+            summary(&findings),
+            vec![("OpenResolver", vec!["example.com A 93.184.215.14"])]
+        );
+        assert_eq!(findings[0].url, format!("dns://ns1.example.net:{}", open));
+
+        let refusing = fake_server(false).await;
+        assert!(
+            module
+                .probe("ns1.example.net", ip, refusing)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
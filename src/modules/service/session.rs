@@ -0,0 +1,149 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::time::Duration;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::TcpStream;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+use tokio_native_tls::TlsConnector;
+use tokio_native_tls::native_tls;
+
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A reply of the server, continuation lines included
+pub struct Reply {
+    pub code: u16,
+    pub lines: Vec<String>,
+}
+
+impl Reply {
+    pub fn is_positive(&self) -> bool {
+        (200..400).contains(&self.code)
+    }
+
+    /// Whether the lines following the first one start with `keyword`, as SMTP EHLO and FTP FEAT list extensions
+    pub fn advertises(&self, keyword: &str) -> bool {
+        self.lines.iter().skip(1).any(|line| {
+            line.split_whitespace()
+                .next()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case(keyword))
+        })
+    }
+}
+
+impl std::fmt::Display for Reply {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}", self.code, self.lines.join(" / "))
+    }
+}
+
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// A plaintext or TLS session with a server speaking SMTP-style `250-`/`250 ` replies
+pub struct Session {
+    stream: BufReader<Box<dyn Stream>>,
+}
+
+impl Session {
+    pub fn new(stream: Box<dyn Stream>) -> Self {
+        Session {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    /// Connect to a plaintext service
+    pub async fn connect(ip: IpAddr, port: u16) -> Result<Self> {
+        let tcp = timeout(TIMEOUT, TcpStream::connect((ip, port))).await??;
+        Ok(Session::new(Box::new(tcp)))
+    }
+
+    pub async fn read_reply(&mut self) -> Result<Reply> {
+        let mut lines = Vec::new();
+        let mut code = None;
+
+        loop {
+            let mut line = String::new();
+            if timeout(TIMEOUT, self.stream.read_line(&mut line)).await?? == 0 {
+                bail!("Connection closed by the server");
+            }
+            let line = line.trim_end();
+
+            let expected = match code {
+                Some(code) => code,
+                None => {
+                    let parsed: u16 = line
+                        .get(..3)
+                        .and_then(|code| code.parse().ok())
+                        .ok_or_else(|| anyhow!("Invalid reply: {}", line))?;
+                    code = Some(parsed);
+                    parsed
+                }
+            };
+
+            // `250-` continues and `250 ` ends the reply, FTP also allows lines without a code in between
+            let prefix = expected.to_string();
+            match line.strip_prefix(prefix.as_str()) {
+                Some(rest) if rest.starts_with('-') => lines.push(rest[1..].to_string()),
+                Some(rest) if rest.is_empty() || rest.starts_with(' ') => {
+                    lines.push(rest.trim_start().to_string());
+                    return Ok(Reply {
+                        code: expected,
+                        lines,
+                    });
+                }
+                _ => lines.push(line.trim().to_string()),
+            }
+        }
+    }
+
+    pub async fn command(&mut self, command: &str) -> Result<Reply> {
+        timeout(
+            TIMEOUT,
+            self.stream
+                .get_mut()
+                .write_all(format!("{}\r\n", command).as_bytes()),
+        )
+        .await??;
+        self.read_reply().await
+    }
+
+    /// Continue the session over TLS, once the server accepted to upgrade it
+    pub async fn upgrade(self, host: &str) -> Result<Session> {
+        let stream = tls_connect(host, self.stream.into_inner()).await?;
+        Ok(Session::new(Box::new(stream)))
+    }
+}
+
+/// A UDP socket sending to and receiving from `ip:port` only
+pub async fn udp_socket(ip: IpAddr, port: u16) -> Result<UdpSocket> {
+    let local: IpAddr = match ip {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((local, 0)).await?;
+    socket.connect((ip, port)).await?;
+    Ok(socket)
+}
+
+/// Certificates are not verified, services rarely present one matching the probed name
+pub async fn tls_connect<S: AsyncRead + AsyncWrite + Unpin>(
+    host: &str,
+    stream: S,
+) -> Result<tokio_native_tls::TlsStream<S>> {
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()?;
+
+    Ok(timeout(TIMEOUT, TlsConnector::from(connector).connect(host, stream)).await??)
+}
@@ -0,0 +1,298 @@
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::ServiceModule;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use crate::modules::service::no_start_tls;
+use crate::modules::service::session::Session;
+use crate::modules::service::session::Stream;
+use crate::modules::service::session::TIMEOUT;
+use crate::modules::service::session::tls_connect;
+use anyhow::Result;
+use anyhow::bail;
+use std::net::IpAddr;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+// SMTP, SMTPS and submission
+const PORTS: &[u16] = &[25, 465, 587];
+const IMPLICIT_TLS_PORT: u16 = 465;
+const EHLO_NAME: &str = "vulnscan.invalid";
+// Reserved domains, so an accepted recipient never receives anything even if a message slipped through
+const RELAY_SENDER: &str = "vulnscan@example.net";
+const RELAY_RECIPIENT: &str = "vulnscan@example.org";
+
+pub struct Smtp;
+
+impl Smtp {
+    pub fn new() -> Self {
+        Smtp
+    }
+}
+
+impl Module for Smtp {
+    fn name(&self) -> String {
+        String::from("service/smtp")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-service-smtp")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://www.rfc-editor.org/rfc/rfc3207",
+            "https://www.rfc-editor.org/rfc/rfc5321#section-7.3",
+            "https://www.rfc-editor.org/rfc/rfc5321#section-7.9",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "service/smtp",
+        tags: &[Tag::Exposure, Tag::Misconfig],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Service(|_| Box::new(Smtp::new())),
+    }
+}
+
+#[async_trait]
+impl ServiceModule for Smtp {
+    fn ports(&self) -> &'static [u16] {
+        PORTS
+    }
+
+    async fn probe(&self, host: &str, ip: IpAddr, port: u16) -> Result<Vec<Finding>> {
+        let url = format!("smtp://{}:{}", host, port);
+        let tcp = timeout(TIMEOUT, TcpStream::connect((ip, port))).await??;
+
+        let tls = port == IMPLICIT_TLS_PORT;
+        let stream: Box<dyn Stream> = if tls {
+            Box::new(tls_connect(host, tcp).await?)
+        } else {
+            Box::new(tcp)
+        };
+
+        let mut session = Session::new(stream);
+        let greeting = session.read_reply().await?;
+        if greeting.code != 220 {
+            bail!("{} refused the session: {}", url, greeting);
+        }
+
+        let ehlo_command = format!("EHLO {}", EHLO_NAME);
+        let mut ehlo = session.command(&ehlo_command).await?;
+        if !ehlo.is_positive() {
+            bail!("{} rejected EHLO: {}", url, ehlo);
+        }
+
+        let mut findings = Vec::new();
+
+        if !tls {
+            let reason = if !ehlo.advertises("STARTTLS") {
+                Some(String::from("STARTTLS is not advertised"))
+            } else {
+                let reply = session.command("STARTTLS").await?;
+                if reply.code != 220 {
+                    Some(format!("STARTTLS was refused: {}", reply))
+                } else {
+                    match session.upgrade(host).await {
+                        Ok(upgraded) => {
+                            session = upgraded;
+                            ehlo = session.command(&ehlo_command).await?;
+                            None
+                        }
+                        // The session is unusable past a failed handshake
+                        Err(e) => {
+                            findings.push(no_start_tls(
+                                self.name(),
+                                &url,
+                                format!("STARTTLS handshake failed: {}", e),
+                            ));
+                            return Ok(findings);
+                        }
+                    }
+                }
+            };
+
+            if let Some(reason) = reason {
+                findings.push(no_start_tls(self.name(), &url, reason));
+            }
+        }
+
+        let mut evidence: Vec<String> = ["VRFY", "EXPN"]
+            .into_iter()
+            .filter(|command| ehlo.advertises(command))
+            .map(|command| format!("{} is advertised", command))
+            .collect();
+
+        // 252 is the RFC 5321 way of declining to verify
+        let vrfy = session.command("VRFY postmaster").await?;
+        if vrfy.code == 250 || vrfy.code == 251 {
+            evidence.push(format!("VRFY postmaster: {}", vrfy));
+        }
+
+        if !evidence.is_empty() {
+            findings.push(
+                Finding::new(self.name(), "UserEnumeration", Severity::Low, &url)
+                    .with_evidence(evidence),
+            );
+        }
+
+        // Accepting the recipient is enough, the transaction is reset before DATA
+        let mail = session
+            .command(&format!("MAIL FROM:<{}>", RELAY_SENDER))
+            .await?;
+        if mail.is_positive() {
+            let rcpt = session
+                .command(&format!("RCPT TO:<{}>", RELAY_RECIPIENT))
+                .await?;
+            if rcpt.code == 250 || rcpt.code == 251 {
+                findings.push(
+                    Finding::new(self.name(), "OpenRelay", Severity::High, &url).with_evidence(
+                        vec![format!(
+                            "RCPT TO:<{}> from <{}>: {}",
+                            RELAY_RECIPIENT, RELAY_SENDER, rcpt
+                        )],
+                    ),
+                );
+            }
+        }
+
+        let _ = session.command("RSET").await;
+        let _ = session.command("QUIT").await;
+
+        log::debug!("{}: Probed {}", self.name(), url);
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::service::tests::summary;
+    use tokio::io::AsyncBufReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::io::BufReader;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    /// Serve a scripted SMTP session, returning the commands received
+    async fn fake_server(
+        extensions: &'static [&'static str],
+        reply: fn(&str) -> &'static str,
+    ) -> (u16, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            let mut commands = Vec::new();
+
+            write
+                .write_all(b"220 mail.example.com ESMTP\r\n")
+                .await
+                .unwrap();
+
+            while let Ok(Some(command)) = lines.next_line().await {
+                let response = if command.starts_with("EHLO") {
+                    std::iter::once("mail.example.com")
+                        .chain(extensions.iter().copied())
+                        .enumerate()
+                        .map(|(i, line)| {
+                            let separator = if i == extensions.len() { ' ' } else { '-' };
+                            format!("250{}{}\r\n", separator, line)
+                        })
+                        .collect()
+                } else {
+                    format!("{}\r\n", reply(&command))
+                };
+                write.write_all(response.as_bytes()).await.unwrap();
+                commands.push(command.clone());
+
+                // Nothing resembling a TLS handshake follows STARTTLS
+                if command == "QUIT" || command == "STARTTLS" {
+                    break;
+                }
+            }
+
+            commands
+        });
+
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn test_probe_should_report_weak_configuration_without_sending_mail() {
+        let (port, server) =
+            fake_server(
+                &["SIZE 10240000", "VRFY", "8BITMIME"],
+                |command| match command {
+                    "VRFY postmaster" => "250 Postmaster <postmaster@example.com>",
+                    "QUIT" => "221 Bye",
+                    _ => "250 OK",
+                },
+            )
+            .await;
+
+        let findings = Smtp::new()
+            .probe("mail.example.com", "127.0.0.1".parse().unwrap(), port)
+            .await
+            .unwrap();
+        let commands = server.await.unwrap();
+
+        let url = format!("smtp://mail.example.com:{}", port);
+        assert!(findings.iter().all(|finding| finding.url == url));
+        assert_eq!(
+            summary(&findings),
+            vec![
+                ("NoStartTls", vec!["STARTTLS is not advertised"]),
+                (
+                    "UserEnumeration",
+                    vec![
+                        "VRFY is advertised",
+                        "VRFY postmaster: 250 Postmaster <postmaster@example.com>"
+                    ]
+                ),
+                (
+                    "OpenRelay",
+                    vec!["RCPT TO:<vulnscan@example.org> from <vulnscan@example.net>: 250 OK"]
+                ),
+            ]
+        );
+        assert!(commands.contains(&String::from("RSET")));
+        assert!(!commands.iter().any(|command| command == "DATA"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_should_report_failed_starttls_handshake() {
+        let (port, server) = fake_server(&["STARTTLS"], |command| match command {
+            "STARTTLS" => "220 Ready to start TLS",
+            _ => "502 Not implemented",
+        })
+        .await;
+
+        let findings = Smtp::new()
+            .probe("mail.example.com", "127.0.0.1".parse().unwrap(), port)
+            .await
+            .unwrap();
+        server.await.unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, "NoStartTls");
+        assert!(findings[0].evidence[0].starts_with("STARTTLS handshake failed"));
+    }
+}
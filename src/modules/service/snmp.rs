@@ -0,0 +1,358 @@
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::ServiceModule;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use crate::modules::service::session::udp_socket;
+use anyhow::Result;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio::time::timeout_at;
+
+const PORTS: &[u16] = &[161];
+// Agents silently drop requests with a wrong community, so every attempt is sent at once
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(3);
+pub(super) const COMMUNITIES: &[&str] = &["public", "private"];
+// (version field, name)
+const VERSIONS: &[(u8, &str)] = &[(1, "SNMPv2c"), (0, "SNMPv1")];
+// 1.3.6.1.2.1.1.1.0
+const SYS_DESCR_OID: &[u8] = &[0x2b, 6, 1, 2, 1, 1, 1, 0];
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_GET_REQUEST: u8 = 0xa0;
+const TAG_GET_RESPONSE: u8 = 0xa2;
+
+pub struct Snmp;
+
+impl Snmp {
+    pub fn new() -> Self {
+        Snmp
+    }
+}
+
+impl Module for Snmp {
+    fn name(&self) -> String {
+        String::from("service/snmp")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-service-snmp")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec!["https://www.cisa.gov/news-events/alerts/2017/06/05/reducing-risk-snmp-abuse"]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "service/snmp",
+        tags: &[Tag::Exposure, Tag::Misconfig],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Service(|_| Box::new(Snmp::new())),
+    }
+}
+
+#[async_trait]
+impl ServiceModule for Snmp {
+    fn ports(&self) -> &'static [u16] {
+        PORTS
+    }
+
+    fn udp(&self) -> bool {
+        true
+    }
+
+    async fn probe(&self, host: &str, ip: IpAddr, port: u16) -> Result<Vec<Finding>> {
+        let url = format!("snmp://{}:{}", host, port);
+        let socket = udp_socket(ip, port).await?;
+
+        // The request ID of an attempt is its index, plus one
+        let attempts: Vec<(&str, u8, &str)> = COMMUNITIES
+            .iter()
+            .flat_map(|community| {
+                VERSIONS
+                    .iter()
+                    .map(move |(version, name)| (*community, *version, *name))
+            })
+            .collect();
+
+        for (i, (community, version, _)) in attempts.iter().enumerate() {
+            socket
+                .send(&get_request(*version, community, i as u8 + 1))
+                .await?;
+        }
+
+        let deadline = Instant::now() + RESPONSE_TIMEOUT;
+        let mut answered: Vec<Option<String>> = vec![None; attempts.len()];
+        let mut buf = vec![0; 65535];
+
+        while answered.iter().any(Option::is_none) {
+            let Ok(received) = timeout_at(deadline, socket.recv(&mut buf)).await else {
+                break;
+            };
+            let Some((request_id, sys_descr)) = parse_response(&buf[..received?]) else {
+                continue;
+            };
+            if let Some(slot) = (request_id as usize)
+                .checked_sub(1)
+                .and_then(|i| answered.get_mut(i))
+            {
+                *slot = Some(sys_descr);
+            }
+        }
+
+        // One finding per community, under the first version it worked with
+        let mut findings: Vec<Finding> = Vec::new();
+        for ((community, _, version), sys_descr) in attempts.iter().zip(answered) {
+            let Some(sys_descr) = sys_descr else {
+                continue;
+            };
+            let reported = findings
+                .iter()
+                .any(|finding| finding.metadata_str("community") == Some(community));
+            if !reported {
+                // `private` is the default read-write community
+                let severity = if *community == "private" {
+                    Severity::High
+                } else {
+                    Severity::Medium
+                };
+                findings.push(
+                    Finding::new(self.name(), "SnmpDefaultCommunity", severity, &url)
+                        .with_evidence(vec![
+                            format!("Community {:?} ({})", community, version),
+                            format!("sysDescr: {}", sys_descr),
+                        ])
+                        .with_metadata("community", community)
+                        .with_metadata("version", version),
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+/// BER encoding of a tag, its length and its content
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    if content.len() < 0x80 {
+        encoded.push(content.len() as u8);
+    } else {
+        let length = (content.len() as u32).to_be_bytes();
+        let skip = length.iter().take_while(|byte| **byte == 0).count();
+        encoded.push(0x80 | (4 - skip) as u8);
+        encoded.extend(&length[skip..]);
+    }
+    encoded.extend(content);
+    encoded
+}
+
+/// A GetRequest of sysDescr.0
+fn get_request(version: u8, community: &str, request_id: u8) -> Vec<u8> {
+    let varbind = tlv(
+        TAG_SEQUENCE,
+        &[tlv(TAG_OID, SYS_DESCR_OID), tlv(TAG_NULL, &[])].concat(),
+    );
+    let pdu = tlv(
+        TAG_GET_REQUEST,
+        &[
+            tlv(TAG_INTEGER, &[request_id]),
+            // Error status and index
+            tlv(TAG_INTEGER, &[0]),
+            tlv(TAG_INTEGER, &[0]),
+            tlv(TAG_SEQUENCE, &varbind),
+        ]
+        .concat(),
+    );
+
+    tlv(
+        TAG_SEQUENCE,
+        &[
+            tlv(TAG_INTEGER, &[version]),
+            tlv(TAG_OCTET_STRING, community.as_bytes()),
+            pdu,
+        ]
+        .concat(),
+    )
+}
+
+/// Split the first BER element of `bytes` into its tag, its content and what follows
+fn read_tlv(bytes: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *bytes.first()?;
+    let first = *bytes.get(1)? as usize;
+
+    let (length, header) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let count = first & 0x7f;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let length = bytes
+            .get(2..2 + count)?
+            .iter()
+            .fold(0, |length, byte| length << 8 | *byte as usize);
+        (length, 2 + count)
+    };
+
+    let content = bytes.get(header..header + length)?;
+    Some((tag, content, &bytes[header + length..]))
+}
+
+/// The request ID and sysDescr of a GetResponse, sysDescr being empty when the agent hides it
+fn parse_response(bytes: &[u8]) -> Option<(u32, String)> {
+    let (TAG_SEQUENCE, message, _) = read_tlv(bytes)? else {
+        return None;
+    };
+    // Version and community
+    let (TAG_INTEGER, _, rest) = read_tlv(message)? else {
+        return None;
+    };
+    let (TAG_OCTET_STRING, _, rest) = read_tlv(rest)? else {
+        return None;
+    };
+    let (TAG_GET_RESPONSE, pdu, _) = read_tlv(rest)? else {
+        return None;
+    };
+
+    let (TAG_INTEGER, request_id, rest) = read_tlv(pdu)? else {
+        return None;
+    };
+    let request_id = request_id
+        .iter()
+        .fold(0u32, |id, byte| id << 8 | *byte as u32);
+
+    // Error status and index, then the variable bindings
+    let (TAG_INTEGER, _, rest) = read_tlv(rest)? else {
+        return None;
+    };
+    let (TAG_INTEGER, _, rest) = read_tlv(rest)? else {
+        return None;
+    };
+    let (TAG_SEQUENCE, varbinds, _) = read_tlv(rest)? else {
+        return None;
+    };
+
+    let sys_descr = read_tlv(varbinds)
+        .and_then(|(_, varbind, _)| read_tlv(varbind))
+        .and_then(|(_, _, value)| read_tlv(value))
+        .filter(|(tag, _, _)| *tag == TAG_OCTET_STRING)
+        .map(|(_, value, _)| String::from_utf8_lossy(value).trim().to_string())
+        .unwrap_or_default();
+
+    Some((request_id, sys_descr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::service::tests::summary;
+    use tokio::net::UdpSocket;
+
+    /// A GetResponse mirroring `request` with `sys_descr`
+    fn get_response(request: &[u8], sys_descr: &str) -> Vec<u8> {
+        let (_, message, _) = read_tlv(request).unwrap();
+        let (_, version, rest) = read_tlv(message).unwrap();
+        let (_, community, rest) = read_tlv(rest).unwrap();
+        let (_, pdu, _) = read_tlv(rest).unwrap();
+        let (_, request_id, _) = read_tlv(pdu).unwrap();
+
+        let varbind = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_OID, SYS_DESCR_OID),
+                tlv(TAG_OCTET_STRING, sys_descr.as_bytes()),
+            ]
+            .concat(),
+        );
+        let pdu = tlv(
+            TAG_GET_RESPONSE,
+            &[
+                tlv(TAG_INTEGER, request_id),
+                tlv(TAG_INTEGER, &[0]),
+                tlv(TAG_INTEGER, &[0]),
+                tlv(TAG_SEQUENCE, &varbind),
+            ]
+            .concat(),
+        );
+
+        tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_INTEGER, version),
+                tlv(TAG_OCTET_STRING, community),
+                pdu,
+            ]
+            .concat(),
+        )
+    }
+
+    #[test]
+    fn test_parse_response_should_read_long_sys_descr() {
+        let sys_descr = "Cisco IOS Software ".repeat(10);
+        let response = get_response(&get_request(1, "public", 3), &sys_descr);
+
+        assert_eq!(
+            parse_response(&response),
+            Some((3, sys_descr.trim().to_string()))
+        );
+        assert_eq!(parse_response(&get_request(1, "public", 3)), None);
+    }
+
+    #[tokio::test]
+    async fn test_probe_should_report_working_default_community() {
+        let agent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = agent.local_addr().unwrap().port();
+
+        // Only `public` is accepted, under both versions
+        tokio::spawn(async move {
+            let mut buf = vec![0; 1500];
+            loop {
+                let (received, peer) = agent.recv_from(&mut buf).await.unwrap();
+                let request = &buf[..received];
+                if request.windows(6).any(|window| window == b"public") {
+                    let response = get_response(request, "Linux router 5.15.0 armv7l");
+                    agent.send_to(&response, peer).await.unwrap();
+                }
+            }
+        });
+
+        let findings = Snmp::new()
+            .probe("router.example.com", "127.0.0.1".parse().unwrap(), port)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            summary(&findings),
+            vec![(
+                "SnmpDefaultCommunity",
+                vec![
+                    "Community \"public\" (SNMPv2c)",
+                    "sysDescr: Linux router 5.15.0 armv7l"
+                ]
+            )]
+        );
+        assert_eq!(
+            findings[0].url,
+            format!("snmp://router.example.com:{}", port)
+        );
+        assert_eq!(findings[0].severity, Severity::Medium);
+    }
+}
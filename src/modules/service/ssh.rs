@@ -0,0 +1,423 @@
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::ServiceModule;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use crate::modules::service::session::TIMEOUT;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+const PORTS: &[u16] = &[22];
+const CLIENT_BANNER: &str = "SSH-2.0-vulnscan";
+// Servers may send other lines before their banner (RFC 4253 section 4.2)
+const MAX_PRE_BANNER_LINES: usize = 20;
+// Largest packet implementations must accept (RFC 4253 section 6.1)
+const MAX_PACKET_LENGTH: usize = 35000;
+const SSH_MSG_KEXINIT: u8 = 20;
+// Authentication methods are asked for this user, which needs not exist
+const PROBE_USER: &str = "vulnscan";
+
+// Deprecated by RFC 9142, RFC 8758 and OpenSSH
+const WEAK_KEX: &[&str] = &[
+    "diffie-hellman-group1-sha1",
+    "diffie-hellman-group14-sha1",
+    "diffie-hellman-group-exchange-sha1",
+    "rsa1024-sha1",
+];
+const WEAK_HOST_KEYS: &[&str] = &["ssh-dss", "ssh-rsa"];
+const WEAK_CIPHERS: &[&str] = &[
+    "none",
+    "des-cbc",
+    "3des-cbc",
+    "blowfish-cbc",
+    "cast128-cbc",
+    "arcfour",
+    "arcfour128",
+    "arcfour256",
+    "aes128-cbc",
+    "aes192-cbc",
+    "aes256-cbc",
+    "rijndael-cbc@lysator.liu.se",
+];
+const WEAK_MACS: &[&str] = &[
+    "none",
+    "hmac-md5",
+    "hmac-md5-96",
+    "hmac-sha1-96",
+    "hmac-md5-etm@openssh.com",
+    "hmac-md5-96-etm@openssh.com",
+    "hmac-sha1-96-etm@openssh.com",
+];
+
+pub struct Ssh;
+
+impl Ssh {
+    pub fn new() -> Self {
+        Ssh
+    }
+}
+
+/// Algorithms offered by the server, both directions merged
+#[derive(Debug, Default, PartialEq, Eq)]
+struct KexInit {
+    kex: Vec<String>,
+    host_keys: Vec<String>,
+    ciphers: Vec<String>,
+    macs: Vec<String>,
+}
+
+impl KexInit {
+    /// `kind: algorithm` of every deprecated algorithm offered
+    fn weak_algorithms(&self) -> Vec<String> {
+        [
+            ("kex", &self.kex, WEAK_KEX),
+            ("host key", &self.host_keys, WEAK_HOST_KEYS),
+            ("cipher", &self.ciphers, WEAK_CIPHERS),
+            ("mac", &self.macs, WEAK_MACS),
+        ]
+        .into_iter()
+        .flat_map(|(kind, offered, weak)| {
+            offered
+                .iter()
+                .filter(|algorithm| weak.contains(&algorithm.as_str()))
+                .map(move |algorithm| format!("{}: {}", kind, algorithm))
+        })
+        .collect()
+    }
+}
+
+impl Module for Ssh {
+    fn name(&self) -> String {
+        String::from("service/ssh")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-service-ssh")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://www.rfc-editor.org/rfc/rfc9142",
+            "https://www.rfc-editor.org/rfc/rfc8758",
+            "https://www.openssh.com/txt/release-8.8",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "service/ssh",
+        tags: &[Tag::Misconfig],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Service(|_| Box::new(Ssh::new())),
+    }
+}
+
+#[async_trait]
+impl ServiceModule for Ssh {
+    fn ports(&self) -> &'static [u16] {
+        PORTS
+    }
+
+    async fn probe(&self, host: &str, ip: IpAddr, port: u16) -> Result<Vec<Finding>> {
+        let url = format!("ssh://{}:{}", host, port);
+        let tcp = timeout(TIMEOUT, TcpStream::connect((ip, port))).await??;
+        // Servers may send their KEXINIT along with the banner, both are read through the same buffer
+        let mut stream = BufReader::new(tcp);
+        let mut findings = Vec::new();
+
+        let banner = read_banner(&mut stream).await?;
+        // `SSH-1.99` servers speak both versions
+        if banner.starts_with("SSH-1.") {
+            findings.push(
+                Finding::new(self.name(), "SshLegacyProtocol", Severity::High, &url)
+                    .with_evidence(vec![banner.clone()]),
+            );
+        }
+        // SSH-1 only servers send no KEXINIT and close on our SSH-2 banner
+        if !banner.starts_with("SSH-1.99-") && !banner.starts_with("SSH-2.0-") {
+            return Ok(findings);
+        }
+
+        stream
+            .get_mut()
+            .write_all(format!("{}\r\n", CLIENT_BANNER).as_bytes())
+            .await?;
+        let kexinit = parse_kexinit(&read_packet(&mut stream).await?)?;
+        drop(stream);
+
+        let algorithms = kexinit.weak_algorithms();
+        if !algorithms.is_empty() {
+            findings.push(
+                Finding::new(self.name(), "SshWeakAlgorithms", Severity::Medium, &url)
+                    .with_evidence(
+                        std::iter::once(banner.clone())
+                            .chain(algorithms.iter().cloned())
+                            .collect(),
+                    )
+                    .with_metadata("algorithms", &algorithms),
+            );
+        }
+
+        // libssh2 completes the key exchange the methods are asked under
+        let addr = SocketAddr::new(ip, port);
+        match tokio::task::spawn_blocking(move || auth_methods(addr)).await? {
+            Ok(methods) => {
+                let password = methods
+                    .iter()
+                    .any(|method| method == "password" || method == "keyboard-interactive");
+                if password && !methods.iter().any(|method| method == "publickey") {
+                    findings.push(
+                        Finding::new(self.name(), "SshPasswordOnly", Severity::Low, &url)
+                            .with_evidence(vec![format!("Methods: {}", methods.join(", "))])
+                            .with_metadata("methods", &methods),
+                    );
+                }
+            }
+            Err(e) => log::debug!(
+                "{}: Failed to list the methods of {}: {}",
+                self.name(),
+                url,
+                e
+            ),
+        }
+
+        Ok(findings)
+    }
+}
+
+/// The identification string of the server, e.g. `SSH-2.0-OpenSSH_9.6`
+async fn read_banner(stream: &mut BufReader<TcpStream>) -> Result<String> {
+    for _ in 0..MAX_PRE_BANNER_LINES {
+        let mut line = String::new();
+        if timeout(TIMEOUT, stream.read_line(&mut line)).await?? == 0 {
+            bail!("Connection closed before the SSH banner");
+        }
+        if line.starts_with("SSH-") {
+            return Ok(line.trim_end().to_string());
+        }
+    }
+
+    bail!("No SSH banner in the first {} lines", MAX_PRE_BANNER_LINES)
+}
+
+/// The payload of the next unencrypted binary packet
+async fn read_packet(stream: &mut BufReader<TcpStream>) -> Result<Vec<u8>> {
+    let length = timeout(TIMEOUT, stream.read_u32()).await?? as usize;
+    if !(2..=MAX_PACKET_LENGTH).contains(&length) {
+        bail!("Invalid SSH packet length: {}", length);
+    }
+
+    let mut packet = vec![0; length];
+    timeout(TIMEOUT, stream.read_exact(&mut packet)).await??;
+
+    let padding = packet[0] as usize;
+    let end = length
+        .checked_sub(padding)
+        .filter(|end| *end > 1)
+        .ok_or_else(|| anyhow!("Invalid SSH padding length: {}", padding))?;
+
+    Ok(packet[1..end].to_vec())
+}
+
+/// Parse a `SSH_MSG_KEXINIT` payload (RFC 4253 section 7.1)
+fn parse_kexinit(payload: &[u8]) -> Result<KexInit> {
+    if payload.first() != Some(&SSH_MSG_KEXINIT) {
+        bail!("Expected KEXINIT, got message {:?}", payload.first());
+    }
+
+    // Message type and 16 bytes of cookie
+    let mut rest = payload
+        .get(17..)
+        .ok_or_else(|| anyhow!("Truncated KEXINIT"))?;
+    let mut lists = Vec::new();
+
+    // kex, host keys, then client to server and server to client ciphers and MACs
+    for _ in 0..6 {
+        let length = rest
+            .get(..4)
+            .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+            .ok_or_else(|| anyhow!("Truncated KEXINIT"))?;
+        let list = rest
+            .get(4..4 + length)
+            .ok_or_else(|| anyhow!("Truncated KEXINIT"))?;
+        lists.push(
+            String::from_utf8_lossy(list)
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>(),
+        );
+        rest = &rest[4 + length..];
+    }
+
+    let merge = |a: &[String], b: &[String]| {
+        let mut merged = a.to_vec();
+        merged.extend(b.iter().filter(|name| !a.contains(name)).cloned());
+        merged
+    };
+
+    Ok(KexInit {
+        kex: lists[0].clone(),
+        host_keys: lists[1].clone(),
+        ciphers: merge(&lists[2], &lists[3]),
+        macs: merge(&lists[4], &lists[5]),
+    })
+}
+
+/// Authentication methods offered after the `none` request
+fn auth_methods(addr: SocketAddr) -> Result<Vec<String>> {
+    let tcp = std::net::TcpStream::connect_timeout(&addr, TIMEOUT)?;
+    let mut session = ssh2::Session::new()?;
+    session.set_timeout(TIMEOUT.as_millis() as u32);
+    session.set_tcp_stream(tcp);
+    session.handshake()?;
+
+    let methods = session.auth_methods(PROBE_USER)?;
+    Ok(methods.split(',').map(str::to_string).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::service::tests::summary;
+    use tokio::net::TcpListener;
+
+    fn name_list(names: &str) -> Vec<u8> {
+        let mut bytes = (names.len() as u32).to_be_bytes().to_vec();
+        bytes.extend(names.as_bytes());
+        bytes
+    }
+
+    fn kexinit_payload() -> Vec<u8> {
+        let mut payload = vec![SSH_MSG_KEXINIT];
+        payload.extend([0; 16]);
+        for names in [
+            "curve25519-sha256,diffie-hellman-group1-sha1",
+            "ssh-ed25519,ssh-rsa",
+            "aes128-ctr,3des-cbc",
+            "aes128-ctr,arcfour",
+            "hmac-sha2-256,hmac-md5",
+            "hmac-sha2-256",
+            "none",
+            "none",
+            "",
+            "",
+        ] {
+            payload.extend(name_list(names));
+        }
+        // first_kex_packet_follows and reserved
+        payload.extend([0, 0, 0, 0, 0]);
+        payload
+    }
+
+    fn kexinit_packet() -> Vec<u8> {
+        let payload = kexinit_payload();
+        let padding = 8 - (payload.len() + 5) % 8 + 8;
+        let mut packet = ((payload.len() + padding + 1) as u32)
+            .to_be_bytes()
+            .to_vec();
+        packet.push(padding as u8);
+        packet.extend(payload);
+        packet.extend(vec![0; padding]);
+        packet
+    }
+
+    #[test]
+    fn test_weak_algorithms_should_cover_both_directions() {
+        let kexinit = parse_kexinit(&kexinit_payload()).unwrap();
+
+        assert_eq!(
+            kexinit.weak_algorithms(),
+            vec![
+                "kex: diffie-hellman-group1-sha1",
+                "host key: ssh-rsa",
+                "cipher: 3des-cbc",
+                "cipher: arcfour",
+                "mac: hmac-md5",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_should_report_legacy_protocol_and_weak_algorithms() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream
+                .write_all(b"Welcome\r\nSSH-1.99-OpenSSH_3.9p1\r\n")
+                .await
+                .unwrap();
+            stream.write_all(&kexinit_packet()).await.unwrap();
+            let mut banner = [0; CLIENT_BANNER.len()];
+            stream.read_exact(&mut banner).await.unwrap();
+            banner
+        });
+
+        let findings = Ssh::new()
+            .probe("ssh.example.com", "127.0.0.1".parse().unwrap(), port)
+            .await
+            .unwrap();
+
+        let url = format!("ssh://ssh.example.com:{}", port);
+        assert_eq!(server.await.unwrap(), CLIENT_BANNER.as_bytes());
+        assert!(findings.iter().all(|finding| finding.url == url));
+        assert_eq!(findings.len(), 2);
+        assert_eq!(
+            summary(&findings[..1]),
+            vec![("SshLegacyProtocol", vec!["SSH-1.99-OpenSSH_3.9p1"])]
+        );
+        assert_eq!(findings[1].kind, "SshWeakAlgorithms");
+        assert_eq!(
+            findings[1].metadata["algorithms"].as_array().unwrap().len(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_should_report_ssh1_only_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all(b"SSH-1.5-OpenSSH_1.2.3\n").await.unwrap();
+            stream
+                .write_all(b"Protocol major versions differ.\n")
+                .await
+                .unwrap();
+        });
+
+        let findings = Ssh::new()
+            .probe("ssh.example.com", "127.0.0.1".parse().unwrap(), port)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            summary(&findings),
+            vec![("SshLegacyProtocol", vec!["SSH-1.5-OpenSSH_1.2.3"])]
+        );
+        assert_eq!(findings[0].url, format!("ssh://ssh.example.com:{}", port));
+    }
+}
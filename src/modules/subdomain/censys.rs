@@ -0,0 +1,264 @@
+use crate::body;
+use crate::body::ReadBounded;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::SubdomainModule;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use anyhow::Result;
+use anyhow::bail;
+use futures::SinkExt;
+use futures::channel::mpsc::Sender;
+use reqwest::Client;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Name of the credentials in `[sources]` and the key store, given as `API_ID:SECRET`
+pub const SOURCE: &str = "censys";
+
+const PER_PAGE: usize = 100;
+// Each page costs a query of the monthly quota, 250 on free accounts
+const MAX_PAGES: usize = 10;
+
+pub struct Censys {
+    api_url: String,
+    // (API ID, secret)
+    credentials: Option<(String, String)>,
+}
+
+impl Censys {
+    pub fn new(credentials: Option<&str>) -> Self {
+        let credentials = credentials.and_then(|credentials| match credentials.split_once(':') {
+            Some((id, secret)) => Some((id.to_string(), secret.to_string())),
+            None => {
+                log::warn!(
+                    "Ignoring the {} credentials, expected API_ID:SECRET",
+                    SOURCE
+                );
+                None
+            }
+        });
+
+        Censys {
+            api_url: String::from("https://search.censys.io/api/v2/"),
+            credentials,
+        }
+    }
+}
+
+impl Module for Censys {
+    fn name(&self) -> String {
+        String::from("subdomain/censys")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-subdomain-censys")
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "subdomain/censys",
+        tags: &[Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Subdomain(|options| Box::new(Censys::new(options.api_keys.get(SOURCE)))),
+    }
+}
+
+#[async_trait]
+impl SubdomainModule for Censys {
+    async fn enumerate(&self, domain: &str, mut found: Sender<String>) -> Result<()> {
+        // Declare needed API response fields
+        #[derive(Debug, Deserialize)]
+        struct SearchResponse {
+            result: SearchResult,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct SearchResult {
+            #[serde(default)]
+            hits: Vec<Certificate>,
+            #[serde(default)]
+            links: Links,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Certificate {
+            // Subject alternative names and common names
+            #[serde(default)]
+            names: Vec<String>,
+        }
+
+        #[derive(Debug, Default, Deserialize)]
+        struct Links {
+            #[serde(default)]
+            next: String,
+        }
+
+        let Some((api_id, secret)) = &self.credentials else {
+            log::info!(
+                "{}: Skipped, no API key (set one with `vulnscan keys set {} API_ID:SECRET`)",
+                self.name(),
+                SOURCE
+            );
+            return Ok(());
+        };
+
+        let http_client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+        let query = format!("names: {}", domain);
+        let per_page = PER_PAGE.to_string();
+
+        let suffix = format!(".{}", domain);
+        let mut sent = 0;
+        let mut cursor = String::new();
+
+        for page in 0..MAX_PAGES {
+            let mut params = vec![("q", query.as_str()), ("per_page", per_page.as_str())];
+            if !cursor.is_empty() {
+                params.push(("cursor", cursor.as_str()));
+            }
+
+            let resp = http_client
+                .get(format!("{}certificates/search", self.api_url))
+                .basic_auth(api_id, Some(secret))
+                .query(&params)
+                .send()
+                .await?;
+
+            let resp: SearchResponse = match resp.status() {
+                status if status.is_success() => {
+                    resp.json_limited(body::MAX_API_RESPONSE_SIZE).await?
+                }
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                    bail!("Censys rejected the API credentials: {}", resp.status())
+                }
+                // Keep the pages already fetched
+                StatusCode::TOO_MANY_REQUESTS if page > 0 => {
+                    log::warn!("{}: Rate limited after {} pages", self.name(), page);
+                    break;
+                }
+                status => bail!("Unexpected status code from Censys: {}", status),
+            };
+
+            let names = resp.result.hits.into_iter().flat_map(|hit| hit.names);
+            for name in names {
+                let name = name.trim().to_lowercase();
+                if !name.contains('*') && name.ends_with(&suffix) {
+                    found.send(name).await?;
+                    sent += 1;
+                }
+            }
+
+            cursor = resp.result.links.next;
+            if cursor.is_empty() {
+                break;
+            }
+            if page + 1 == MAX_PAGES {
+                log::warn!(
+                    "{}: Stopped paging certificates after {} pages",
+                    self.name(),
+                    MAX_PAGES
+                );
+            }
+        }
+
+        log::info!("{}: Found {} names", self.name(), sent);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::subdomain::tests::collect;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_enumerate_should_follow_cursors_and_extract_names() {
+        // Set up mock Censys server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/api/v2/certificates/search")
+                    .query_param("q", "names: example.com")
+                    .query_param("cursor", "page2");
+                then.status(200).json_body(serde_json::json!({
+                    "result": {
+                        "hits": [{ "names": ["vpn.example.com", "example.net"] }],
+                        "links": { "next": "" }
+                    }
+                }));
+            })
+            .await;
+
+        let first_page = mock_server
+            .mock_async(|when, then| {
+                // "id:secret"
+                when.method(GET)
+                    .path("/api/v2/certificates/search")
+                    .header("Authorization", "Basic aWQ6c2VjcmV0");
+                then.status(200).json_body(serde_json::json!({
+                    "result": {
+                        "hits": [
+                            { "names": ["*.example.com", "WWW.example.com", "example.com"] },
+                            { "names": ["www.example.com", "mail.example.com"] }
+                        ],
+                        "links": { "next": "page2" }
+                    }
+                }));
+            })
+            .await;
+
+        let module = Censys {
+            api_url: format!(
+                "http://{}:{}/api/v2/",
+                mock_server.host(),
+                mock_server.port()
+            ),
+            ..Censys::new(Some("id:secret"))
+        };
+
+        let subdomains = collect(&module, "example.com").await.unwrap();
+
+        assert_eq!(
+            subdomains,
+            vec!["mail.example.com", "vpn.example.com", "www.example.com"]
+        );
+        first_page.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_enumerate_should_fail_on_rejected_credentials() {
+        // Set up mock Censys server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/api/v2/certificates/search");
+                then.status(401);
+            })
+            .await;
+
+        let module = Censys {
+            api_url: format!(
+                "http://{}:{}/api/v2/",
+                mock_server.host(),
+                mock_server.port()
+            ),
+            ..Censys::new(Some("id:wrong"))
+        };
+
+        assert!(collect(&module, "example.com").await.is_err());
+
+        // Keys without a secret are ignored, skipping the source
+        assert!(Censys::new(Some("id")).credentials.is_none());
+    }
+}
@@ -1,19 +1,131 @@
+use crate::body;
+use crate::body::ReadBounded;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Intrusiveness;
 use crate::modules::Module;
+use crate::modules::Registration;
 use crate::modules::SubdomainModule;
+use crate::modules::Tag;
 use crate::modules::async_trait;
 use anyhow::Result;
 use anyhow::anyhow;
 use anyhow::bail;
+#[cfg(feature = "crtsh-postgres")]
+use chrono::Datelike;
+#[cfg(feature = "crtsh-postgres")]
+use chrono::Utc;
+use futures::SinkExt;
+use futures::channel::mpsc::Sender;
 use reqwest::Client;
 use serde::Deserialize;
-use std::collections::HashSet;
 use std::time::Duration;
+#[cfg(feature = "crtsh-postgres")]
+use tokio_postgres::NoTls;
+#[cfg(feature = "crtsh-postgres")]
+use tokio_postgres::SimpleQueryMessage;
 
-pub struct CrtSh;
+pub struct CrtSh {
+    api_url: String,
+    // Query the public PostgreSQL replica when the JSON API keeps failing
+    postgres: bool,
+    // Delay before the first retry, doubled on each attempt
+    backoff: Duration,
+}
+
+const ATTEMPTS: u32 = 3;
+#[cfg(feature = "crtsh-postgres")]
+const POSTGRES_CONFIG: &str =
+    "host=crt.sh port=5432 user=guest dbname=certwatch connect_timeout=30";
+// Certificate Transparency logs took off in 2013
+#[cfg(feature = "crtsh-postgres")]
+const FIRST_YEAR: i32 = 2013;
 
 impl CrtSh {
-    pub fn new() -> Self {
-        CrtSh
+    pub fn new(postgres: bool) -> Self {
+        CrtSh {
+            api_url: String::from("https://crt.sh/"),
+            postgres,
+            backoff: Duration::from_secs(2),
+        }
+    }
+
+    /// Names of the certificates of `domain`, as complete as crt.sh lets them be listed
+    /// - The full history is asked to the JSON API first, then to the PostgreSQL replica one year at
+    ///   a time with `--crtsh-postgres`
+    /// - Only unexpired certificates, which crt.sh answers much faster, are asked last, logging
+    ///   that names of expired certificates are missing
+    async fn entries(&self, domain: &str) -> Result<Vec<String>> {
+        let error = match self.query_api(domain, "").await {
+            Ok(entries) => return Ok(entries),
+            Err(e) => e,
+        };
+
+        if self.postgres {
+            log::warn!(
+                "{}: JSON API failed ({}), falling back to PostgreSQL",
+                self.name(),
+                error
+            );
+            match query_postgres(domain).await {
+                Ok(entries) => return Ok(entries),
+                Err(e) => log::warn!("{}: PostgreSQL failed: {}", self.name(), e),
+            }
+        }
+
+        let entries = self.query_api(domain, "&exclude=expired").await?;
+        log::warn!(
+            "{}: Results for {} are partial, names only found in expired certificates are missing",
+            self.name(),
+            domain
+        );
+        Ok(entries)
+    }
+
+    /// Query the JSON API with `filter` appended, retrying with backoff
+    async fn query_api(&self, domain: &str, filter: &str) -> Result<Vec<String>> {
+        // Declare needed API response fields
+        #[derive(Debug, Deserialize)]
+        struct CrtShEntry {
+            name_value: String,
+        }
+
+        let http_client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+        let url = format!("{}?q=%25.{}&output=json{}", self.api_url, domain, filter);
+        let mut last_error = anyhow!("No crt.sh query was sent");
+
+        for attempt in 0..ATTEMPTS {
+            if attempt > 0 {
+                tokio::time::sleep(self.backoff * 2u32.pow(attempt - 1)).await;
+            }
+
+            let resp = match http_client.get(&url).send().await {
+                Ok(resp) => resp,
+                Err(e) => {
+                    last_error = anyhow!("crt.sh connection failed (likely timeout): {}", e);
+                    continue;
+                }
+            };
+
+            if !resp.status().is_success() {
+                last_error = anyhow!("Failed to get crt.sh entries: {}", resp.status());
+                continue;
+            }
+
+            // Parse CT log entries
+            match resp
+                .json_limited::<Vec<CrtShEntry>>(body::MAX_API_RESPONSE_SIZE)
+                .await
+            {
+                Ok(entries) => {
+                    return Ok(entries.into_iter().map(|entry| entry.name_value).collect());
+                }
+                Err(e) => last_error = anyhow!("Failed to parse crt.sh entries: {}", e),
+            }
+        }
+
+        log::warn!("{}: {} ({})", self.name(), last_error, url);
+        Err(last_error)
     }
 }
 
@@ -22,65 +134,169 @@ impl Module for CrtSh {
         String::from("subdomain/crtsh")
     }
 
-    fn description(&self) -> String {
-        String::from("Use crt.sh to enumerate subdomains")
+    fn description(&self) -> Message {
+        Message::new("module-subdomain-crtsh")
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "subdomain/crtsh",
+        tags: &[Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Subdomain(|options| Box::new(CrtSh::new(options.crtsh_postgres))),
     }
 }
 
 #[async_trait]
 impl SubdomainModule for CrtSh {
-    async fn enumerate(&self, domain: &str) -> Result<Vec<String>> {
-        // Declare needed API response fields
-        #[derive(Debug, Deserialize)]
-        struct CrtShEntry {
-            name_value: String,
+    async fn enumerate(&self, domain: &str, mut found: Sender<String>) -> Result<()> {
+        // Query crt.sh for Certificate Transparency (CT) log entries
+        let entries = self.entries(domain).await?;
+
+        // Entries list every name of a certificate, one per line
+        let mut sent = 0;
+        for name_value in entries {
+            for subdomain in name_value.split('\n') {
+                let subdomain = subdomain.trim().to_lowercase();
+
+                // Neither wildcards nor the parent domain are subdomains
+                if subdomain.contains('*') || subdomain == domain {
+                    continue;
+                }
+
+                found.send(subdomain).await?;
+                sent += 1;
+            }
         }
 
-        // Query crt.sh for Certificate Transparency (CT) log entries
-        let url = format!("https://crt.sh/?q=%25.{}&output=json", domain);
-        let http_client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .unwrap();
-
-        let resp = http_client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| anyhow!("crt.sh connection failed (likely timeout): {}", e))?;
-
-        if !resp.status().is_success() {
-            bail!("Failed to get crt.sh entries: {}", resp.status());
+        log::info!("{}: Found {} names", self.name(), sent);
+
+        Ok(())
+    }
+}
+
+/// Query the public crt.sh PostgreSQL replica, one certificate issuance year at a time
+/// - A failing year is logged and skipped instead of losing the whole history
+#[cfg(feature = "crtsh-postgres")]
+async fn query_postgres(domain: &str) -> Result<Vec<String>> {
+    // The domain is inlined into the query, as the replica does not support prepared statements
+    if !domain
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    {
+        bail!("Refusing to query crt.sh PostgreSQL for {}", domain);
+    }
+
+    let (client, connection) = tokio_postgres::connect(POSTGRES_CONFIG, NoTls).await?;
+
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            log::debug!("crt.sh PostgreSQL connection closed: {}", e);
         }
+    });
 
-        // Parse CT log entries to get subdomains set
-        let entries: Vec<CrtShEntry> = match resp.json().await {
-            Ok(entries) => entries,
-            Err(e) => bail!("Failed to parse crt.sh entries: {}", e),
-        };
+    let mut names = Vec::new();
+
+    for year in FIRST_YEAR..=Utc::now().year() {
+        let query = format!(
+            "SELECT DISTINCT ci.NAME_VALUE FROM certificate_and_identities ci \
+             WHERE reverse(lower(ci.NAME_VALUE)) LIKE reverse(lower('%.{domain}')) \
+             AND x509_notBefore(ci.CERTIFICATE) >= '{year}-01-01' \
+             AND x509_notBefore(ci.CERTIFICATE) < '{next}-01-01'",
+            domain = domain,
+            year = year,
+            next = year + 1
+        );
+
+        match client.simple_query(&query).await {
+            Ok(messages) => {
+                names.extend(messages.into_iter().filter_map(|message| match message {
+                    SimpleQueryMessage::Row(row) => row.get(0).map(String::from),
+                    _ => None,
+                }))
+            }
+            Err(e) => log::warn!("crt.sh PostgreSQL query for {} failed: {}", year, e),
+        }
+    }
 
-        let mut subdomains: HashSet<String> = entries
-            .into_iter()
-            .flat_map(|entry| {
-                entry
-                    .name_value
-                    .split("\n")
-                    .map(|subdomain| subdomain.trim().to_lowercase())
-                    .collect::<Vec<String>>()
+    Ok(names)
+}
+
+/// Stands in for the replica query in builds without the `crtsh-postgres` feature, which refuse
+/// `--crtsh-postgres` before scanning
+#[cfg(not(feature = "crtsh-postgres"))]
+async fn query_postgres(_domain: &str) -> Result<Vec<String>> {
+    bail!("--crtsh-postgres needs vulnscan built with --features crtsh-postgres")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::subdomain::tests::collect;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_enumerate_should_fall_back_to_unexpired_certificates() {
+        // Set up mock crt.sh server: the full history query keeps failing
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).query_param("exclude", "expired");
+                then.status(200).json_body(
+                    serde_json::json!([{"name_value": "www.example.com\n*.example.com"}]),
+                );
             })
-            .filter(|subdomain| !subdomain.contains("*")) // Remove wildcard subdomains
-            .collect();
+            .await;
 
-        // Ensure the parent domain `domain` is not in subdomains (purify)
-        subdomains.remove(domain);
+        let full_history = mock_server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(502);
+            })
+            .await;
 
-        // Cast subdomains `HashSet` to `Vec<String>`
-        let mut subdomains: Vec<String> = subdomains.into_iter().collect();
+        // Set up input arguments
+        let module = CrtSh {
+            api_url: format!("http://{}:{}/", mock_server.host(), mock_server.port()),
+            postgres: false,
+            backoff: Duration::ZERO,
+        };
 
-        subdomains.sort_unstable();
+        // Run enumeration
+        let subdomains = collect(&module, "example.com").await.unwrap();
+
+        // Check result
+        assert_eq!(subdomains, vec!["www.example.com"]);
+        full_history.assert_calls_async(ATTEMPTS as usize).await;
+    }
+
+    #[tokio::test]
+    async fn test_enumerate_should_fail_after_retries() {
+        // Set up mock crt.sh server
+        let mock_server = MockServer::start_async().await;
+
+        let any_request = mock_server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(503);
+            })
+            .await;
+
+        // Set up input arguments
+        let module = CrtSh {
+            api_url: format!("http://{}:{}/", mock_server.host(), mock_server.port()),
+            postgres: false,
+            backoff: Duration::ZERO,
+        };
 
-        log::info!("{}: Found {} subdomains", self.name(), subdomains.len());
+        // Run enumeration
+        let result = collect(&module, "example.com").await;
 
-        Ok(subdomains)
+        // Check result
+        assert!(result.is_err(), "Should fail when every attempt fails");
+        any_request.assert_calls_async(2 * ATTEMPTS as usize).await;
     }
 }
@@ -1,5 +1,36 @@
+pub mod censys;
 mod crtsh;
+mod permutations;
+mod scrapers;
+pub mod securitytrails;
 mod webarchive;
 
-pub use crtsh::CrtSh;
-pub use webarchive::WebArchive;
+pub use webarchive::archived_exposures;
+pub use webarchive::archived_urls;
+pub use webarchive::interesting_paths;
+
+/// Built-in wordlists of the subdomain sources, by name
+pub fn wordlists() -> Vec<(&'static str, &'static [&'static str])> {
+    vec![("subdomain/permutations", permutations::WORDS)]
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::modules::SubdomainModule;
+    use anyhow::Result;
+    use futures::StreamExt;
+    use futures::channel::mpsc;
+    use futures::future;
+    use std::collections::BTreeSet;
+
+    /// The distinct names a source sent, sorted
+    pub async fn collect(module: &dyn SubdomainModule, domain: &str) -> Result<Vec<String>> {
+        let (sender, receiver) = mpsc::channel(16);
+        let (result, names) = future::join(
+            module.enumerate(domain, sender),
+            receiver.collect::<BTreeSet<String>>(),
+        )
+        .await;
+        result.map(|()| names.into_iter().collect())
+    }
+}
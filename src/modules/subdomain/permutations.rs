@@ -0,0 +1,233 @@
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::DerivedSubdomainModule;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Tag;
+use async_trait::async_trait;
+
+use anyhow::Result;
+use futures::StreamExt;
+use futures::future;
+use futures::stream;
+use hickory_resolver::TokioResolver;
+use hickory_resolver::config::ResolverConfig;
+use hickory_resolver::name_server::TokioConnectionProvider;
+use once_cell::sync::Lazy;
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+
+pub struct Permutations;
+
+const DNS_CONCURRENCY: usize = 100;
+// Candidates grow quickly with the number of known subdomains
+const MAX_CANDIDATES: usize = 10_000;
+
+// Words commonly prepended, appended or inserted into hostnames
+pub(super) const WORDS: &[&str] = &[
+    "dev",
+    "development",
+    "staging",
+    "stage",
+    "stg",
+    "test",
+    "qa",
+    "uat",
+    "preprod",
+    "prod",
+    "old",
+    "new",
+    "beta",
+    "demo",
+    "api",
+    "admin",
+    "internal",
+    "backup",
+    "v1",
+    "v2",
+];
+
+static NUMBER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\d+").expect("Failed to compile regex patterns"));
+
+impl Permutations {
+    pub fn new() -> Self {
+        Permutations
+    }
+}
+
+impl Module for Permutations {
+    fn name(&self) -> String {
+        String::from("subdomain/permutations")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-subdomain-permutations")
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "subdomain/permutations",
+        tags: &[Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::DerivedSubdomain(|_| Box::new(Permutations::new())),
+    }
+}
+
+#[async_trait]
+impl DerivedSubdomainModule for Permutations {
+    async fn derive(&self, domain: &str, known: &HashSet<&str>) -> Result<Vec<String>> {
+        let candidates = candidates(domain, known);
+
+        let resolver = TokioResolver::builder_with_config(
+            ResolverConfig::default(),
+            TokioConnectionProvider::default(),
+        )
+        .build();
+
+        // Skip parents answering every name, as all of their candidates would resolve
+        let parents: BTreeSet<String> = candidates.iter().map(|c| parent(c).to_string()).collect();
+
+        let wildcard_parents: HashSet<String> = stream::iter(parents)
+            .map(|parent| {
+                let probe = format!("{}.{}", random_label(), parent);
+                let lookup = resolver.lookup_ip(probe);
+                async move { lookup.await.is_ok().then_some(parent) }
+            })
+            .buffer_unordered(DNS_CONCURRENCY)
+            .filter_map(future::ready)
+            .collect()
+            .await;
+
+        let candidates: Vec<String> = candidates
+            .into_iter()
+            .filter(|candidate| !wildcard_parents.contains(parent(candidate)))
+            .collect();
+
+        let mut subdomains: Vec<String> = stream::iter(candidates)
+            .map(|candidate| {
+                let lookup = resolver.lookup_ip(candidate.clone());
+                async move { lookup.await.is_ok().then_some(candidate) }
+            })
+            .buffer_unordered(DNS_CONCURRENCY)
+            .filter_map(future::ready)
+            .collect()
+            .await;
+
+        subdomains.sort_unstable();
+
+        log::info!("{}: Found {} subdomains", self.name(), subdomains.len());
+
+        Ok(subdomains)
+    }
+}
+
+/// Generate altdns-style alterations of known subdomains of `domain`
+fn candidates(domain: &str, known: &HashSet<&str>) -> BTreeSet<String> {
+    let suffix = format!(".{}", domain);
+    let mut candidates = BTreeSet::new();
+
+    // Sorted for stable candidates, since generation stops at `MAX_CANDIDATES`
+    let mut subdomains: Vec<&str> = known.iter().copied().collect();
+    subdomains.sort_unstable();
+
+    for subdomain in subdomains {
+        // Bounded while generating, as large domains would yield millions of candidates
+        if candidates.len() >= MAX_CANDIDATES {
+            break;
+        }
+
+        let Some((label, parent)) = subdomain.split_once('.') else {
+            continue;
+        };
+
+        // Only alter names below the target
+        if !subdomain.ends_with(&suffix) {
+            continue;
+        }
+
+        for word in WORDS {
+            candidates.insert(format!("{}-{}.{}", word, label, parent));
+            candidates.insert(format!("{}-{}.{}", label, word, parent));
+            candidates.insert(format!("{}{}.{}", label, word, parent));
+            candidates.insert(format!("{}.{}.{}", word, label, parent));
+        }
+
+        // Numbered variants: api2 -> api1, api3; web -> web1, web2
+        if let Some(number) = NUMBER.find(label) {
+            let value: u64 = number.as_str().parse().unwrap_or(0);
+            for other in [value.saturating_sub(1), value + 1] {
+                candidates.insert(format!(
+                    "{}{}{}.{}",
+                    &label[..number.start()],
+                    other,
+                    &label[number.end()..],
+                    parent
+                ));
+            }
+        } else {
+            for n in 1..=3 {
+                candidates.insert(format!("{}{}.{}", label, n, parent));
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter(|candidate| !known.contains(candidate.as_str()))
+        .filter(|candidate| candidate.split('.').all(|label| label.len() <= 63))
+        .take(MAX_CANDIDATES)
+        .collect()
+}
+
+fn parent(subdomain: &str) -> &str {
+    subdomain
+        .split_once('.')
+        .map_or(subdomain, |(_, parent)| parent)
+}
+
+fn random_label() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(|c| (c as char).to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_should_alter_known_subdomains() {
+        let known: HashSet<&str> = ["api2.example.com", "www.example.com", "other.net"]
+            .into_iter()
+            .collect();
+
+        let candidates = candidates("example.com", &known);
+
+        for expected in [
+            "dev-api2.example.com",
+            "api2-old.example.com",
+            "staging.www.example.com",
+            "api1.example.com",
+            "api3.example.com",
+            "www1.example.com",
+        ] {
+            assert!(candidates.contains(expected), "Missing {}", expected);
+        }
+
+        assert!(!candidates.contains("api2.example.com"));
+        assert!(
+            !candidates
+                .iter()
+                .any(|candidate| candidate.ends_with("other.net"))
+        );
+    }
+}
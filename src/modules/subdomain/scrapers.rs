@@ -0,0 +1,316 @@
+use crate::body;
+use crate::body::ReadBounded;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::SubdomainModule;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use anyhow::Result;
+use anyhow::bail;
+use chrono::Days;
+use chrono::Utc;
+use futures::SinkExt;
+use futures::channel::mpsc::Sender;
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use reqwest::StatusCode;
+use reqwest::Url;
+use scraper::Html;
+use scraper::Selector;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+// Free aggregators are run on a shoestring, a request every few seconds is plenty
+const POLITE_INTERVAL: Duration = Duration::from_secs(3);
+const MAX_PAGES: usize = 10;
+// C99 publishes one scan per day at most, look back a week for the latest
+const C99_LOOKBACK_DAYS: u64 = 7;
+const USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/143.0.0.0 Safari/537.36";
+
+/// When each scraped site was last requested, shared by every domain of the scan
+static LAST_REQUEST: Lazy<Mutex<HashMap<String, Instant>>> = Lazy::new(Default::default);
+
+/// Fetch a page, waiting for `interval` since the previous request to the same site
+/// - `None` when the page does not exist
+async fn fetch_politely(
+    http_client: &Client,
+    url: &str,
+    interval: Duration,
+) -> Result<Option<String>> {
+    let host = Url::parse(url)?.host_str().unwrap_or_default().to_string();
+
+    {
+        // Held while sleeping, so concurrent callers queue up
+        let mut last_request = LAST_REQUEST.lock().await;
+        if let Some(last) = last_request.get(&host) {
+            tokio::time::sleep(interval.saturating_sub(last.elapsed())).await;
+        }
+        last_request.insert(host, Instant::now());
+    }
+
+    let resp = http_client.get(url).send().await?;
+
+    match resp.status() {
+        status if status.is_success() => {
+            Ok(Some(resp.text_limited(body::MAX_API_RESPONSE_SIZE).await?))
+        }
+        StatusCode::NOT_FOUND => Ok(None),
+        status => bail!("Unexpected status code from {}: {}", url, status),
+    }
+}
+
+/// Subdomains of `domain` anywhere in the text or links of a page
+/// - Nothing depends on the layout of the page, which scraped sites change at will
+fn extract_subdomains(page: &str, domain: &str) -> BTreeSet<String> {
+    let html = Html::parse_document(page);
+    let links = Selector::parse("a[href]").expect("Failed to parse selector");
+
+    let texts = html.root_element().text().map(str::to_string);
+    let hrefs = html
+        .select(&links)
+        .filter_map(|link| link.value().attr("href"))
+        .map(|href| {
+            Url::parse(href)
+                .or_else(|_| Url::parse(&format!("https:{}", href)))
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_default()
+        });
+
+    let suffix = format!(".{}", domain);
+
+    texts
+        .chain(hrefs)
+        .flat_map(|text| {
+            text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_'))
+                .map(|token| token.trim_matches('.').to_lowercase())
+                .filter(|token| token.ends_with(&suffix))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn http_client() -> Result<Client> {
+    Ok(Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(30))
+        .build()?)
+}
+
+pub struct RapidDns {
+    base_url: String,
+    interval: Duration,
+}
+
+impl RapidDns {
+    pub fn new() -> Self {
+        RapidDns {
+            base_url: String::from("https://rapiddns.io/"),
+            interval: POLITE_INTERVAL,
+        }
+    }
+}
+
+impl Module for RapidDns {
+    fn name(&self) -> String {
+        String::from("subdomain/rapiddns")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-subdomain-rapiddns")
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "subdomain/rapiddns",
+        tags: &[Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Subdomain(|_| Box::new(RapidDns::new())),
+    }
+}
+
+#[async_trait]
+impl SubdomainModule for RapidDns {
+    async fn enumerate(&self, domain: &str, mut found: Sender<String>) -> Result<()> {
+        let http_client = http_client()?;
+        let mut previous = BTreeSet::new();
+        let mut sent = 0;
+
+        // Pages past the last one repeat it or are empty
+        for page in 1..=MAX_PAGES {
+            let url = format!("{}subdomain/{}?full=1&page={}", self.base_url, domain, page);
+            let Some(page) = fetch_politely(&http_client, &url, self.interval).await? else {
+                break;
+            };
+
+            let subdomains = extract_subdomains(&page, domain);
+            if subdomains.is_empty() || subdomains == previous {
+                break;
+            }
+            for subdomain in &subdomains {
+                found.send(subdomain.clone()).await?;
+                sent += 1;
+            }
+            previous = subdomains;
+        }
+
+        log::info!("{}: Found {} names", self.name(), sent);
+
+        Ok(())
+    }
+}
+
+pub struct C99 {
+    base_url: String,
+    interval: Duration,
+}
+
+impl C99 {
+    pub fn new() -> Self {
+        C99 {
+            base_url: String::from("https://subdomainfinder.c99.nl/"),
+            interval: POLITE_INTERVAL,
+        }
+    }
+}
+
+impl Module for C99 {
+    fn name(&self) -> String {
+        String::from("subdomain/c99")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-subdomain-c99")
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "subdomain/c99",
+        tags: &[Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Subdomain(|_| Box::new(C99::new())),
+    }
+}
+
+#[async_trait]
+impl SubdomainModule for C99 {
+    async fn enumerate(&self, domain: &str, mut found: Sender<String>) -> Result<()> {
+        let http_client = http_client()?;
+        let today = Utc::now().date_naive();
+
+        // Scans are published under the day they ran
+        for days in 0..C99_LOOKBACK_DAYS {
+            let Some(date) = today.checked_sub_days(Days::new(days)) else {
+                break;
+            };
+            let url = format!(
+                "{}scans/{}/{}",
+                self.base_url,
+                date.format("%Y-%m-%d"),
+                domain
+            );
+
+            let Some(page) = fetch_politely(&http_client, &url, self.interval).await? else {
+                continue;
+            };
+
+            let subdomains = extract_subdomains(&page, domain);
+            if subdomains.is_empty() {
+                continue;
+            }
+
+            log::info!(
+                "{}: Found {} subdomains in the scan of {}",
+                self.name(),
+                subdomains.len(),
+                date
+            );
+            for subdomain in subdomains {
+                found.send(subdomain).await?;
+            }
+            return Ok(());
+        }
+
+        log::info!("{}: No scan of {} in the last week", self.name(), domain);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::subdomain::tests::collect;
+    use httpmock::prelude::*;
+
+    #[test]
+    fn test_extract_subdomains_should_ignore_markup() {
+        let page = r#"
+            <table><tr><td>1</td><td>WWW.example.com.</td><td>203.0.113.7</td></tr>
+            <tr><td><span>api.example.com</span></td><td>mail.example.com,cdn.example.com</td></tr></table>
+            <a class="link sd" href="//vpn.example.com">VPN</a>
+            <a href="https://notexample.com/">Other</a>
+            <p>Results for example.com by rapiddns.io</p>
+        "#;
+
+        assert_eq!(
+            extract_subdomains(page, "example.com"),
+            BTreeSet::from([
+                String::from("api.example.com"),
+                String::from("cdn.example.com"),
+                String::from("mail.example.com"),
+                String::from("vpn.example.com"),
+                String::from("www.example.com"),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rapiddns_should_page_until_no_new_subdomains() {
+        // Set up mock rapiddns.io server
+        let mock_server = MockServer::start_async().await;
+
+        let first_page = mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/subdomain/example.com")
+                    .query_param("page", "1");
+                then.status(200).body(
+                    "<table><tr><td>www.example.com</td><td>mail.example.com</td></tr></table>",
+                );
+            })
+            .await;
+
+        // The last page is repeated past the end
+        let last_page = mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/subdomain/example.com");
+                then.status(200)
+                    .body("<table><tr><td>vpn.example.com</td></tr></table>");
+            })
+            .await;
+
+        let module = RapidDns {
+            base_url: format!("http://{}:{}/", mock_server.host(), mock_server.port()),
+            interval: Duration::ZERO,
+        };
+
+        let subdomains = collect(&module, "example.com").await.unwrap();
+
+        assert_eq!(
+            subdomains,
+            vec!["mail.example.com", "vpn.example.com", "www.example.com"]
+        );
+        first_page.assert_async().await;
+        last_page.assert_calls_async(2).await;
+    }
+}
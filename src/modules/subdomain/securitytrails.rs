@@ -0,0 +1,325 @@
+use crate::body;
+use crate::body::ReadBounded;
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::SubdomainModule;
+use crate::modules::Tag;
+use crate::modules::async_trait;
+use anyhow::Result;
+use anyhow::bail;
+use futures::SinkExt;
+use futures::channel::mpsc::Sender;
+use reqwest::Client;
+use reqwest::RequestBuilder;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::time::Duration;
+
+/// Name of the API key in `[sources]` and the key store
+pub const SOURCE: &str = "securitytrails";
+
+// Scroll pages hold 100 hosts, stop before burning through the monthly quota
+const MAX_SCROLL_PAGES: usize = 100;
+
+pub struct SecurityTrails {
+    api_url: String,
+    api_key: Option<String>,
+}
+
+impl SecurityTrails {
+    pub fn new(api_key: Option<&str>) -> Self {
+        SecurityTrails {
+            api_url: String::from("https://api.securitytrails.com/v1/"),
+            api_key: api_key.map(str::to_string),
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let resp = request.send().await?;
+
+        match resp.status() {
+            status if status.is_success() => resp.json_limited(body::MAX_API_RESPONSE_SIZE).await,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                bail!("SecurityTrails rejected the API key: {}", resp.status())
+            }
+            StatusCode::TOO_MANY_REQUESTS => bail!("SecurityTrails quota exceeded"),
+            status => bail!("Unexpected status code from SecurityTrails: {}", status),
+        }
+    }
+
+    /// Send every hostname of `domain` with the scroll API, which paid plans allow past the
+    /// subdomains limit, one page at a time, returning how many were sent
+    async fn scroll(
+        &self,
+        http_client: &Client,
+        api_key: &str,
+        domain: &str,
+        found: &mut Sender<String>,
+    ) -> Result<usize> {
+        #[derive(Debug, Deserialize)]
+        struct Page {
+            #[serde(default)]
+            records: Vec<Record>,
+            meta: ScrollMeta,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Record {
+            hostname: String,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct ScrollMeta {
+            scroll_id: Option<String>,
+        }
+
+        let mut page: Page = self
+            .send(
+                http_client
+                    .post(format!("{}domains/list?scroll=true", self.api_url))
+                    .header("APIKEY", api_key)
+                    .json(&json!({ "filter": { "apex_domain": domain } })),
+            )
+            .await?;
+
+        let suffix = format!(".{}", domain);
+        let mut sent = 0;
+
+        for _ in 0..MAX_SCROLL_PAGES {
+            if page.records.is_empty() {
+                return Ok(sent);
+            }
+            for record in page.records {
+                if let Some(subdomain) = subdomain(&record.hostname, &suffix) {
+                    found.send(subdomain).await?;
+                    sent += 1;
+                }
+            }
+
+            let Some(scroll_id) = page.meta.scroll_id else {
+                return Ok(sent);
+            };
+
+            page = self
+                .send(
+                    http_client
+                        .get(format!("{}scroll/{}", self.api_url, scroll_id))
+                        .header("APIKEY", api_key),
+                )
+                .await?;
+        }
+
+        log::warn!(
+            "{}: Stopped listing hostnames after {} pages",
+            self.name(),
+            MAX_SCROLL_PAGES
+        );
+        Ok(sent)
+    }
+}
+
+impl Module for SecurityTrails {
+    fn name(&self) -> String {
+        String::from("subdomain/securitytrails")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-subdomain-securitytrails")
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "subdomain/securitytrails",
+        tags: &[Tag::Passive],
+        intrusiveness: Intrusiveness::Safe,
+        requires: &[],
+        build: Build::Subdomain(|options| {
+            Box::new(SecurityTrails::new(options.api_keys.get(SOURCE)))
+        }),
+    }
+}
+
+#[async_trait]
+impl SubdomainModule for SecurityTrails {
+    async fn enumerate(&self, domain: &str, mut found: Sender<String>) -> Result<()> {
+        // Declare needed API response fields
+        #[derive(Debug, Deserialize)]
+        struct SubdomainsResponse {
+            #[serde(default)]
+            subdomains: Vec<String>,
+            #[serde(default)]
+            meta: SubdomainsMeta,
+        }
+
+        #[derive(Debug, Default, Deserialize)]
+        struct SubdomainsMeta {
+            #[serde(default)]
+            limit_reached: bool,
+        }
+
+        let Some(api_key) = &self.api_key else {
+            log::info!(
+                "{}: Skipped, no API key (set one with `vulnscan keys set {}`)",
+                self.name(),
+                SOURCE
+            );
+            return Ok(());
+        };
+
+        let http_client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+
+        let resp: SubdomainsResponse = self
+            .send(
+                http_client
+                    .get(format!(
+                        "{}domain/{}/subdomains?children_only=false&include_inactive=true",
+                        self.api_url, domain
+                    ))
+                    .header("APIKEY", api_key),
+            )
+            .await?;
+
+        // Labels relative to `domain`
+        let suffix = format!(".{}", domain);
+        let mut sent = 0;
+        for label in &resp.subdomains {
+            if let Some(subdomain) = subdomain(&format!("{}{}", label, suffix), &suffix) {
+                found.send(subdomain).await?;
+                sent += 1;
+            }
+        }
+
+        // The subdomains endpoint truncates large zones to the plan's limit
+        if resp.meta.limit_reached {
+            match self.scroll(&http_client, api_key, domain, &mut found).await {
+                Ok(scrolled) => sent += scrolled,
+                Err(e) => log::warn!(
+                    "{}: Only got the first {} subdomains, listing the rest failed: {}",
+                    self.name(),
+                    resp.subdomains.len(),
+                    e
+                ),
+            }
+        }
+
+        log::info!("{}: Found {} names", self.name(), sent);
+
+        Ok(())
+    }
+}
+
+/// `name` normalized, unless it is no subdomain of the domain ending with `suffix`
+fn subdomain(name: &str, suffix: &str) -> Option<String> {
+    let name = name.trim().trim_end_matches('.').to_lowercase();
+    (!name.contains('*') && name.ends_with(suffix)).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::subdomain::tests::collect;
+    use httpmock::prelude::*;
+
+    fn module(mock_server: &MockServer) -> SecurityTrails {
+        SecurityTrails {
+            api_url: format!("http://{}:{}/v1/", mock_server.host(), mock_server.port()),
+            api_key: Some(String::from("st-key")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enumerate_should_scroll_past_the_subdomains_limit() {
+        // Set up mock SecurityTrails server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/v1/domain/example.com/subdomains")
+                    .header("APIKEY", "st-key");
+                then.status(200).json_body(serde_json::json!({
+                    "subdomains": ["www", "Mail"],
+                    "subdomain_count": 3,
+                    "meta": { "limit_reached": true }
+                }));
+            })
+            .await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/v1/domains/list")
+                    .query_param("scroll", "true")
+                    .body_includes(r#""apex_domain":"example.com""#);
+                then.status(200).json_body(serde_json::json!({
+                    "records": [{ "hostname": "www.example.com" }, { "hostname": "example.com" }],
+                    "meta": { "scroll_id": "abc" }
+                }));
+            })
+            .await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/scroll/abc");
+                then.status(200).json_body(serde_json::json!({
+                    "records": [{ "hostname": "vpn.example.com" }],
+                    "meta": { "scroll_id": "def" }
+                }));
+            })
+            .await;
+
+        // The scroll ends with an empty page
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/scroll/def");
+                then.status(200)
+                    .json_body(serde_json::json!({ "records": [], "meta": {} }));
+            })
+            .await;
+
+        let subdomains = collect(&module(&mock_server), "example.com").await.unwrap();
+
+        assert_eq!(
+            subdomains,
+            vec!["mail.example.com", "vpn.example.com", "www.example.com"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enumerate_should_keep_limited_results_without_scroll_access() {
+        // Set up mock SecurityTrails server on a free plan
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET).path("/v1/domain/example.com/subdomains");
+                then.status(200).json_body(serde_json::json!({
+                    "subdomains": ["www"],
+                    "meta": { "limit_reached": true }
+                }));
+            })
+            .await;
+
+        let scroll = mock_server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v1/domains/list");
+                then.status(403);
+            })
+            .await;
+
+        let subdomains = collect(&module(&mock_server), "example.com").await.unwrap();
+
+        assert_eq!(subdomains, vec!["www.example.com"]);
+        scroll.assert_async().await;
+
+        // Without a key the source is skipped
+        let module = SecurityTrails::new(None);
+        assert!(collect(&module, "example.com").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::TlsModule;
+use crate::modules::async_trait;
+use crate::modules::tls;
+use crate::modules::tls::handshake::ALERT;
+use crate::modules::tls::handshake::CHANGE_CIPHER_SPEC;
+use crate::modules::tls::handshake::Handshake;
+use anyhow::Result;
+use std::net::IpAddr;
+use std::time::Duration;
+
+// Patched servers reject an early ChangeCipherSpec right away
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+// Alert descriptions
+const UNEXPECTED_MESSAGE: u8 = 10;
+const BAD_RECORD_MAC: u8 = 20;
+const DECRYPTION_FAILED: u8 = 21;
+
+// The connection is left in a broken state on vulnerable servers, so the probe is only sent on request
+pub struct CcsInjection;
+
+impl CcsInjection {
+    pub fn new() -> Self {
+        CcsInjection
+    }
+}
+
+impl Module for CcsInjection {
+    fn name(&self) -> String {
+        String::from("tls/ccs_injection")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-tls-ccs_injection").arg("flag", "--aggressive")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://nvd.nist.gov/vuln/detail/CVE-2014-0224",
+            "https://www.openssl.org/news/secadv/20140605.txt",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "tls/ccs_injection",
+        tags: &[Tag::Intrusive],
+        intrusiveness: Intrusiveness::Intrusive,
+        requires: &[],
+        build: Build::Tls(|_| Box::new(CcsInjection::new())),
+    }
+}
+
+#[async_trait]
+impl TlsModule for CcsInjection {
+    async fn check(&self, host: &str, ip: IpAddr, port: u16) -> Result<Option<Finding>> {
+        let mut handshake = Handshake::start(host, ip, port).await?;
+        handshake.send(CHANGE_CIPHER_SPEC, &[1]).await?;
+
+        match handshake.receive(RESPONSE_TIMEOUT).await {
+            // Silence is acceptance
+            None => (),
+            Some((ALERT, alert)) if alert.get(1) == Some(&UNEXPECTED_MESSAGE) => return Ok(None),
+            Some(_) => return Ok(None),
+        }
+
+        // Having switched to keys derived from an empty master secret, a vulnerable server fails to decrypt the next record
+        handshake.send(CHANGE_CIPHER_SPEC, &[1]).await?;
+
+        match handshake.receive(RESPONSE_TIMEOUT).await {
+            Some((ALERT, alert))
+                if matches!(alert.get(1), Some(&BAD_RECORD_MAC | &DECRYPTION_FAILED)) =>
+            {
+                Ok(Some(
+                    Finding::new(
+                        self.name(),
+                        "CcsInjection",
+                        Severity::High,
+                        &tls::url(host, port),
+                    )
+                    .with_evidence(vec![String::from(
+                        "An early ChangeCipherSpec was accepted, the next record failed to decrypt",
+                    )]),
+                ))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::tls::handshake::tests::fake_server;
+
+    #[tokio::test]
+    async fn test_check_should_tell_accepted_from_rejected_ccs() {
+        let ip = "127.0.0.1".parse().unwrap();
+        let module = CcsInjection::new();
+
+        // Silent on the first ChangeCipherSpec, failing to decrypt the second
+        let vulnerable = fake_server(|_, received| match received {
+            1 => Vec::new(),
+            _ => vec![ALERT, 3, 3, 0, 2, 2, BAD_RECORD_MAC],
+        })
+        .await;
+        let finding = module
+            .check("www.example.com", ip, vulnerable)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(finding.kind, "CcsInjection");
+        assert_eq!(finding.url, format!("tls://www.example.com:{}", vulnerable));
+
+        let patched = fake_server(|_, _| vec![ALERT, 3, 3, 0, 2, 2, UNEXPECTED_MESSAGE]).await;
+        assert_eq!(
+            module.check("www.example.com", ip, patched).await.unwrap(),
+            None
+        );
+    }
+}
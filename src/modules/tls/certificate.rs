@@ -0,0 +1,92 @@
+use anyhow::Result;
+use anyhow::bail;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_native_tls::TlsConnector;
+use tokio_native_tls::native_tls;
+use x509_parser::extensions::GeneralName;
+use x509_parser::parse_x509_certificate;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Host names the certificate served on `ip:port` for `host` is valid for
+pub async fn names(host: &str, ip: IpAddr, port: u16) -> Result<Vec<String>> {
+    let stream = timeout(TIMEOUT, TcpStream::connect((ip, port))).await??;
+
+    // Only the names matter, not whether anybody should trust them
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()?;
+    let stream = timeout(TIMEOUT, TlsConnector::from(connector).connect(host, stream)).await??;
+
+    let Some(certificate) = stream.get_ref().peer_certificate()? else {
+        bail!("{}:{} presented no certificate", host, port);
+    };
+    certificate_names(&certificate.to_der()?)
+}
+
+/// Subject alternative DNS names and common names of a DER certificate, lowercased and without
+/// wildcard labels, as `*.dev.example.com` still reveals `dev.example.com`
+fn certificate_names(der: &[u8]) -> Result<Vec<String>> {
+    let (_, certificate) = parse_x509_certificate(der)?;
+
+    let common_names = certificate
+        .subject()
+        .iter_common_name()
+        .filter_map(|name| name.as_str().ok());
+    let alternative_names = certificate
+        .subject_alternative_name()?
+        .into_iter()
+        .flat_map(|extension| &extension.value.general_names)
+        .filter_map(|name| match name {
+            GeneralName::DNSName(name) => Some(*name),
+            _ => None,
+        });
+
+    let mut names: Vec<String> = common_names
+        .chain(alternative_names)
+        .map(|name| {
+            name.trim_start_matches("*.")
+                .trim_end_matches('.')
+                .to_lowercase()
+        })
+        // Common names are not always host names
+        .filter(|name| name.contains('.') && !name.contains(['*', ' ']))
+        .collect();
+    names.sort();
+    names.dedup();
+
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+
+    // Self-signed for CN=portal.example.com, with DNS names portal.example.com, *.dev.example.com,
+    // mail.example.org and IP address 192.0.2.1
+    const CERTIFICATE: &str = concat!(
+        "MIIB2zCCAYGgAwIBAgIUPPlcE9qd5chMyEjhQabnIh357igwCgYIKoZIzj0EAwIwHTEbMBkGA1UEAwwScG9ydGFsLmV4YW1w",
+        "bGUuY29tMB4XDTI2MTAxNjE2MzE0M1oXDTM2MTAxMzE2MzE0M1owHTEbMBkGA1UEAwwScG9ydGFsLmV4YW1wbGUuY29tMFkw",
+        "EwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE8asXIlAtSYH6bKJ+rXKYlLlqa24sbduhFmJpYF0LTCyHPgg42Dfym4JMlAjjkynv",
+        "PZqLiRh+KtOIOxEBX+PtWqOBnjCBmzAdBgNVHQ4EFgQUcAFpo6d3XDxd4CnTtbsLrGvSGAUwHwYDVR0jBBgwFoAUcAFpo6d3",
+        "XDxd4CnTtbsLrGvSGAUwDwYDVR0TAQH/BAUwAwEB/zBIBgNVHREEQTA/ghJwb3J0YWwuZXhhbXBsZS5jb22CESouZGV2LmV4",
+        "YW1wbGUuY29tghBtYWlsLmV4YW1wbGUub3JnhwTAAAIBMAoGCCqGSM49BAMCA0gAMEUCIQDrK68UQrTRDKgvNv1K17TJS8Dq",
+        "rEGp7qot0Y2e3gP1JAIgdusC2e+QJ5kEwPbtINjyH7cFUlK+u+xMora8UeaiaeQ=",
+    );
+
+    #[test]
+    fn test_certificate_names_should_strip_wildcards_and_skip_addresses() {
+        let der = STANDARD.decode(CERTIFICATE).unwrap();
+
+        assert_eq!(
+            certificate_names(&der).unwrap(),
+            vec!["dev.example.com", "mail.example.org", "portal.example.com"]
+        );
+    }
+}
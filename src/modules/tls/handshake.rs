@@ -0,0 +1,219 @@
+use anyhow::Result;
+use anyhow::bail;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// Record content types
+pub const CHANGE_CIPHER_SPEC: u8 = 20;
+pub const ALERT: u8 = 21;
+pub const HANDSHAKE: u8 = 22;
+pub const HEARTBEAT: u8 = 24;
+
+const CLIENT_HELLO: u8 = 1;
+const SERVER_HELLO: u8 = 2;
+const SERVER_HELLO_DONE: u8 = 14;
+// Records carry 2^14 bytes, plus some expansion
+const MAX_RECORD_LENGTH: usize = 18432;
+// Certificate chains fit, anything longer is not a handshake worth waiting for
+const MAX_HANDSHAKE_LENGTH: usize = 65536;
+
+// ECDHE, DHE and RSA suites OpenSSL 1.0.1 supports, so no vulnerable server refuses the hello
+const CIPHER_SUITES: &[u16] = &[
+    0xc02f, 0xc030, 0xc02b, 0xc02c, 0xc027, 0xc028, 0xc013, 0xc014, 0xc009, 0xc00a, 0x009e, 0x009f,
+    0x0033, 0x0039, 0x009c, 0x009d, 0x003c, 0x003d, 0x002f, 0x0035, 0x000a,
+];
+const SUPPORTED_GROUPS: &[u16] = &[0x0017, 0x0018, 0x0019];
+const SIGNATURE_ALGORITHMS: &[u16] = &[
+    0x0401, 0x0501, 0x0601, 0x0403, 0x0503, 0x0603, 0x0201, 0x0203,
+];
+
+/// A TLS 1.2 handshake stopped after ServerHelloDone, before any key is exchanged
+pub struct Handshake {
+    stream: TcpStream,
+    // Protocol version chosen by the server
+    version: [u8; 2],
+}
+
+impl Handshake {
+    /// Send a ClientHello offering heartbeats, then read the server's first flight
+    pub async fn start(host: &str, ip: IpAddr, port: u16) -> Result<Self> {
+        let mut stream = timeout(TIMEOUT, TcpStream::connect((ip, port))).await??;
+        stream.write_all(&client_hello(host)).await?;
+
+        let mut messages = Vec::new();
+        let mut version = None;
+
+        loop {
+            let (content_type, fragment) = read_record(&mut stream, TIMEOUT).await?;
+            match content_type {
+                HANDSHAKE => messages.extend(fragment),
+                ALERT => bail!("Handshake refused with alert {:?}", fragment.get(1)),
+                _ => bail!("Unexpected record type {} in the handshake", content_type),
+            }
+            if messages.len() > MAX_HANDSHAKE_LENGTH {
+                bail!("Handshake longer than {} bytes", MAX_HANDSHAKE_LENGTH);
+            }
+
+            // Handshake messages may span records, or share one
+            let mut offset = 0;
+            while let Some(header) = messages.get(offset..offset + 4) {
+                let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+                let Some(body) = messages.get(offset + 4..offset + 4 + length) else {
+                    break;
+                };
+                match header[0] {
+                    SERVER_HELLO if body.len() >= 2 => version = Some([body[0], body[1]]),
+                    SERVER_HELLO_DONE => {
+                        let Some(version) = version else {
+                            bail!("ServerHelloDone without ServerHello");
+                        };
+                        return Ok(Handshake { stream, version });
+                    }
+                    _ => (),
+                }
+                offset += 4 + length;
+            }
+        }
+    }
+
+    /// Send a record under the negotiated version
+    pub async fn send(&mut self, content_type: u8, fragment: &[u8]) -> Result<()> {
+        let mut record = vec![content_type, self.version[0], self.version[1]];
+        record.extend((fragment.len() as u16).to_be_bytes());
+        record.extend(fragment);
+        self.stream.write_all(&record).await?;
+        Ok(())
+    }
+
+    /// The next record, `None` when the server stays silent for `wait` or closes the connection
+    pub async fn receive(&mut self, wait: Duration) -> Option<(u8, Vec<u8>)> {
+        read_record(&mut self.stream, wait).await.ok()
+    }
+}
+
+async fn read_record(stream: &mut TcpStream, wait: Duration) -> Result<(u8, Vec<u8>)> {
+    let mut header = [0; 5];
+    timeout(wait, stream.read_exact(&mut header)).await??;
+
+    let length = u16::from_be_bytes([header[3], header[4]]) as usize;
+    if header[1] != 3 || length > MAX_RECORD_LENGTH {
+        bail!("Not a TLS record");
+    }
+
+    let mut fragment = vec![0; length];
+    timeout(wait, stream.read_exact(&mut fragment)).await??;
+    Ok((header[0], fragment))
+}
+
+fn with_length(length_size: usize, content: &[u8]) -> Vec<u8> {
+    let mut encoded = (content.len() as u32).to_be_bytes()[4 - length_size..].to_vec();
+    encoded.extend(content);
+    encoded
+}
+
+fn extension(extension_type: u16, content: &[u8]) -> Vec<u8> {
+    let mut encoded = extension_type.to_be_bytes().to_vec();
+    encoded.extend(with_length(2, content));
+    encoded
+}
+
+fn u16_list(values: &[u16]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_be_bytes())
+        .collect()
+}
+
+fn client_hello(host: &str) -> Vec<u8> {
+    let mut extensions = Vec::new();
+    // Addresses are not valid server names
+    if host.parse::<IpAddr>().is_err() {
+        let mut name = vec![0];
+        name.extend(with_length(2, host.as_bytes()));
+        extensions.extend(extension(0x0000, &with_length(2, &name)));
+    }
+    extensions.extend(extension(
+        0x000a,
+        &with_length(2, &u16_list(SUPPORTED_GROUPS)),
+    ));
+    // Uncompressed points only
+    extensions.extend(extension(0x000b, &[1, 0]));
+    extensions.extend(extension(
+        0x000d,
+        &with_length(2, &u16_list(SIGNATURE_ALGORITHMS)),
+    ));
+    // Heartbeat, the peer being allowed to send requests
+    extensions.extend(extension(0x000f, &[1]));
+
+    let mut body = vec![3, 3];
+    body.extend(rand::random::<[u8; 32]>());
+    // No session to resume
+    body.push(0);
+    body.extend(with_length(2, &u16_list(CIPHER_SUITES)));
+    // Null compression only
+    body.extend([1, 0]);
+    body.extend(with_length(2, &extensions));
+
+    let mut handshake = vec![CLIENT_HELLO];
+    handshake.extend(with_length(3, &body));
+
+    // Record versions stay at TLS 1.0 in hellos, for old servers
+    let mut record = vec![HANDSHAKE, 3, 1];
+    record.extend(with_length(2, &handshake));
+    record
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Serve one handshake, answering each later record with `respond`
+    pub async fn fake_server(respond: fn(u8, usize) -> Vec<u8>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (content_type, _) = read_record(&mut stream, TIMEOUT).await.unwrap();
+            assert_eq!(content_type, HANDSHAKE);
+
+            // ServerHello (TLS 1.2, ECDHE-RSA-AES128-GCM-SHA256) then ServerHelloDone in one record
+            let mut server_hello = vec![3, 3];
+            server_hello.extend([0; 32]);
+            server_hello.extend([0, 0xc0, 0x2f, 0]);
+            let mut messages = vec![SERVER_HELLO];
+            messages.extend(with_length(3, &server_hello));
+            messages.extend([SERVER_HELLO_DONE, 0, 0, 0]);
+            let mut record = vec![HANDSHAKE, 3, 3];
+            record.extend(with_length(2, &messages));
+            stream.write_all(&record).await.unwrap();
+
+            let mut received = 0;
+            while let Ok((content_type, _)) = read_record(&mut stream, TIMEOUT).await {
+                received += 1;
+                let response = respond(content_type, received);
+                stream.write_all(&response).await.unwrap();
+            }
+        });
+
+        port
+    }
+
+    #[tokio::test]
+    async fn test_start_should_stop_after_server_hello_done() {
+        let port = fake_server(|_, _| Vec::new()).await;
+
+        let handshake = Handshake::start("www.example.com", "127.0.0.1".parse().unwrap(), port)
+            .await
+            .unwrap();
+
+        assert_eq!(handshake.version, [3, 3]);
+    }
+}
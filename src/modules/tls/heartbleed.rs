@@ -0,0 +1,134 @@
+use crate::i18n::Message;
+use crate::modules::Build;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Module;
+use crate::modules::Registration;
+use crate::modules::Severity;
+use crate::modules::Tag;
+use crate::modules::TlsModule;
+use crate::modules::async_trait;
+use crate::modules::tls;
+use crate::modules::tls::handshake::ALERT;
+use crate::modules::tls::handshake::HEARTBEAT;
+use crate::modules::tls::handshake::Handshake;
+use anyhow::Result;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::time::Instant;
+
+// Patched servers silently discard the request
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(3);
+const HEARTBEAT_REQUEST: u8 = 1;
+// Bytes claimed but not sent, kept small so a vulnerable server leaks next to nothing
+const CLAIMED_LENGTH: u16 = 16;
+
+// Vulnerable servers echo a few bytes of their memory, so the probe is only sent on request
+pub struct Heartbleed;
+
+impl Heartbleed {
+    pub fn new() -> Self {
+        Heartbleed
+    }
+}
+
+impl Module for Heartbleed {
+    fn name(&self) -> String {
+        String::from("tls/heartbleed")
+    }
+
+    fn description(&self) -> Message {
+        Message::new("module-tls-heartbleed").arg("flag", "--aggressive")
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::High
+    }
+
+    fn references(&self) -> Vec<&'static str> {
+        vec![
+            "https://nvd.nist.gov/vuln/detail/CVE-2014-0160",
+            "https://heartbleed.com/",
+        ]
+    }
+}
+
+inventory::submit! {
+    Registration {
+        name: "tls/heartbleed",
+        tags: &[Tag::Intrusive],
+        intrusiveness: Intrusiveness::Intrusive,
+        requires: &[],
+        build: Build::Tls(|_| Box::new(Heartbleed::new())),
+    }
+}
+
+#[async_trait]
+impl TlsModule for Heartbleed {
+    async fn check(&self, host: &str, ip: IpAddr, port: u16) -> Result<Option<Finding>> {
+        let mut handshake = Handshake::start(host, ip, port).await?;
+
+        // The request claims a payload but carries none, before any key is exchanged
+        let mut request = vec![HEARTBEAT_REQUEST];
+        request.extend(CLAIMED_LENGTH.to_be_bytes());
+        handshake.send(HEARTBEAT, &request).await?;
+
+        let deadline = Instant::now() + RESPONSE_TIMEOUT;
+        while let Some((content_type, fragment)) = handshake
+            .receive(deadline.saturating_duration_since(Instant::now()))
+            .await
+        {
+            match content_type {
+                // Only the length is kept, leaked memory is dropped right away
+                HEARTBEAT if fragment.len() > 3 => {
+                    let leaked = u16::from_be_bytes([fragment[1], fragment[2]]);
+                    return Ok(Some(
+                        Finding::new(
+                            self.name(),
+                            "Heartbleed",
+                            Severity::High,
+                            &tls::url(host, port),
+                        )
+                        .with_evidence(vec![format!(
+                            "{} bytes of memory echoed for a heartbeat carrying none",
+                            leaked
+                        )])
+                        .with_metadata("leaked", leaked),
+                    ));
+                }
+                ALERT => break,
+                _ => (),
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::tls::handshake::tests::fake_server;
+
+    #[tokio::test]
+    async fn test_check_should_report_echoed_heartbeat() {
+        // A vulnerable server echoes the claimed length, then padding
+        let port = fake_server(|content_type, _| {
+            assert_eq!(content_type, HEARTBEAT);
+            let mut response = vec![HEARTBEAT, 3, 3, 0, 35, 2, 0, 16];
+            response.extend([0xaa; 32]);
+            response
+        })
+        .await;
+        let ip = "127.0.0.1".parse().unwrap();
+
+        let finding = Heartbleed::new()
+            .check("www.example.com", ip, port)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(finding.kind, "Heartbleed");
+        assert_eq!(finding.url, format!("tls://www.example.com:{}", port));
+        assert_eq!(finding.metadata["leaked"], 16);
+    }
+}
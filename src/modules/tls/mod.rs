@@ -0,0 +1,9 @@
+mod ccs_injection;
+pub mod certificate;
+mod handshake;
+mod heartbleed;
+
+/// Findings are reported against the port, as `tls://host:port`
+pub fn url(host: &str, port: u16) -> String {
+    format!("tls://{}:{}", host, port)
+}
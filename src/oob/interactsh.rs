@@ -0,0 +1,248 @@
+use super::Backend;
+use super::Interaction;
+use super::PAYLOAD_ID_LENGTH;
+use super::random_string;
+use crate::body;
+use crate::body::ReadBounded;
+use anyhow::Result;
+use anyhow::bail;
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use cfb_mode::cipher::AsyncStreamCipher;
+use cfb_mode::cipher::KeyIvInit;
+use reqwest::Client;
+use rsa::Oaep;
+use rsa::RsaPrivateKey;
+use rsa::pkcs8::EncodePublicKey;
+use rsa::pkcs8::LineEnding;
+use rsa::sha2::Sha256;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+type Aes256CfbDec = cfb_mode::Decryptor<aes::Aes256>;
+
+// Interactsh matches callbacks on a 20 characters correlation ID followed by a 13 characters nonce
+const CORRELATION_ID_LENGTH: usize = 20;
+const NONCE_LENGTH: usize = PAYLOAD_ID_LENGTH - CORRELATION_ID_LENGTH;
+
+/// Client of an interactsh-compatible server
+///
+/// See https://github.com/projectdiscovery/interactsh
+pub struct Interactsh {
+    server_url: String,
+    domain: String,
+    token: Option<String>,
+    correlation_id: String,
+    secret_key: String,
+    private_key: RsaPrivateKey,
+    http_client: Client,
+}
+
+impl Interactsh {
+    /// Register a new session on the interaction server
+    ///
+    /// # Arguments
+    /// * `server` - The server URL or domain, e.g. `oast.fun`
+    /// * `token` - The server authentication token, if required
+    pub async fn register(server: &str, token: Option<&str>) -> Result<Self> {
+        let server_url = if server.contains("://") {
+            server.trim_end_matches('/').to_string()
+        } else {
+            format!("https://{}", server.trim_end_matches('/'))
+        };
+
+        let domain = match reqwest::Url::parse(&server_url)?.host_str() {
+            Some(host) => host.to_string(),
+            None => bail!("Invalid interaction server: {}", server),
+        };
+
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 2048)?;
+        let public_key = private_key
+            .to_public_key()
+            .to_public_key_pem(LineEnding::LF)?;
+
+        let client = Interactsh {
+            server_url,
+            domain,
+            token: token.map(String::from),
+            correlation_id: random_string(CORRELATION_ID_LENGTH),
+            secret_key: random_string(32),
+            private_key,
+            http_client: Client::builder().timeout(Duration::from_secs(30)).build()?,
+        };
+
+        let resp = client
+            .request(reqwest::Method::POST, "register")
+            .json(&json!({
+                "public-key": BASE64.encode(public_key),
+                "secret-key": client.secret_key,
+                "correlation-id": client.correlation_id,
+            }))
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            bail!(
+                "Failed to register on {}: {}",
+                client.server_url,
+                resp.status()
+            );
+        }
+
+        Ok(client)
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .http_client
+            .request(method, format!("{}/{}", self.server_url, path));
+
+        match &self.token {
+            Some(token) => request.header(reqwest::header::AUTHORIZATION, token),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl Backend for Interactsh {
+    fn new_payload(&self) -> (String, String) {
+        let unique_id = format!("{}{}", self.correlation_id, random_string(NONCE_LENGTH));
+        let host = format!("{}.{}", unique_id, self.domain);
+        (unique_id, host)
+    }
+
+    async fn poll(&self) -> Result<Vec<Interaction>> {
+        #[derive(Deserialize)]
+        struct PollResponse {
+            data: Option<Vec<String>>,
+            aes_key: Option<String>,
+        }
+
+        let resp = self
+            .request(reqwest::Method::GET, "poll")
+            .query(&[("id", &self.correlation_id), ("secret", &self.secret_key)])
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            bail!("Failed to poll {}: {}", self.server_url, resp.status());
+        }
+
+        let poll: PollResponse = resp.json_limited(body::MAX_API_RESPONSE_SIZE).await?;
+
+        let (Some(data), Some(aes_key)) = (poll.data, poll.aes_key) else {
+            return Ok(Vec::new());
+        };
+
+        // The AES key is encrypted with our public key, each interaction with the AES key
+        let aes_key = self
+            .private_key
+            .decrypt(Oaep::new::<Sha256>(), &BASE64.decode(aes_key)?)?;
+
+        let mut interactions = Vec::new();
+
+        for item in data {
+            let encrypted = BASE64.decode(item)?;
+            if encrypted.len() < 16 || aes_key.len() != 32 {
+                bail!("Malformed interaction received from {}", self.server_url);
+            }
+
+            let (iv, ciphertext) = encrypted.split_at(16);
+            let mut plaintext = ciphertext.to_vec();
+            Aes256CfbDec::new(aes_key.as_slice().into(), iv.into()).decrypt(&mut plaintext);
+
+            match serde_json::from_slice(&plaintext) {
+                Ok(interaction) => interactions.push(interaction),
+                Err(e) => log::debug!("Failed to parse interaction: {}", e),
+            }
+        }
+
+        Ok(interactions)
+    }
+
+    // Remove the session from the interaction server
+    async fn close(&self) -> Result<()> {
+        self.request(reqwest::Method::POST, "deregister")
+            .json(&json!({
+                "secret-key": self.secret_key,
+                "correlation-id": self.correlation_id,
+            }))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfb_mode::cipher::AsyncStreamCipher;
+    use httpmock::prelude::*;
+
+    type Aes256CfbEnc = cfb_mode::Encryptor<aes::Aes256>;
+
+    #[tokio::test]
+    async fn test_poll_should_decrypt_interactions() {
+        // Set up mock interaction server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(POST).path("/register");
+                then.status(200)
+                    .body(r#"{"message":"registration successful"}"#);
+            })
+            .await;
+
+        let server = format!("http://{}:{}", mock_server.host(), mock_server.port());
+        let client = Interactsh::register(&server, None).await.unwrap();
+        let (unique_id, host) = client.new_payload();
+
+        assert_eq!(unique_id.len(), CORRELATION_ID_LENGTH + NONCE_LENGTH);
+        assert_eq!(host, format!("{}.{}", unique_id, mock_server.host()));
+
+        // Encrypt an interaction the way the server does
+        let aes_key = [7u8; 32];
+        let iv = [9u8; 16];
+        let mut ciphertext = serde_json::to_vec(&json!({
+            "protocol": "http",
+            "unique-id": unique_id,
+            "full-id": unique_id,
+            "remote-address": "203.0.113.7",
+            "timestamp": "2025-12-22T18:16:31Z",
+        }))
+        .unwrap();
+        Aes256CfbEnc::new(&aes_key.into(), &iv.into()).encrypt(&mut ciphertext);
+
+        let encrypted_key = client
+            .private_key
+            .to_public_key()
+            .encrypt(&mut rand::thread_rng(), Oaep::new::<Sha256>(), &aes_key)
+            .unwrap();
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/poll")
+                    .query_param("id", &client.correlation_id);
+                then.status(200).json_body(json!({
+                    "data": [BASE64.encode([iv.as_slice(), &ciphertext].concat())],
+                    "aes_key": BASE64.encode(encrypted_key),
+                }));
+            })
+            .await;
+
+        // Run poll
+        let interactions = client.poll().await.unwrap();
+
+        // Check result
+        assert_eq!(interactions.len(), 1);
+        assert_eq!(interactions[0].unique_id, unique_id);
+        assert_eq!(interactions[0].protocol, "http");
+        assert_eq!(interactions[0].remote_address, "203.0.113.7");
+    }
+}
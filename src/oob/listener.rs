@@ -0,0 +1,320 @@
+use super::Backend;
+use super::Interaction;
+use super::PAYLOAD_ID_LENGTH;
+use super::random_string;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+
+const HTTP_RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+/// Settings of the built-in callback listener
+pub struct ListenerConfig {
+    /// The domain whose DNS is delegated to this host, payload hosts are its subdomains
+    pub domain: String,
+    /// Where to accept HTTP callbacks
+    pub http_addr: SocketAddr,
+    /// Where to answer DNS queries
+    pub dns_addr: SocketAddr,
+    /// The address returned for payload hosts, so HTTP callbacks reach this host
+    pub public_ip: Option<IpAddr>,
+}
+
+/// Built-in HTTP and DNS callback listener
+pub struct Listener {
+    domain: String,
+    interactions: Arc<Mutex<Vec<Interaction>>>,
+    tasks: Vec<JoinHandle<()>>,
+    #[cfg(test)]
+    http_addr: SocketAddr,
+    #[cfg(test)]
+    dns_addr: SocketAddr,
+}
+
+impl Listener {
+    pub async fn start(config: &ListenerConfig) -> Result<Self> {
+        let interactions = Arc::new(Mutex::new(Vec::new()));
+
+        let tcp_listener = TcpListener::bind(config.http_addr).await?;
+        let udp_socket = UdpSocket::bind(config.dns_addr).await?;
+
+        log::info!(
+            "OOB listener accepting HTTP on {} and DNS on {} for *.{}",
+            tcp_listener.local_addr()?,
+            udp_socket.local_addr()?,
+            config.domain
+        );
+
+        Ok(Listener {
+            domain: config.domain.to_lowercase(),
+            #[cfg(test)]
+            http_addr: tcp_listener.local_addr()?,
+            #[cfg(test)]
+            dns_addr: udp_socket.local_addr()?,
+            tasks: vec![
+                tokio::spawn(serve_http(tcp_listener, interactions.clone())),
+                tokio::spawn(serve_dns(
+                    udp_socket,
+                    config.public_ip,
+                    interactions.clone(),
+                )),
+            ],
+            interactions,
+        })
+    }
+}
+
+#[async_trait]
+impl Backend for Listener {
+    fn new_payload(&self) -> (String, String) {
+        let unique_id = random_string(PAYLOAD_ID_LENGTH);
+        let host = format!("{}.{}", unique_id, self.domain);
+        (unique_id, host)
+    }
+
+    async fn poll(&self) -> Result<Vec<Interaction>> {
+        let mut interactions = self.interactions.lock().expect("OOB listener poisoned");
+        Ok(std::mem::take(&mut *interactions))
+    }
+
+    async fn close(&self) -> Result<()> {
+        for task in &self.tasks {
+            task.abort();
+        }
+        Ok(())
+    }
+}
+
+/// Record an interaction if `host` carries a payload ID
+fn record(
+    interactions: &Mutex<Vec<Interaction>>,
+    protocol: &str,
+    host: &str,
+    remote_address: SocketAddr,
+) {
+    let Some(unique_id) = host.split('.').map(str::to_lowercase).find(|label| {
+        label.len() == PAYLOAD_ID_LENGTH
+            && label
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('a'..='v').contains(&c))
+    }) else {
+        return;
+    };
+
+    log::debug!(
+        "OOB {} interaction {} from {}",
+        protocol,
+        unique_id,
+        remote_address
+    );
+
+    interactions
+        .lock()
+        .expect("OOB listener poisoned")
+        .push(Interaction {
+            protocol: protocol.to_string(),
+            unique_id,
+            remote_address: remote_address.ip().to_string(),
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        });
+}
+
+async fn serve_http(listener: TcpListener, interactions: Arc<Mutex<Vec<Interaction>>>) {
+    loop {
+        let (mut stream, remote_address) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                // Errors such as running out of file descriptors persist for a while, retrying
+                // right away would spin
+                log::warn!("OOB HTTP listener failed to accept a connection: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        let interactions = interactions.clone();
+
+        tokio::spawn(async move {
+            // Only the request head is needed to find the Host header
+            let mut buffer = vec![0u8; 8192];
+            let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buffer));
+            let Ok(Ok(size)) = read.await else {
+                return;
+            };
+
+            let head = String::from_utf8_lossy(&buffer[..size]);
+            let host = head.lines().find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case("host").then(|| value.trim())
+            });
+
+            if let Some(host) = host {
+                record(&interactions, "http", host, remote_address);
+            }
+
+            let _ = stream.write_all(HTTP_RESPONSE).await;
+        });
+    }
+}
+
+async fn serve_dns(
+    socket: UdpSocket,
+    public_ip: Option<IpAddr>,
+    interactions: Arc<Mutex<Vec<Interaction>>>,
+) {
+    let mut buffer = [0u8; 512];
+
+    loop {
+        let Ok((size, remote_address)) = socket.recv_from(&mut buffer).await else {
+            continue;
+        };
+
+        let Some((name, question_end, qtype)) = parse_dns_question(&buffer[..size]) else {
+            continue;
+        };
+
+        record(&interactions, "dns", &name, remote_address);
+
+        let response = build_dns_response(&buffer[..question_end], qtype, public_ip);
+        let _ = socket.send_to(&response, remote_address).await;
+    }
+}
+
+/// Parse the first question of a DNS query
+///
+/// Returns the queried name, where the question ends and the query type
+fn parse_dns_question(packet: &[u8]) -> Option<(String, usize, u16)> {
+    // Header: ID, flags, QDCOUNT, ANCOUNT, NSCOUNT, ARCOUNT
+    if packet.len() < 12 || u16::from_be_bytes([packet[4], packet[5]]) == 0 {
+        return None;
+    }
+
+    let mut labels = Vec::new();
+    let mut offset = 12;
+
+    loop {
+        let length = *packet.get(offset)? as usize;
+        offset += 1;
+
+        if length == 0 {
+            break;
+        }
+
+        // Compression pointers never appear in questions of well-formed queries
+        if length > 63 {
+            return None;
+        }
+
+        labels.push(String::from_utf8_lossy(packet.get(offset..offset + length)?).into_owned());
+        offset += length;
+    }
+
+    let qtype = u16::from_be_bytes([*packet.get(offset)?, *packet.get(offset + 1)?]);
+
+    // QTYPE and QCLASS
+    offset += 4;
+    if offset > packet.len() {
+        return None;
+    }
+
+    Some((labels.join("."), offset, qtype))
+}
+
+/// Build an authoritative answer, resolving A queries to `public_ip`
+fn build_dns_response(query: &[u8], qtype: u16, public_ip: Option<IpAddr>) -> Vec<u8> {
+    const TYPE_A: u16 = 1;
+
+    let answer_ip = match public_ip {
+        Some(IpAddr::V4(ip)) if qtype == TYPE_A => Some(ip),
+        _ => None,
+    };
+
+    let mut response = query.to_vec();
+
+    // QR + AA, keeping the RD bit of the query
+    response[2] = 0x84 | (query[2] & 0x01);
+    response[3] = 0x00;
+    // One question, one or zero answers
+    response[4..12].copy_from_slice(&[0, 1, 0, answer_ip.is_some() as u8, 0, 0, 0, 0]);
+
+    if let Some(ip) = answer_ip {
+        // Pointer to the question name, type A, class IN, TTL 60, 4 bytes of data
+        response.extend_from_slice(&[0xc0, 0x0c, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4]);
+        response.extend_from_slice(&ip.octets());
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[tokio::test]
+    async fn test_listener_should_record_http_and_dns_callbacks() {
+        // Start the listener on ephemeral ports
+        let listener = Listener::start(&ListenerConfig {
+            domain: String::from("oob.example.com"),
+            http_addr: "127.0.0.1:0".parse().unwrap(),
+            dns_addr: "127.0.0.1:0".parse().unwrap(),
+            public_ip: Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7))),
+        })
+        .await
+        .unwrap();
+
+        let (unique_id, host) = listener.new_payload();
+
+        // Resolve the payload host
+        let mut query = vec![0x13, 0x37, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        for label in host.split('.') {
+            query.push(label.len() as u8);
+            query.extend_from_slice(label.as_bytes());
+        }
+        query.extend_from_slice(&[0, 0, 1, 0, 1]);
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket.send_to(&query, listener.dns_addr).await.unwrap();
+
+        let mut response = [0u8; 512];
+        let size = socket.recv(&mut response).await.unwrap();
+        assert_eq!(&response[..2], &[0x13, 0x37]);
+        assert_eq!(&response[size - 4..size], &[203, 0, 113, 7]);
+
+        // Fetch the payload URL
+        reqwest::Client::new()
+            .get(format!("http://{}/", listener.http_addr))
+            .header("Host", &host)
+            .send()
+            .await
+            .unwrap();
+
+        // Check result
+        let interactions = listener.poll().await.unwrap();
+        let protocols: Vec<&str> = interactions
+            .iter()
+            .map(|interaction| interaction.protocol.as_str())
+            .collect();
+
+        assert_eq!(protocols, vec!["dns", "http"]);
+        assert!(
+            interactions
+                .iter()
+                .all(|interaction| interaction.unique_id == unique_id)
+        );
+        assert!(listener.poll().await.unwrap().is_empty());
+
+        listener.close().await.unwrap();
+    }
+}
@@ -0,0 +1,192 @@
+mod interactsh;
+mod listener;
+
+use crate::modules::Finding;
+use anyhow::Result;
+use async_trait::async_trait;
+use interactsh::Interactsh;
+use listener::Listener;
+pub use listener::ListenerConfig;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Payload IDs are 33 lowercase base32 characters, as issued by interactsh
+const PAYLOAD_ID_LENGTH: usize = 33;
+const ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuv";
+
+/// A callback received on a payload host
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Interaction {
+    pub protocol: String,
+    pub unique_id: String,
+    #[serde(default)]
+    pub remote_address: String,
+    #[serde(default)]
+    pub timestamp: String,
+}
+
+/// Where callbacks are received
+#[async_trait]
+trait Backend: Send + Sync {
+    /// Issue a new payload
+    ///
+    /// Returns the unique ID reported back in interactions and the host to embed in payloads
+    fn new_payload(&self) -> (String, String);
+
+    /// Fetch the interactions received since the last poll
+    async fn poll(&self) -> Result<Vec<Interaction>>;
+
+    /// Release the resources held by the backend
+    async fn close(&self) -> Result<()>;
+}
+
+type OnInteraction = Box<dyn FnOnce(&Interaction) -> Finding + Send>;
+
+/// Out-of-band (OOB) interaction tracking
+/// - Issues unique callback hosts that modules embed into their payloads
+/// - Receives callbacks through an interactsh server or a built-in HTTP/DNS listener
+/// - Turns received callbacks into the findings of the correlated payloads
+pub struct Oob {
+    backend: Box<dyn Backend>,
+    // Payload unique ID -> how to report a callback on it
+    pending: Mutex<HashMap<String, OnInteraction>>,
+}
+
+impl Oob {
+    /// Register on an interactsh-compatible server
+    ///
+    /// # Arguments
+    /// * `server` - The server URL or domain, e.g. `oast.fun`
+    /// * `token` - The server authentication token, if required
+    pub async fn connect(server: &str, token: Option<&str>) -> Result<Self> {
+        let interactsh = Interactsh::register(server, token).await?;
+        Ok(Oob::new(Box::new(interactsh)))
+    }
+
+    /// Start the built-in HTTP and DNS callback listener
+    ///
+    /// # Arguments
+    /// * `config` - The listening addresses and the domain delegated to this host
+    pub async fn listen(config: &ListenerConfig) -> Result<Self> {
+        let listener = Listener::start(config).await?;
+        Ok(Oob::new(Box::new(listener)))
+    }
+
+    fn new(backend: Box<dyn Backend>) -> Self {
+        Oob {
+            backend,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Issue a unique callback host
+    ///
+    /// # Arguments
+    /// * `on_interaction` - Builds the finding to report if the host gets a callback
+    pub fn payload_host(
+        &self,
+        on_interaction: impl FnOnce(&Interaction) -> Finding + Send + 'static,
+    ) -> String {
+        let (unique_id, host) = self.backend.new_payload();
+
+        self.pending
+            .lock()
+            .expect("OOB registry poisoned")
+            .insert(unique_id, Box::new(on_interaction));
+
+        host
+    }
+
+    /// Fetch received callbacks and return the findings of their payloads
+    pub async fn poll(&self) -> Result<Vec<Finding>> {
+        let interactions = self.backend.poll().await?;
+        Ok(self.correlate(&interactions))
+    }
+
+    /// Deregister from the interaction server or stop the listener
+    pub async fn close(&self) {
+        if let Err(e) = self.backend.close().await {
+            log::warn!("Failed to close OOB backend: {}", e);
+        }
+    }
+
+    // A payload is reported once, even if it gets several callbacks (e.g. DNS then HTTP)
+    fn correlate(&self, interactions: &[Interaction]) -> Vec<Finding> {
+        let mut pending = self.pending.lock().expect("OOB registry poisoned");
+
+        interactions
+            .iter()
+            .filter_map(|interaction| {
+                let on_interaction = pending.remove(&interaction.unique_id.to_lowercase())?;
+                Some(on_interaction(interaction))
+            })
+            .collect()
+    }
+}
+
+fn random_string(length: usize) -> String {
+    let mut rng = rand::thread_rng();
+    (0..length)
+        .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::Severity;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_correlate_should_report_each_payload_once() {
+        // Set up mock interaction server
+        let mock_server = MockServer::start_async().await;
+
+        mock_server
+            .mock_async(|when, then| {
+                when.method(POST).path("/register");
+                then.status(200);
+            })
+            .await;
+
+        let oob = Oob::connect(
+            &format!("http://{}:{}", mock_server.host(), mock_server.port()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Issue a payload and simulate a DNS then HTTP callback on it
+        let host = oob.payload_host(|interaction| {
+            Finding::new(
+                "http/ssrf_probe",
+                "SsrfCallback",
+                Severity::High,
+                "https://example.com/",
+            )
+            .with_metadata("parameter", "url")
+            .with_metadata("protocol", &interaction.protocol)
+        });
+        let unique_id = host.split('.').next().unwrap().to_string();
+
+        let interactions: Vec<Interaction> = ["dns", "http"]
+            .into_iter()
+            .map(|protocol| Interaction {
+                protocol: protocol.to_string(),
+                unique_id: unique_id.clone(),
+                remote_address: String::from("203.0.113.7"),
+                timestamp: String::new(),
+            })
+            .collect();
+
+        // Check result
+        let findings = oob.correlate(&interactions);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, "SsrfCallback");
+        assert_eq!(findings[0].metadata_str("parameter"), Some("url"));
+        assert_eq!(findings[0].metadata_str("protocol"), Some("dns"));
+    }
+}
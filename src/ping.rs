@@ -0,0 +1,164 @@
+use crate::capabilities::PingTechnique;
+
+use anyhow::Result;
+use futures::future;
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+// Ports knocked on by TCP pings, a refused connection also giving the host away
+const TCP_PING_PORTS: &[u16] = &[443, 80];
+const ECHO_REQUEST: u8 = 8;
+const ECHO_REPLY: u8 = 0;
+
+/// Whether `ip` answers a ping
+/// - ICMP pings fall back to TCP pings for hosts dropping ICMP, and IPv6 hosts
+pub async fn alive(ip: IpAddr, technique: PingTechnique, timeout: Duration) -> bool {
+    if let (PingTechnique::Icmp, IpAddr::V4(ip)) = (technique, ip) {
+        match echo(ip, timeout).await {
+            Ok(true) => return true,
+            Ok(false) => {}
+            Err(e) => log::debug!("Failed to ping {}: {}", ip, e),
+        }
+    }
+
+    let knocks = TCP_PING_PORTS.iter().map(|&port| async move {
+        match tokio::time::timeout(timeout, TcpStream::connect((ip, port))).await {
+            Ok(Ok(_stream)) => true,
+            Ok(Err(e)) => e.kind() == ErrorKind::ConnectionRefused,
+            Err(_) => false,
+        }
+    });
+
+    future::join_all(knocks)
+        .await
+        .into_iter()
+        .any(|answered| answered)
+}
+
+/// An ICMP socket, unprivileged where the system allows it (Linux ping sockets, macOS), raw
+/// otherwise
+#[cfg(unix)]
+pub fn icmp_socket() -> Result<socket2::Socket> {
+    use socket2::Domain;
+    use socket2::Protocol;
+    use socket2::Socket;
+    use socket2::Type;
+
+    Ok(
+        Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::ICMPV4))
+            .or_else(|_| Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::ICMPV4)))?,
+    )
+}
+
+/// Windows only lets administrators open raw ICMP sockets, pinging with TCP is enough there
+#[cfg(not(unix))]
+pub fn icmp_socket() -> Result<socket2::Socket> {
+    anyhow::bail!("ICMP sockets are not supported on this system")
+}
+
+/// Whether `ip` replies to an ICMP echo request within `timeout`
+#[cfg(unix)]
+async fn echo(ip: Ipv4Addr, timeout: Duration) -> Result<bool> {
+    use socket2::SockAddr;
+    use socket2::Socket;
+    use std::io::Read;
+    use std::net::SocketAddrV4;
+    use tokio::io::Interest;
+    use tokio::io::unix::AsyncFd;
+
+    // Connected, so that only replies of `ip` are read
+    let socket = icmp_socket()?;
+    socket.connect(&SockAddr::from(SocketAddrV4::new(ip, 0)))?;
+    socket.set_nonblocking(true)?;
+    let socket = AsyncFd::new(socket)?;
+
+    let sequence: u16 = rand::random();
+    let request = echo_request(rand::random(), sequence);
+    socket
+        .async_io(Interest::WRITABLE, |socket| socket.send(&request))
+        .await?;
+
+    let reply = async {
+        let mut buffer = [0; 1500];
+        loop {
+            let length = socket
+                .async_io(Interest::READABLE, |socket| {
+                    let mut socket: &Socket = socket;
+                    socket.read(&mut buffer)
+                })
+                .await?;
+            if is_echo_reply(&buffer[..length], sequence) {
+                return Ok::<_, std::io::Error>(());
+            }
+        }
+    };
+
+    Ok(matches!(
+        tokio::time::timeout(timeout, reply).await,
+        Ok(Ok(()))
+    ))
+}
+
+#[cfg(not(unix))]
+async fn echo(_: Ipv4Addr, _: Duration) -> Result<bool> {
+    icmp_socket().map(|_| false)
+}
+
+#[cfg_attr(not(unix), allow(dead_code))]
+fn echo_request(identifier: u16, sequence: u16) -> Vec<u8> {
+    let mut request = vec![ECHO_REQUEST, 0, 0, 0];
+    request.extend(identifier.to_be_bytes());
+    request.extend(sequence.to_be_bytes());
+    request.extend(b"vulnscan");
+
+    let checksum = crate::syn::checksum(&[&request]);
+    request[2..4].copy_from_slice(&checksum.to_be_bytes());
+    request
+}
+
+/// Whether a packet is the reply to the request numbered `sequence`
+/// - Raw sockets read the IP header, Linux ping sockets only the ICMP message and rewrite its
+///   identifier, so only the sequence number is compared
+#[cfg_attr(not(unix), allow(dead_code))]
+fn is_echo_reply(packet: &[u8], sequence: u16) -> bool {
+    let message = match packet.first() {
+        Some(byte) if byte >> 4 == 4 => packet.get(usize::from(byte & 0x0f) * 4..),
+        _ => Some(packet),
+    };
+
+    message.is_some_and(|message| {
+        message.len() >= 8
+            && message[0] == ECHO_REPLY
+            && u16::from_be_bytes([message[6], message[7]]) == sequence
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_echo_reply_should_match_sequence_with_or_without_ip_header() {
+        let mut reply = echo_request(7, 42);
+        reply[0] = ECHO_REPLY;
+        assert!(is_echo_reply(&reply, 42));
+        assert!(!is_echo_reply(&reply, 43));
+        assert!(!is_echo_reply(&echo_request(7, 42), 42));
+
+        let mut packet = vec![
+            0x45, 0, 0, 36, 0, 0, 0, 0, 64, 1, 0, 0, 127, 0, 0, 1, 127, 0, 0, 1,
+        ];
+        packet.extend(&reply);
+        assert!(is_echo_reply(&packet, 42));
+    }
+
+    #[tokio::test]
+    async fn test_alive_should_count_refused_connections() {
+        // Ports nothing listens on refuse connections, which still gives the host away
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        assert!(alive(ip, PingTechnique::Tcp, Duration::from_secs(1)).await);
+    }
+}
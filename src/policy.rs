@@ -0,0 +1,268 @@
+use crate::annotations::Annotation;
+use crate::artifact::Domain;
+use crate::environment::host_of;
+use crate::export::Record;
+use crate::i18n::Message;
+use crate::modules::Severity;
+use crate::scope::Rule;
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+
+/// Exposure accepted per host, anything beyond it is a violation
+#[derive(Debug)]
+pub struct Policy {
+    hosts: Vec<HostPolicy>,
+}
+
+#[derive(Debug)]
+struct HostPolicy {
+    pattern: String,
+    // None for `*`, matching every host
+    rule: Option<Rule>,
+    ports: Option<Vec<u16>>,
+    forbidden: Vec<String>,
+    max_severity: Option<Severity>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    hosts: Vec<HostPolicyFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HostPolicyFile {
+    pattern: String,
+    /// Ports allowed to be open, any port when absent
+    ports: Option<Vec<u16>>,
+    /// Kinds of findings never accepted, e.g. `GitConfigLeakage`
+    #[serde(default)]
+    forbidden: Vec<String>,
+    /// The highest severity accepted, e.g. `low`
+    max_severity: Option<Severity>,
+}
+
+/// Exposure a policy does not accept
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    /// The pattern of the host policy violated
+    pub pattern: String,
+    pub message: Message,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            Message::new("policy-violation")
+                .arg("violation", self.message.to_string())
+                .arg("pattern", self.pattern.clone())
+        )
+    }
+}
+
+impl Policy {
+    /// Load a policy file, written in YAML
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read policy file {}", path.display()))?;
+
+        Self::parse(&content)
+            .with_context(|| format!("Failed to parse policy file {}", path.display()))
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let file: PolicyFile = serde_yaml_ng::from_str(content)?;
+        if file.hosts.is_empty() {
+            bail!("The policy defines no hosts");
+        }
+
+        let hosts = file
+            .hosts
+            .into_iter()
+            .map(|host| {
+                let rule = match host.pattern.trim() {
+                    "*" => None,
+                    pattern => Some(Rule::parse(pattern)?),
+                };
+                Ok(HostPolicy {
+                    pattern: host.pattern,
+                    rule,
+                    ports: host.ports,
+                    forbidden: host.forbidden,
+                    max_severity: host.max_severity,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Policy { hosts })
+    }
+
+    /// The policy of `host`, the first whose pattern matches
+    fn of(&self, host: &str) -> Option<&HostPolicy> {
+        let host = host.to_lowercase();
+        self.hosts
+            .iter()
+            .find(|policy| policy.rule.as_ref().is_none_or(|rule| rule.matches(&host)))
+    }
+
+    /// Open ports the policies of their hosts do not allow
+    pub(crate) fn port_violations(&self, subdomains: &[Domain]) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        for subdomain in subdomains {
+            let Some(policy) = self.of(&subdomain.name) else {
+                continue;
+            };
+            let Some(allowed) = &policy.ports else {
+                continue;
+            };
+
+            for port in subdomain
+                .open_ports
+                .iter()
+                .filter(|port| !allowed.contains(port))
+            {
+                violations.push(Violation {
+                    pattern: policy.pattern.clone(),
+                    message: Message::new("policy-port")
+                        .arg("host", subdomain.name.clone())
+                        .arg("port", port.to_string()),
+                });
+            }
+        }
+
+        violations
+    }
+
+    /// Findings the policies of their hosts forbid, by kind or severity
+    /// - Findings annotated as accepted risks or false positives are let through
+    pub(crate) fn finding_violations(&self, records: &[Record]) -> Vec<Violation> {
+        records
+            .iter()
+            .filter(|record| {
+                !record
+                    .annotation
+                    .as_ref()
+                    .is_some_and(Annotation::dismisses)
+            })
+            .filter_map(|record| {
+                let policy = self.of(&host_of(&record.url)?)?;
+
+                let forbidden = policy.forbidden.contains(&record.kind)
+                    || policy
+                        .max_severity
+                        .is_some_and(|max_severity| record.severity > max_severity);
+                if !forbidden {
+                    return None;
+                }
+
+                Some(Violation {
+                    pattern: policy.pattern.clone(),
+                    message: Message::new("policy-finding").arg("finding", record.to_string()),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::title;
+
+    const POLICY: &str = r#"
+hosts:
+  - pattern: "legacy.example.com"
+    ports: [21, 80, 443]
+    forbidden: [DotEnvDisclosure]
+  - pattern: "*.example.com"
+    ports: [80, 443]
+    max_severity: low
+  - pattern: "*"
+    forbidden: [GitConfigLeakage]
+"#;
+
+    #[test]
+    fn test_port_violations_should_apply_first_matching_pattern() {
+        let policy = Policy::parse(POLICY).unwrap();
+        let domain = |name: &str, open_ports: Vec<u16>| Domain {
+            name: name.to_string(),
+            ip: "203.0.113.10".parse().unwrap(),
+            open_ports,
+        };
+
+        let violations = policy.port_violations(&[
+            domain("legacy.example.com", vec![21, 443]),
+            domain("www.example.com", vec![21, 443]),
+            domain("example.org", vec![3306]),
+        ]);
+
+        assert_eq!(
+            violations,
+            vec![Violation {
+                pattern: String::from("*.example.com"),
+                message: Message::new("policy-port")
+                    .arg("host", String::from("www.example.com"))
+                    .arg("port", String::from("21")),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_finding_violations_should_accept_tolerated_exposure() {
+        let policy = Policy::parse(POLICY).unwrap();
+        let record = |kind: &'static str, url: &str, severity: Severity| Record {
+            kind: kind.to_string(),
+            module: None,
+            title: title(kind),
+            url: url.to_string(),
+            severity,
+            evidence: Vec::new(),
+            annotation: None,
+        };
+
+        let violations = policy.finding_violations(&[
+            // Accepted below the maximum severity
+            record(
+                "DirectoryListing",
+                "https://www.example.com/",
+                Severity::Low,
+            ),
+            record(
+                "WebDavExposure",
+                "https://www.example.com/",
+                Severity::Medium,
+            ),
+            // Forbidden by kind, whatever the severity
+            record(
+                "DotEnvDisclosure",
+                "https://legacy.example.com/.env",
+                Severity::High,
+            ),
+            record(
+                "GitConfigLeakage",
+                "https://example.org/.git/config",
+                Severity::Medium,
+            ),
+            record("DirectoryListing", "https://example.org/", Severity::Low),
+        ]);
+
+        let patterns: Vec<&str> = violations
+            .iter()
+            .map(|violation| violation.pattern.as_str())
+            .collect();
+        assert_eq!(patterns, vec!["*.example.com", "legacy.example.com", "*"]);
+        assert_eq!(
+            violations[0].to_string(),
+            "[Medium] WebDAV exposure: https://www.example.com/ (violates *.example.com)"
+        );
+        assert!(Policy::parse("hosts: []").is_err());
+    }
+}
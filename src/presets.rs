@@ -0,0 +1,200 @@
+use crate::action::ScanFormat;
+use crate::action::ScanOptions;
+use crate::compliance::Framework;
+use crate::modules::Tag;
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use clap::ValueEnum;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Bundles of scan options following the norms of who runs the scan, selected with `--preset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// Stay within the program scope, never disturb the target and identify the researcher
+    #[value(name = "bugbounty")]
+    BugBounty,
+    /// Run every check against owned networks, keeping their names off public sources
+    Internal,
+    /// Map the results to OWASP Top 10 and PCI DSS, within the assessed scope
+    Compliance,
+}
+
+/// Overrides of a preset, from a `[presets.<name>]` table of the configuration file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PresetConfig {
+    pub require_scope: Option<bool>,
+    pub skip_enumeration: Option<bool>,
+    pub aggressive: Option<bool>,
+    pub exclude_tags: Option<Vec<Tag>>,
+    /// Added to the headers of the preset, an empty value removing one
+    pub headers: BTreeMap<String, String>,
+    pub format: Option<ScanFormat>,
+    pub compliance: Option<Vec<Framework>>,
+}
+
+/// The scan options set by a preset
+#[derive(Debug, PartialEq)]
+pub struct Settings {
+    pub preset: Preset,
+    /// Refuse to scan without `--scope-file`
+    pub require_scope: bool,
+    pub skip_enumeration: bool,
+    pub aggressive: bool,
+    pub exclude_tags: Vec<Tag>,
+    /// Sent with every request to the targets, identifying the scan
+    pub headers: BTreeMap<String, String>,
+    /// The output format unless `--format` is given
+    pub format: ScanFormat,
+    pub compliance: Vec<Framework>,
+}
+
+impl Preset {
+    /// The settings of the preset, with the overrides of the configuration file if any
+    pub fn settings(self, overrides: Option<&PresetConfig>) -> Settings {
+        let mut settings = match self {
+            Preset::BugBounty => Settings {
+                preset: self,
+                require_scope: true,
+                skip_enumeration: false,
+                aggressive: false,
+                exclude_tags: vec![Tag::Intrusive],
+                headers: headers("vulnscan (bug bounty research)"),
+                format: ScanFormat::Lines,
+                compliance: Vec::new(),
+            },
+            Preset::Internal => Settings {
+                preset: self,
+                require_scope: false,
+                skip_enumeration: true,
+                aggressive: true,
+                exclude_tags: Vec::new(),
+                headers: headers("vulnscan (internal assessment)"),
+                format: ScanFormat::Text,
+                compliance: Vec::new(),
+            },
+            Preset::Compliance => Settings {
+                preset: self,
+                require_scope: true,
+                skip_enumeration: false,
+                aggressive: false,
+                exclude_tags: vec![Tag::Intrusive],
+                headers: headers("vulnscan (compliance scan)"),
+                format: ScanFormat::Text,
+                compliance: vec![Framework::Owasp, Framework::Pci],
+            },
+        };
+
+        let Some(overrides) = overrides else {
+            return settings;
+        };
+
+        settings.require_scope = overrides.require_scope.unwrap_or(settings.require_scope);
+        settings.skip_enumeration = overrides
+            .skip_enumeration
+            .unwrap_or(settings.skip_enumeration);
+        settings.aggressive = overrides.aggressive.unwrap_or(settings.aggressive);
+        if let Some(exclude_tags) = &overrides.exclude_tags {
+            settings.exclude_tags = exclude_tags.clone();
+        }
+        for (name, value) in &overrides.headers {
+            if value.is_empty() {
+                settings.headers.remove(name);
+            } else {
+                settings.headers.insert(name.clone(), value.clone());
+            }
+        }
+        settings.format = overrides.format.unwrap_or(settings.format);
+        if let Some(compliance) = &overrides.compliance {
+            settings.compliance = compliance.clone();
+        }
+
+        settings
+    }
+}
+
+impl Settings {
+    /// Add the settings to the options given on the command line, which still apply
+    pub fn apply(&self, options: &mut ScanOptions) -> Result<()> {
+        let name = self
+            .preset
+            .to_possible_value()
+            .expect("Presets are not skipped");
+
+        if self.require_scope && options.scope.is_none() {
+            bail!(
+                "The {} preset needs the scope of the assessment, given with --scope-file",
+                name.get_name()
+            );
+        }
+
+        options.skip_enumeration |= self.skip_enumeration;
+        options.aggressive |= self.aggressive;
+        for tag in &self.exclude_tags {
+            if !options.selection.exclude_tags.contains(tag) {
+                options.selection.exclude_tags.push(*tag);
+            }
+        }
+        for framework in &self.compliance {
+            if !options.compliance.contains(framework) {
+                options.compliance.push(*framework);
+            }
+        }
+        options.headers = header_map(&self.headers)
+            .with_context(|| format!("Invalid header of the {} preset", name.get_name()))?;
+
+        Ok(())
+    }
+}
+
+fn headers(user_agent: &str) -> BTreeMap<String, String> {
+    BTreeMap::from([(String::from("User-Agent"), user_agent.to_string())])
+}
+
+fn header_map(headers: &BTreeMap<String, String>) -> Result<HeaderMap> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            Ok((
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_should_apply_configured_overrides() {
+        let overrides: PresetConfig = toml::from_str(
+            r#"
+                exclude_tags = ["intrusive", "injection"]
+                headers = { "X-Bug-Bounty" = "alice", "User-Agent" = "" }
+            "#,
+        )
+        .unwrap();
+
+        let settings = Preset::BugBounty.settings(Some(&overrides));
+        assert!(settings.require_scope);
+        assert_eq!(settings.exclude_tags, vec![Tag::Intrusive, Tag::Injection]);
+        assert_eq!(
+            settings.headers,
+            BTreeMap::from([(String::from("X-Bug-Bounty"), String::from("alice"))])
+        );
+        assert_eq!(settings.format, ScanFormat::Lines);
+
+        assert_eq!(
+            Preset::Internal.settings(None),
+            Preset::Internal.settings(Some(&PresetConfig::default()))
+        );
+    }
+}
@@ -0,0 +1,1032 @@
+use crate::capture;
+use crate::modules;
+use crate::modules::Dependency;
+use crate::modules::Finding;
+use crate::modules::FollowUp;
+use crate::modules::HttpModule;
+use crate::modules::ParameterModule;
+use crate::modules::http::ParameterTarget;
+use crate::modules::http::ScanContext;
+use crate::watchdog::Watchdog;
+
+use anyhow::Result;
+use anyhow::bail;
+use reqwest::Client;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+
+// How many completed tasks between two appends to the checkpoint
+const CHECKPOINT_INTERVAL: usize = 500;
+// How many follow-ups deep a path may be queued from an endpoint scanned first
+const MAX_FOLLOW_UP_DEPTH: usize = 2;
+// Follow-up endpoints queued per run at most, so that a crawlable tree cannot grow the scan
+const MAX_FOLLOW_UPS: usize = 100;
+
+/// A module to run
+enum Task {
+    /// An HTTP module against an endpoint
+    Endpoint {
+        module: usize,
+        context: Arc<ScanContext>,
+    },
+    /// An injection module against a parameter mined on an endpoint
+    Parameter {
+        module: usize,
+        context: Arc<ScanContext>,
+        target: Arc<ParameterTarget>,
+    },
+}
+
+/// Tasks waiting for a worker, and how many taken by one are still running
+#[derive(Default)]
+struct Pending {
+    tasks: VecDeque<Task>,
+    // Running tasks may still queue dependents and follow-ups
+    in_flight: usize,
+}
+
+/// Progress of the vulnerability stage, persisted to resume interrupted scans
+#[derive(Default)]
+struct Checkpoint {
+    target: String,
+    // `module@endpoint` keys of finished tasks
+    completed: HashSet<String>,
+    // Keys of finished tasks which reported a finding, for the modules depending on them
+    fired: HashSet<String>,
+    // Endpoints queued by findings, with how many follow-ups deep they are
+    follow_ups: BTreeMap<String, usize>,
+    findings: Vec<Finding>,
+    // Entries recorded since the last save
+    unsaved: Vec<Entry>,
+}
+
+/// A line of the checkpoint file, which is appended to rather than rewritten
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Entry {
+    /// First line, the scanned target
+    Target {
+        target: String,
+    },
+    /// A finished task, `fired` when it reported a finding
+    Completed {
+        key: String,
+        fired: bool,
+    },
+    Finding {
+        finding: Box<Finding>,
+    },
+    /// An endpoint queued by a finding, with how many follow-ups deep it is
+    FollowUp {
+        endpoint: String,
+        depth: usize,
+    },
+}
+
+impl Checkpoint {
+    fn new(target: &str) -> Self {
+        Checkpoint {
+            target: target.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Apply an entry, keeping it for the next save
+    fn record(&mut self, entry: Entry) {
+        self.apply(&entry);
+        self.unsaved.push(entry);
+    }
+
+    fn apply(&mut self, entry: &Entry) {
+        match entry {
+            Entry::Target { target } => self.target = target.clone(),
+            Entry::Completed { key, fired } => {
+                self.completed.insert(key.clone());
+                if *fired {
+                    self.fired.insert(key.clone());
+                }
+            }
+            Entry::Finding { finding } => self.findings.push(finding.as_ref().clone()),
+            Entry::FollowUp { endpoint, depth } => {
+                self.follow_ups.insert(endpoint.clone(), *depth);
+            }
+        }
+    }
+
+    /// Every entry of the progress so far, starting a new checkpoint file
+    fn snapshot(&self) -> Vec<Entry> {
+        let target = Entry::Target {
+            target: self.target.clone(),
+        };
+        let completed = self.completed.iter().map(|key| Entry::Completed {
+            key: key.clone(),
+            fired: self.fired.contains(key),
+        });
+        let findings = self.findings.iter().map(|finding| Entry::Finding {
+            finding: Box::new(finding.clone()),
+        });
+        let follow_ups = self
+            .follow_ups
+            .iter()
+            .map(|(endpoint, depth)| Entry::FollowUp {
+                endpoint: endpoint.clone(),
+                depth: *depth,
+            });
+
+        std::iter::once(target)
+            .chain(completed)
+            .chain(findings)
+            .chain(follow_ups)
+            .collect()
+    }
+}
+
+/// Queue running every (endpoint x module) and (parameter x injection module) task of the
+/// vulnerability stage
+/// - `workers` workers are spawned on the runtime, each taking the next task from a shared queue
+/// - Idle workers wait while the queue is empty until no task is running, as running tasks may
+///   still queue more
+/// - Progress is appended to the checkpoint every `CHECKPOINT_INTERVAL` completions when a path
+///   is given, off the executor
+/// - Tasks of modules depending on findings wait for the tasks of the same endpoint they depend
+///   on, and are dropped unless all of them reported a finding
+/// - Follow-ups of findings queue the tasks of new endpoints while the queue runs, up to
+///   `MAX_FOLLOW_UP_DEPTH` deep
+pub struct TaskQueue {
+    modules: Vec<Box<dyn HttpModule>>,
+    parameter_modules: Vec<Box<dyn ParameterModule>>,
+    workers: usize,
+    checkpoint_path: Option<PathBuf>,
+    // Modules whose findings each module depends on, `None` when one of them does not run
+    prerequisites: Vec<Option<Vec<usize>>>,
+    // Held while saving, so that entries are appended in order
+    saving: tokio::sync::Mutex<()>,
+}
+
+/// State of a run, shared by its workers
+struct Run {
+    queue: Mutex<Pending>,
+    // Notified when tasks are queued or finish
+    idle: Notify,
+    checkpoint: Mutex<Checkpoint>,
+    // Endpoint -> modules waiting for the tasks they depend on
+    blocked: Mutex<HashMap<String, Vec<usize>>>,
+    // Endpoints given to the run, never queued again as follow-ups
+    scanned: HashSet<String>,
+    done: AtomicUsize,
+    total: AtomicUsize,
+}
+
+impl TaskQueue {
+    pub fn new(
+        modules: Vec<Box<dyn HttpModule>>,
+        workers: usize,
+        checkpoint_path: Option<&Path>,
+    ) -> Self {
+        let prerequisites = modules
+            .iter()
+            .map(|module| {
+                modules::dependencies(&module.name())
+                    .iter()
+                    .filter_map(|dependency| match dependency {
+                        Dependency::Finding(name) => Some(*name),
+                        Dependency::Technology(_) => None,
+                    })
+                    .map(|name| modules.iter().position(|module| module.name() == name))
+                    .collect()
+            })
+            .collect();
+
+        TaskQueue {
+            modules,
+            parameter_modules: Vec::new(),
+            workers: workers.max(1),
+            checkpoint_path: checkpoint_path.map(Path::to_path_buf),
+            prerequisites,
+            saving: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Also inject payloads into the parameters given to `run`
+    pub fn with_parameter_modules(mut self, modules: Vec<Box<dyn ParameterModule>>) -> Self {
+        self.parameter_modules = modules;
+        self
+    }
+
+    /// Run the modules against the endpoints and parameters, skipping tasks completed by a
+    /// previous run
+    ///
+    /// # Arguments
+    /// * `target` - The scanned domain, checkpoints of other targets are ignored
+    /// * `http_client` - The HTTP client to use
+    /// * `endpoints` - The endpoints to scan, with what was detected about them
+    /// * `parameters` - The parameters mined on each endpoint, for the injection modules
+    /// * `watchdog` - Gives up on hung tasks, cancelled ones are left for the next run
+    pub async fn run(
+        self: &Arc<Self>,
+        target: &str,
+        http_client: &Client,
+        endpoints: &[ScanContext],
+        parameters: &[(ScanContext, Vec<ParameterTarget>)],
+        watchdog: &Arc<Watchdog>,
+    ) -> Vec<Finding> {
+        let checkpoint = self.load_checkpoint(target);
+
+        let mut tasks = VecDeque::new();
+        let mut total = 0;
+        let mut blocked = HashMap::new();
+
+        // Endpoints followed up on by a previous run are queued again, from the port they are on
+        let followed_up = checkpoint.follow_ups.keys().filter_map(|endpoint| {
+            endpoints.iter().find_map(|port| {
+                let authority = format!("{}:{}", port.host, port.port);
+                let base_path = endpoint.strip_prefix(&authority)?;
+                base_path
+                    .starts_with('/')
+                    .then(|| port.with_base_path(base_path))
+            })
+        });
+        let contexts: Vec<ScanContext> = endpoints.iter().cloned().chain(followed_up).collect();
+
+        for context in contexts {
+            let context = Arc::new(context);
+            let (ready, queued) = self.plan(&checkpoint, &context, &mut blocked);
+            for module in ready {
+                tasks.push_back(Task::Endpoint {
+                    module,
+                    context: context.clone(),
+                });
+            }
+            total += queued;
+        }
+
+        for (context, targets) in parameters {
+            let context = Arc::new(context.clone());
+            for parameter in targets {
+                let parameter = Arc::new(parameter.clone());
+                for (module, parameter_module) in self.parameter_modules.iter().enumerate() {
+                    let key = task_key(&parameter_module.name(), &parameter_key(&parameter));
+                    if checkpoint.completed.contains(&key) {
+                        continue;
+                    }
+                    tasks.push_back(Task::Parameter {
+                        module,
+                        context: context.clone(),
+                        target: parameter.clone(),
+                    });
+                    total += 1;
+                }
+            }
+        }
+
+        log::info!(
+            "{} tasks queued on {} workers ({} already completed)",
+            total,
+            self.workers,
+            checkpoint.completed.len()
+        );
+
+        let run = Arc::new(Run {
+            queue: Mutex::new(Pending {
+                tasks,
+                in_flight: 0,
+            }),
+            idle: Notify::new(),
+            checkpoint: Mutex::new(checkpoint),
+            blocked: Mutex::new(blocked),
+            scanned: endpoints
+                .iter()
+                .map(|context| context.endpoint.clone())
+                .collect(),
+            done: AtomicUsize::new(0),
+            total: AtomicUsize::new(total),
+        });
+
+        let mut workers = JoinSet::new();
+        for _ in 0..self.workers {
+            // Workers capture exchanges if the scan does
+            workers.spawn(capture::propagate(self.clone().work(
+                run.clone(),
+                http_client.clone(),
+                watchdog.clone(),
+            )));
+        }
+        while let Some(worker) = workers.join_next().await {
+            if let Err(e) = worker
+                && e.is_panic()
+            {
+                std::panic::resume_unwind(e.into_panic());
+            }
+        }
+
+        self.save_checkpoint(&run.checkpoint).await;
+
+        std::mem::take(&mut run.checkpoint.lock().expect("Checkpoint poisoned").findings)
+    }
+
+    /// Run the tasks of the queue until it is drained or the scan is cancelled
+    async fn work(self: Arc<Self>, run: Arc<Run>, http_client: Client, watchdog: Arc<Watchdog>) {
+        while let Some(task) = next_task(&run.queue, &run.idle).await {
+            let (name, subject, execution) = match &task {
+                Task::Endpoint { module, context } => {
+                    let module = &self.modules[*module];
+                    (
+                        module.name(),
+                        context.endpoint.clone(),
+                        module.scan(&http_client, context),
+                    )
+                }
+                Task::Parameter {
+                    module,
+                    context,
+                    target,
+                } => {
+                    let module = &self.parameter_modules[*module];
+                    (
+                        module.name(),
+                        parameter_key(target),
+                        module.inject(&http_client, context, target),
+                    )
+                }
+            };
+            let result = watchdog
+                .guard(&name, &subject, capture::attach(execution))
+                .await;
+            if watchdog.is_cancelled() {
+                run.queue.lock().expect("Queue poisoned").in_flight -= 1;
+                run.idle.notify_waiters();
+                break;
+            }
+
+            // Locks are released before the checkpoint is saved
+            {
+                let key = task_key(&name, &subject);
+                let mut checkpoint = run.checkpoint.lock().expect("Checkpoint poisoned");
+
+                let mut follow_ups = Vec::new();
+                match result {
+                    Some(Ok(Some(mut finding))) => {
+                        follow_ups = std::mem::take(&mut finding.follow_ups);
+                        checkpoint.record(Entry::Completed { key, fired: true });
+                        checkpoint.record(Entry::Finding {
+                            finding: Box::new(finding),
+                        });
+                    }
+                    Some(Ok(None)) | None => {
+                        checkpoint.record(Entry::Completed { key, fired: false });
+                    }
+                    Some(Err(err)) => {
+                        log::debug!("Error: {}", err);
+                        checkpoint.record(Entry::Completed { key, fired: false });
+                    }
+                }
+
+                let mut blocked = run.blocked.lock().expect("Blocked tasks poisoned");
+                let mut queue = run.queue.lock().expect("Queue poisoned");
+                queue.in_flight -= 1;
+
+                // Dependent tasks of the endpoint run next, tasks of the endpoints followed up on
+                // are queued last
+                if let Task::Endpoint { context, .. } = &task {
+                    if let Some(waiting) = blocked.get_mut(&context.endpoint) {
+                        for module in self.unblock(&checkpoint, &context.endpoint, waiting) {
+                            queue.tasks.push_front(Task::Endpoint {
+                                module,
+                                context: context.clone(),
+                            });
+                        }
+                    }
+
+                    let depth = checkpoint
+                        .follow_ups
+                        .get(&context.endpoint)
+                        .map_or(1, |depth| depth + 1);
+                    for follow_up in follow_ups {
+                        let FollowUp::Path(path) = follow_up;
+                        let next = Arc::new(context.with_base_path(&path));
+
+                        if depth > MAX_FOLLOW_UP_DEPTH
+                            || checkpoint.follow_ups.len() >= MAX_FOLLOW_UPS
+                            || run.scanned.contains(&next.endpoint)
+                            || checkpoint.follow_ups.contains_key(&next.endpoint)
+                        {
+                            continue;
+                        }
+
+                        log::info!("Following up {} on {}", name, next.endpoint);
+                        checkpoint.record(Entry::FollowUp {
+                            endpoint: next.endpoint.clone(),
+                            depth,
+                        });
+
+                        let (ready, queued) = self.plan(&checkpoint, &next, &mut blocked);
+                        for module in ready {
+                            queue.tasks.push_back(Task::Endpoint {
+                                module,
+                                context: next.clone(),
+                            });
+                        }
+                        run.total.fetch_add(queued, Ordering::Relaxed);
+                    }
+                }
+            }
+            // Wakes the workers waiting for the tasks queued above, or for the last task to finish
+            run.idle.notify_waiters();
+
+            let done = run.done.fetch_add(1, Ordering::Relaxed) + 1;
+            if done.is_multiple_of(CHECKPOINT_INTERVAL) {
+                log::info!(
+                    "{}/{} tasks completed",
+                    done,
+                    run.total.load(Ordering::Relaxed)
+                );
+                self.save_checkpoint(&run.checkpoint).await;
+            }
+        }
+    }
+
+    /// Plan the tasks of an endpoint, returning the modules ready to run and how many tasks were
+    /// queued, the others waiting in `blocked` for the tasks they depend on
+    fn plan(
+        &self,
+        checkpoint: &Checkpoint,
+        context: &ScanContext,
+        blocked: &mut HashMap<String, Vec<usize>>,
+    ) -> (Vec<usize>, usize) {
+        let mut ready = Vec::new();
+        let mut waiting = Vec::new();
+
+        for (module, http_module) in self.modules.iter().enumerate() {
+            let name = http_module.name();
+            if checkpoint
+                .completed
+                .contains(&task_key(&name, &context.endpoint))
+            {
+                continue;
+            }
+
+            // Modules depending on technologies missing on the endpoint, or on modules not run,
+            // never run there
+            let Some(prerequisites) = &self.prerequisites[module] else {
+                continue;
+            };
+            if !modules::dependencies(&name)
+                .iter()
+                .all(|dependency| dependency.detected(context))
+            {
+                continue;
+            }
+
+            if prerequisites.is_empty() {
+                ready.push(module);
+            } else {
+                waiting.push(module);
+            }
+        }
+
+        let queued = ready.len() + waiting.len();
+
+        // Prerequisites may have completed in a previous run
+        ready.extend(self.unblock(checkpoint, &context.endpoint, &mut waiting));
+        if !waiting.is_empty() {
+            blocked.insert(context.endpoint.clone(), waiting);
+        }
+
+        (ready, queued)
+    }
+
+    /// Take the modules of `waiting` whose prerequisites completed on `endpoint`, returning the
+    /// ones whose prerequisites all reported a finding
+    fn unblock(
+        &self,
+        checkpoint: &Checkpoint,
+        endpoint: &str,
+        waiting: &mut Vec<usize>,
+    ) -> Vec<usize> {
+        let mut ready = Vec::new();
+
+        waiting.retain(|&module| {
+            let prerequisites = self.prerequisites[module].as_deref().unwrap_or_default();
+            let keys: Vec<String> = prerequisites
+                .iter()
+                .map(|&prerequisite| task_key(&self.modules[prerequisite].name(), endpoint))
+                .collect();
+
+            if !keys.iter().all(|key| checkpoint.completed.contains(key)) {
+                return true;
+            }
+            if keys.iter().all(|key| checkpoint.fired.contains(key)) {
+                ready.push(module);
+            }
+            false
+        });
+
+        ready
+    }
+
+    /// Load the checkpoint of `target`, whose entries are all written again on the first save
+    /// - The new file drops a line truncated by an interruption, or the progress of another target
+    fn load_checkpoint(&self, target: &str) -> Checkpoint {
+        let mut checkpoint = Checkpoint::new(target);
+
+        if let Some(path) = self.checkpoint_path.as_ref().filter(|path| path.exists()) {
+            match read_checkpoint(path) {
+                Ok(previous) if previous.target == target => {
+                    log::info!("Resuming from checkpoint {}", path.display());
+                    checkpoint = previous;
+                }
+                Ok(_) => log::warn!("Ignoring checkpoint {} of another target", path.display()),
+                Err(e) => log::warn!("Ignoring checkpoint {}: {}", path.display(), e),
+            }
+        }
+
+        checkpoint.unsaved = checkpoint.snapshot();
+        checkpoint
+    }
+
+    /// Append the entries recorded since the last save, writing the file in a blocking task
+    async fn save_checkpoint(&self, checkpoint: &Mutex<Checkpoint>) {
+        let Some(path) = &self.checkpoint_path else {
+            return;
+        };
+
+        let _saving = self.saving.lock().await;
+        let entries = std::mem::take(&mut checkpoint.lock().expect("Checkpoint poisoned").unsaved);
+        if entries.is_empty() {
+            return;
+        }
+
+        let file = path.clone();
+        let written = tokio::task::spawn_blocking(move || write_checkpoint(&file, &entries)).await;
+        if let Err(e) = written
+            .map_err(anyhow::Error::from)
+            .and_then(|written| written)
+        {
+            log::warn!("Failed to write checkpoint {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn task_key(module: &str, endpoint: &str) -> String {
+    format!("{}@{}", module, endpoint)
+}
+
+/// A mined parameter as in checkpoints and logs, e.g. `https://www.example.com/search?q`
+fn parameter_key(target: &ParameterTarget) -> String {
+    format!("{}?{}", target.url, target.name)
+}
+
+/// The next task to run, `None` once the queue is empty and no running task may queue more
+async fn next_task(queue: &Mutex<Pending>, idle: &Notify) -> Option<Task> {
+    loop {
+        // Registered before checking the queue, so that a notification in between is not missed
+        let mut notified = std::pin::pin!(idle.notified());
+        notified.as_mut().enable();
+
+        {
+            let mut queue = queue.lock().expect("Queue poisoned");
+            if let Some(task) = queue.tasks.pop_front() {
+                queue.in_flight += 1;
+                return Some(task);
+            }
+            if queue.in_flight == 0 {
+                return None;
+            }
+        }
+
+        notified.await;
+    }
+}
+
+/// Replay the entries of a checkpoint file, up to a line truncated by an interruption
+fn read_checkpoint(path: &Path) -> Result<Checkpoint> {
+    let content = std::fs::read_to_string(path)?;
+    let mut entries = content
+        .lines()
+        .map_while(|line| serde_json::from_str::<Entry>(line).ok());
+
+    let Some(Entry::Target { target }) = entries.next() else {
+        bail!("No target on the first line");
+    };
+
+    let mut checkpoint = Checkpoint::new(&target);
+    for entry in entries {
+        checkpoint.apply(&entry);
+    }
+    Ok(checkpoint)
+}
+
+/// Append JSON lines, a batch starting with the target replacing the file
+// The replacement is written to a temporary file first so an interruption never loses progress
+fn write_checkpoint(path: &Path, entries: &[Entry]) -> Result<()> {
+    let mut lines = Vec::new();
+    for entry in entries {
+        serde_json::to_writer(&mut lines, entry)?;
+        lines.push(b'\n');
+    }
+
+    if matches!(entries.first(), Some(Entry::Target { .. })) {
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, lines)?;
+        std::fs::rename(temporary, path)?;
+    } else {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(path)?
+            .write_all(&lines)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Message;
+    use crate::modules::Module;
+    use crate::modules::Severity;
+    use crate::modules::http::context::tests::local;
+    use async_trait::async_trait;
+    use reqwest::Url;
+    use std::time::Duration;
+    use tokio_util::sync::CancellationToken;
+
+    // Report a directory listing on the endpoints `reports` accepts, counting calls
+    struct CountingModule {
+        name: &'static str,
+        reports: fn(&ScanContext) -> bool,
+        // Directory listed under every endpoint, if any
+        lists: Option<&'static str>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Module for CountingModule {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn description(&self) -> Message {
+            Message::new("module-http-counting")
+        }
+    }
+
+    #[async_trait]
+    impl HttpModule for CountingModule {
+        async fn scan(&self, _: &Client, context: &ScanContext) -> Result<Option<Finding>> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            if !(self.reports)(context) {
+                return Ok(None);
+            }
+            let follow_ups = self
+                .lists
+                .map(|directory| FollowUp::Path(format!("{}/{}", context.base_path, directory)))
+                .into_iter()
+                .collect();
+            Ok(Some(
+                Finding::new(
+                    self.name(),
+                    "DirectoryListing",
+                    Severity::Low,
+                    &context.url("http", "/"),
+                )
+                .with_follow_ups(follow_ups),
+            ))
+        }
+    }
+
+    // Slowly list `FOLLOW_UPS` directories under the root of endpoints, tracking how many of its
+    // scans run at once
+    struct SlowModule {
+        running: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    const FOLLOW_UPS: usize = 4;
+
+    impl Module for SlowModule {
+        fn name(&self) -> String {
+            String::from("http/slow")
+        }
+
+        fn description(&self) -> Message {
+            Message::new("module-http-slow")
+        }
+    }
+
+    #[async_trait]
+    impl HttpModule for SlowModule {
+        async fn scan(&self, _: &Client, context: &ScanContext) -> Result<Option<Finding>> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+
+            if !context.is_root() {
+                return Ok(None);
+            }
+            let follow_ups = (0..FOLLOW_UPS)
+                .map(|i| FollowUp::Path(format!("/dir{}", i)))
+                .collect();
+            Ok(Some(
+                Finding::new(
+                    self.name(),
+                    "DirectoryListing",
+                    Severity::Low,
+                    &context.url("http", "/"),
+                )
+                .with_follow_ups(follow_ups),
+            ))
+        }
+    }
+
+    // Report every parameter it is injected into, counting calls
+    struct CountingInjection {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Module for CountingInjection {
+        fn name(&self) -> String {
+            String::from("http/counting_injection")
+        }
+
+        fn description(&self) -> Message {
+            Message::new("module-http-counting_injection")
+        }
+    }
+
+    #[async_trait]
+    impl ParameterModule for CountingInjection {
+        async fn inject(
+            &self,
+            _: &Client,
+            _: &ScanContext,
+            target: &ParameterTarget,
+        ) -> Result<Option<Finding>> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Ok(Some(Finding::new(
+                self.name(),
+                "ReflectedXss",
+                Severity::Medium,
+                target.url.as_str(),
+            )))
+        }
+    }
+
+    async fn run(
+        target: &str,
+        endpoints: &[ScanContext],
+        checkpoint_path: &Path,
+    ) -> (usize, usize) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let modules: Vec<Box<dyn HttpModule>> = vec![Box::new(CountingModule {
+            name: "http/counting",
+            reports: |_| true,
+            lists: None,
+            calls: calls.clone(),
+        })];
+
+        let watchdog = Arc::new(Watchdog::new(
+            Duration::from_secs(60),
+            CancellationToken::new(),
+        ));
+        let findings = Arc::new(TaskQueue::new(modules, 3, Some(checkpoint_path)))
+            .run(target, &Client::new(), endpoints, &[], &watchdog)
+            .await;
+
+        (findings.len(), calls.load(Ordering::Relaxed))
+    }
+
+    #[tokio::test]
+    async fn test_run_should_resume_from_checkpoint() {
+        let endpoints: Vec<ScanContext> = (0..10)
+            .map(|i| local(&format!("host{}.example.com:80", i)))
+            .collect();
+        let checkpoint_path =
+            std::env::temp_dir().join(format!("vulnscan-checkpoint-{}.json", std::process::id()));
+
+        // First run scans every endpoint
+        assert_eq!(
+            run("example.com", &endpoints, &checkpoint_path).await,
+            (10, 10)
+        );
+
+        // Second run restores the findings without scanning again
+        assert_eq!(
+            run("example.com", &endpoints, &checkpoint_path).await,
+            (10, 0)
+        );
+
+        // Another target starts from scratch
+        assert_eq!(
+            run("example.org", &endpoints, &checkpoint_path).await,
+            (10, 10)
+        );
+
+        std::fs::remove_file(&checkpoint_path).unwrap();
+    }
+
+    #[test]
+    fn test_read_checkpoint_should_replay_appended_entries() {
+        let checkpoint_path = std::env::temp_dir().join(format!(
+            "vulnscan-checkpoint-append-{}.jsonl",
+            std::process::id()
+        ));
+
+        let mut checkpoint = Checkpoint::new("example.com");
+        checkpoint.record(Entry::Completed {
+            key: task_key("http/a", "host0.example.com:80"),
+            fired: false,
+        });
+        write_checkpoint(&checkpoint_path, &checkpoint.snapshot()).unwrap();
+        write_checkpoint(
+            &checkpoint_path,
+            &[
+                Entry::Completed {
+                    key: task_key("http/b", "host0.example.com:80"),
+                    fired: true,
+                },
+                Entry::FollowUp {
+                    endpoint: String::from("host0.example.com:80/old"),
+                    depth: 1,
+                },
+            ],
+        )
+        .unwrap();
+        // Interrupted while appending
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&checkpoint_path)
+            .unwrap();
+        file.write_all(b"{\"type\":\"completed\",\"key\":").unwrap();
+
+        let checkpoint = read_checkpoint(&checkpoint_path).unwrap();
+
+        assert_eq!(checkpoint.target, "example.com");
+        assert_eq!(checkpoint.completed.len(), 2);
+        assert_eq!(checkpoint.fired.len(), 1);
+        assert_eq!(checkpoint.follow_ups.len(), 1);
+
+        std::fs::remove_file(&checkpoint_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_should_only_run_dependent_modules_after_findings() {
+        let endpoints: Vec<ScanContext> = (0..10)
+            .map(|i| local(&format!("host{}.example.com:80", i)))
+            .collect();
+        let (head_calls, config_calls) =
+            (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+
+        // `.git/config` is only fetched where `.git/HEAD` leaked
+        let modules: Vec<Box<dyn HttpModule>> = vec![
+            Box::new(CountingModule {
+                name: "http/git_config_leakage",
+                reports: |_| true,
+                lists: None,
+                calls: config_calls.clone(),
+            }),
+            Box::new(CountingModule {
+                name: "http/git_head_leakage",
+                reports: |context| context.host.as_str() < "host3",
+                lists: None,
+                calls: head_calls.clone(),
+            }),
+        ];
+
+        let watchdog = Arc::new(Watchdog::new(
+            Duration::from_secs(60),
+            CancellationToken::new(),
+        ));
+        let findings = Arc::new(TaskQueue::new(modules, 3, None))
+            .run("example.com", &Client::new(), &endpoints, &[], &watchdog)
+            .await;
+
+        assert_eq!(head_calls.load(Ordering::Relaxed), 10);
+        assert_eq!(config_calls.load(Ordering::Relaxed), 3);
+        assert_eq!(findings.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_run_should_follow_up_on_findings() {
+        let endpoints = vec![local("host0.example.com:80"), local("host1.example.com:80")];
+        let checkpoint_path = std::env::temp_dir().join(format!(
+            "vulnscan-checkpoint-follow-up-{}.json",
+            std::process::id()
+        ));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        // Every endpoint lists `old/`, scanned in turn until the follow-ups are too deep
+        let modules: Vec<Box<dyn HttpModule>> = vec![Box::new(CountingModule {
+            name: "http/directory_listing",
+            reports: |_| true,
+            lists: Some("old"),
+            calls: calls.clone(),
+        })];
+
+        let watchdog = Arc::new(Watchdog::new(
+            Duration::from_secs(60),
+            CancellationToken::new(),
+        ));
+        let queue = Arc::new(TaskQueue::new(modules, 3, Some(&checkpoint_path)));
+        let findings = queue
+            .run("example.com", &Client::new(), &endpoints, &[], &watchdog)
+            .await;
+
+        assert_eq!(calls.load(Ordering::Relaxed), 6);
+        assert!(
+            findings
+                .iter()
+                .any(|finding| finding.url == "http://host1.example.com:80/old/old/")
+        );
+
+        // Followed up endpoints are restored along with the checkpoint
+        let findings = queue
+            .run("example.com", &Client::new(), &endpoints, &[], &watchdog)
+            .await;
+        assert_eq!((findings.len(), calls.load(Ordering::Relaxed)), (6, 6));
+
+        std::fs::remove_file(&checkpoint_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_should_run_follow_ups_of_a_slow_task_concurrently() {
+        let (running, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let modules: Vec<Box<dyn HttpModule>> = vec![Box::new(SlowModule {
+            running,
+            peak: peak.clone(),
+        })];
+
+        // Every worker but one finds the queue empty while the root endpoint is scanned
+        let watchdog = Arc::new(Watchdog::new(
+            Duration::from_secs(60),
+            CancellationToken::new(),
+        ));
+        let findings = Arc::new(TaskQueue::new(modules, FOLLOW_UPS, None))
+            .run(
+                "example.com",
+                &Client::new(),
+                &[local("host0.example.com:80")],
+                &[],
+                &watchdog,
+            )
+            .await;
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(peak.load(Ordering::SeqCst), FOLLOW_UPS);
+    }
+
+    #[tokio::test]
+    async fn test_run_should_inject_parameters_and_resume_them() {
+        let checkpoint_path = std::env::temp_dir().join(format!(
+            "vulnscan-checkpoint-parameters-{}.json",
+            std::process::id()
+        ));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let targets: Vec<ParameterTarget> = ["q", "page", "sort"]
+            .iter()
+            .map(|name| ParameterTarget {
+                url: Url::parse("http://host0.example.com/search").unwrap(),
+                name: name.to_string(),
+                value: String::new(),
+            })
+            .collect();
+        let parameters = [(local("host0.example.com:80"), targets)];
+
+        let queue = Arc::new(
+            TaskQueue::new(Vec::new(), 3, Some(&checkpoint_path)).with_parameter_modules(vec![
+                Box::new(CountingInjection {
+                    calls: calls.clone(),
+                }),
+            ]),
+        );
+        let watchdog = Arc::new(Watchdog::new(
+            Duration::from_secs(60),
+            CancellationToken::new(),
+        ));
+
+        let findings = queue
+            .run("example.com", &Client::new(), &[], &parameters, &watchdog)
+            .await;
+        assert_eq!((findings.len(), calls.load(Ordering::Relaxed)), (3, 3));
+
+        // Injections are checkpointed like the other tasks
+        let findings = queue
+            .run("example.com", &Client::new(), &[], &parameters, &watchdog)
+            .await;
+        assert_eq!((findings.len(), calls.load(Ordering::Relaxed)), (3, 3));
+
+        std::fs::remove_file(&checkpoint_path).unwrap();
+    }
+}
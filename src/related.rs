@@ -0,0 +1,306 @@
+use crate::body;
+use crate::body::ReadBounded;
+use crate::detections;
+use crate::keys::ApiKeys;
+use crate::modules::dns::whois;
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reqwest::Client;
+use reqwest::Url;
+use scraper::Html;
+use scraper::Selector;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt;
+
+const MAX_FAVICON_SIZE: usize = 1024 * 1024;
+
+/// Something a domain shares with the other domains of its owner
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Pivot {
+    AnalyticsId(String),
+    /// MurmurHash3 of the favicon, as Shodan indexes it
+    FaviconHash(i32),
+    RegistrantOrg(String),
+}
+
+/// A domain sharing pivots with the target, a candidate for the scope rather than a target
+#[derive(Debug, Serialize)]
+pub struct Candidate {
+    pub domain: String,
+    pub pivots: Vec<Pivot>,
+}
+
+impl fmt::Display for Pivot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pivot::AnalyticsId(id) => write!(f, "analytics ID {}", id),
+            Pivot::FaviconHash(hash) => write!(f, "favicon hash {}", hash),
+            Pivot::RegistrantOrg(org) => write!(f, "registrant {}", org),
+        }
+    }
+}
+
+/// The analytics IDs and favicon of the landing page of `domain`, and its registrant
+pub async fn pivots(http_client: &Client, domain: &str) -> Vec<Pivot> {
+    let mut pivots = BTreeSet::new();
+
+    match landing_page(http_client, domain).await {
+        Ok((url, html)) => {
+            for id in detections::get().pivots.analytics_id.find_iter(&html) {
+                pivots.insert(Pivot::AnalyticsId(id.as_str().to_string()));
+            }
+            match favicon_hash(http_client, &url, &html).await {
+                Ok(hash) => {
+                    pivots.insert(Pivot::FaviconHash(hash));
+                }
+                Err(e) => log::info!("No favicon of {}: {:#}", domain, e),
+            }
+        }
+        Err(e) => log::warn!("Failed to fetch the landing page of {}: {:#}", domain, e),
+    }
+
+    match whois::lookup(domain).await {
+        Ok(registration) => {
+            if let Some(registrant) = registration.registrant {
+                pivots.insert(Pivot::RegistrantOrg(registrant));
+            }
+        }
+        Err(e) => log::warn!("Failed to look up the registrant of {}: {:#}", domain, e),
+    }
+
+    pivots.into_iter().collect()
+}
+
+/// Domains sharing `pivots`, other than `domain` and its subdomains, sorted
+/// - Analytics IDs are looked up on HackerTarget, favicons on Shodan, registrants on Whoxy
+/// - Pivots needing a missing API key are skipped
+pub async fn candidates(
+    http_client: &Client,
+    api_keys: &ApiKeys,
+    domain: &str,
+    pivots: &[Pivot],
+) -> Vec<Candidate> {
+    let mut candidates: BTreeMap<String, Vec<Pivot>> = BTreeMap::new();
+
+    for pivot in pivots {
+        let siblings = match pivot {
+            Pivot::AnalyticsId(id) => analytics_siblings(http_client, id).await,
+            Pivot::FaviconHash(hash) => favicon_siblings(http_client, api_keys, *hash).await,
+            Pivot::RegistrantOrg(org) => registrant_siblings(http_client, api_keys, org).await,
+        };
+
+        match siblings {
+            Ok(siblings) => {
+                for sibling in siblings {
+                    let sibling = sibling.trim_end_matches('.').to_lowercase();
+                    if sibling != domain && !sibling.ends_with(&format!(".{}", domain)) {
+                        candidates.entry(sibling).or_default().push(pivot.clone());
+                    }
+                }
+            }
+            Err(e) => log::warn!("Skipping {}: {:#}", pivot, e),
+        }
+    }
+
+    candidates
+        .into_iter()
+        .map(|(domain, pivots)| Candidate { domain, pivots })
+        .collect()
+}
+
+async fn landing_page(http_client: &Client, domain: &str) -> Result<(Url, String)> {
+    let mut last_error = None;
+    for scheme in ["https", "http"] {
+        match http_client
+            .get(format!("{}://{}/", scheme, domain))
+            .send()
+            .await
+        {
+            Ok(resp) => {
+                let url = resp.url().clone();
+                return Ok((url, resp.text_bounded(body::DEFAULT_MAX_BODY_SIZE).await?));
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.context("No scheme to try")?.into())
+}
+
+/// The favicon declared by the page at `url`, or `/favicon.ico`
+async fn favicon_hash(http_client: &Client, url: &Url, html: &str) -> Result<i32> {
+    let icon = Selector::parse("link[rel~=icon][href]").expect("Failed to parse selector");
+    let href = Html::parse_document(html)
+        .select(&icon)
+        .find_map(|link| link.value().attr("href").map(str::to_string));
+    let favicon = url.join(href.as_deref().unwrap_or("/favicon.ico"))?;
+
+    let resp = http_client.get(favicon).send().await?.error_for_status()?;
+    let bytes = resp.bytes_limited(MAX_FAVICON_SIZE).await?;
+    if bytes.is_empty() {
+        bail!("The favicon is empty");
+    }
+    Ok(shodan_hash(&bytes))
+}
+
+/// Shodan hashes favicons base64 encoded in lines of 76 characters, like Python's `encodebytes`
+fn shodan_hash(bytes: &[u8]) -> i32 {
+    let encoded = BASE64.encode(bytes);
+    let mut lines = String::new();
+    for chunk in encoded.as_bytes().chunks(76) {
+        lines.push_str(&String::from_utf8_lossy(chunk));
+        lines.push('\n');
+    }
+    murmur3_32(lines.as_bytes(), 0) as i32
+}
+
+/// MurmurHash3 x86 32-bit
+fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e2d51;
+    const C2: u32 = 0x1b873593;
+
+    let mut hash = seed;
+    let mut blocks = data.chunks_exact(4);
+    for block in &mut blocks {
+        let k = u32::from_le_bytes([block[0], block[1], block[2], block[3]])
+            .wrapping_mul(C1)
+            .rotate_left(15)
+            .wrapping_mul(C2);
+        hash = (hash ^ k)
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe6546b64);
+    }
+
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .rev()
+            .fold(0u32, |k, byte| (k << 8) | u32::from(*byte))
+            .wrapping_mul(C1)
+            .rotate_left(15)
+            .wrapping_mul(C2);
+        hash ^= k;
+    }
+
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85ebca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2ae35);
+    hash ^ (hash >> 16)
+}
+
+/// HackerTarget answers one domain per line, or an error message
+async fn analytics_siblings(http_client: &Client, id: &str) -> Result<Vec<String>> {
+    let body = http_client
+        .get("https://api.hackertarget.com/analyticslookup/")
+        .query(&[("q", id)])
+        .send()
+        .await?
+        .error_for_status()?
+        .text_limited(body::MAX_API_RESPONSE_SIZE)
+        .await?;
+
+    let domains: Vec<String> = body
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.contains('.') && !line.contains(char::is_whitespace))
+        .map(str::to_string)
+        .collect();
+    if domains.is_empty() && !body.trim().is_empty() {
+        bail!("HackerTarget answered: {}", body.trim());
+    }
+    Ok(domains)
+}
+
+async fn favicon_siblings(
+    http_client: &Client,
+    api_keys: &ApiKeys,
+    hash: i32,
+) -> Result<Vec<String>> {
+    let key = api_keys
+        .get("shodan")
+        .context("Needs the API key of shodan, set with `vulnscan keys set shodan`")?;
+
+    let results: Value = http_client
+        .get("https://api.shodan.io/shodan/host/search")
+        .query(&[
+            ("key", key),
+            ("query", &format!("http.favicon.hash:{}", hash)),
+            ("minify", "true"),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json_limited(body::MAX_API_RESPONSE_SIZE)
+        .await?;
+
+    Ok(results["matches"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|host| host["domains"].as_array().into_iter().flatten())
+        .filter_map(|domain| domain.as_str().map(str::to_string))
+        .collect())
+}
+
+async fn registrant_siblings(
+    http_client: &Client,
+    api_keys: &ApiKeys,
+    org: &str,
+) -> Result<Vec<String>> {
+    let key = api_keys
+        .get("whoxy")
+        .context("Needs the API key of whoxy, set with `vulnscan keys set whoxy`")?;
+
+    let results: Value = http_client
+        .get("https://api.whoxy.com/")
+        .query(&[("key", key), ("reverse", "whois"), ("company", org)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json_limited(body::MAX_API_RESPONSE_SIZE)
+        .await?;
+
+    if results["status"] != 1 {
+        bail!(
+            "Whoxy answered: {}",
+            results["status_reason"].as_str().unwrap_or("an error")
+        );
+    }
+    Ok(results["search_result"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|result| result["domain_name"].as_str().map(str::to_string))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shodan_hash_should_match_mmh3() {
+        assert_eq!(murmur3_32(b"hello", 0) as i32, 613153351);
+        assert_eq!(murmur3_32(b"foo", 0) as i32, -156908512);
+        assert_eq!(murmur3_32(b"", 0), 0);
+
+        // Lines are cut every 76 characters and always end with a newline
+        let icon = vec![0u8; 100];
+        let encoded = BASE64.encode(&icon);
+        let expected = format!("{}\n{}\n", &encoded[..76], &encoded[76..]);
+        assert_eq!(
+            shodan_hash(&icon),
+            murmur3_32(expected.as_bytes(), 0) as i32
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::body;
+use crate::body::ReadBounded;
+use crate::modules::Severity;
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reqwest::Client;
+use reqwest::Method;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+// Share of identical lines above which a changed response still counts as the recorded one
+const SAME_RESPONSE_SIMILARITY: f64 = 0.9;
+
+#[derive(Deserialize)]
+struct Har {
+    log: Log,
+}
+
+#[derive(Deserialize)]
+struct Log {
+    entries: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    request: Request,
+    response: Response,
+    /// Set by `--har`, entries of other tools being skipped
+    #[serde(rename = "_finding")]
+    finding: Option<StoredFinding>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: String,
+    url: String,
+    headers: Vec<Header>,
+    post_data: Option<PostData>,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    name: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct PostData {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    status: u16,
+    content: Content,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    #[serde(default)]
+    text: String,
+    encoding: Option<String>,
+}
+
+/// A finding as `--har` stored it
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StoredFinding {
+    pub id: String,
+    pub module: String,
+    pub kind: String,
+    pub url: String,
+    pub severity: Severity,
+}
+
+/// A finding with the request it was observed in and the response recorded then
+pub struct Stored {
+    pub finding: StoredFinding,
+    request: Request,
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+/// Whether the response to the replayed request still looks like the recorded one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// Same status, and the same body give or take dynamic parts
+    Reproduced,
+    /// Same status but a different body, e.g. a reflected payload now encoded
+    Changed,
+    /// Another status, e.g. the exposed file is now missing or forbidden
+    NotReproduced,
+}
+
+/// The findings stored in a HAR file written by `--har`
+pub fn load(path: &Path) -> Result<Vec<Stored>> {
+    let contents =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let har: Har = serde_json::from_slice(&contents)
+        .with_context(|| format!("{} is not a HAR file", path.display()))?;
+
+    har.log
+        .entries
+        .into_iter()
+        .filter_map(|entry| Some((entry.finding?, entry.request, entry.response)))
+        .map(|(finding, request, response)| {
+            let body = match response.content.encoding.as_deref() {
+                Some("base64") => BASE64
+                    .decode(&response.content.text)
+                    .context("Invalid base64 response body")?,
+                _ => response.content.text.into_bytes(),
+            };
+            Ok(Stored {
+                finding,
+                request,
+                status: response.status,
+                body,
+            })
+        })
+        .collect()
+}
+
+/// The finding whose ID starts with `id`
+pub fn find<'a>(stored: &'a [Stored], id: &str) -> Result<&'a Stored> {
+    let matches: Vec<&Stored> = stored
+        .iter()
+        .filter(|stored| stored.finding.id.starts_with(&id.to_lowercase()))
+        .collect();
+    match matches[..] {
+        [stored] => Ok(stored),
+        [] => bail!("No finding {} in the HAR file", id),
+        _ => bail!(
+            "{} findings start with {}, give more of the ID",
+            matches.len(),
+            id
+        ),
+    }
+}
+
+/// Re-issue the exact request of a stored finding, returning the status and body it gets now
+pub async fn replay(http_client: &Client, stored: &Stored) -> Result<(u16, Vec<u8>)> {
+    let request = &stored.request;
+    let method = Method::from_bytes(request.method.as_bytes()).context("Invalid method")?;
+
+    let mut builder = http_client.request(method, &request.url);
+    for header in &request.headers {
+        // Set by the client from the URL and body
+        if !header.name.eq_ignore_ascii_case("host")
+            && !header.name.eq_ignore_ascii_case("content-length")
+        {
+            builder = builder.header(&header.name, &header.value);
+        }
+    }
+    if let Some(post_data) = &request.post_data {
+        builder = builder.body(post_data.text.clone());
+    }
+
+    let resp = builder
+        .send()
+        .await
+        .with_context(|| format!("Failed to replay the request to {}", request.url))?;
+    let status = resp.status().as_u16();
+    // As much as was recorded, which `--max-body-size` may have raised
+    let limit = stored.body.len().max(body::DEFAULT_MAX_BODY_SIZE);
+    let body = resp.bytes_limited(limit).await?;
+    Ok((status, body))
+}
+
+/// Compare the response received now with the recorded one
+pub fn compare(stored: &Stored, status: u16, body: &[u8]) -> Outcome {
+    if status != stored.status {
+        return Outcome::NotReproduced;
+    }
+
+    let lines = |body: &[u8]| -> BTreeSet<String> {
+        String::from_utf8_lossy(body)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .collect()
+    };
+    let (recorded, current) = (lines(&stored.body), lines(body));
+    let union = recorded.union(&current).count();
+    let similarity = if union == 0 {
+        1.0
+    } else {
+        recorded.intersection(&current).count() as f64 / union as f64
+    };
+
+    if similarity >= SAME_RESPONSE_SIMILARITY {
+        Outcome::Reproduced
+    } else {
+        Outcome::Changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_should_tell_fixed_findings_apart() {
+        let path = std::env::temp_dir().join(format!("vulnscan-replay-{}.har", std::process::id()));
+        let entry = |id: &str, text: &str| {
+            serde_json::json!({
+                "request": { "method": "GET", "url": "http://127.0.0.1/.env", "headers": [] },
+                "response": { "status": 200, "content": { "text": text } },
+                "_finding": {
+                    "id": id,
+                    "module": "http/dotenv_disclosure",
+                    "kind": "DotEnvDisclosure",
+                    "url": "http://127.0.0.1/.env",
+                    "severity": "high",
+                },
+            })
+        };
+        let har = serde_json::json!({ "log": { "entries": [
+            entry("ab12", "APP_KEY=secret\nDB_PASSWORD=hunter2"),
+            entry("ab34", "DEBUG=true"),
+            // Recorded by another tool
+            { "request": { "method": "GET", "url": "http://127.0.0.1/", "headers": [] },
+              "response": { "status": 200, "content": {} } },
+        ] } });
+        std::fs::write(&path, har.to_string()).unwrap();
+
+        let stored = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert!(find(&stored, "ab").is_err());
+        assert!(find(&stored, "cd").is_err());
+
+        let env = find(&stored, "AB12").unwrap();
+        let body = b"APP_KEY=secret\nDB_PASSWORD=hunter2";
+        assert_eq!(compare(env, 200, body), Outcome::Reproduced);
+        assert_eq!(compare(env, 200, b"APP_KEY=rotated"), Outcome::Changed);
+        assert_eq!(compare(env, 404, b"Not Found"), Outcome::NotReproduced);
+    }
+}
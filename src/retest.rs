@@ -0,0 +1,348 @@
+use crate::annotations::Annotation;
+use crate::kb;
+use crate::modules;
+use crate::modules::Finding;
+use crate::modules::Intrusiveness;
+use crate::modules::Selection;
+use crate::modules::Severity;
+use crate::modules::http::HttpOptions;
+use crate::modules::http::ScanContext;
+use crate::modules::http::parameters;
+use crate::modules::{http_modules, parameter_modules, service_modules, tls_modules};
+use crate::throttle::Limiter;
+use anyhow::Context;
+use anyhow::Result;
+use futures::StreamExt;
+use futures::stream;
+use reqwest::Client;
+use reqwest::Url;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+use std::path::Path;
+
+// Module x endpoint pairs rerun at once
+const RETEST_CONCURRENCY: usize = 16;
+
+/// A finding of a previous scan, as its `finding` event
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Previous {
+    /// Missing from reports of older versions
+    #[serde(default)]
+    pub id: Option<String>,
+    pub kind: String,
+    /// Missing from reports of older versions and from findings of typed modules
+    #[serde(default, skip_serializing)]
+    pub module: Option<String>,
+    pub url: String,
+    pub severity: Severity,
+}
+
+/// Whether a previous finding is still there
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Status {
+    StillPresent,
+    Remediated,
+    /// The module could not be rerun, so the finding may or may not be remediated
+    NotRetested {
+        reason: String,
+    },
+}
+
+/// A previous finding with the module it came from and its status now
+#[derive(Debug, Serialize)]
+pub struct Retested {
+    #[serde(flatten)]
+    pub finding: Previous,
+    pub module: Option<&'static str>,
+    #[serde(flatten)]
+    pub status: Status,
+    /// Triage of the finding, carried forward from `annotate`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<Annotation>,
+}
+
+/// The findings of an event stream written by `--output` or `--format jsonl`
+pub fn load(path: &Path) -> Result<Vec<Previous>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut findings = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: Value = serde_json::from_str(line)
+            .with_context(|| format!("Line {} of {} is not an event", i + 1, path.display()))?;
+        if event["event"] == "finding" {
+            findings.push(
+                serde_json::from_value(event)
+                    .with_context(|| format!("Invalid finding on line {}", i + 1))?,
+            );
+        }
+    }
+    Ok(findings)
+}
+
+/// Rerun the module of each finding against the endpoint it was found on, once per pair
+/// - Findings without their module are attributed to one by the knowledge base
+/// - Only HTTP, parameter, service and TLS modules check endpoints, the others are not rerun
+/// - Modules with disruptive checks are only rerun with `aggressive`
+pub async fn retest(
+    http_client: &Client,
+    findings: Vec<Previous>,
+    aggressive: bool,
+) -> Vec<Retested> {
+    // Endpoints are rerun like a scan would, sharing its per-host limits
+    let limiter = &Limiter::default();
+    let mut pairs: BTreeMap<(&'static str, String, u16), Vec<Previous>> = BTreeMap::new();
+    let mut retested = Vec::new();
+    for finding in findings {
+        let module = match &finding.module {
+            Some(module) => modules::registered_name(module),
+            None => kb::for_finding(&finding.kind, &finding.url).map(|(module, _)| module),
+        };
+        let Some(module) = module else {
+            let reason = match &finding.module {
+                Some(module) => format!("unknown module {}", module),
+                None => String::from("no module reports this kind"),
+            };
+            retested.push(not_retested(finding, None, &reason));
+            continue;
+        };
+        let endpoint = Url::parse(&finding.url)
+            .ok()
+            .and_then(|url| Some((url.host_str()?.to_string(), url.port_or_known_default()?)));
+        match endpoint {
+            Some((host, port)) => pairs.entry((module, host, port)).or_default().push(finding),
+            None => retested.push(not_retested(finding, Some(module), "the URL has no port")),
+        }
+    }
+
+    let rerun = stream::iter(pairs)
+        .map(|((module, host, port), findings)| async move {
+            let status =
+                |finding: &Previous, found: &Result<BTreeSet<(String, String)>, String>| match found
+                {
+                    Ok(found)
+                        if found.contains(&(finding.kind.clone(), without_query(&finding.url))) =>
+                    {
+                        Status::StillPresent
+                    }
+                    Ok(_) => Status::Remediated,
+                    Err(reason) => Status::NotRetested {
+                        reason: reason.clone(),
+                    },
+                };
+
+            let found = rerun(
+                http_client,
+                limiter,
+                module,
+                &host,
+                port,
+                &findings,
+                aggressive,
+            )
+            .await;
+            findings
+                .into_iter()
+                .map(|finding| Retested {
+                    status: status(&finding, &found),
+                    finding,
+                    module: Some(module),
+                    annotation: None,
+                })
+                .collect::<Vec<_>>()
+        })
+        .buffer_unordered(RETEST_CONCURRENCY)
+        .flat_map(stream::iter)
+        .collect::<Vec<_>>()
+        .await;
+
+    retested.extend(rerun);
+    retested
+        .sort_by(|a, b| (&a.finding.url, &a.finding.kind).cmp(&(&b.finding.url, &b.finding.kind)));
+    retested
+}
+
+fn not_retested(finding: Previous, module: Option<&'static str>, reason: &str) -> Retested {
+    Retested {
+        finding,
+        module,
+        status: Status::NotRetested {
+            reason: reason.to_string(),
+        },
+        annotation: None,
+    }
+}
+
+/// The kinds and URLs, without query, of what `module` finds on `host:port` now
+async fn rerun(
+    http_client: &Client,
+    limiter: &Limiter,
+    module: &str,
+    host: &str,
+    port: u16,
+    findings: &[Previous],
+    aggressive: bool,
+) -> Result<BTreeSet<(String, String)>, String> {
+    if !aggressive && modules::intrusiveness(module) != Intrusiveness::Safe {
+        return Err(format!("{} needs --aggressive", module));
+    }
+
+    let ip: IpAddr = tokio::net::lookup_host((host, port))
+        .await
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .map(|addr| addr.ip())
+        .ok_or_else(|| format!("{} no longer resolves", host))?;
+    let error = |e: anyhow::Error| format!("{:#}", e);
+    let options = HttpOptions::default();
+    // Findings are retested whatever the modules selected when they were found
+    let every = Selection::default();
+
+    let mut found: Vec<Finding> = Vec::new();
+    if let Some(http) = http_modules(&every, None, aggressive)
+        .into_iter()
+        .find(|candidate| candidate.name() == module)
+    {
+        let context = ScanContext::detect(http_client, limiter, &options, host, ip, port).await;
+        // Findings under a base path were found on a path followed up on
+        let base_paths: BTreeSet<String> = findings
+            .iter()
+            .filter_map(|finding| {
+                let path = Url::parse(&finding.url).ok()?.path().to_string();
+                Some(path[..path.rfind('/')?].to_string())
+            })
+            .collect();
+        for base_path in base_paths {
+            let context = context.with_base_path(&base_path);
+            if let Some(finding) = http.scan(http_client, &context).await.map_err(error)? {
+                found.push(finding);
+            }
+        }
+    } else if let Some(injection) = parameter_modules(&every, aggressive)
+        .into_iter()
+        .find(|candidate| candidate.name() == module)
+    {
+        // The parameters of the findings are mined along with those of the landing page
+        let urls: Vec<Url> = findings
+            .iter()
+            .filter_map(|finding| Url::parse(&finding.url).ok())
+            .collect();
+        let paths: BTreeSet<String> = findings
+            .iter()
+            .map(|finding| without_query(&finding.url))
+            .collect();
+        let context =
+            ScanContext::new(&format!("{}:{}", host, port), ip).with_scan(limiter, &options);
+        let targets = parameters::mine(http_client, &context, &urls).await;
+        for target in targets
+            .iter()
+            .filter(|target| paths.contains(target.url.as_str()))
+        {
+            let injected = injection.inject(http_client, &context, target).await;
+            if let Some(finding) = injected.map_err(error)? {
+                found.push(finding);
+            }
+        }
+    } else if let Some(service) = service_modules(&every)
+        .into_iter()
+        .find(|candidate| candidate.name() == module)
+    {
+        found.extend(service.probe(host, ip, port).await.map_err(error)?);
+    } else if let Some(tls) = tls_modules(&every, aggressive)
+        .into_iter()
+        .find(|candidate| candidate.name() == module)
+    {
+        found.extend(tls.check(host, ip, port).await.map_err(error)?);
+    } else {
+        return Err(format!("{} does not check endpoints on its own", module));
+    }
+
+    Ok(found
+        .iter()
+        .map(|finding| (finding.kind.clone(), without_query(&finding.url)))
+        .collect())
+}
+
+fn without_query(url: &str) -> String {
+    url.split(['?', '#']).next().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn test_retest_should_tell_remediated_findings_apart() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/open/");
+            then.status(200)
+                .body("<html><title>Index of /open</title><a href=\"a.txt\">a.txt</a></html>");
+        });
+
+        let path =
+            std::env::temp_dir().join(format!("vulnscan-retest-{}.jsonl", std::process::id()));
+        let event = |kind: &str, path: &str| {
+            serde_json::json!({
+                "event": "finding",
+                "kind": kind,
+                "title": "",
+                "url": format!("http://{}{}", server.address(), path),
+                "severity": "low",
+                "evidence": [],
+            })
+            .to_string()
+        };
+        let report = [
+            r#"{"event":"host","host":"127.0.0.1","ip":"127.0.0.1"}"#.to_string(),
+            event("DirectoryListing", "/open/"),
+            event("DirectoryListing", "/closed/"),
+            event("Heartbleed", "/"),
+            event("Unknown", "/"),
+            // Reported by the module named in the event, which no longer finds it
+            serde_json::json!({
+                "event": "finding",
+                "kind": "CustomListing",
+                "module": "http/directory_listing",
+                "title": "",
+                "url": format!("http://{}/open/", server.address()),
+                "severity": "low",
+                "evidence": [],
+            })
+            .to_string(),
+        ];
+        std::fs::write(&path, report.join("\n")).unwrap();
+        let findings = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(findings.len(), 5);
+
+        let retested = retest(&Client::new(), findings, false).await;
+        let status = |kind: &str, path: &str| {
+            let url = format!("http://{}{}", server.address(), path);
+            retested
+                .iter()
+                .find(|retested| retested.finding.kind == kind && retested.finding.url == url)
+                .map(|retested| retested.status.clone())
+                .unwrap()
+        };
+        assert_eq!(status("DirectoryListing", "/open/"), Status::StillPresent);
+        assert_eq!(status("DirectoryListing", "/closed/"), Status::Remediated);
+        assert_eq!(
+            status("Heartbleed", "/"),
+            Status::NotRetested {
+                reason: "tls/heartbleed needs --aggressive".to_string()
+            }
+        );
+        assert!(matches!(status("Unknown", "/"), Status::NotRetested { .. }));
+        assert_eq!(status("CustomListing", "/open/"), Status::Remediated);
+    }
+}
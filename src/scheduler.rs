@@ -0,0 +1,287 @@
+use crate::target::Target;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// Concurrency budgets shared by every target of a scan, instead of scanning targets one by one
+pub struct Scheduler {
+    targets: Vec<Target>,
+    /// Name resolutions in flight
+    pub dns: Budget,
+    /// Hosts being port scanned
+    pub ports: Budget,
+}
+
+impl Scheduler {
+    pub fn new(targets: &[Target], dns: usize, ports: usize) -> Self {
+        Scheduler {
+            targets: targets.to_vec(),
+            dns: Budget::new(dns),
+            ports: Budget::new(ports),
+        }
+    }
+
+    /// The target `host` belongs to, the most specific one when targets overlap
+    /// - Hosts of no target, e.g. imported ones, are their own target
+    pub fn target_of(&self, host: &str) -> String {
+        let ip: Option<IpAddr> = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse()
+            .ok();
+
+        self.targets
+            .iter()
+            .filter_map(|target| {
+                let specificity = match (target, ip) {
+                    (Target::Domain(domain), _)
+                        if host == domain || host.ends_with(&format!(".{}", domain)) =>
+                    {
+                        domain.len()
+                    }
+                    (Target::Ip(target), Some(ip)) if *target == ip => usize::MAX,
+                    (Target::Network(network), Some(ip)) if network.contains(&ip) => {
+                        network.prefix_len().into()
+                    }
+                    _ => return None,
+                };
+                Some((specificity, target))
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .map_or_else(|| host.to_string(), |(_, target)| target.to_string())
+    }
+
+    /// `items` alternating between their targets, in their order within a target
+    ///
+    /// Streams buffering a fixed number of items then work on every target from the start,
+    /// rather than on the largest one first.
+    pub fn interleave<T>(&self, items: Vec<T>, host: impl Fn(&T) -> &str) -> Vec<T> {
+        let mut order: Vec<String> = Vec::new();
+        let mut queues: HashMap<String, VecDeque<T>> = HashMap::new();
+        for item in items {
+            let target = self.target_of(host(&item));
+            if !queues.contains_key(&target) {
+                order.push(target.clone());
+            }
+            queues.entry(target).or_default().push_back(item);
+        }
+
+        let mut interleaved = Vec::new();
+        while !queues.is_empty() {
+            for target in &order {
+                let Some(queue) = queues.get_mut(target) else {
+                    continue;
+                };
+                interleaved.extend(queue.pop_front());
+                if queue.is_empty() {
+                    queues.remove(target);
+                }
+            }
+        }
+
+        interleaved
+    }
+}
+
+/// A number of concurrent operations shared fairly between targets
+/// - A freed slot goes to the waiting target with the fewest operations in flight
+/// - A target alone may use every slot
+pub struct Budget {
+    limit: usize,
+    state: Mutex<BudgetState>,
+    released: Notify,
+}
+
+#[derive(Default)]
+struct BudgetState {
+    in_flight: usize,
+    targets: HashMap<String, Usage>,
+}
+
+#[derive(Default)]
+struct Usage {
+    in_flight: usize,
+    waiting: usize,
+}
+
+/// A slot of a budget, returned when dropped
+pub struct Permit<'a> {
+    budget: &'a Budget,
+    target: String,
+}
+
+// Counts a target as waiting until the acquisition completes or is cancelled
+struct Waiting<'a> {
+    budget: &'a Budget,
+    target: &'a str,
+}
+
+impl Budget {
+    pub fn new(limit: usize) -> Self {
+        Budget {
+            limit: limit.max(1),
+            state: Mutex::new(BudgetState::default()),
+            released: Notify::new(),
+        }
+    }
+
+    /// Wait for a slot on behalf of `target`
+    pub async fn acquire(&self, target: &str) -> Permit<'_> {
+        let _waiting = Waiting::new(self, target);
+
+        loop {
+            // Registered before checking, so that no release is missed in between
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            if self.try_acquire(target) {
+                return Permit {
+                    budget: self,
+                    target: target.to_string(),
+                };
+            }
+            released.await;
+        }
+    }
+
+    fn try_acquire(&self, target: &str) -> bool {
+        let mut state = self.state.lock().expect("Budget poisoned");
+        if state.in_flight >= self.limit {
+            return false;
+        }
+
+        let in_flight = state.targets.get(target).map_or(0, |usage| usage.in_flight);
+        let starved = state
+            .targets
+            .values()
+            .any(|other| other.waiting > 0 && other.in_flight < in_flight);
+        if starved {
+            return false;
+        }
+
+        state.in_flight += 1;
+        state
+            .targets
+            .entry(target.to_string())
+            .or_default()
+            .in_flight += 1;
+        true
+    }
+
+    /// Stop counting a slot or a wait of `target`, forgetting targets with neither left
+    fn leave(&self, target: &str, slot: bool) {
+        let mut state = self.state.lock().expect("Budget poisoned");
+        if slot {
+            state.in_flight -= 1;
+        }
+        let usage = state.targets.entry(target.to_string()).or_default();
+        if slot {
+            usage.in_flight -= 1;
+        } else {
+            usage.waiting -= 1;
+        }
+        if usage.in_flight == 0 && usage.waiting == 0 {
+            state.targets.remove(target);
+        }
+        drop(state);
+
+        // Targets waiting behind this one may now be served
+        self.released.notify_waiters();
+    }
+}
+
+impl<'a> Waiting<'a> {
+    fn new(budget: &'a Budget, target: &'a str) -> Self {
+        budget
+            .state
+            .lock()
+            .expect("Budget poisoned")
+            .targets
+            .entry(target.to_string())
+            .or_default()
+            .waiting += 1;
+        Waiting { budget, target }
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.budget.leave(self.target, false);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.budget.leave(&self.target, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn scheduler() -> Scheduler {
+        let targets: Vec<Target> = ["example.com", "dev.example.com", "10.0.0.0/24"]
+            .iter()
+            .map(|target| target.parse().unwrap())
+            .collect();
+        Scheduler::new(&targets, 2, 2)
+    }
+
+    #[test]
+    fn test_interleave_should_alternate_between_targets() {
+        let scheduler = scheduler();
+        assert_eq!(
+            scheduler.target_of("api.dev.example.com"),
+            "dev.example.com"
+        );
+        assert_eq!(scheduler.target_of("10.0.0.7"), "10.0.0.0/24");
+        assert_eq!(scheduler.target_of("imported.org"), "imported.org");
+
+        let hosts = vec![
+            "www.example.com",
+            "api.example.com",
+            "mail.example.com",
+            "10.0.0.1",
+            "10.0.0.2",
+            "api.dev.example.com",
+        ];
+        assert_eq!(
+            scheduler.interleave(hosts, |host| host),
+            vec![
+                "www.example.com",
+                "10.0.0.1",
+                "api.dev.example.com",
+                "api.example.com",
+                "10.0.0.2",
+                "mail.example.com",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_acquire_should_serve_the_least_busy_target_first() {
+        let budget = Budget::new(2);
+        let wait = Duration::from_millis(50);
+
+        // A large target alone takes every slot
+        let first = budget.acquire("large.com").await;
+        let _second = budget.acquire("large.com").await;
+
+        // Both wait for a slot, the small target is served first
+        let large = budget.acquire("large.com");
+        let small = budget.acquire("small.com");
+        tokio::pin!(large, small);
+        assert!(tokio::time::timeout(wait, &mut small).await.is_err());
+
+        drop(first);
+        assert!(tokio::time::timeout(wait, &mut large).await.is_err());
+        let _small = tokio::time::timeout(wait, &mut small)
+            .await
+            .expect("Small target should get the freed slot");
+    }
+}
@@ -0,0 +1,279 @@
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use ipnet::IpNet;
+use regex::Regex;
+use regex::RegexBuilder;
+use reqwest::Url;
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Hosts allowed to be kept after enumeration and actively checked
+#[derive(Debug, Default)]
+pub struct Scope {
+    include: Vec<Rule>,
+    exclude: Vec<Rule>,
+}
+
+/// A host, wildcard, pattern or network of a scope
+#[derive(Debug)]
+pub(crate) enum Rule {
+    /// Exactly this host
+    Host(String),
+    /// Any subdomain of this domain, written `*.example.com`
+    Wildcard(String),
+    /// Hosts matching a regular expression, from Burp's advanced scope
+    Pattern(Regex),
+    /// Addresses of a CIDR range
+    Network(IpNet),
+}
+
+impl Rule {
+    pub(crate) fn matches(&self, host: &str) -> bool {
+        match self {
+            Rule::Host(expected) => host == expected,
+            Rule::Wildcard(domain) => host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.')),
+            Rule::Pattern(pattern) => pattern.is_match(host),
+            Rule::Network(network) => host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| network.contains(&ip)),
+        }
+    }
+
+    /// Parse `*.example.com`, `example.com` or a CIDR range, ignoring schemes, ports and paths
+    pub(crate) fn parse(entry: &str) -> Result<Self> {
+        let entry = entry.trim().to_lowercase();
+
+        if let Ok(network) = entry.parse() {
+            return Ok(Rule::Network(network));
+        }
+
+        if let Some(domain) = entry.strip_prefix("*.") {
+            return Ok(Rule::Wildcard(domain.to_string()));
+        }
+
+        let url = if entry.contains("://") {
+            entry.clone()
+        } else {
+            format!("http://{}", entry)
+        };
+
+        let host = Url::parse(&url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .with_context(|| format!("Invalid scope entry {}", entry))?;
+
+        Ok(Rule::Host(host))
+    }
+}
+
+#[derive(Deserialize)]
+struct BurpConfig {
+    target: BurpTarget,
+}
+
+#[derive(Deserialize)]
+struct BurpTarget {
+    scope: BurpScope,
+}
+
+#[derive(Deserialize)]
+struct BurpScope {
+    #[serde(default)]
+    advanced_mode: bool,
+    #[serde(default)]
+    include: Vec<BurpRule>,
+    #[serde(default)]
+    exclude: Vec<BurpRule>,
+}
+
+#[derive(Deserialize)]
+struct BurpRule {
+    #[serde(default = "enabled")]
+    enabled: bool,
+    // Advanced mode
+    host: Option<String>,
+    // Simple mode
+    prefix: Option<String>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Scope {
+    /// Load a scope file, detecting its format
+    /// - Burp Suite target scope export (JSON, simple or advanced mode)
+    /// - HackerOne scope export (CSV with an `identifier` column)
+    /// - A list of hosts and `*.` wildcards separated by commas or new lines,
+    ///   entries starting with `!` being excluded
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scope file {}", path.display()))?;
+
+        let scope = Self::parse(&content)
+            .with_context(|| format!("Failed to parse scope file {}", path.display()))?;
+
+        if scope.include.is_empty() && scope.exclude.is_empty() {
+            bail!("Scope file {} defines no hosts", path.display());
+        }
+
+        Ok(scope)
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let content = content.trim_start_matches('\u{feff}').trim();
+
+        if content.starts_with('{') {
+            return Self::parse_burp(content);
+        }
+
+        let header = content.lines().next().unwrap_or_default();
+        if header
+            .split(',')
+            .any(|column| column.trim() == "identifier")
+        {
+            return Self::parse_hackerone(content);
+        }
+
+        Self::parse_list(content)
+    }
+
+    fn parse_list(content: &str) -> Result<Self> {
+        let mut scope = Scope::default();
+
+        for entry in content
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(|line| line.split([',', ' ', '\t']))
+            .filter(|entry| !entry.is_empty())
+        {
+            match entry.strip_prefix('!') {
+                Some(excluded) => scope.exclude.push(Rule::parse(excluded)?),
+                None => scope.include.push(Rule::parse(entry)?),
+            }
+        }
+
+        Ok(scope)
+    }
+
+    fn parse_hackerone(content: &str) -> Result<Self> {
+        let mut lines = content.lines();
+        let header: Vec<&str> = lines
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .collect();
+
+        let column = |name: &str| header.iter().position(|column| *column == name);
+        let identifier = column("identifier").context("Missing identifier column")?;
+        let eligible = column("eligible_for_submission");
+
+        let mut scope = Scope::default();
+
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+            let Some(entry) = fields.get(identifier) else {
+                continue;
+            };
+
+            let rule = Rule::parse(entry.trim_matches('"'))?;
+
+            match eligible.and_then(|index| fields.get(index)) {
+                Some(&"false") => scope.exclude.push(rule),
+                _ => scope.include.push(rule),
+            }
+        }
+
+        Ok(scope)
+    }
+
+    fn parse_burp(content: &str) -> Result<Self> {
+        let config: BurpConfig = serde_json::from_str(content)?;
+        let burp = config.target.scope;
+
+        let convert = |rules: Vec<BurpRule>| -> Result<Vec<Rule>> {
+            rules
+                .into_iter()
+                .filter(|rule| rule.enabled)
+                .filter_map(|rule| match (burp.advanced_mode, rule.host, rule.prefix) {
+                    (true, Some(host), _) => Some(
+                        RegexBuilder::new(&host)
+                            .case_insensitive(true)
+                            .build()
+                            .map(Rule::Pattern)
+                            .map_err(anyhow::Error::from),
+                    ),
+                    (false, _, Some(prefix)) => Some(Rule::parse(&prefix)),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        Ok(Scope {
+            include: convert(burp.include)?,
+            exclude: convert(burp.exclude)?,
+        })
+    }
+
+    /// Whether `host` is included and not excluded, scopes without inclusions including every host
+    pub fn contains(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+
+        (self.include.is_empty() || self.include.iter().any(|rule| rule.matches(&host)))
+            && !self.exclude.iter().any(|rule| rule.matches(&host))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_should_read_wildcard_lists_and_hackerone_exports() {
+        let scope =
+            Scope::parse("*.example.com, api.other.com\n!internal.example.com # staff only\n")
+                .unwrap();
+
+        assert!(scope.contains("www.example.com"));
+        assert!(scope.contains("API.other.com"));
+        assert!(!scope.contains("example.com"));
+        assert!(!scope.contains("internal.example.com"));
+        assert!(!scope.contains("www.other.com"));
+
+        let scope = Scope::parse("198.51.100.0/24").unwrap();
+        assert!(scope.contains("198.51.100.7"));
+        assert!(!scope.contains("203.0.113.7"));
+
+        let scope = Scope::parse(
+            "identifier,asset_type,instruction,eligible_for_bounty,eligible_for_submission\n\
+             *.example.com,WILDCARD,,true,true\n\
+             legacy.example.com,URL,,false,false\n",
+        )
+        .unwrap();
+
+        assert!(scope.contains("shop.example.com"));
+        assert!(!scope.contains("legacy.example.com"));
+    }
+
+    #[test]
+    fn test_parse_should_read_burp_advanced_scope() {
+        let scope = Scope::parse(
+            r#"{"target":{"scope":{"advanced_mode":true,
+                "include":[{"enabled":true,"host":"^.*\\.example\\.com$","protocol":"any"}],
+                "exclude":[{"enabled":true,"host":"^vpn\\.example\\.com$","protocol":"any"},
+                           {"enabled":false,"host":"^www\\.example\\.com$","protocol":"any"}]}}}"#,
+        )
+        .unwrap();
+
+        assert!(scope.contains("www.example.com"));
+        assert!(!scope.contains("vpn.example.com"));
+        assert!(!scope.contains("example.org"));
+    }
+}
@@ -0,0 +1,262 @@
+use crate::modules::HttpModule;
+use crate::modules::ParameterModule;
+use crate::modules::Selection;
+use crate::modules::http;
+use crate::modules::http::ParameterTarget;
+use crate::modules::http::ScanContext;
+use crate::modules::http_modules;
+use crate::modules::parameter_modules;
+use anyhow::Result;
+use httpmock::prelude::*;
+use reqwest::Client;
+use reqwest::Url;
+
+const MARKER: &str = "vsxselftest";
+
+/// A canned response of a fixture server, other requests get a 404
+struct Response {
+    path: &'static str,
+    // Only answer when a query parameter matches `(name, value pattern)`
+    query: Option<(&'static str, &'static str)>,
+    content_type: &'static str,
+    body: &'static str,
+}
+
+/// Module under test
+enum Subject {
+    Http(Box<dyn HttpModule>),
+    Parameter(Box<dyn ParameterModule>),
+}
+
+/// A module against a known-vulnerable and a known-clean target
+struct Case {
+    module: &'static str,
+    build: fn() -> Subject,
+    vulnerable: &'static [Response],
+    clean: &'static [Response],
+}
+
+const fn response(path: &'static str, content_type: &'static str, body: &'static str) -> Response {
+    Response {
+        path,
+        query: None,
+        content_type,
+        body,
+    }
+}
+
+const CASES: &[Case] = &[
+    Case {
+        module: "http/debug_endpoints",
+        build: || Subject::Http(Box::new(http::DebugEndpoints::new())),
+        vulnerable: &[response(
+            "/phpinfo.php",
+            "text/html",
+            "<html><head><title>phpinfo()</title></head></html>",
+        )],
+        clean: &[response(
+            "/phpinfo.php",
+            "text/html",
+            "<html><body>Welcome</body></html>",
+        )],
+    },
+    Case {
+        module: "http/directory_listing",
+        build: || Subject::Http(Box::new(http::DirectoryListing::new())),
+        vulnerable: &[response(
+            "/",
+            "text/html",
+            "<html><head><title>Index of /</title></head></html>",
+        )],
+        clean: &[response(
+            "/",
+            "text/html",
+            "<html><body>Welcome</body></html>",
+        )],
+    },
+    Case {
+        module: "http/dotenv_disclosure",
+        build: || Subject::Http(Box::new(http::DotEnvDisclosure::new())),
+        vulnerable: &[response("/.env", "text/plain", "DB_PASSWORD=s3cr3t-p4ss")],
+        clean: &[response(
+            "/.env",
+            "text/html",
+            "<html><body>Page Not Found but 200 OK</body></html>",
+        )],
+    },
+    Case {
+        module: "http/git_config_leakage",
+        build: || Subject::Http(Box::new(http::GitConfigLeakage::new())),
+        vulnerable: &[response(
+            "/.git/config",
+            "text/plain",
+            "[core]\n\tbare = false\n[branch \"main\"]\n\tremote = origin\n",
+        )],
+        clean: &[response(
+            "/.git/config",
+            "text/html",
+            "<html><body>Welcome</body></html>",
+        )],
+    },
+    Case {
+        module: "http/git_head_leakage",
+        build: || Subject::Http(Box::new(http::GitHeadLeakage::new())),
+        vulnerable: &[response(
+            "/.git/HEAD",
+            "text/plain",
+            "ref: refs/heads/main\n",
+        )],
+        clean: &[response(
+            "/.git/HEAD",
+            "text/html",
+            "<html><body>Welcome</body></html>",
+        )],
+    },
+    Case {
+        module: "http/reflected_xss",
+        build: || Subject::Parameter(Box::new(http::ReflectedXss::with_marker(MARKER))),
+        vulnerable: &[Response {
+            path: "/search",
+            query: Some(("q", "^vsxselftest")),
+            content_type: "text/html",
+            body: r#"<p>No results for vsxselftest"'<></p>"#,
+        }],
+        clean: &[response(
+            "/search",
+            "text/html",
+            "<p>No results for vsxselftest&quot;&#39;&lt;&gt;</p>",
+        )],
+    },
+    Case {
+        module: "http/sqli_error",
+        build: || Subject::Parameter(Box::new(http::SqliError::new())),
+        vulnerable: &[Response {
+            path: "/search",
+            query: Some(("q", r#"['"\\]"#)),
+            content_type: "text/html",
+            body: "You have an error in your SQL syntax; check the manual",
+        }],
+        clean: &[response("/search", "text/html", "<p>No results</p>")],
+    },
+];
+
+/// Outcome of a module's self-test
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Passed,
+    Skipped(&'static str),
+    Failed(&'static str),
+}
+
+/// Run every HTTP and parameter module against its fixtures
+pub async fn run() -> Result<Vec<(String, Verdict)>> {
+    let http_client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+
+    // Modules are listed from the registries, so new ones without a fixture show up
+    let mut modules: Vec<(String, bool)> = http_modules(&Selection::default(), None, true)
+        .iter()
+        .map(|module| (module.name(), module.max_requests() == 0))
+        .collect();
+    modules.extend(
+        parameter_modules(&Selection::default(), true)
+            .iter()
+            .map(|module| (module.name(), false)),
+    );
+
+    let mut verdicts = Vec::new();
+
+    for (name, needs_oob) in modules {
+        let verdict = match CASES.iter().find(|case| case.module == name) {
+            Some(case) => check(&http_client, case).await,
+            None if needs_oob => Verdict::Skipped("needs out-of-band callbacks"),
+            None => Verdict::Skipped("no fixture"),
+        };
+        verdicts.push((name, verdict));
+    }
+
+    Ok(verdicts)
+}
+
+async fn check(http_client: &Client, case: &Case) -> Verdict {
+    let subject = (case.build)();
+
+    let vulnerable = MockServer::start_async().await;
+    serve(&vulnerable, case.vulnerable).await;
+    let clean = MockServer::start_async().await;
+    serve(&clean, case.clean).await;
+
+    if !detects(http_client, &subject, &vulnerable).await {
+        return Verdict::Failed("missed the vulnerable fixture");
+    }
+
+    if detects(http_client, &subject, &clean).await {
+        return Verdict::Failed("flagged the clean fixture");
+    }
+
+    Verdict::Passed
+}
+
+async fn serve(server: &MockServer, responses: &[Response]) {
+    for response in responses {
+        server
+            .mock_async(|when, then| {
+                let when = when.method(GET).path(response.path);
+                if let Some((name, value)) = response.query {
+                    when.query_param_matches(format!("^{}$", name), value);
+                }
+                then.status(200)
+                    .header("Content-Type", response.content_type)
+                    .body(response.body);
+            })
+            .await;
+    }
+}
+
+async fn detects(http_client: &Client, subject: &Subject, server: &MockServer) -> bool {
+    let endpoint = format!("{}:{}", server.host(), server.port());
+
+    let context = ScanContext::new(&endpoint, server.address().ip());
+    let finding = match subject {
+        Subject::Http(module) => module.scan(http_client, &context).await,
+        Subject::Parameter(module) => {
+            let target = ParameterTarget {
+                url: Url::parse(&format!("http://{}/search", endpoint)).expect("Invalid URL"),
+                name: String::from("q"),
+                value: String::from("shoes"),
+            };
+            module.inject(http_client, &context, &target).await
+        }
+    };
+
+    matches!(finding, Ok(Some(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_should_pass_every_module_with_a_fixture() {
+        let verdicts = run().await.unwrap();
+
+        for (module, verdict) in &verdicts {
+            assert!(
+                !matches!(verdict, Verdict::Failed(_)),
+                "{}: {:?}",
+                module,
+                verdict
+            );
+        }
+
+        assert_eq!(
+            verdicts
+                .iter()
+                .filter(|(_, verdict)| *verdict == Verdict::Passed)
+                .count(),
+            CASES.len()
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use crate::config::SigningConfig;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ed25519_dalek::Signature;
+use ed25519_dalek::Signer;
+use ed25519_dalek::SigningKey;
+use ed25519_dalek::VerifyingKey;
+use ed25519_dalek::pkcs8::DecodePrivateKey;
+use ed25519_dalek::pkcs8::DecodePublicKey;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The key of `[signing]` reports are signed with, if configured
+/// - Keys are PKCS#8 PEM files, as written by `openssl genpkey -algorithm ed25519`
+pub fn load_key(config: &SigningConfig) -> Result<Option<SigningKey>> {
+    let Some(path) = &config.key_file else {
+        return Ok(None);
+    };
+
+    let pem = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the signing key {}", path.display()))?;
+    let key = SigningKey::from_pkcs8_pem(&pem)
+        .map_err(|e| anyhow::anyhow!("Invalid Ed25519 signing key {}: {}", path.display(), e))?;
+    Ok(Some(key))
+}
+
+/// The public key reports are verified with, an SPKI PEM file as written by `openssl pkey -pubout`
+pub fn load_public_key(path: &Path) -> Result<VerifyingKey> {
+    let pem = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the public key {}", path.display()))?;
+    VerifyingKey::from_public_key_pem(&pem)
+        .map_err(|e| anyhow::anyhow!("Invalid Ed25519 public key {}: {}", path.display(), e))
+}
+
+/// The detached signature of `path`, next to it
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut signature = path.as_os_str().to_owned();
+    signature.push(".sig");
+    PathBuf::from(signature)
+}
+
+/// Sign the report written to `path`, as it was written, encrypted or not
+pub fn sign(path: &Path, key: &SigningKey) -> Result<()> {
+    let report = std::fs::read(path)?;
+    let signature = key.sign(&report);
+
+    let signature_path = signature_path(path);
+    std::fs::write(
+        &signature_path,
+        format!("{}\n", STANDARD.encode(signature.to_bytes())),
+    )
+    .with_context(|| format!("Failed to write {}", signature_path.display()))
+}
+
+/// Fail unless `signature` is a signature of `path` by the owner of `key`
+pub fn verify(path: &Path, signature: &Path, key: &VerifyingKey) -> Result<()> {
+    let report =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let encoded = std::fs::read_to_string(signature)
+        .with_context(|| format!("Failed to read {}", signature.display()))?;
+    let signed = is_signed(&report, &encoded, key)
+        .with_context(|| format!("{} is not an Ed25519 signature", signature.display()))?;
+
+    if !signed {
+        bail!(
+            "{} does not match its signature, it was modified or signed with another key",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Whether `encoded`, a base64 signature as written by `sign`, signs `content` by the owner of
+/// `key`, failing when it is no signature at all
+pub fn is_signed(content: &[u8], encoded: &str, key: &VerifyingKey) -> Result<bool> {
+    let bytes = STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        .context("Not a base64 Ed25519 signature")?;
+
+    Ok(key
+        .verify_strict(content, &Signature::from_bytes(&bytes))
+        .is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::pkcs8::EncodePrivateKey;
+    use ed25519_dalek::pkcs8::EncodePublicKey;
+    use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+
+    #[test]
+    fn test_verify_should_reject_modified_reports() {
+        let dir = std::env::temp_dir().join(format!("vulnscan-signing-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let key_file = dir.join("signing.pem");
+        std::fs::write(&key_file, key.to_pkcs8_pem(LineEnding::LF).unwrap()).unwrap();
+        let public_key_file = dir.join("signing.pub.pem");
+        let public_key = key
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap();
+        std::fs::write(&public_key_file, public_key).unwrap();
+
+        let config = SigningConfig {
+            key_file: Some(key_file),
+            public_key_file: None,
+        };
+        let key = load_key(&config).unwrap().unwrap();
+        let public_key = load_public_key(&public_key_file).unwrap();
+
+        let report = dir.join("assets.json");
+        std::fs::write(&report, "{\"assets\":[]}").unwrap();
+        sign(&report, &key).unwrap();
+        let signature = signature_path(&report);
+        assert!(verify(&report, &signature, &public_key).is_ok());
+
+        std::fs::write(&report, "{\"assets\":[{}]}").unwrap();
+        assert!(verify(&report, &signature, &public_key).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
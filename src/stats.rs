@@ -0,0 +1,143 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Most subdomain modules a scan can attribute names to, one bit of a `u64` each
+pub const MAX_SOURCES: usize = u64::BITS as usize;
+
+/// Subdomain enumeration statistics of the last scan, shown by `modules --stats`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnumerationStats {
+    pub target: String,
+    pub finished_at: String,
+    pub total: usize,
+    pub sources: Vec<SourceStats>,
+}
+
+/// What a single subdomain module contributed to the enumeration
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceStats {
+    pub module: String,
+    /// Subdomains reported by this module
+    pub found: usize,
+    /// Subdomains reported by no other module
+    pub unique: usize,
+}
+
+impl EnumerationStats {
+    /// Compute per-source statistics
+    ///
+    /// # Arguments
+    /// * `target` - The enumerated domain
+    /// * `modules` - Names of every module that ran, including the ones finding nothing
+    /// * `attribution` - Subdomain -> modules that found it, bit `i` standing for `modules[i]`
+    pub fn new(target: &str, modules: &[String], attribution: &HashMap<String, u64>) -> Self {
+        let mut sources: Vec<SourceStats> = modules
+            .iter()
+            .enumerate()
+            .map(|(i, module)| {
+                let found = attribution.values().filter(|s| *s & (1 << i) != 0);
+                SourceStats {
+                    module: module.clone(),
+                    found: found.clone().count(),
+                    unique: found.filter(|s| s.count_ones() == 1).count(),
+                }
+            })
+            .collect();
+
+        sources.sort_by(|a, b| b.unique.cmp(&a.unique).then(a.module.cmp(&b.module)));
+
+        EnumerationStats {
+            target: target.to_string(),
+            finished_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            total: attribution.len(),
+            sources,
+        }
+    }
+
+    pub fn print(&self) {
+        for source in &self.sources {
+            println!(
+                "\t{}: {} subdomains ({} unique)",
+                source.module, source.found, source.unique
+            );
+        }
+    }
+
+    /// Keep the statistics for `modules --stats`
+    pub fn save(&self) -> Result<()> {
+        let path = stats_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Load the statistics of the last scan, if any
+    pub fn load() -> Result<Option<Self>> {
+        let path = stats_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(&std::fs::read(path)?)?))
+    }
+}
+
+fn stats_path() -> Result<PathBuf> {
+    let data_dir = dirs::data_local_dir().ok_or_else(|| anyhow!("No local data directory"))?;
+    Ok(data_dir.join("vulnscan").join("enumeration_stats.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_should_count_unique_contributions() {
+        let modules = vec![
+            String::from("subdomain/crtsh"),
+            String::from("subdomain/webarchive"),
+            String::from("subdomain/broken"),
+        ];
+        let attribution: HashMap<String, u64> = [
+            // Found by crt.sh and the Wayback Machine
+            ("a.example.com", 0b011),
+            ("b.example.com", 0b001),
+            ("c.example.com", 0b001),
+            ("d.example.com", 0b010),
+        ]
+        .into_iter()
+        .map(|(subdomain, sources)| (subdomain.to_string(), sources))
+        .collect();
+
+        let stats = EnumerationStats::new("example.com", &modules, &attribution);
+
+        assert_eq!(stats.total, 4);
+        assert_eq!(
+            stats.sources,
+            vec![
+                SourceStats {
+                    module: String::from("subdomain/crtsh"),
+                    found: 3,
+                    unique: 2
+                },
+                SourceStats {
+                    module: String::from("subdomain/webarchive"),
+                    found: 2,
+                    unique: 1
+                },
+                SourceStats {
+                    module: String::from("subdomain/broken"),
+                    found: 0,
+                    unique: 0
+                },
+            ]
+        );
+    }
+}
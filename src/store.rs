@@ -0,0 +1,308 @@
+use crate::annotations::Annotation;
+use crate::annotations::Annotations;
+use crate::annotations::TriageStatus;
+use crate::export::Record;
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use clap::ValueEnum;
+use postgres_native_tls::MakeTlsConnector;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use tokio_native_tls::native_tls::TlsConnector;
+use tokio_postgres::Client;
+use tokio_postgres::Row;
+
+/// Schema changes, applied in order and never edited once released
+const MIGRATIONS: &[&str] = &[include_str!("migrations/0001_initial.sql")];
+
+// Key of the advisory lock taken while migrating, so that concurrent scans migrate once
+const MIGRATION_LOCK: i64 = 0x76756c6e7363616e;
+
+// Timestamps are read as RFC 3339 text, like those written to local files
+const TIMESTAMP: &str = r#"'YYYY-MM-DD"T"HH24:MI:SS"Z"'"#;
+
+/// PostgreSQL database a team shares scan history, annotations and finding state in
+pub struct Store {
+    client: Client,
+}
+
+/// A scan recorded in the store
+#[derive(Debug, Serialize)]
+pub struct Run {
+    pub id: i64,
+    pub targets: String,
+    /// The database user who ran the scan
+    pub operator: String,
+    pub started_at: String,
+    /// Unset while the scan runs, or if it was interrupted
+    pub finished_at: Option<String>,
+    pub findings: i64,
+    /// Findings no earlier run reported
+    pub new: i64,
+}
+
+impl Store {
+    /// Connect to the store at `url`, migrating its schema to the one of this version
+    pub async fn connect(url: &str) -> Result<Self> {
+        let tls = MakeTlsConnector::new(TlsConnector::new()?);
+        let (mut client, connection) = tokio_postgres::connect(url, tls)
+            .await
+            .context("Failed to connect to the store")?;
+
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                log::warn!("Store connection closed: {}", e);
+            }
+        });
+
+        migrate(&mut client).await?;
+        Ok(Store { client })
+    }
+
+    /// Record the start of a scan of `targets`, returning the ID of its run
+    pub async fn start_run(&self, targets: &str) -> Result<i64> {
+        let row = self
+            .client
+            .query_one(
+                "INSERT INTO runs (targets, version) VALUES ($1, $2) RETURNING id",
+                &[&targets, &env!("CARGO_PKG_VERSION")],
+            )
+            .await?;
+        Ok(row.get(0))
+    }
+
+    /// Record the findings of a run and mark it finished, returning how many no earlier run
+    /// reported
+    pub async fn finish_run(&mut self, run: i64, records: &[Record]) -> Result<usize> {
+        let transaction = self.client.transaction().await?;
+        let mut new = BTreeSet::new();
+        for record in records {
+            let id = record.dedup_key();
+            let severity = serde_json::to_value(record.severity)?;
+            let row = transaction
+                .query_one(
+                    "INSERT INTO findings (id, kind, url, title, severity, first_seen_run, last_seen_run) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $6) \
+                     ON CONFLICT (id) DO UPDATE \
+                     SET title = EXCLUDED.title, severity = EXCLUDED.severity, last_seen_run = $6 \
+                     RETURNING first_seen_run",
+                    &[
+                        &id,
+                        &record.kind,
+                        &record.url,
+                        &record.title.to_string(),
+                        &severity.as_str(),
+                        &run,
+                    ],
+                )
+                .await?;
+            if row.get::<_, i64>(0) == run {
+                new.insert(id.clone());
+            }
+            transaction
+                .execute(
+                    "INSERT INTO run_findings (run_id, finding_id, evidence) VALUES ($1, $2, $3) \
+                     ON CONFLICT DO NOTHING",
+                    &[&run, &id, &record.evidence],
+                )
+                .await?;
+        }
+        transaction
+            .execute("UPDATE runs SET finished_at = now() WHERE id = $1", &[&run])
+            .await?;
+        transaction.commit().await?;
+        Ok(new.len())
+    }
+
+    /// The latest `limit` runs, latest first
+    pub async fn runs(&self, limit: i64) -> Result<Vec<Run>> {
+        let query = format!(
+            "SELECT id, targets, operator, \
+             to_char(started_at AT TIME ZONE 'UTC', {timestamp}), \
+             to_char(finished_at AT TIME ZONE 'UTC', {timestamp}), \
+             (SELECT COUNT(*) FROM run_findings WHERE run_id = runs.id), \
+             (SELECT COUNT(*) FROM findings WHERE first_seen_run = runs.id) \
+             FROM runs ORDER BY id DESC LIMIT $1",
+            timestamp = TIMESTAMP
+        );
+        let rows = self.client.query(&query, &[&limit]).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| Run {
+                id: row.get(0),
+                targets: row.get(1),
+                operator: row.get(2),
+                started_at: row.get(3),
+                finished_at: row.get(4),
+                findings: row.get(5),
+                new: row.get(6),
+            })
+            .collect())
+    }
+
+    /// The annotations of the team
+    pub async fn annotations(&self) -> Result<Annotations> {
+        let query = format!(
+            "SELECT finding_id, status, note, to_char(updated_at AT TIME ZONE 'UTC', {}) \
+             FROM annotations",
+            TIMESTAMP
+        );
+        let entries = self
+            .client
+            .query(&query, &[])
+            .await?
+            .iter()
+            .map(annotation)
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        Ok(Annotations::from_entries(entries))
+    }
+
+    pub async fn save_annotation(&self, id: &str, annotation: &Annotation) -> Result<()> {
+        self.client
+            .execute(
+                "INSERT INTO annotations (finding_id, status, note) VALUES ($1, $2, $3) \
+                 ON CONFLICT (finding_id) DO UPDATE \
+                 SET status = $2, note = $3, updated_by = current_user, updated_at = now()",
+                &[
+                    &id,
+                    &annotation.status.map(|status| status.to_string()),
+                    &annotation.note,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Forget the annotation of a finding, returning whether there was one
+    pub async fn remove_annotation(&self, id: &str) -> Result<bool> {
+        let removed = self
+            .client
+            .execute("DELETE FROM annotations WHERE finding_id = $1", &[&id])
+            .await?;
+        Ok(removed > 0)
+    }
+}
+
+fn annotation(row: &Row) -> Result<(String, Annotation)> {
+    let status = row
+        .get::<_, Option<&str>>(1)
+        .map(|status| TriageStatus::from_str(status, false).map_err(|e| anyhow!(e)))
+        .transpose()?;
+    Ok((
+        row.get(0),
+        Annotation {
+            status,
+            note: row.get(2),
+            updated_at: row.get(3),
+        },
+    ))
+}
+
+/// Apply the migrations the store is missing
+async fn migrate(client: &mut Client) -> Result<()> {
+    let transaction = client.transaction().await?;
+    transaction
+        .execute("SELECT pg_advisory_xact_lock($1)", &[&MIGRATION_LOCK])
+        .await?;
+    transaction
+        .batch_execute(
+            // Without the notice that the table exists on every connection
+            "SET LOCAL client_min_messages = warning; \
+             CREATE TABLE IF NOT EXISTS vulnscan_migrations (\
+             version INTEGER PRIMARY KEY, \
+             applied_at TIMESTAMPTZ NOT NULL DEFAULT now())",
+        )
+        .await?;
+
+    let applied: i32 = transaction
+        .query_one(
+            "SELECT COALESCE(MAX(version), 0) FROM vulnscan_migrations",
+            &[],
+        )
+        .await?
+        .get(0);
+    if applied as usize > MIGRATIONS.len() {
+        bail!(
+            "The store is at schema version {}, newer than version {} of this vulnscan",
+            applied,
+            MIGRATIONS.len()
+        );
+    }
+
+    for (version, migration) in (1..).zip(MIGRATIONS).skip(applied as usize) {
+        transaction
+            .batch_execute(migration)
+            .await
+            .with_context(|| format!("Failed to apply migration {} to the store", version))?;
+        transaction
+            .execute(
+                "INSERT INTO vulnscan_migrations (version) VALUES ($1)",
+                &[&version],
+            )
+            .await?;
+        log::info!("Migrated the store to schema version {}", version);
+    }
+    transaction.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::Finding;
+    use crate::modules::Severity;
+
+    /// Needs a disposable database, like `VULNSCAN_TEST_STORE=postgres://localhost/vulnscan_test`
+    #[tokio::test]
+    async fn test_store_should_tell_new_findings_apart() {
+        let Ok(url) = std::env::var("VULNSCAN_TEST_STORE") else {
+            return;
+        };
+        let mut store = Store::connect(&url).await.unwrap();
+        // Migrating again is a no-op
+        Store::connect(&url).await.unwrap();
+
+        let url = format!("http://{}.example.com/", std::process::id());
+        let record = Record::new(&Finding::new(
+            "http/directory_listing",
+            "DirectoryListing",
+            Severity::Low,
+            &url,
+        ));
+        let first = store.start_run("example.com").await.unwrap();
+        assert_eq!(
+            store
+                .finish_run(first, std::slice::from_ref(&record))
+                .await
+                .unwrap(),
+            1
+        );
+        let second = store.start_run("example.com").await.unwrap();
+        assert_eq!(
+            store
+                .finish_run(second, std::slice::from_ref(&record))
+                .await
+                .unwrap(),
+            0
+        );
+
+        let runs = store.runs(2).await.unwrap();
+        assert_eq!(runs[0].id, second);
+        assert_eq!((runs[0].findings, runs[0].new), (1, 0));
+        assert!(runs[0].finished_at.is_some());
+
+        let mut annotations = store.annotations().await.unwrap();
+        let id = record.dedup_key();
+        let annotation = annotations
+            .annotate(&id, Some(TriageStatus::FalsePositive), None)
+            .unwrap()
+            .clone();
+        store.save_annotation(&id, &annotation).await.unwrap();
+        let annotations = store.annotations().await.unwrap();
+        assert!(annotations.get(&id).unwrap().dismisses());
+        assert!(store.remove_annotation(&id).await.unwrap());
+    }
+}
@@ -0,0 +1,318 @@
+use std::net::Ipv4Addr;
+
+const TCP_PROTOCOL: u8 = 6;
+const SYN: u8 = 0x02;
+const RST: u8 = 0x04;
+const SYN_ACK: u8 = 0x12;
+
+#[cfg(target_os = "linux")]
+pub use linux::SynScanner;
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::*;
+    use anyhow::Result;
+    use anyhow::anyhow;
+    use futures::channel::oneshot;
+    use rand::Rng;
+    use socket2::Domain;
+    use socket2::Protocol;
+    use socket2::SockAddr;
+    use socket2::Socket;
+    use socket2::Type;
+    use std::collections::HashMap;
+    use std::io::Read;
+    use std::net::IpAddr;
+    use std::net::SocketAddrV4;
+    use std::net::UdpSocket;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::sync::MutexGuard;
+    use std::time::Duration;
+    use tokio::io::Interest;
+    use tokio::io::unix::AsyncFd;
+    use tokio::task::JoinHandle;
+
+    // Probes waiting for a reply, by address and port
+    type Probes = HashMap<(Ipv4Addr, u16), Vec<oneshot::Sender<bool>>>;
+
+    /// Half-open port scanner, sending bare SYN segments from a raw socket
+    /// - A SYN-ACK marks an open port, the kernel resetting the connection since no socket owns it
+    /// - A RST marks a closed port, silence a filtered one
+    /// - Only IPv4 on Linux, where raw sockets receive TCP segments, connect scans cover the rest
+    pub struct SynScanner {
+        socket: Arc<AsyncFd<Socket>>,
+        // Every probe is sent from this port, replies to other ports are ignored
+        source_port: u16,
+        waiting: Arc<Mutex<Probes>>,
+        receiver: JoinHandle<()>,
+    }
+
+    impl SynScanner {
+        /// Open the raw socket, failing without root or `CAP_NET_RAW`
+        pub fn new() -> Result<Self> {
+            let socket = Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::TCP))?;
+            socket.set_nonblocking(true)?;
+            let socket = Arc::new(AsyncFd::new(socket)?);
+
+            let source_port = rand::thread_rng().gen_range(40000..60000);
+            let waiting: Arc<Mutex<Probes>> = Arc::default();
+            let receiver = tokio::spawn(receive(socket.clone(), source_port, waiting.clone()));
+
+            Ok(SynScanner {
+                socket,
+                source_port,
+                waiting,
+                receiver,
+            })
+        }
+
+        /// Whether `port` of `ip` is open, unanswered probes being sent once more halfway
+        /// through `timeout` like the kernel retransmits connection attempts, then probed again
+        /// `retries` times
+        pub async fn probe(
+            &self,
+            ip: Ipv4Addr,
+            port: u16,
+            timeout: Duration,
+            retries: usize,
+        ) -> Result<bool> {
+            let (sender, mut receiver) = oneshot::channel();
+            self.lock().entry((ip, port)).or_default().push(sender);
+
+            let mut answer = None;
+            for _ in 0..2 * (retries + 1) {
+                if let Err(e) = self.send(ip, port).await {
+                    answer = Some(Err(anyhow!("Failed to probe port {}: {}", port, e)));
+                    break;
+                }
+                if let Ok(open) = tokio::time::timeout(timeout / 2, &mut receiver).await {
+                    answer = Some(Ok(open.unwrap_or_default()));
+                    break;
+                }
+            }
+
+            // Drop the waiting sender of an unanswered probe
+            drop(receiver);
+            let mut waiting = self.lock();
+            if let Some(senders) = waiting.get_mut(&(ip, port)) {
+                senders.retain(|sender| !sender.is_canceled());
+                if senders.is_empty() {
+                    waiting.remove(&(ip, port));
+                }
+            }
+
+            answer.unwrap_or(Ok(false))
+        }
+
+        async fn send(&self, ip: Ipv4Addr, port: u16) -> std::io::Result<()> {
+            // The kernel fills the IP header, from the address routing to the destination
+            let route = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+            route.connect((ip, port))?;
+            let IpAddr::V4(source) = route.local_addr()?.ip() else {
+                unreachable!("IPv4 sockets have IPv4 addresses");
+            };
+
+            let segment = syn_segment(source, ip, self.source_port, port, rand::random());
+            let address = SockAddr::from(SocketAddrV4::new(ip, 0));
+            self.socket
+                .async_io(Interest::WRITABLE, |socket| {
+                    socket.send_to(&segment, &address)
+                })
+                .await?;
+            Ok(())
+        }
+
+        fn lock(&self) -> MutexGuard<'_, Probes> {
+            self.waiting.lock().expect("Waiting probes poisoned")
+        }
+    }
+
+    impl Drop for SynScanner {
+        fn drop(&mut self) {
+            self.receiver.abort();
+        }
+    }
+
+    /// Hand the replies read from the raw socket to the probes waiting for them
+    async fn receive(socket: Arc<AsyncFd<Socket>>, source_port: u16, waiting: Arc<Mutex<Probes>>) {
+        // Raw IPv4 sockets read whole packets, IP header included
+        let mut buffer = [0; 1500];
+
+        loop {
+            let Ok(mut guard) = socket.readable().await else {
+                return;
+            };
+            let Ok(result) = guard.try_io(|socket| {
+                let mut socket: &Socket = socket.get_ref();
+                socket.read(&mut buffer)
+            }) else {
+                continue;
+            };
+            let Ok(length) = result else {
+                continue;
+            };
+
+            if let Some((ip, port, open)) = parse_reply(&buffer[..length], source_port) {
+                let senders = waiting
+                    .lock()
+                    .expect("Waiting probes poisoned")
+                    .remove(&(ip, port));
+                for sender in senders.into_iter().flatten() {
+                    let _ = sender.send(open);
+                }
+            }
+        }
+    }
+}
+
+/// A SYN segment without options, its checksum covering the IPv4 pseudo-header
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn syn_segment(
+    source: Ipv4Addr,
+    destination: Ipv4Addr,
+    source_port: u16,
+    port: u16,
+    sequence: u32,
+) -> [u8; 20] {
+    let mut segment = [0; 20];
+    segment[0..2].copy_from_slice(&source_port.to_be_bytes());
+    segment[2..4].copy_from_slice(&port.to_be_bytes());
+    segment[4..8].copy_from_slice(&sequence.to_be_bytes());
+    // Header of 5 words, no options
+    segment[12] = 5 << 4;
+    segment[13] = SYN;
+    segment[14..16].copy_from_slice(&u16::MAX.to_be_bytes());
+
+    let mut pseudo_header = [0; 12];
+    pseudo_header[0..4].copy_from_slice(&source.octets());
+    pseudo_header[4..8].copy_from_slice(&destination.octets());
+    pseudo_header[9] = TCP_PROTOCOL;
+    pseudo_header[10..12].copy_from_slice(&(segment.len() as u16).to_be_bytes());
+
+    let checksum = checksum(&[&pseudo_header, &segment]);
+    segment[16..18].copy_from_slice(&checksum.to_be_bytes());
+    segment
+}
+
+/// The address and port of a reply to a probe sent from `source_port`, open on SYN-ACK and
+/// closed on RST
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_reply(packet: &[u8], source_port: u16) -> Option<(Ipv4Addr, u16, bool)> {
+    let header_length = usize::from(packet.first()? & 0x0f) * 4;
+    if *packet.get(9)? != TCP_PROTOCOL {
+        return None;
+    }
+
+    let source: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+    let ip = Ipv4Addr::from(source);
+    let segment = packet.get(header_length..header_length + 20)?;
+    let port = u16::from_be_bytes([segment[0], segment[1]]);
+    if u16::from_be_bytes([segment[2], segment[3]]) != source_port {
+        return None;
+    }
+
+    match segment[13] {
+        flags if flags & SYN_ACK == SYN_ACK => Some((ip, port, true)),
+        flags if flags & RST != 0 => Some((ip, port, false)),
+        _ => None,
+    }
+}
+
+/// The Internet checksum (RFC 1071) of consecutive chunks of even lengths, but the last
+pub(crate) fn checksum(chunks: &[&[u8]]) -> u16 {
+    let mut sum: u32 = 0;
+    for chunk in chunks {
+        for word in chunk.chunks(2) {
+            sum += u32::from(u16::from_be_bytes([word[0], *word.get(1).unwrap_or(&0)]));
+        }
+    }
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Stand-in where raw sockets cannot receive TCP segments, never built
+#[cfg(not(target_os = "linux"))]
+pub struct SynScanner(std::convert::Infallible);
+
+#[cfg(not(target_os = "linux"))]
+impl SynScanner {
+    pub fn new() -> anyhow::Result<Self> {
+        anyhow::bail!("SYN scans are only supported on Linux")
+    }
+
+    pub async fn probe(
+        &self,
+        _: Ipv4Addr,
+        _: u16,
+        _: std::time::Duration,
+        _: usize,
+    ) -> anyhow::Result<bool> {
+        match self.0 {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply_should_read_syn_ack_of_own_probes() {
+        let source = Ipv4Addr::new(192, 0, 2, 1);
+        let target = Ipv4Addr::new(198, 51, 100, 7);
+
+        // A SYN-ACK from 443 to 40000, behind a 20-byte IP header
+        let mut reply = syn_segment(target, source, 443, 40000, 1);
+        reply[13] = SYN_ACK;
+        let mut packet = vec![0x45, 0, 0, 40, 0, 0, 0, 0, 64, TCP_PROTOCOL, 0, 0];
+        packet.extend(target.octets());
+        packet.extend(source.octets());
+        packet.extend(reply);
+
+        assert_eq!(parse_reply(&packet, 40000), Some((target, 443, true)));
+        assert_eq!(parse_reply(&packet, 40001), None);
+
+        // The checksum of a segment with its checksum verifies to zero
+        let segment = syn_segment(source, target, 40000, 443, 1);
+        let mut pseudo_header = [0; 12];
+        pseudo_header[0..4].copy_from_slice(&source.octets());
+        pseudo_header[4..8].copy_from_slice(&target.octets());
+        pseudo_header[9] = TCP_PROTOCOL;
+        pseudo_header[11] = 20;
+        assert_eq!(checksum(&[&pseudo_header, &segment]), 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_probe_should_tell_open_and_closed_ports_apart() {
+        // Raw sockets need root or CAP_NET_RAW
+        let Ok(scanner) = SynScanner::new() else {
+            return;
+        };
+        let timeout = std::time::Duration::from_secs(1);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        assert!(
+            scanner
+                .probe(Ipv4Addr::LOCALHOST, open, timeout, 0)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !scanner
+                .probe(Ipv4Addr::LOCALHOST, closed, timeout, 0)
+                .await
+                .unwrap()
+        );
+    }
+}
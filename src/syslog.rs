@@ -0,0 +1,250 @@
+use crate::config::SyslogConfig;
+use crate::export::Record;
+use crate::i18n::Lang;
+use crate::modules::Severity;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::net::UdpSocket;
+use tokio_native_tls::TlsConnector;
+use tokio_native_tls::native_tls;
+
+// How long connecting and sending every finding may take
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+// local0, leaving the system facilities to the system
+const FACILITY: u8 = 16;
+const PRODUCT: &str = "vulnscan";
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How syslog messages reach the collector
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    #[default]
+    Udp,
+    /// Messages separated by newlines
+    Tcp,
+    /// Messages prefixed with their length (RFC 5425)
+    Tls,
+}
+
+/// The event format SIEMs parse out of the syslog message
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// ArcSight Common Event Format, read by Splunk and most SIEMs
+    #[default]
+    Cef,
+    /// IBM QRadar Log Event Extended Format
+    Leef,
+}
+
+/// Fail on missing settings before scanning, rather than when sending findings
+pub fn validate(config: &SyslogConfig) -> Result<&str> {
+    config
+        .address
+        .as_deref()
+        .context("Missing address in the [syslog] section of the configuration")
+}
+
+/// Send every finding as one syslog message to the collector of `[syslog]`
+pub async fn push(config: &SyslogConfig, records: &[Record]) -> Result<()> {
+    let address = validate(config)?;
+    let messages = records.iter().map(|record| message(record, config.format));
+
+    let send = async {
+        match config.transport {
+            Transport::Udp => {
+                let collector = tokio::net::lookup_host(address)
+                    .await?
+                    .next()
+                    .context("The collector address resolves to nothing")?;
+                let local = if collector.is_ipv4() {
+                    "0.0.0.0:0"
+                } else {
+                    "[::]:0"
+                };
+                let socket = UdpSocket::bind(local).await?;
+                socket.connect(collector).await?;
+                for message in messages {
+                    socket.send(message.as_bytes()).await?;
+                }
+            }
+            Transport::Tcp => {
+                let mut stream = TcpStream::connect(address).await?;
+                for message in messages {
+                    stream
+                        .write_all(format!("{}\n", message).as_bytes())
+                        .await?;
+                }
+                stream.shutdown().await?;
+            }
+            Transport::Tls => {
+                let host = address.rsplit_once(':').map_or(address, |(host, _)| host);
+                let stream = TcpStream::connect(address).await?;
+                let connector = TlsConnector::from(native_tls::TlsConnector::new()?);
+                let mut stream = connector
+                    .connect(host.trim_matches(['[', ']']), stream)
+                    .await?;
+                for message in messages {
+                    stream
+                        .write_all(format!("{} {}", message.len(), message).as_bytes())
+                        .await?;
+                }
+                stream.shutdown().await?;
+            }
+        }
+        Ok::<_, anyhow::Error>(())
+    };
+
+    tokio::time::timeout(SEND_TIMEOUT, send)
+        .await
+        .context("Timed out sending findings to the syslog collector")?
+        .with_context(|| {
+            format!(
+                "Failed to send findings to the syslog collector {}",
+                address
+            )
+        })?;
+
+    log::info!("Sent {} findings to {} over syslog", records.len(), address);
+    Ok(())
+}
+
+/// An RFC 5424 message carrying the finding as a CEF or LEEF event
+fn message(record: &Record, format: Format) -> String {
+    // Findings are notices to errors, never emergencies
+    let level = match record.severity {
+        Severity::Info => 6,
+        Severity::Low => 5,
+        Severity::Medium => 4,
+        Severity::High => 3,
+    };
+    let event = match format {
+        Format::Cef => cef(record),
+        Format::Leef => leef(record),
+    };
+
+    format!(
+        "<{}>1 {} - {} - - - {}",
+        FACILITY * 8 + level,
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        PRODUCT,
+        event
+    )
+}
+
+/// CEF severities run from 0 to 10
+fn severity(severity: Severity) -> u8 {
+    match severity {
+        Severity::Info => 1,
+        Severity::Low => 3,
+        Severity::Medium => 5,
+        Severity::High => 8,
+    }
+}
+
+fn cef(record: &Record) -> String {
+    let header = |value: &str| value.replace('\\', "\\\\").replace('|', "\\|");
+    let extension = |value: &str| {
+        value
+            .replace('\\', "\\\\")
+            .replace('=', "\\=")
+            .replace('\r', "\\r")
+            .replace('\n', "\\n")
+    };
+
+    format!(
+        "CEF:0|{}|{}|{}|{}|{}|{}|request={} externalId={} cs1Label=evidence cs1={}",
+        PRODUCT,
+        PRODUCT,
+        VERSION,
+        header(&record.kind),
+        header(&record.title.translate(Lang::En)),
+        severity(record.severity),
+        extension(&record.url),
+        record.dedup_key(),
+        extension(&record.evidence.join("\n"))
+    )
+}
+
+/// LEEF 1.0, its attributes separated by tabs
+fn leef(record: &Record) -> String {
+    let value = |value: &str| value.replace(['\t', '\r', '\n'], " ");
+
+    format!(
+        "LEEF:1.0|{}|{}|{}|{}|sev={}\turl={}\tmsg={}\texternalId={}\tevidence={}",
+        PRODUCT,
+        PRODUCT,
+        VERSION,
+        record.kind.replace('|', "\\|"),
+        severity(record.severity),
+        value(&record.url),
+        value(&record.title.translate(Lang::En)),
+        record.dedup_key(),
+        value(&record.evidence.join(" | "))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::title;
+
+    fn record() -> Record {
+        Record {
+            kind: String::from("DotEnvDisclosure"),
+            module: None,
+            title: title("DotEnvDisclosure"),
+            url: String::from("https://www.example.com/.env?a=b"),
+            severity: Severity::High,
+            evidence: vec![
+                String::from("DB_PASSWORD=s3******** (Database, secret)"),
+                String::from("APP_KEY=ba********"),
+            ],
+            annotation: None,
+        }
+    }
+
+    #[test]
+    fn test_message_should_escape_cef_and_leef_events() {
+        let message = message(&record(), Format::Cef);
+        assert!(message.starts_with("<131>1 "), "{}", message);
+        assert!(message.contains(
+            "|DotEnvDisclosure|.env disclosure|8|request=https://www.example.com/.env?a\\=b "
+        ));
+        assert!(
+            message.ends_with(
+                "cs1=DB_PASSWORD\\=s3******** (Database, secret)\\nAPP_KEY\\=ba********"
+            )
+        );
+
+        let event = leef(&record());
+        assert!(event.starts_with("LEEF:1.0|vulnscan|vulnscan|"));
+        assert_eq!(event.matches('\t').count(), 4);
+        assert!(event.contains("\tmsg=.env disclosure\t"));
+    }
+
+    #[tokio::test]
+    async fn test_push_should_send_one_datagram_per_finding() {
+        let collector = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = SyslogConfig {
+            address: Some(collector.local_addr().unwrap().to_string()),
+            transport: Transport::Udp,
+            format: Format::Leef,
+        };
+
+        push(&config, &[record(), record()]).await.unwrap();
+
+        let mut buffer = [0; 2048];
+        for _ in 0..2 {
+            let length = collector.recv(&mut buffer).await.unwrap();
+            let message = std::str::from_utf8(&buffer[..length]).unwrap();
+            assert!(message.contains(" vulnscan - - - LEEF:1.0|"), "{}", message);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use ipnet::IpNet;
+use reqwest::Url;
+use std::fmt;
+use std::io::BufRead;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+// Larger ranges would take ages to port scan
+const MAX_NETWORK_HOSTS_BITS: u8 = 16;
+
+/// What the `scan` subcommand is pointed at, normalized before the pipeline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// An ASCII (punycode) domain whose subdomains are enumerated
+    Domain(String),
+    /// A single host, scanned without enumeration
+    Ip(IpAddr),
+    /// A range of hosts, scanned without enumeration
+    Network(IpNet),
+}
+
+impl Target {
+    /// The domain to enumerate, `None` for IPs and networks
+    pub fn domain(&self) -> Option<&str> {
+        match self {
+            Target::Domain(domain) => Some(domain),
+            _ => None,
+        }
+    }
+
+    /// The `(name, address)` of every host of an IP or network target
+    pub fn hosts(&self) -> Vec<(String, IpAddr)> {
+        let host = |ip: IpAddr| (host_name(ip), ip);
+
+        match self {
+            Target::Domain(_) => Vec::new(),
+            Target::Ip(ip) => vec![host(*ip)],
+            Target::Network(network) => network.hosts().map(host).collect(),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Target::Domain(domain) => write!(f, "{}", domain),
+            Target::Ip(ip) => write!(f, "{}", ip),
+            Target::Network(network) => write!(f, "{}", network),
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = anyhow::Error;
+
+    /// Accept domains, URLs, IP addresses and CIDR ranges
+    /// - Schemes, ports, paths and `*.` prefixes are stripped
+    /// - Internationalized domains are punycode-encoded and lowercased
+    fn from_str(input: &str) -> Result<Self> {
+        let input = input.trim();
+
+        if input.is_empty() {
+            bail!("The target is empty, expected a domain, URL, IP address or CIDR range");
+        }
+
+        if let Ok(network) = input.parse::<IpNet>() {
+            return network_target(network);
+        }
+
+        // Let the URL parser split off ports and paths, and encode IDNs
+        let input = input.strip_prefix("*.").unwrap_or(input);
+        let url = if input.contains("://") {
+            input.to_string()
+        } else {
+            format!("http://{}", input)
+        };
+
+        let url = Url::parse(&url).with_context(|| {
+            format!(
+                "Invalid target {}, expected a domain, URL, IP address or CIDR range",
+                input
+            )
+        })?;
+
+        if !["http", "https"].contains(&url.scheme()) {
+            bail!(
+                "Unsupported scheme {}://, use a domain or an HTTP(S) URL",
+                url.scheme()
+            );
+        }
+
+        let Some(host) = url.host_str() else {
+            bail!("{} has no host to scan", input);
+        };
+
+        // IPv6 hosts are bracketed in URLs
+        if let Ok(ip) = host.trim_start_matches('[').trim_end_matches(']').parse() {
+            return Ok(Target::Ip(ip));
+        }
+
+        let domain = host.trim_end_matches('.');
+        validate_domain(domain)?;
+        Ok(Target::Domain(domain.to_string()))
+    }
+}
+
+/// Name of a host only known by its address
+pub fn host_name(ip: IpAddr) -> String {
+    match ip {
+        // Bracketed, so that `name:port` endpoints stay valid
+        IpAddr::V6(_) => format!("[{}]", ip),
+        IpAddr::V4(_) => ip.to_string(),
+    }
+}
+
+/// Parse one target per line, skipping blank lines, `#` comments and duplicates
+pub fn parse_lines(reader: impl BufRead) -> Result<Vec<Target>> {
+    let mut targets = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.context("Failed to read targets")?;
+        let line = line.split('#').next().unwrap_or_default().trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let target: Target = line
+            .parse()
+            .with_context(|| format!("Invalid target on line {}", index + 1))?;
+
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+
+    if targets.is_empty() {
+        bail!("No target was read, expected one domain, URL, IP address or CIDR range per line");
+    }
+
+    Ok(targets)
+}
+
+fn network_target(network: IpNet) -> Result<Target> {
+    let host_bits = network.max_prefix_len() - network.prefix_len();
+
+    if host_bits == 0 {
+        return Ok(Target::Ip(network.addr()));
+    }
+
+    if host_bits > MAX_NETWORK_HOSTS_BITS {
+        bail!(
+            "{} is too large, split it into ranges of at most /{}",
+            network,
+            network.max_prefix_len() - MAX_NETWORK_HOSTS_BITS
+        );
+    }
+
+    Ok(Target::Network(network.trunc()))
+}
+
+fn validate_domain(domain: &str) -> Result<()> {
+    if !domain.contains('.') {
+        bail!(
+            "{} is not a fully qualified domain, e.g. example.com",
+            domain
+        );
+    }
+
+    if domain.len() > 253 {
+        bail!("{} is longer than 253 characters", domain);
+    }
+
+    for label in domain.split('.') {
+        let is_valid = (1..=63).contains(&label.len())
+            && label
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            && !label.starts_with('-')
+            && !label.ends_with('-');
+
+        if !is_valid {
+            bail!(
+                "{} has an invalid label {:?}, labels are 1 to 63 letters, digits or inner hyphens",
+                domain,
+                label
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_should_normalize_targets() {
+        let cases = [
+            ("example.com", "example.com"),
+            ("https://Example.com/", "example.com"),
+            (
+                "http://api.example.com:8080/login?next=/",
+                "api.example.com",
+            ),
+            ("*.example.com", "example.com"),
+            ("example.com.", "example.com"),
+            ("bücher.example", "xn--bcher-kva.example"),
+            ("203.0.113.7", "203.0.113.7"),
+            ("https://[2001:db8::1]:8443/", "2001:db8::1"),
+            ("198.51.100.17/28", "198.51.100.16/28"),
+            ("203.0.113.7/32", "203.0.113.7"),
+        ];
+
+        for (input, expected) in cases {
+            let target: Target = input.parse().unwrap();
+            assert_eq!(target.to_string(), expected, "input: {}", input);
+        }
+
+        let network: Target = "198.51.100.0/30".parse().unwrap();
+        assert_eq!(network.hosts().len(), 2);
+        assert_eq!(network.domain(), None);
+
+        let targets = parse_lines(
+            "# from amass\nwww.example.com\n\nhttps://www.example.com/\n203.0.113.7 # vpn\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(
+            targets,
+            [
+                Target::Domain(String::from("www.example.com")),
+                Target::Ip("203.0.113.7".parse().unwrap())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_should_reject_invalid_targets() {
+        for input in [
+            "",
+            "localhost",
+            "exa mple.com",
+            "-example.com",
+            "under_score.example.com",
+            "ftp://example.com",
+            "10.0.0.0/8",
+        ] {
+            assert!(input.parse::<Target>().is_err(), "input: {}", input);
+        }
+
+        assert!(parse_lines("www.example.com\nnot a target\n".as_bytes()).is_err());
+        assert!(parse_lines("# nothing\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,404 @@
+use crate::capture;
+use async_trait::async_trait;
+use reqwest::RequestBuilder;
+use reqwest::Response;
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::sync::Notify;
+
+const INITIAL_LIMIT: f64 = 8.0;
+const MIN_LIMIT: f64 = 1.0;
+const MAX_LIMIT: f64 = 64.0;
+// Responses slower than this many times the fastest one are a sign of saturation
+const SLOW_FACTOR: u32 = 4;
+// Consecutive timeouts or connection errors after which an endpoint is no longer contacted
+const BREAKER_THRESHOLD: usize = 5;
+
+/// Per-host concurrency and endpoints given up on, shared by every module request of a scan
+/// - Clones share the same limits
+#[derive(Debug, Clone, Default)]
+pub struct Limiter {
+    controller: Arc<AdaptiveConcurrency>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl Limiter {
+    /// Endpoints, as `host:port`, given up on during the scan
+    pub fn unreachable_endpoints(&self) -> Vec<String> {
+        self.breaker.open_endpoints()
+    }
+}
+
+/// Send a request within the adaptive concurrency limit of its host
+/// - Requests to endpoints the circuit breaker gave up on fail without being sent
+#[async_trait]
+pub trait SendAdaptive {
+    async fn send_adaptive(self, limiter: &Limiter) -> Result<Response, SendError>;
+}
+
+#[async_trait]
+impl SendAdaptive for RequestBuilder {
+    async fn send_adaptive(self, limiter: &Limiter) -> Result<Response, SendError> {
+        let (client, request) = self.build_split();
+        let request = request?;
+        let host = request.url().host_str().unwrap_or_default().to_string();
+        let endpoint = format!(
+            "{}:{}",
+            host,
+            request.url().port_or_known_default().unwrap_or_default()
+        );
+
+        // Checked once a slot is free, as the breaker may open while waiting for it
+        let permit = limiter.controller.acquire(&host).await;
+        if limiter.breaker.is_open(&endpoint) {
+            return Err(SendError::Unreachable(endpoint));
+        }
+        let pending = capture::request(&request);
+        let result = client.execute(request).await;
+        if let (Some(pending), Ok(resp)) = (pending, &result) {
+            capture::response(pending, resp);
+        }
+        limiter
+            .breaker
+            .record(&endpoint, result.as_ref().is_err_and(is_unreachable));
+
+        permit.finish(match &result {
+            Err(e) if e.is_timeout() => Outcome::Overloaded,
+            // e.g. HTTPS attempted on a plain HTTP port, which says nothing about capacity
+            Err(_) => Outcome::Failed,
+            Ok(resp)
+                if resp.status() == StatusCode::TOO_MANY_REQUESTS
+                    || resp.status() == StatusCode::SERVICE_UNAVAILABLE =>
+            {
+                Outcome::Overloaded
+            }
+            _ => Outcome::Responded,
+        });
+
+        Ok(result?)
+    }
+}
+
+/// Why a request sent with `send_adaptive` failed
+#[derive(Debug)]
+pub enum SendError {
+    Request(reqwest::Error),
+    /// The endpoint kept timing out or refusing connections, so the request was not sent
+    Unreachable(String),
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Request(e) => write!(f, "{}", e),
+            SendError::Unreachable(endpoint) => write!(
+                f,
+                "{} is unreachable, skipped after {} consecutive failures",
+                endpoint, BREAKER_THRESHOLD
+            ),
+        }
+    }
+}
+
+impl Error for SendError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SendError::Request(e) => Some(e),
+            SendError::Unreachable(_) => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for SendError {
+    fn from(e: reqwest::Error) -> Self {
+        SendError::Request(e)
+    }
+}
+
+/// Whether `e` says the endpoint is down, rather than e.g. not speaking TLS
+fn is_unreachable(e: &reqwest::Error) -> bool {
+    if e.is_timeout() {
+        return true;
+    }
+
+    let mut source = e.source();
+    while let Some(cause) = source {
+        if let Some(e) = cause.downcast_ref::<io::Error>() {
+            return matches!(
+                e.kind(),
+                ErrorKind::ConnectionRefused
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::TimedOut
+                    | ErrorKind::HostUnreachable
+                    | ErrorKind::NetworkUnreachable
+            );
+        }
+        source = cause.source();
+    }
+    false
+}
+
+/// Consecutive failure counts, one per `host:port` endpoint
+/// - Any other outcome resets the count
+/// - Endpoints reaching `BREAKER_THRESHOLD` stay open until the end of the scan
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    failures: Mutex<HashMap<String, usize>>,
+}
+
+impl CircuitBreaker {
+    pub fn is_open(&self, endpoint: &str) -> bool {
+        self.failures
+            .lock()
+            .expect("Circuit breaker poisoned")
+            .get(endpoint)
+            .is_some_and(|failures| *failures >= BREAKER_THRESHOLD)
+    }
+
+    pub fn record(&self, endpoint: &str, unreachable: bool) {
+        let mut failures = self.failures.lock().expect("Circuit breaker poisoned");
+        let count = failures.entry(endpoint.to_string()).or_default();
+        // Requests in flight when the breaker opened must not close it again
+        if *count >= BREAKER_THRESHOLD {
+            return;
+        }
+
+        if unreachable {
+            *count += 1;
+        } else {
+            failures.remove(endpoint);
+        }
+    }
+
+    /// Endpoints given up on, sorted
+    pub fn open_endpoints(&self) -> Vec<String> {
+        let mut endpoints: Vec<String> = self
+            .failures
+            .lock()
+            .expect("Circuit breaker poisoned")
+            .iter()
+            .filter(|(_, failures)| **failures >= BREAKER_THRESHOLD)
+            .map(|(endpoint, _)| endpoint.clone())
+            .collect();
+        endpoints.sort();
+        endpoints
+    }
+}
+
+/// How a request ended, as far as the target's capacity is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Responded,
+    Overloaded,
+    Failed,
+}
+
+/// Additive-increase / multiplicative-decrease concurrency limits, one per host
+/// - Fast responses raise the limit by about one per round trip
+/// - Timeouts, 429 and 503 halve it, slow responses lower it slightly
+#[derive(Debug, Default)]
+pub struct AdaptiveConcurrency {
+    hosts: Mutex<HashMap<String, Arc<Host>>>,
+}
+
+#[derive(Debug)]
+struct Host {
+    state: Mutex<HostState>,
+    released: Notify,
+}
+
+#[derive(Debug)]
+struct HostState {
+    limit: f64,
+    in_flight: usize,
+    fastest: Option<Duration>,
+}
+
+/// A request slot, returned to its host when finished or dropped
+pub struct Permit {
+    host: Arc<Host>,
+    started: Instant,
+    finished: bool,
+}
+
+impl AdaptiveConcurrency {
+    /// Wait for a request slot on `host`
+    pub async fn acquire(&self, host: &str) -> Permit {
+        let host = self
+            .hosts
+            .lock()
+            .expect("Concurrency controller poisoned")
+            .entry(host.to_string())
+            .or_insert_with(|| {
+                Arc::new(Host {
+                    state: Mutex::new(HostState {
+                        limit: INITIAL_LIMIT,
+                        in_flight: 0,
+                        fastest: None,
+                    }),
+                    released: Notify::new(),
+                })
+            })
+            .clone();
+
+        loop {
+            {
+                let mut state = host.state.lock().expect("Concurrency controller poisoned");
+                if (state.in_flight as f64) < state.limit.floor() {
+                    state.in_flight += 1;
+                    break;
+                }
+            }
+            host.released.notified().await;
+        }
+
+        Permit {
+            host,
+            started: Instant::now(),
+            finished: false,
+        }
+    }
+
+    /// Current limit of `host`, if it was ever contacted
+    #[cfg(test)]
+    pub fn limit(&self, host: &str) -> Option<f64> {
+        let hosts = self.hosts.lock().expect("Concurrency controller poisoned");
+        let state = hosts
+            .get(host)?
+            .state
+            .lock()
+            .expect("Concurrency controller poisoned");
+        Some(state.limit)
+    }
+}
+
+impl Permit {
+    /// Release the slot, adjusting the host limit from the request outcome
+    pub fn finish(mut self, outcome: Outcome) {
+        self.release(outcome);
+    }
+
+    fn release(&mut self, outcome: Outcome) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+
+        let latency = self.started.elapsed();
+        let mut state = self
+            .host
+            .state
+            .lock()
+            .expect("Concurrency controller poisoned");
+        state.in_flight -= 1;
+
+        match outcome {
+            Outcome::Overloaded => state.limit = (state.limit / 2.0).max(MIN_LIMIT),
+            Outcome::Responded => {
+                let fastest = state
+                    .fastest
+                    .map_or(latency, |fastest| fastest.min(latency));
+                state.fastest = Some(fastest);
+
+                if latency > fastest * SLOW_FACTOR {
+                    state.limit = (state.limit * 0.9).max(MIN_LIMIT);
+                } else {
+                    state.limit = (state.limit + 1.0 / state.limit).min(MAX_LIMIT);
+                }
+            }
+            Outcome::Failed => {}
+        }
+
+        drop(state);
+        self.host.released.notify_one();
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.release(Outcome::Failed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limit_should_grow_on_success_and_halve_on_overload() {
+        let controller = AdaptiveConcurrency::default();
+
+        for _ in 0..50 {
+            controller
+                .acquire("fast.example.com")
+                .await
+                .finish(Outcome::Responded);
+        }
+        let raised = controller.limit("fast.example.com").unwrap();
+        assert!(raised > INITIAL_LIMIT, "Limit should grow, got {}", raised);
+
+        controller
+            .acquire("fast.example.com")
+            .await
+            .finish(Outcome::Overloaded);
+        assert_eq!(controller.limit("fast.example.com").unwrap(), raised / 2.0);
+
+        // Hosts are controlled independently
+        assert_eq!(controller.limit("slow.example.com"), None);
+    }
+
+    #[test]
+    fn test_breaker_should_open_after_consecutive_failures() {
+        let breaker = CircuitBreaker::default();
+
+        // A success in between starts counting again
+        for _ in 0..BREAKER_THRESHOLD - 1 {
+            breaker.record("flaky.example.com:443", true);
+        }
+        breaker.record("flaky.example.com:443", false);
+        breaker.record("flaky.example.com:443", true);
+        assert!(!breaker.is_open("flaky.example.com:443"));
+
+        for _ in 0..BREAKER_THRESHOLD {
+            breaker.record("down.example.com:443", true);
+        }
+        breaker.record("down.example.com:443", false);
+        assert!(breaker.is_open("down.example.com:443"));
+        assert!(!breaker.is_open("down.example.com:80"));
+        assert_eq!(breaker.open_endpoints(), vec!["down.example.com:443"]);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_should_wait_for_a_free_slot() {
+        let controller = AdaptiveConcurrency::default();
+
+        // Drive the limit down to a single slot
+        for _ in 0..10 {
+            controller
+                .acquire("example.com")
+                .await
+                .finish(Outcome::Overloaded);
+        }
+
+        let permit = controller.acquire("example.com").await;
+        let blocked =
+            tokio::time::timeout(Duration::from_millis(50), controller.acquire("example.com"))
+                .await;
+        assert!(blocked.is_err(), "Second request should wait");
+
+        drop(permit);
+        let unblocked =
+            tokio::time::timeout(Duration::from_millis(50), controller.acquire("example.com"))
+                .await;
+        assert!(unblocked.is_ok(), "Dropped permit should free its slot");
+    }
+}
@@ -0,0 +1,252 @@
+use anyhow::Result;
+use anyhow::bail;
+use futures::StreamExt;
+use futures::stream;
+use hickory_resolver::TokioResolver;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+
+const DNS_CONCURRENCY: usize = 100;
+
+// Generic and country code TLDs most often registered by squatters
+const TLDS: &[&str] = &[
+    "com", "net", "org", "info", "biz", "co", "io", "app", "dev", "xyz", "online", "site", "shop",
+    "us", "uk", "co.uk", "de", "fr", "cn", "ru", "in", "tk",
+];
+
+// ASCII sequences passing for others at a glance
+const ASCII_HOMOGLYPHS: &[(&str, &str)] = &[
+    ("o", "0"),
+    ("0", "o"),
+    ("l", "1"),
+    ("l", "i"),
+    ("i", "1"),
+    ("i", "l"),
+    ("1", "l"),
+    ("m", "rn"),
+    ("rn", "m"),
+    ("w", "vv"),
+    ("vv", "w"),
+    ("d", "cl"),
+    ("cl", "d"),
+];
+
+// Cyrillic letters rendered like Latin ones, registered as IDNs
+const UNICODE_HOMOGLYPHS: &[(char, char)] = &[
+    ('a', 'а'),
+    ('c', 'с'),
+    ('e', 'е'),
+    ('i', 'і'),
+    ('j', 'ј'),
+    ('o', 'о'),
+    ('p', 'р'),
+    ('s', 'ѕ'),
+    ('x', 'х'),
+    ('y', 'у'),
+];
+
+/// How a lookalike domain was derived
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Technique {
+    /// Characters swapped for ones looking the same, in ASCII or other scripts
+    Homoglyph,
+    /// One bit flipped, as memory errors do to names on their way to a resolver
+    Bitsquat,
+    /// The same name under another TLD
+    TldSwap,
+}
+
+/// A lookalike domain to check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// In ASCII, internationalized names being Punycode
+    pub domain: String,
+    /// How internationalized names are displayed
+    pub unicode: Option<String>,
+    pub technique: Technique,
+}
+
+/// A lookalike domain which is registered, resolves or receives mail
+#[derive(Debug, Serialize)]
+pub struct Lookalike {
+    pub domain: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unicode: Option<String>,
+    pub technique: Technique,
+    /// Delegated to name servers
+    pub registered: bool,
+    pub addresses: Vec<IpAddr>,
+    pub mx: Vec<String>,
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Technique::Homoglyph => write!(f, "homoglyph"),
+            Technique::Bitsquat => write!(f, "bitsquat"),
+            Technique::TldSwap => write!(f, "TLD swap"),
+        }
+    }
+}
+
+/// `examp1e.com [homoglyph] registered, 203.0.113.7, MX mail.examp1e.com`
+impl fmt::Display for Lookalike {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.domain)?;
+        if let Some(unicode) = &self.unicode {
+            write!(f, " ({})", unicode)?;
+        }
+        write!(f, " [{}]", self.technique)?;
+
+        let mut records = Vec::new();
+        if self.registered {
+            records.push(String::from("registered"));
+        }
+        records.extend(self.addresses.iter().map(IpAddr::to_string));
+        if !self.mx.is_empty() {
+            records.push(format!("MX {}", self.mx.join(", ")));
+        }
+        write!(f, " {}", records.join(", "))
+    }
+}
+
+/// Lookalikes of `domain`, whose first label is permuted and whose other labels are the suffix
+/// - `example.co.uk` gives `examp1e.co.uk`, `dxample.co.uk`, `example.com`...
+pub fn permutations(domain: &str) -> Result<Vec<Candidate>> {
+    let domain = domain.trim_end_matches('.').to_lowercase();
+    let Some((label, suffix)) = domain.split_once('.') else {
+        bail!("{} is not a registrable domain, like example.com", domain);
+    };
+    if !label.is_ascii() {
+        bail!(
+            "{} should be given in ASCII, Punycode for internationalized names",
+            domain
+        );
+    }
+
+    // Keyed by domain, the first technique deriving a domain naming it
+    let mut candidates: BTreeMap<String, Candidate> = BTreeMap::new();
+    let mut add = |name: String, technique: Technique| {
+        let Ok(ascii) = idna::domain_to_ascii(&name) else {
+            return;
+        };
+        if ascii == domain || candidates.contains_key(&ascii) {
+            return;
+        }
+        let unicode = (ascii != name).then_some(name);
+        candidates.insert(
+            ascii.clone(),
+            Candidate {
+                domain: ascii,
+                unicode,
+                technique,
+            },
+        );
+    };
+
+    for (from, to) in ASCII_HOMOGLYPHS {
+        for (i, _) in label.match_indices(from) {
+            let permuted = format!("{}{}{}", &label[..i], to, &label[i + from.len()..]);
+            add(format!("{}.{}", permuted, suffix), Technique::Homoglyph);
+        }
+    }
+    for (from, to) in UNICODE_HOMOGLYPHS {
+        for (i, _) in label.match_indices(*from) {
+            let permuted = format!("{}{}{}", &label[..i], to, &label[i + 1..]);
+            add(format!("{}.{}", permuted, suffix), Technique::Homoglyph);
+        }
+    }
+
+    let bytes = label.as_bytes();
+    for (i, byte) in bytes.iter().enumerate() {
+        for bit in 0..8 {
+            let flipped = byte ^ (1 << bit);
+            let valid = flipped.is_ascii_lowercase()
+                || flipped.is_ascii_digit()
+                || (flipped == b'-' && i != 0 && i != bytes.len() - 1);
+            if valid {
+                let mut permuted = bytes.to_vec();
+                permuted[i] = flipped;
+                let permuted = String::from_utf8_lossy(&permuted);
+                add(format!("{}.{}", permuted, suffix), Technique::Bitsquat);
+            }
+        }
+    }
+
+    for tld in TLDS.iter().filter(|tld| **tld != suffix) {
+        add(format!("{}.{}", label, tld), Technique::TldSwap);
+    }
+
+    Ok(candidates.into_values().collect())
+}
+
+/// The candidates delegated to name servers, resolving, or with mail exchangers, sorted
+pub async fn check(resolver: &TokioResolver, candidates: Vec<Candidate>) -> Vec<Lookalike> {
+    let mut lookalikes: Vec<Lookalike> = stream::iter(candidates)
+        .map(|candidate| async move {
+            let (ns, ip, mx) = futures::join!(
+                resolver.ns_lookup(candidate.domain.as_str()),
+                resolver.lookup_ip(candidate.domain.as_str()),
+                resolver.mx_lookup(candidate.domain.as_str()),
+            );
+
+            let lookalike = Lookalike {
+                registered: ns.is_ok_and(|ns| ns.iter().next().is_some()),
+                addresses: ip.map(|ip| ip.iter().collect()).unwrap_or_default(),
+                mx: mx
+                    .map(|mx| {
+                        mx.iter()
+                            .map(|mx| mx.exchange().to_utf8().trim_end_matches('.').to_string())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                domain: candidate.domain,
+                unicode: candidate.unicode,
+                technique: candidate.technique,
+            };
+
+            let live =
+                lookalike.registered || !lookalike.addresses.is_empty() || !lookalike.mx.is_empty();
+            live.then_some(lookalike)
+        })
+        .buffer_unordered(DNS_CONCURRENCY)
+        .filter_map(futures::future::ready)
+        .collect()
+        .await;
+
+    lookalikes.sort_by(|a, b| a.domain.cmp(&b.domain));
+    lookalikes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permutations_should_cover_every_technique_but_the_domain_itself() {
+        let candidates = permutations("Example.com.").unwrap();
+        let technique = |domain: &str| {
+            candidates
+                .iter()
+                .find(|candidate| candidate.domain == domain)
+                .map(|candidate| candidate.technique)
+        };
+
+        assert_eq!(technique("examp1e.com"), Some(Technique::Homoglyph));
+        assert_eq!(technique("dxample.com"), Some(Technique::Bitsquat));
+        assert_eq!(technique("example.net"), Some(Technique::TldSwap));
+        assert_eq!(technique("example.co.uk"), Some(Technique::TldSwap));
+        assert_eq!(technique("example.com"), None);
+
+        let idn = candidates
+            .iter()
+            .find(|candidate| candidate.unicode.as_deref() == Some("exаmple.com"))
+            .unwrap();
+        assert!(idn.domain.starts_with("xn--"));
+
+        assert!(permutations("localhost").is_err());
+    }
+}
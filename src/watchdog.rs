@@ -0,0 +1,112 @@
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Engine-level guard around module executions
+/// - A hung module, e.g. on a stuck connection or a slow regex, is given up on after the timeout
+/// - Cancelling the token gives up on every execution at once, e.g. on Ctrl-C
+pub struct Watchdog {
+    timeout: Duration,
+    token: CancellationToken,
+    // `module target` pairs given up on after the timeout
+    timed_out: Mutex<BTreeSet<String>>,
+}
+
+impl Watchdog {
+    /// # Arguments
+    /// * `timeout` - How long a module may spend on a single target
+    /// * `token` - Cancels the module executions of the scan
+    pub fn new(timeout: Duration, token: CancellationToken) -> Self {
+        Watchdog {
+            timeout,
+            token,
+            timed_out: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Run a module against a target, `None` when it timed out or the scan was cancelled
+    ///
+    /// # Arguments
+    /// * `module` - Name of the module
+    /// * `target` - The endpoint, host or name the module runs against
+    /// * `execution` - The module execution, dropped along with its pending requests when given up on
+    pub async fn guard<T>(
+        &self,
+        module: &str,
+        target: &str,
+        execution: impl Future<Output = T>,
+    ) -> Option<T> {
+        let result = self
+            .token
+            .run_until_cancelled(tokio::time::timeout(self.timeout, execution))
+            .await?;
+
+        match result {
+            Ok(output) => Some(output),
+            Err(_) => {
+                log::warn!(
+                    "{} timed out on {} after {} seconds",
+                    module,
+                    target,
+                    self.timeout.as_secs()
+                );
+                self.timed_out
+                    .lock()
+                    .expect("Timed out modules poisoned")
+                    .insert(format!("{} {}", module, target));
+                None
+            }
+        }
+    }
+
+    /// Whether the module executions of the scan were cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// `module target` pairs given up on after the timeout, sorted
+    pub fn timed_out(&self) -> Vec<String> {
+        self.timed_out
+            .lock()
+            .expect("Timed out modules poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_guard_should_give_up_on_hung_modules() {
+        let token = CancellationToken::new();
+        let watchdog = Watchdog::new(Duration::from_millis(50), token.clone());
+
+        assert_eq!(
+            watchdog.guard("http/fast", "a:80", async { 1 }).await,
+            Some(1)
+        );
+        assert_eq!(
+            watchdog
+                .guard("http/hung", "b:80", std::future::pending::<()>())
+                .await,
+            None
+        );
+        assert_eq!(watchdog.timed_out(), vec![String::from("http/hung b:80")]);
+
+        // Cancelled executions are not reported as timed out
+        token.cancel();
+        assert!(watchdog.is_cancelled());
+        assert_eq!(
+            watchdog
+                .guard("http/hung", "c:80", std::future::pending::<()>())
+                .await,
+            None
+        );
+        assert_eq!(watchdog.timed_out().len(), 1);
+    }
+}
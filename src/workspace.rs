@@ -0,0 +1,156 @@
+use crate::action::ScanOptions;
+use anyhow::Context;
+use anyhow::Result;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The directory of one scan run, `<root>/<target>/<started at>/`, holding its artifacts:
+/// - `command.txt`, the command line of the run
+/// - `artifacts/`, the hosts and open ports of the enumeration and port scan stages
+/// - `state/checkpoint.jsonl`, the progress of the vulnerability stage
+/// - `reports/`, the events, Nmap report, inventory, gallery and HAR of the findings
+/// - `logs/vulnscan.log`
+pub struct Workspace {
+    pub path: PathBuf,
+}
+
+impl Workspace {
+    /// Create the directory of a new run of `target` under `root`
+    /// - Runs started within the same second get a `-2`, `-3`... suffix
+    pub fn create(root: &Path, target: &str) -> Result<Self> {
+        let parent = root.join(dir_name(target));
+        std::fs::create_dir_all(&parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+
+        let started_at = chrono::Local::now().format("%Y%m%dT%H%M%S").to_string();
+        let mut path = parent.join(&started_at);
+        let mut attempt = 1;
+        loop {
+            match std::fs::create_dir(&path) {
+                Ok(()) => break,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    attempt += 1;
+                    path = parent.join(format!("{}-{}", started_at, attempt));
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create {}", path.display()));
+                }
+            }
+        }
+
+        for dir in ["artifacts", "state", "reports", "logs"] {
+            std::fs::create_dir(path.join(dir))
+                .with_context(|| format!("Failed to create {}", path.join(dir).display()))?;
+        }
+
+        let command: Vec<String> = std::env::args().map(|arg| quote(&arg)).collect();
+        std::fs::write(path.join("command.txt"), command.join(" ") + "\n")
+            .context("Failed to record the command line")?;
+
+        Ok(Workspace { path })
+    }
+
+    /// Point the outputs of the scan not given explicitly into the run directory
+    pub fn apply(&self, options: &mut ScanOptions) {
+        let reports = self.path.join("reports");
+        options
+            .artifacts
+            .get_or_insert_with(|| self.path.join("artifacts"));
+        options
+            .checkpoint
+            .get_or_insert_with(|| self.path.join("state").join("checkpoint.jsonl"));
+        options
+            .output
+            .get_or_insert_with(|| reports.join("events.jsonl"));
+        options
+            .export_nmap
+            .get_or_insert_with(|| reports.join("ports.xml"));
+        options
+            .inventory
+            .get_or_insert_with(|| reports.join("inventory.json"));
+        options
+            .gallery
+            .get_or_insert_with(|| reports.join("gallery.html"));
+        options
+            .har
+            .get_or_insert_with(|| reports.join("findings.har"));
+    }
+
+    /// Where logs go besides stderr
+    pub fn log(&self) -> Result<impl Write + Send + 'static> {
+        let path = self.path.join("logs").join("vulnscan.log");
+        let file =
+            File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        Ok(Tee { file })
+    }
+}
+
+/// Log lines written to stderr as usual, and to the log file of the run
+struct Tee {
+    file: File,
+}
+
+impl Write for Tee {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        std::io::stderr().write_all(buf)?;
+        self.file.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()?;
+        self.file.flush()
+    }
+}
+
+/// `example.com` as is, `10.0.0.0/24` as `10.0.0.0_24`
+fn dir_name(target: &str) -> String {
+    target
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// An argument as typed in a POSIX shell
+fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,@%+".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_should_lay_out_a_directory_per_run() {
+        let root = std::env::temp_dir().join(format!("vulnscan-workspace-{}", std::process::id()));
+
+        let first = Workspace::create(&root, "10.0.0.0/24").unwrap();
+        let second = Workspace::create(&root, "10.0.0.0/24").unwrap();
+        assert_ne!(first.path, second.path);
+        assert_eq!(
+            first.path.parent(),
+            Some(root.join("10.0.0.0_24").as_path())
+        );
+        for entry in ["artifacts", "state", "reports", "logs", "command.txt"] {
+            assert!(first.path.join(entry).exists(), "No {}", entry);
+        }
+
+        assert_eq!(quote("--format"), "--format");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}